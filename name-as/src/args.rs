@@ -7,4 +7,8 @@ pub struct Cli {
 
     #[arg(short, long)]
     pub verbose: bool,
+
+    /// Memory layout to assemble for: a preset (name, mars-default, mars-compact-data-at-0, mars-compact-text-at-0) or a TOML file
+    #[arg(long, value_name = "LAYOUT")]
+    pub layout: Option<String>,
 }
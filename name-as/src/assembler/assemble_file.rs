@@ -1,5 +1,6 @@
 use std::path::PathBuf;
 
+use name_core::layout::MemoryLayout;
 use name_core::structs::{LineInfo, Section};
 
 use crate::assembler::assemble_line::assemble_line;
@@ -18,11 +19,30 @@ The Ok variant contains the Assembler environment, which contains the needed inf
 pub fn assemble(
     file_contents: String,
     current_dir: PathBuf,
+    file_name: String,
     line_prefix: Option<String>,
 ) -> Result<Assembler, Vec<String>> {
-    let mut environment: Assembler = Assembler::new();
+    assemble_with_layout(
+        file_contents,
+        current_dir,
+        file_name,
+        line_prefix,
+        MemoryLayout::default(),
+    )
+}
+
+/// Assembles for a memory layout other than the default, so addresses start at its bases.
+pub fn assemble_with_layout(
+    file_contents: String,
+    current_dir: PathBuf,
+    file_name: String,
+    line_prefix: Option<String>,
+    layout: MemoryLayout,
+) -> Result<Assembler, Vec<String>> {
+    let mut environment: Assembler = Assembler::new(layout);
 
     environment.current_dir = current_dir;
+    environment.file_name = file_name;
 
     match line_prefix {
         Some(s) => environment.line_prefix = s,
@@ -46,6 +66,7 @@ pub fn assemble(
         environment.section_dot_line.extend(
             LineInfo {
                 content: line.to_string(),
+                file_name: environment.file_name.clone(),
                 line_number: environment.line_number as u32,
                 start_address: match environment.current_section {
                     Section::Text => start_address,
@@ -63,6 +84,16 @@ pub fn assemble(
         environment.line_number += 1;
     }
 
+    if let Err(e) = layout.check_sizes(
+        environment.section_dot_text.len() as u32,
+        environment.section_dot_data.len() as u32,
+    ) {
+        environment
+            .errors
+            .push(format!("[*] In {}:", environment.file_name));
+        environment.errors.push(format!(" - {e}"));
+    }
+
     if environment.errors.len() == 0 {
         return Ok(environment);
     } else {
@@ -26,7 +26,7 @@ The logic is as follows:
 
 pub fn assemble_line(environment: &mut Assembler, line: &str, expanded_line: String) {
     // Print the line (with expansions)
-    println!(
+    narrate!(
         "{}{}: {}",
         environment.line_prefix, environment.line_number, line
     );
@@ -81,7 +81,7 @@ pub fn assemble_line(environment: &mut Assembler, line: &str, expanded_line: Str
                             .find(|s| s.identifier == content)
                             .is_none()
                         {
-                            environment.add_label(&content, 0);
+                            environment.add_placeholder(&content);
                         }
                     }
                     None => {
@@ -150,7 +150,7 @@ pub fn assemble_line(environment: &mut Assembler, line: &str, expanded_line: Str
 
             for (instr_info, args) in resulting_tuples {
                 let reverse_formatted_instruction: String = reverse_format_instruction(instr_info, &args);
-                println!(
+                narrate!(
                     "{}{}: {}",
                     environment.line_prefix, environment.line_number, reverse_formatted_instruction
                 );
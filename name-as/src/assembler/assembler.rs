@@ -1,10 +1,11 @@
 use std::collections::HashMap;
 use std::path::PathBuf;
 
-use name_core::constants::{MIPS_ADDRESS_ALIGNMENT, MIPS_DATA_START_ADDR, MIPS_TEXT_START_ADDR};
+use name_core::constants::MIPS_ADDRESS_ALIGNMENT;
 use name_core::elf_def::{RelocationEntry, STT_FUNC, STT_OBJECT};
 use name_core::instruction::information::InstructionInformation;
-use name_core::structs::{Section, Symbol, Visibility};
+use name_core::layout::MemoryLayout;
+use name_core::structs::{Section, Symbol, Visibility, LINE_SECTION_HEADER};
 
 use crate::assembler::assemble_instruction::assemble_instruction;
 use crate::assembler::assembly_helpers::{
@@ -28,33 +29,37 @@ pub struct Assembler {
     pub(crate) current_section: Section,
     pub(crate) current_address: u32,
     pub(crate) current_dir: PathBuf,
+    pub(crate) file_name: String,
     pub(crate) text_address: u32,
     pub(crate) data_address: u32,
     pub(crate) line_number: usize,
     pub(crate) line_prefix: String,
     pub(crate) most_recent_label: String,
+    pub(crate) layout: MemoryLayout,
 }
 
 impl Assembler {
-    // Initialize the assembler environment - default constructor.
-    pub(crate) fn new() -> Self {
+    // Initialize the assembler environment for a memory layout.
+    pub(crate) fn new(layout: MemoryLayout) -> Self {
         Assembler {
             pseudo_instruction_table: generate_pseudo_instruction_hashmap(),
             section_dot_text: vec![],
             section_dot_data: vec![],
             section_dot_rel: vec![],
-            section_dot_line: vec![],
+            section_dot_line: LINE_SECTION_HEADER.to_vec(),
             symbol_table: vec![],
             equivalences: HashMap::new(),
             errors: vec![],
             current_section: Section::Null,
             current_address: 0,
             current_dir: PathBuf::new(),
-            text_address: MIPS_TEXT_START_ADDR,
-            data_address: MIPS_DATA_START_ADDR,
+            file_name: String::new(),
+            text_address: layout.text_base,
+            data_address: layout.data_base,
             line_number: 1,
             line_prefix: String::from(""),
             most_recent_label: String::from(""),
+            layout,
         }
     }
 
@@ -68,7 +73,7 @@ impl Assembler {
 
         match existing_symbol {
             Some(sym) => {
-                if sym.value != 0 {
+                if !matches!(sym.section, Section::Null) {
                     self.errors
                         .push(format!("[*] On line {}:", self.line_number));
                     self.errors.push(format!(
@@ -78,6 +83,11 @@ impl Assembler {
                     return;
                 } else {
                     sym.value = value;
+                    sym.section = self.current_section.clone();
+                    sym.symbol_type = match self.current_section {
+                        Section::Text => STT_FUNC,
+                        _ => STT_OBJECT,
+                    };
                     return;
                 }
             }
@@ -103,11 +113,24 @@ impl Assembler {
             section: self.current_section.clone(),
         });
 
-        println!("Inserted symbol {} at 0x{:x}", ident, self.current_address);
+        narrate!("Inserted symbol {} at 0x{:x}", ident, self.current_address);
 
         self.most_recent_label = ident.clone();
     }
 
+    /// Add a symbol that's used before (or without) being defined. It's in no section, so it stays undefined until
+    /// add_label fills it in.
+    pub(crate) fn add_placeholder(&mut self, ident: &String) {
+        self.symbol_table.push(Symbol {
+            symbol_type: 0,
+            identifier: ident.to_owned(),
+            value: 0,
+            size: 4,
+            visibility: Visibility::Local,
+            section: Section::Null,
+        });
+    }
+
     // Expand a line. Try replacing all instances of equivalences.
     pub fn expand_line(&self, line: &str) -> String {
         let mut expanded_line = String::new();
@@ -165,7 +188,7 @@ impl Assembler {
             let symbol_offset: u32 = self.get_symbol_offset(symbol_ident);
 
             let new_bytes: Vec<u8> = RelocationEntry {
-                r_offset: self.current_address - MIPS_TEXT_START_ADDR,
+                r_offset: self.current_address - self.layout.text_base,
                 r_sym: symbol_offset,
                 r_type: info.relocation_type.unwrap().clone(),
             }
@@ -184,7 +207,7 @@ impl Assembler {
         {
             Some(idx) => return (idx as u32) + 1,
             None => {
-                self.add_label(&ident, 0);
+                self.add_placeholder(&ident);
                 return self.symbol_table.len() as u32;
             }
         };
@@ -111,10 +111,10 @@ Pretty print an instruction in the format:
 
 */
 pub fn pretty_print_instruction(addr: &u32, packed: &u32) {
-    println!(" - @0x{:08x}:", addr);
-    println!("   - 0x{:08x}", packed);
-    println!("   - 0b{:032b}", packed);
-    println!();
+    narrate!(" - @0x{:08x}:", addr);
+    narrate!("   - 0x{:08x}", packed);
+    narrate!("   - 0b{:032b}", packed);
+    narrate!();
 }
 
 pub fn search_mnemonic(
@@ -14,6 +14,9 @@ impl Assembler {
             ".eqv" => {
                 self.new_eqv(arguments);
             }
+            ".globl" => {
+                self.make_global(arguments);
+            }
             ".include" => {
                 self.include_file(arguments);
             }
@@ -1,12 +1,12 @@
 use std::fs::read_to_string;
 use std::path::PathBuf;
 
-use crate::assembler::assemble_file::assemble;
+use crate::assembler::assemble_file::assemble_with_layout;
 use crate::assembler::assembler::Assembler;
 
 use crate::definitions::structs::LineComponent;
 
-use name_core::structs::Section;
+use name_core::structs::{Section, Visibility};
 
 impl Assembler {
     // .asciiz
@@ -87,6 +87,44 @@ impl Assembler {
         self.equivalences.insert(name, expansion);
     }
 
+    // .globl
+    pub(crate) fn make_global(&mut self, arguments: &Vec<LineComponent>) {
+        if arguments.is_empty() {
+            self.errors.push(format!(
+                "[*] On line {}{}:",
+                self.line_prefix, self.line_number
+            ));
+            self.errors
+                .push(" - `.globl` expected at least one label.".to_string());
+            return;
+        }
+
+        for argument in arguments {
+            let LineComponent::Identifier(ident) = argument else {
+                self.errors.push(format!(
+                    "[*] On line {}{}:",
+                    self.line_prefix, self.line_number
+                ));
+                self.errors.push(format!(
+                    " - `.globl` expected identifier, found {argument:?}"
+                ));
+                continue;
+            };
+
+            // a label made global before it's defined is filled in by add_label like any other placeholder
+            if !self.symbol_table.iter().any(|sym| &sym.identifier == ident) {
+                self.add_placeholder(ident);
+            }
+            if let Some(sym) = self
+                .symbol_table
+                .iter_mut()
+                .find(|sym| &sym.identifier == ident)
+            {
+                sym.visibility = Visibility::Global;
+            }
+        }
+    }
+
     // .include
     pub(crate) fn include_file(&mut self, arguments: &Vec<LineComponent>) {
         if arguments.len() != 1 {
@@ -114,7 +152,7 @@ impl Assembler {
             }
         };
 
-        println!("\n[+] Found .include, attempting to include {:?}", filename);
+        narrate!("\n[+] Found .include, attempting to include {:?}", filename);
 
         let file_contents = match read_to_string(&filename) {
             Ok(content) => content,
@@ -132,9 +170,19 @@ impl Assembler {
         };
 
         let line_prefix: String = format!("{}  {}->", self.line_prefix, self.line_number);
+        let included_file_name: String = match filename.file_name() {
+            Some(name) => name.to_string_lossy().to_string(),
+            None => String::new(),
+        };
 
         let returned_assembler: Result<Assembler, Vec<String>> =
-            assemble(file_contents, filename, Some(line_prefix));
+            assemble_with_layout(
+                file_contents,
+                filename,
+                included_file_name,
+                Some(line_prefix),
+                self.layout,
+            );
         match returned_assembler {
             Ok(returned_env) => {
                 self.equivalences.extend(returned_env.equivalences);
@@ -144,7 +192,7 @@ impl Assembler {
             }
         }
 
-        println!("[+] Module included.\n");
+        narrate!("[+] Module included.\n");
     }
 
     // .text
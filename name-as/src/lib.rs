@@ -1,3 +1,6 @@
+#[macro_use]
+extern crate name_core;
+
 pub mod args;
 pub mod assembler;
 pub mod definitions;
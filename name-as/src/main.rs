@@ -1,33 +1,49 @@
 use std::path::PathBuf;
 
 use name_as::args::Cli;
-use name_as::assembler::assemble_file::assemble;
+use name_as::assembler::assemble_file::assemble_with_layout;
 
 use name_core::{
     elf_def::ElfType,
     elf_utils::{create_new_elf, extract_symbol_table_to_sections, write_elf_to_file},
+    layout::MemoryLayout,
 };
 
 use clap::Parser;
 
 fn main() {
     let args = Cli::parse();
+    let layout: MemoryLayout = match args.layout.as_deref().map(MemoryLayout::load) {
+        Some(Ok(layout)) => layout,
+        Some(Err(e)) => {
+            eprintln!("{e}");
+            std::process::exit(1);
+        }
+        None => MemoryLayout::default(),
+    };
+
     let file_contents: String = std::fs::read_to_string(&args.input_filename)
         .expect("Failed to read input file (likely does not exist).");
 
     let mut base_path = PathBuf::from(&args.input_filename);
     base_path.pop();
 
+    // Only the file's name is recorded in .line - that's what the debugger matches file:line against.
+    let file_name: String = match args.input_filename.file_name() {
+        Some(name) => name.to_string_lossy().to_string(),
+        None => String::new(),
+    };
+
     // Preprocessor would do its work here
 
     // Allowing assemble to take ownership of the source file contents, because this is the end of its utility in this function.
-    let assembled_result = assemble(file_contents, base_path, None);
+    let assembled_result = assemble_with_layout(file_contents, base_path, file_name, None, layout);
     match assembled_result {
         Ok(assembler_environment) => {
             let (section_dot_symtab, section_dot_strtab) =
                 extract_symbol_table_to_sections(assembler_environment.symbol_table);
 
-            let et_rel = create_new_elf(
+            let mut et_rel = create_new_elf(
                 vec![
                     assembler_environment.section_dot_data,
                     assembler_environment.section_dot_text,
@@ -39,6 +55,7 @@ fn main() {
                 ElfType::Relocatable,
                 true,
             );
+            layout.apply_to(&mut et_rel);
             match write_elf_to_file(&args.output_filename, &et_rel) {
                 Ok(()) => println!(
                     "Object file successfuly written to {:?}",
@@ -22,7 +22,7 @@ fn hello_world_as_test() {
     let file_contents: String =
         read_to_string(test_file_path).expect("Failed to read input file (likely does not exist).");
 
-    let assembled_output = assemble(file_contents, base_path, None);
+    let assembled_output = assemble(file_contents, base_path, "hello_world.asm".to_string(), None);
 
     match assembled_output {
         Ok(assembler_environment) => {
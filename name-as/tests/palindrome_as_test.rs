@@ -30,7 +30,13 @@ fn palindrome_as_test() {
             panic!("Failed to read {input_path:?} (likely does not exist).");
         };
 
-        let assembled_output = assemble(file_contents, test_dir_path.clone(), None);
+        let file_name: String = input_path
+            .file_name()
+            .expect("Input path has no file name")
+            .to_string_lossy()
+            .to_string();
+
+        let assembled_output = assemble(file_contents, test_dir_path.clone(), file_name, None);
 
         match assembled_output {
             Ok(assembler_environment) => {
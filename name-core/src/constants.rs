@@ -5,6 +5,7 @@ pub const MIPS_TEXT_START_ADDR: u32 = 0x00400000; // The address at which, by co
 pub const MIPS_DATA_START_ADDR: u32 = 0x10010000; // The address at which, by convention, MIPS begins the .data section (I really typed this out again!)
 pub const MIPS_HEAP_START_ADDR: u32 = 0x10040000; // Similarly, the heap for dynamic allocation growing upward
 pub const MIPS_STACK_END_ADDR: u32 = 0x7ffffe00; // In like fashion, the stack, which grows downward
+pub const MIPS_STACK_MAX_SIZE: u32 = 0x00100000; // How far below MIPS_STACK_END_ADDR the stack is allowed to grow (1 MiB)
                                                  // pub const MIPS_KERNEL_START_ADDR: u32 = 0x90000000; // Kernel data (currently unused)
                                                  // pub const MIPS_MMIO_START_ADDR: u32 = 0xffff0000; // Memory-mapped I/O devices (currently unused)
 
@@ -0,0 +1,114 @@
+use crate::debug::expression::Expression;
+use crate::structs::{ProgramState, Symbol};
+
+/// A condition attached to a breakpoint, like `$t0 == 5`, `*counter >= 10`, or `nums[$t1] & 1`.
+/// Conditions are debugger expressions, true whenever they're nonzero.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Condition {
+    pub text: String, // what the user typed, for pb
+    pub expression: Expression,
+}
+
+impl Condition {
+    /// Reads a condition the way p reads an expression.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the condition is empty or isn't a valid expression.
+    pub fn parse(text: &str, symbols: &[Symbol]) -> Result<Condition, String> {
+        let text = text.trim();
+        if text.is_empty() {
+            return Err("Expected a condition after \"if\".".to_string());
+        }
+
+        Ok(Condition {
+            text: text.to_string(),
+            expression: Expression::parse(text, symbols)?,
+        })
+    }
+
+    /// Checks the condition against the current state of the program. Values are compared as signed integers.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the expression can't be evaluated, like when it reads memory that isn't there.
+    pub fn evaluate(&self, program_state: &ProgramState) -> Result<bool, String> {
+        Ok(self.expression.evaluate(program_state)? != 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::debug::expression::BinaryOperator;
+    use crate::structs::{Memory, Processor, Section, Visibility};
+
+    fn counter_symbol() -> Symbol {
+        Symbol {
+            symbol_type: 1,
+            identifier: "counter".to_string(),
+            value: 0x1001_0000,
+            size: 4,
+            visibility: Visibility::Local,
+            section: Section::Data,
+        }
+    }
+
+    #[test]
+    fn test_parse_condition() {
+        let symbols = vec![counter_symbol()];
+
+        assert_eq!(
+            Condition::parse("$t0 == 5", &symbols),
+            Ok(Condition {
+                text: "$t0 == 5".to_string(),
+                expression: Expression::Binary(
+                    BinaryOperator::Equal,
+                    Box::new(Expression::Register(8)),
+                    Box::new(Expression::Literal(5)),
+                ),
+            })
+        );
+
+        let condition = Condition::parse("*counter>=0x10", &symbols).unwrap();
+        assert_eq!(
+            condition.expression,
+            Expression::Binary(
+                BinaryOperator::GreaterEqual,
+                Box::new(Expression::Memory {
+                    size: 4,
+                    address: Box::new(Expression::Literal(0x1001_0000)),
+                }),
+                Box::new(Expression::Literal(0x10)),
+            )
+        );
+
+        assert!(Condition::parse("$t10 == 1", &symbols).is_err());
+        assert!(Condition::parse("nowhere < 3", &symbols).is_err());
+        assert!(Condition::parse("", &symbols).is_err());
+    }
+
+    #[test]
+    fn test_evaluate_condition() {
+        let symbols = vec![counter_symbol()];
+        let mut program_state = ProgramState::new(
+            Processor::new(0x0040_0000),
+            Memory::new(vec![0, 0, 0, 12], vec![0; 4]),
+        );
+        program_state.cpu.general_purpose_registers[8] = 0xFFFF_FFFF;
+
+        let evaluate = |text: &str| {
+            Condition::parse(text, &symbols)
+                .unwrap()
+                .evaluate(&program_state)
+        };
+
+        assert_eq!(evaluate("$t0 == -1"), Ok(true));
+        assert_eq!(evaluate("$t0 < 0"), Ok(true));
+        assert_eq!(evaluate("*counter == 12"), Ok(true));
+        assert_eq!(evaluate("*(counter) != 12"), Ok(false));
+        assert_eq!(evaluate("$8"), Ok(true));
+        assert_eq!(evaluate("$t1"), Ok(false));
+        assert!(evaluate("*$t1 == 0").is_err());
+    }
+}
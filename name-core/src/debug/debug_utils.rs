@@ -1,16 +1,22 @@
-use std::{collections::HashMap, sync::LazyLock};
+use std::{
+    collections::{BTreeMap, HashMap, VecDeque},
+    fmt,
+    sync::LazyLock,
+};
 // use std::io::{self, Write};
 
 // use crate::debug::debugger_methods::*;
 
 use crate::{
-    constants::{MIPS_ADDRESS_ALIGNMENT, MIPS_TEXT_START_ADDR},
-    debug::{exception_handler::handle_exception, fetch::fetch},
+    constants::MIPS_ADDRESS_ALIGNMENT,
+    debug::{condition::Condition, exception_handler::try_handle_exception, fetch::fetch},
     exception::definitions::ExceptionType,
     instruction::{
-        information::InstructionInformation, instruction_set::INSTRUCTION_SET, RawInstruction,
+        information::InstructionInformation, instruction_set::INSTRUCTION_SET, MemoryAccess,
+        RawInstruction,
     },
-    structs::{LineInfo, OperatingSystem, ProgramState},
+    observer::{notify, ExecutedInstruction},
+    structs::{LineInfo, OperatingSystem, Processor, ProgramState, Register, Symbol},
 };
 
 static INSTRUCTION_LOOKUP: LazyLock<HashMap<u32, &'static InstructionInformation>> =
@@ -21,12 +27,24 @@ static INSTRUCTION_LOOKUP: LazyLock<HashMap<u32, &'static InstructionInformation
             .collect()
     });
 
+/// Looks up what NAME knows about the instruction, or gives None if it isn't one it knows.
+#[must_use]
+pub fn information_of(instruction: RawInstruction) -> Option<&'static InstructionInformation> {
+    INSTRUCTION_LOOKUP.get(&instruction.get_lookup()).copied()
+}
+
+/// Names the instruction, or gives None if it isn't one NAME knows.
+pub fn mnemonic_of(instruction: RawInstruction) -> Option<&'static str> {
+    information_of(instruction).map(|info| info.mnemonic)
+}
+
 pub fn single_step(_lineinfo: &Vec<LineInfo>, program_state: &mut ProgramState) -> () {
     if !program_state
         .memory
         .allows_execution_of(program_state.cpu.pc)
     {
         program_state.set_exception(ExceptionType::AddressExceptionLoad);
+        notify_exception(program_state);
         return;
     }
 
@@ -39,10 +57,26 @@ pub fn single_step(_lineinfo: &Vec<LineInfo>, program_state: &mut ProgramState)
         Some(info) => info,
         None => {
             program_state.set_exception(ExceptionType::ReservedInstruction);
+            notify_exception(program_state);
             return;
         }
     };
 
+    let address: u32 = program_state.cpu.pc;
+
+    // observers hear what's about to happen before anything changes
+    let observing: bool = !program_state.observers.is_empty();
+    let memory_access: Option<MemoryAccess> = if observing {
+        raw_instruction.get_memory_access(&program_state.cpu.general_purpose_registers)
+    } else {
+        None
+    };
+    if observing {
+        notify(program_state, |observer, state| {
+            observer.before_execute(address, raw_instruction, state);
+        });
+    }
+
     program_state.cpu.pc += MIPS_ADDRESS_ALIGNMENT;
 
     // Execute the instruction; program_state is modified.
@@ -52,139 +86,288 @@ pub fn single_step(_lineinfo: &Vec<LineInfo>, program_state: &mut ProgramState)
         println!("Executing {}", instr_info.mnemonic);
     }
     let _ = (instr_info.implementation)(program_state, raw_instruction);
+    program_state.update_call_stack(raw_instruction, address);
 
     // The $0 register should never have been permanently changed. Don't let it remain changed.
 
     program_state.cpu.general_purpose_registers[0] = 0;
+
+    if observing {
+        notify_executed(
+            program_state,
+            address,
+            raw_instruction,
+            instr_info,
+            memory_access,
+        );
+    }
+}
+
+/// Tells observers what the instruction at address did.
+fn notify_executed(
+    program_state: &mut ProgramState,
+    address: u32,
+    instruction: RawInstruction,
+    information: &InstructionInformation,
+    memory_access: Option<MemoryAccess>,
+) {
+    // a load or store that raised an exception didn't happen, and neither did the store of a failed sc
+    let failed_sc: bool = instruction.is_failed_sc(&program_state.cpu.general_purpose_registers);
+    let executed: ExecutedInstruction = ExecutedInstruction {
+        address,
+        instruction,
+        next_pc: program_state.cpu.pc,
+        memory_access: memory_access.filter(|_| !program_state.is_exception() && !failed_sc),
+    };
+
+    notify(program_state, |observer, state| {
+        observer.after_execute(&executed, information, state);
+    });
+    notify_exception(program_state);
+}
+
+/// Tells observers about an exception the last instruction raised, if it did.
+fn notify_exception(program_state: &mut ProgramState) {
+    if program_state.observers.is_empty() || !program_state.is_exception() {
+        return;
+    }
+    let Ok(exception) = ExceptionType::try_from(program_state.cp0.get_exc_code()) else {
+        return;
+    };
+
+    notify(program_state, |observer, state| {
+        observer.exception(exception, state);
+    });
+    if exception == ExceptionType::Syscall {
+        let number: u32 = program_state.cpu.general_purpose_registers[Register::V0 as usize];
+        notify(program_state, |observer, state| {
+            observer.syscall(number, state);
+        });
+    }
+}
+
+/// Why `db_step`, or a command built on it, stopped before it was done.
+#[derive(Debug, Clone, PartialEq)]
+pub enum StopReason {
+    Breakpoint(String), // a debugger breakpoint, with the message reporting it
+    BreakInstruction,   // a break instruction in the program itself
+    Watchpoint,         // the watchpoint has already reported what set it off
+    Crashed(String),    // an exception the program can't recover from, with the report on it
+    Error(String),      // the command couldn't go ahead, like finish in the outermost frame
+}
+
+impl StopReason {
+    /// Whether the stop has already been reported to the user, so there's nothing more to print.
+    #[must_use]
+    pub fn is_reported(&self) -> bool {
+        matches!(self, StopReason::Watchpoint)
+    }
+}
+
+impl fmt::Display for StopReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StopReason::Breakpoint(message)
+            | StopReason::Crashed(message)
+            | StopReason::Error(message) => write!(f, "{message}"),
+            StopReason::BreakInstruction => write!(f, "Breakpoint reached."),
+            StopReason::Watchpoint => write!(f, "Watchpoint reached."),
+        }
+    }
+}
+
+impl From<String> for StopReason {
+    fn from(message: String) -> Self {
+        StopReason::Error(message)
+    }
 }
 
 /// Executes only the next line of code. Invoked by "s" in the CLI.
 // Also called by continuously_execute
+///
+/// # Errors
+///
+/// Fails with why execution stopped, like a breakpoint being reached.
 pub fn db_step(
     lineinfo: &Vec<LineInfo>,
     program_state: &mut ProgramState,
     os: &mut OperatingSystem,
     debugger_state: &mut DebuggerState,
-) -> Result<(), String> {
-    let prev_funct_code = match program_state.memory.read_byte(program_state.cpu.pc - 1) {
-        Ok(byte) => byte & 0b00111111,
-        Err(e) => {
-            if program_state.cpu.pc != MIPS_TEXT_START_ADDR {
-                panic!("{e}"); // TODO: once this is back in name-emu, make nicer errors using generate_err
-            } else {
-                // we're only using the prev_funct_code to check for a breakpoint.
-                // so we can set it to 0 if we don't care what it actually is
-                0
-            }
-        }
+) -> Result<(), StopReason> {
+    if debugger_state.watchpoints.is_empty() {
+        return execute_next(lineinfo, program_state, os, debugger_state);
+    }
+
+    // Figure out what's about to happen to memory before it happens, so watchpoints can report old values.
+    let upcoming = upcoming_instruction(program_state);
+    let access: Option<MemoryAccess> = upcoming.and_then(|(_, instruction)| {
+        instruction.get_memory_access(&program_state.cpu.general_purpose_registers)
+    });
+    let snapshots: Vec<Vec<Option<u8>>> =
+        debugger_state.snapshot_watchpoints(&program_state.memory);
+
+    let result = execute_next(lineinfo, program_state, os, debugger_state);
+
+    // stopping on a breakpoint runs nothing, so there's nothing for the watchpoints to see
+    if debugger_state.stopped_at_breakpoint.is_some() {
+        return result;
+    }
+
+    let (pc, instruction) = match upcoming {
+        Some((pc, instruction)) => (pc, Some(instruction)),
+        None => (program_state.cpu.pc, None),
     };
+    let mnemonic: &str = instruction
+        .and_then(mnemonic_of)
+        .unwrap_or("an unknown instruction");
 
-    // if we just ran past a breakpoint, execute the instruction it replaced.
-    if prev_funct_code == 0b001101 {
-        let bp_num = match get_bp_num(program_state) {
-            Ok(idx) => idx,
-            Err(e) => return Err(format!("{e}")),
-        };
-
-        let bp: &mut Breakpoint = match debugger_state.breakpoints.get_mut(bp_num as usize) {
-            Some(bp) => bp,
-            None => {
-                return Err(format!("Breakpoint {} not found in memory.", bp_num));
-            }
-        };
+    if debugger_state.check_watchpoints(lineinfo, program_state, pc, mnemonic, access, &snapshots) {
+        // if a breakpoint was also reached, let its message through
+        return result.and(Err(StopReason::Watchpoint));
+    }
 
-        /* This is just copy paste from single_step with edits to make it make sense for our case */
+    result
+}
 
-        if bp.already_executed {
-            bp.flip_execution_status();
-        } else {
-            let raw_instruction: RawInstruction;
-            let instr_info: &&InstructionInformation;
-
-            // Fetch the instruction replaced by the breakpoint
-            raw_instruction = RawInstruction::new(bp.replaced_instruction); // lol
-            instr_info = match INSTRUCTION_LOOKUP.get(&raw_instruction.get_lookup()) {
-                Some(info) => info,
-                None => {
-                    program_state.set_exception(ExceptionType::ReservedInstruction);
-                    return Err(format!("Reserved instruction reached. (My code is bad so the program state has been changed as a result. Lord help us)"));
-                }
-            };
-            // Execute the instruction; program_state is modified.
-            if true
-            /* Allowing for some later verbose mode */
-            {
-                println!("Executing {}", instr_info.mnemonic);
-            }
-            let _ = (instr_info.implementation)(program_state, raw_instruction);
+/// Determines the instruction the next call to db_step will execute along with the address it came from.
+#[must_use]
+pub fn upcoming_instruction(program_state: &ProgramState) -> Option<(u32, RawInstruction)> {
+    let mut word: u32 = 0;
+    for i in 0..4 {
+        match program_state
+            .memory
+            .read_byte(program_state.cpu.pc.wrapping_add(i))
+        {
+            Ok(b) => word |= (b as u32) << (24 - i * 8),
+            Err(_) => return None,
+        }
+    }
 
-            // resolve the breakpoint exception
-            program_state.recover_from_exception();
+    Some((program_state.cpu.pc, RawInstruction::new(word)))
+}
 
-            // program_state.cpu.pc += MIPS_ADDRESS_ALIGNMENT;
+/// Where the program is stopped: the instruction that runs next, or the instruction that crashed.
+/// A crash leaves the pc one past the instruction responsible, so this is what should be shown to the user as the pc.
+pub fn stopped_address(program_state: &ProgramState) -> u32 {
+    if program_state.is_exception()
+        && program_state.cp0.get_exc_code() != ExceptionType::Breakpoint.into()
+    {
+        return program_state.cp0.get_epc();
+    }
 
-            bp.flip_execution_status();
+    match upcoming_instruction(program_state) {
+        Some((address, _)) => address,
+        None => program_state.cpu.pc,
+    }
+}
 
-            // The $0 register should never have been permanently changed. Don't let it remain changed.
-            program_state.cpu.general_purpose_registers[0] = 0;
+/// Performs the actual work of db_step: stops at breakpoints before their instructions run, and otherwise runs one instruction.
+/// Breakpoints live in the debugger's table rather than in memory, so the program never sees them.
+fn execute_next(
+    lineinfo: &Vec<LineInfo>,
+    program_state: &mut ProgramState,
+    os: &mut OperatingSystem,
+    debugger_state: &mut DebuggerState,
+) -> Result<(), StopReason> {
+    // the program's own break instruction has been reported; carry on after it
+    if program_state.is_exception()
+        && program_state.cp0.get_exc_code() == ExceptionType::Breakpoint.into()
+    {
+        program_state.recover_from_exception();
+    }
 
-            return Ok(());
+    // the breakpoint we're stopped on has already been reported, so let its instruction run this time.
+    let pc: u32 = program_state.cpu.pc;
+    let resuming: bool = debugger_state.stopped_at_breakpoint.take() == Some(pc);
+
+    if !resuming {
+        // disabled breakpoints, false conditions, and ignored hits are passed over as if the breakpoint weren't there.
+        if let Some(bp_num) = debugger_state.breakpoint_at(pc) {
+            if debugger_state.should_stop_at(bp_num, program_state) {
+                debugger_state.stopped_at_breakpoint = Some(pc);
+
+                let bp: &Breakpoint = &debugger_state.breakpoints[&bp_num];
+                return Err(StopReason::Breakpoint(format!(
+                    "Breakpoint {} at line {} reached. (hit {} time{})",
+                    bp_num,
+                    bp.line_num,
+                    bp.hit_count,
+                    if bp.hit_count == 1 { "" } else { "s" }
+                )));
+            }
         }
     }
 
     single_step(lineinfo, program_state);
     if program_state.is_exception() {
-        // todo!("Handle exception");
-        // return Err("exceptionnnnnnnnn".to_string())
         if program_state.cp0.get_exc_code() != ExceptionType::Breakpoint.into() {
-            handle_exception(program_state, os, lineinfo, debugger_state);
+            if let Err(report) = try_handle_exception(program_state, os, lineinfo, debugger_state) {
+                program_state.should_continue_execution = false;
+                return Err(StopReason::Crashed(report));
+            }
         } else {
-            return Err("Breakpoint reached.".to_string());
+            // a break instruction that was already in the program
+            return Err(StopReason::BreakInstruction);
         }
     }
+    os.tick(program_state);
     Ok(())
 }
 
-/// Returns the breakpoint number of the instruction at $pc.
-fn get_bp_num(program_state: &ProgramState) -> Result<u32, String> {
-    let bitmask: u32 = 0b00000011111111111111111111000000; // sorry for the magic number. this is filtering out the code field of the break instruction
-    let mut bp_num = 0;
-
-    for i in 0..4 {
-        let mut word_portion = match program_state
-            .memory
-            .read_byte(program_state.cpu.pc - MIPS_ADDRESS_ALIGNMENT + i)
-        {
-            Ok(byte) => byte as u32,
-            Err(e) => return Err(format!("{e}")),
-        };
-
-        word_portion &= (bitmask >> (24 - 8 * i)) & 0xff;
-
-        bp_num |= word_portion << 24 - 8 * i;
-    }
+/// How register values are shown by p and pa.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DisplayFormat {
+    Hex,
+    Signed,
+    Unsigned,
+    Char,
+    Binary,
+}
 
-    // we have the break instruction with the code field filtered out.
-    // shift it to the right by the number of bits in the funct code to get the actual breakpoint number :^)
-    bp_num >>= 6;
+/// Which kinds of access cause a watchpoint to stop execution.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum WatchType {
+    Read,
+    Write,
+    ReadWrite,
+}
 
-    return Ok(bp_num);
+/// A watchpoint stops execution when the watched region of memory is read and/or written.
+#[derive(Debug)]
+pub struct Watchpoint {
+    pub wp_num: usize, // kept for the watchpoint's lifetime, so deleting another doesn't renumber it
+    pub address: u32,
+    pub size: u32,
+    pub watch_type: WatchType,
+    pub label: Option<String>, // what the user typed to create the watchpoint, if it was a label
 }
 
+/// A breakpoint stops execution just before the instruction at its address runs.
 #[derive(Debug)]
 pub struct Breakpoint {
     // pub bp_num: u16, // why do you have 65535 breakpoints. do better
     pub line_num: u32,
     pub address: u32,
-    pub replaced_instruction: u32,
-    pub already_executed: bool,
+    pub enabled: bool,
+    pub condition: Option<Condition>, // only stop if this holds
+    pub ignore_count: u32,            // number of upcoming hits to pass over without stopping
+    pub hit_count: u32,
 }
 
 pub struct DebuggerState {
-    pub global_bp_num: usize, // point to the first available empty space in the breakpoint vector
-    pub breakpoints: Vec<Breakpoint>, // indexed by bp_num
+    pub breakpoints: BTreeMap<usize, Breakpoint>, // keyed by bp_num, so pb lists them in order
+    pub breakpoint_addresses: HashMap<u32, usize>, // address -> bp_num, for checking every step
+    pub next_bp_num: usize, // breakpoint numbers are never reused, like watchpoint numbers
     // pub replaced_instructions: Vec<u32>, // also indexed by bp num
     pub global_list_loc: usize, // for the l command; like the center of the output
+    pub watchpoints: Vec<Watchpoint>,
+    pub next_wp_num: usize,       // watchpoint numbers are never reused
+    pub symbols: Vec<Symbol>,     // from .symtab, so labels can be used in place of addresses
+    pub script: VecDeque<String>, // commands to run before asking the user for any (from -x)
+    pub batch: bool,              // quit once the script runs out instead of asking the user
+    pub stopped_at_breakpoint: Option<u32>, // the breakpoint execution is stopped on, which is let through on resuming
+    pub display_format: DisplayFormat,      // how p and pa show registers unless told otherwise
+    pub registers_at_last_stop: Option<Processor>, // for pointing out which registers changed since then
 }
 
 // pub type DebugFn = fn(&Vec<LineInfo>, &mut Memory, &mut Processor, &Vec<Breakpoint>) -> Result<(), String>;
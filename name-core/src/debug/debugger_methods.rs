@@ -1,13 +1,17 @@
 // use std::os;
 
 use crate::{
-    constants::{/*MIPS_ADDRESS_ALIGNMENT,*/ REGISTERS},
-    // structs::Register,
-    structs::{LineInfo, OperatingSystem, ProgramState},
+    constants::REGISTERS,
+    debug::simulator_helpers::{find_lineinfo, format_backtrace},
+    structs::{LineInfo, OperatingSystem, ProgramState, Register},
     // exception::definitions::ExceptionType,
 };
 
-use crate::debug::debug_utils::{db_step, DebuggerState};
+use crate::debug::debug_utils::{
+    db_step, upcoming_instruction, DebuggerState, DisplayFormat, StopReason,
+};
+use crate::debug::expression::Expression;
+use crate::exception::register_set::CP0_REGISTER_INFO;
 // use crate::debug::exception_handler::handle_exception;
 // use crate::debug::fetch::fetch;
 
@@ -22,7 +26,7 @@ pub fn continuously_execute(
     program_state: &mut ProgramState,
     os: &mut OperatingSystem,
     debugger_state: &mut DebuggerState,
-) -> Result<(), String> {
+) -> Result<(), StopReason> {
     // TODO: make a reinitializer
     while program_state.should_continue_execution {
         match db_step(lineinfo, program_state, os, debugger_state) {
@@ -33,6 +37,155 @@ pub fn continuously_execute(
     Ok(())
 }
 
+/// Prints where execution stopped, for the commands that can run a long way before stopping.
+fn print_location(lineinfo: &Vec<LineInfo>, program_state: &ProgramState) {
+    if !program_state.should_continue_execution {
+        return;
+    }
+
+    match find_lineinfo(lineinfo, program_state.cpu.pc) {
+        Some(line) => println!(
+            "Stopped at 0x{:08x} (line {}: {})",
+            program_state.cpu.pc,
+            line.line_number,
+            line.content.trim()
+        ),
+        None => println!("Stopped at 0x{:08x}", program_state.cpu.pc),
+    }
+}
+
+/// Executes the next instruction, treating a jal/jalr as one step by running until the call returns. Invoked by "n" in the CLI.
+/// Breakpoints and watchpoints inside the call still stop execution.
+///
+/// # Errors
+///
+/// Fails with why execution stopped early, like a breakpoint being reached.
+pub fn step_over(
+    lineinfo: &Vec<LineInfo>,
+    program_state: &mut ProgramState,
+    os: &mut OperatingSystem,
+    debugger_state: &mut DebuggerState,
+) -> Result<(), StopReason> {
+    // the call stack (rather than the return address) keeps recursive calls from stopping early
+    let depth: usize = program_state.call_stack.len();
+    db_step(lineinfo, program_state, os, debugger_state)?;
+    while program_state.should_continue_execution && program_state.call_stack.len() > depth {
+        db_step(lineinfo, program_state, os, debugger_state)?;
+    }
+
+    print_location(lineinfo, program_state);
+    Ok(())
+}
+
+/// Runs until the current function returns through jr $ra. Invoked by "finish" in the CLI.
+///
+/// # Errors
+///
+/// Fails outside of any function, or with why execution stopped early.
+pub fn finish(
+    lineinfo: &Vec<LineInfo>,
+    program_state: &mut ProgramState,
+    os: &mut OperatingSystem,
+    debugger_state: &mut DebuggerState,
+) -> Result<(), StopReason> {
+    let depth: usize = program_state.call_stack.len();
+    if depth == 0 {
+        return Err(StopReason::Error(
+            "finish isn't meaningful in the outermost frame.".to_string(),
+        ));
+    }
+
+    while program_state.should_continue_execution {
+        db_step(lineinfo, program_state, os, debugger_state)?;
+        if program_state.call_stack.len() < depth {
+            print_location(lineinfo, program_state);
+            println!(
+                "Returned with $v0 = {} (0x{:08x}), $v1 = {} (0x{:08x})",
+                program_state.cpu.general_purpose_registers[Register::V0 as usize].cast_signed(),
+                program_state.cpu.general_purpose_registers[Register::V0 as usize],
+                program_state.cpu.general_purpose_registers[Register::V1 as usize].cast_signed(),
+                program_state.cpu.general_purpose_registers[Register::V1 as usize],
+            );
+            return Ok(());
+        }
+    }
+    Ok(())
+}
+
+/// Runs until a location is reached in the current function, or until the current function returns. Invoked by "until" in the CLI.
+/// With no location, runs until execution gets past the current instruction without going deeper - handy for finishing off a loop.
+///
+/// # Errors
+///
+/// Fails if the location can't be found, or with why execution stopped early.
+pub fn run_until(
+    lineinfo: &Vec<LineInfo>,
+    program_state: &mut ProgramState,
+    os: &mut OperatingSystem,
+    debugger_state: &mut DebuggerState,
+    db_args: &[String],
+) -> Result<(), StopReason> {
+    if db_args.len() > 2 {
+        return Err(StopReason::Error(format!(
+            "until expects 0 or 1 arguments, received {}",
+            db_args.len() - 1
+        )));
+    }
+
+    let target: Option<u32> = match db_args.get(1) {
+        Some(location) => Some(debugger_state.resolve_breakpoint_target(lineinfo, location)?),
+        None => None,
+    };
+    let start_address: u32 = match upcoming_instruction(program_state) {
+        Some((address, _)) => address,
+        None => program_state.cpu.pc,
+    };
+
+    let depth: usize = program_state.call_stack.len();
+    while program_state.should_continue_execution {
+        db_step(lineinfo, program_state, os, debugger_state)?;
+
+        let arrived: bool = match target {
+            Some(address) => program_state.cpu.pc == address,
+            None => program_state.cpu.pc > start_address,
+        };
+        let current_depth: usize = program_state.call_stack.len();
+        if current_depth < depth || (current_depth == depth && arrived) {
+            break;
+        }
+    }
+
+    print_location(lineinfo, program_state);
+    Ok(())
+}
+
+/// Prints the call stack, innermost frame first. Invoked by "bt" in the CLI.
+///
+/// # Errors
+///
+/// Never fails; the Result matches the other commands.
+pub fn backtrace(
+    lineinfo: &Vec<LineInfo>,
+    program_state: &ProgramState,
+    debugger_state: &DebuggerState,
+) -> Result<(), String> {
+    // sitting on a breakpoint leaves the pc one past it
+    let pc: u32 = match upcoming_instruction(program_state) {
+        Some((address, _)) => address,
+        None => program_state.cpu.pc,
+    };
+
+    for frame in format_backtrace(
+        lineinfo,
+        &debugger_state.symbols,
+        &program_state.call_stack,
+        pc,
+    ) {
+        println!("{frame}");
+    }
+    Ok(())
+}
+
 /// Lists the text surrounding a given line number. Invoked by "l" in the CLI.
 pub fn list_text(
     lineinfo: &Vec<LineInfo>,
@@ -77,8 +230,10 @@ pub fn list_text(
 }
 
 /// Prints the value at a given register. Invoked by "p" in the CLI.
+/// p/FMT shows registers in FMT (x, d, u, c, or t) instead of the display format.
 pub fn print_register(
     program_state: &mut ProgramState,
+    debugger_state: &DebuggerState,
     db_args: &Vec<String>,
 ) -> Result<(), String> {
     if db_args.len() < 2 {
@@ -88,35 +243,41 @@ pub fn print_register(
         ));
     }
 
-    // if the first character of the argument isn't a dollar sign,
-    // assume the user isn't referring to a register
-    // (eventually expand the functionality of this method to also be able to print the value of memory addresses
-    // and stuff like that)
+    let format: DisplayFormat = match db_args[0].strip_prefix("p/") {
+        Some(letter) => DisplayFormat::parse(letter)?,
+        None => debugger_state.display_format,
+    };
 
-    for arg in db_args[1..].to_vec() {
-        if arg.chars().nth(0) == Some('$') {
-            let register = arg;
-            if register == "$pc" {
-                println!(
-                    "Value in register {} is {:08x}",
-                    register, program_state.cpu.pc
-                );
-                continue;
-            }
+    // plain registers and #addresses are printed one by one; anything else is one expression
+    let is_plain = |arg: &String| {
+        arg.starts_with('#')
+            || matches!(
+                Expression::parse(arg, &[]),
+                Ok(Expression::Register(_) | Expression::Pc | Expression::Hi | Expression::Lo)
+            )
+    };
+    if !db_args[1..].iter().all(is_plain) {
+        let text: String = db_args[1..].join(" ");
+        let value: u32 =
+            Expression::parse(&text, &debugger_state.symbols)?.evaluate(program_state)?;
+        println!("{} = {}", text, format.format(value));
+        return Ok(());
+    }
 
-            match REGISTERS.iter().position(|&x| x == register) {
-                Some(found_register) => {
-                    // should we continue printing the actual number of the register?
-                    // this will all eventually be a table or something anyways :^)
-                    println!(
-                        "Value in register {} is {:08x}",
-                        found_register, program_state.cpu.general_purpose_registers[found_register]
-                    );
-                }
-                None => {
-                    return Err(format!("{} is not a valid register.", db_args[1]));
-                }
-            }
+    for arg in db_args[1..].to_vec() {
+        if arg.starts_with('$') {
+            let register: Expression = Expression::parse(&arg, &[])?;
+            // should we continue printing the actual number of the register?
+            // this will all eventually be a table or something anyways :^)
+            let name: String = match register {
+                Expression::Register(idx) => idx.to_string(),
+                _ => arg.clone(),
+            };
+            println!(
+                "Value in register {} is {}",
+                name,
+                format.format(register.evaluate(program_state)?)
+            );
         } else if arg.chars().nth(0) == Some('#') {
             // there's a method in the assembler to convert a word into a line
             // for future reference
@@ -134,10 +295,6 @@ pub fn print_register(
             };
 
             println!("Value in address {:08x} is {:08b}", address, value);
-        } else {
-            return Err(format!(
-                "Congrats! You discovered an unimplemented feature... or you forgot the dollar sign on your register, or the hashtag in your memory address."
-            ));
         }
     }
     Ok(())
@@ -182,6 +339,332 @@ pub fn modify_register(
     Ok(())
 }
 
+/// How the examine command displays each unit of memory.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ExamineFormat {
+    Hex,
+    Decimal,
+    Unsigned,
+    Char,
+    String,
+}
+
+/// Reads `size` bytes starting at address as one big-endian value.
+fn read_memory_value(program_state: &ProgramState, address: u32, size: u32) -> Result<u32, String> {
+    let mut value: u32 = 0;
+    for i in 0..size {
+        let byte_address: u32 = address.wrapping_add(i);
+        match program_state.memory.read_byte(byte_address) {
+            Ok(byte) => value = (value << 8) | u32::from(byte),
+            Err(e) => {
+                return Err(format!(
+                    "Cannot access memory at address 0x{byte_address:08x}: {e}"
+                ))
+            }
+        }
+    }
+    Ok(value)
+}
+
+/// Shows a byte the way it would be written in a MIPS string literal.
+pub(crate) fn escape_byte(byte: u8) -> String {
+    match byte {
+        b'\n' => "\\n".to_string(),
+        b'\t' => "\\t".to_string(),
+        b'\r' => "\\r".to_string(),
+        b'\0' => "\\0".to_string(),
+        b'\\' => "\\\\".to_string(),
+        b'"' => "\\\"".to_string(),
+        b'\'' => "\\'".to_string(),
+        0x20..=0x7e => (byte as char).to_string(),
+        _ => format!("\\x{byte:02x}"),
+    }
+}
+
+/// Examines memory. Invoked by "x" in the CLI.
+/// Usage: x/<count><format><size> <addr|label|$reg+off>, where format is x, d, u, c, or s and size is b, h, or w.
+///
+/// # Errors
+///
+/// Fails if the arguments don't parse or the memory can't be read.
+pub fn examine_memory(
+    program_state: &ProgramState,
+    debugger_state: &DebuggerState,
+    db_args: &[String],
+) -> Result<(), String> {
+    if db_args.len() < 2 {
+        return Err("x expects an address to examine.".to_string());
+    }
+
+    // pick apart the /<count><format><size> suffix
+    let spec: &str = db_args[0].strip_prefix("x").unwrap_or("");
+    let spec: &str = spec.strip_prefix('/').unwrap_or(spec);
+    let count_digits: usize = spec.chars().take_while(char::is_ascii_digit).count();
+    let count: u32 = match &spec[..count_digits] {
+        "" => 1,
+        digits => match digits.parse() {
+            Ok(0) | Err(_) => return Err(format!("{digits} is not a valid count.")),
+            Ok(count) => count,
+        },
+    };
+
+    let mut format: ExamineFormat = ExamineFormat::Hex;
+    let mut size: u32 = 4;
+    for letter in spec[count_digits..].chars() {
+        match letter {
+            'x' => format = ExamineFormat::Hex,
+            'd' => format = ExamineFormat::Decimal,
+            'u' => format = ExamineFormat::Unsigned,
+            'c' => format = ExamineFormat::Char,
+            's' => format = ExamineFormat::String,
+            'b' => size = 1,
+            'h' => size = 2,
+            'w' => size = 4,
+            _ => {
+                return Err(format!(
+                    "{letter} is not a valid format (x, d, u, c, s) or size (b, h, w)."
+                ))
+            }
+        }
+    }
+
+    // the address may be an expression with spaces in it
+    let mut address: u32 =
+        debugger_state.resolve_location(&db_args[1..].join(" "), program_state)?;
+    let label_for = |address: u32| match debugger_state.describe_address(address) {
+        Some(label) => format!(" {label}"),
+        None => String::new(),
+    };
+
+    // strings are printed one per line, up to the null terminator
+    if format == ExamineFormat::String {
+        for _ in 0..count {
+            let start: u32 = address;
+            let mut contents: String = String::new();
+            loop {
+                let [.., byte] = read_memory_value(program_state, address, 1)?.to_be_bytes();
+                address = address.wrapping_add(1);
+                if byte == 0 {
+                    break;
+                }
+                contents.push_str(&escape_byte(byte));
+            }
+            println!("0x{:08x}{}:  \"{}\"", start, label_for(start), contents);
+        }
+        return Ok(());
+    }
+
+    // characters only make sense a byte at a time
+    if format == ExamineFormat::Char {
+        size = 1;
+    }
+
+    let per_line: u32 = if size == 4 { 4 } else { 8 };
+    let mut line: Vec<String> = Vec::new();
+    let mut line_start: u32 = address;
+
+    for i in 0..count {
+        let value: u32 = read_memory_value(program_state, address, size)?;
+        let [_, _, high, low] = value.to_be_bytes();
+        line.push(match format {
+            ExamineFormat::Hex => format!("0x{:0width$x}", value, width = (size * 2) as usize),
+            ExamineFormat::Decimal => match size {
+                1 => low.cast_signed().to_string(),
+                2 => i16::from_be_bytes([high, low]).to_string(),
+                _ => value.cast_signed().to_string(),
+            },
+            ExamineFormat::Unsigned => value.to_string(),
+            ExamineFormat::Char => format!("{} '{}'", value, escape_byte(low)),
+            ExamineFormat::String => unreachable!(),
+        });
+        address = address.wrapping_add(size);
+
+        if (i + 1) % per_line == 0 || i + 1 == count {
+            println!(
+                "0x{:08x}{}:  {}",
+                line_start,
+                label_for(line_start),
+                line.join("  ")
+            );
+            line.clear();
+            line_start = address;
+        }
+    }
+
+    Ok(())
+}
+
+/// Handles the "set" family of commands. Invoked by "set" in the CLI.
+///
+/// # Errors
+///
+/// Fails if there's nothing to set by that name, or the setting can't be changed.
+pub fn set_command(
+    program_state: &mut ProgramState,
+    debugger_state: &mut DebuggerState,
+    db_args: &[String],
+) -> Result<(), String> {
+    match db_args
+        .get(1)
+        .map(|arg| arg.split('/').next().unwrap_or(""))
+    {
+        Some("mem") => set_memory(program_state, debugger_state, db_args),
+        Some("format") => set_display_format(debugger_state, db_args),
+        Some(other) => Err(format!(
+            "{other} is not something set knows how to change (try set mem or set format)."
+        )),
+        None => Err("set expects something to change (try set mem or set format).".to_string()),
+    }
+}
+
+/// Changes how p and pa show registers. Invoked by "set format" in the CLI.
+/// Usage: set format <hex|signed|unsigned|char|binary>
+fn set_display_format(
+    debugger_state: &mut DebuggerState,
+    db_args: &[String],
+) -> Result<(), String> {
+    if db_args.len() != 3 {
+        return Err(format!(
+            "set format expects 1 argument, received {}",
+            db_args.len() - 2
+        ));
+    }
+
+    debugger_state.display_format = DisplayFormat::parse(&db_args[2])?;
+    println!(
+        "Registers will be displayed in {}.",
+        debugger_state.display_format.as_str()
+    );
+    Ok(())
+}
+
+/// Handles the "info" family of commands. Invoked by "info" in the CLI.
+///
+/// # Errors
+///
+/// Fails if there's nothing to show by that name.
+pub fn info_command(program_state: &ProgramState, db_args: &[String]) -> Result<(), String> {
+    match db_args.get(1).map(String::as_str) {
+        Some("cp0") => {
+            print_cp0(program_state);
+            Ok(())
+        }
+        Some(other) => Err(format!(
+            "{other} is not something info knows about (try info cp0)."
+        )),
+        None => Err("info expects something to show (try info cp0).".to_string()),
+    }
+}
+
+/// Prints each Coprocessor 0 register NAME uses, broken down into its fields. Invoked by "info cp0" in the CLI.
+fn print_cp0(program_state: &ProgramState) {
+    for info in CP0_REGISTER_INFO {
+        let value: u32 = program_state.cp0.registers[info.register()];
+        println!("{:?} ({}): 0x{:08x}", info.name(), info.register(), value);
+
+        for field in info.fields() {
+            let field_value: u32 = field.extract(value);
+            match field.describe {
+                Some(describe) => println!(
+                    "    {}: {} ({})",
+                    field.name,
+                    field_value,
+                    describe(field_value)
+                ),
+                None => println!("    {}: {}", field.name, field_value),
+            }
+        }
+    }
+}
+
+/// Writes values into memory. Invoked by "set mem" in the CLI.
+/// Usage: set mem[/b|/h|/w] <addr|label|$reg+off> <value> [value...]
+/// Consecutive values are written to consecutive bytes, halfs, or words.
+fn set_memory(
+    program_state: &mut ProgramState,
+    debugger_state: &DebuggerState,
+    db_args: &[String],
+) -> Result<(), String> {
+    if db_args.len() < 4 {
+        return Err(format!(
+            "set mem expects at least 2 arguments, received {}",
+            db_args.len() - 2
+        ));
+    }
+
+    let size: u32 = match db_args[1].strip_prefix("mem").unwrap_or("") {
+        "" | "/w" => 4,
+        "/h" => 2,
+        "/b" => 1,
+        other => return Err(format!("{other} is not a valid size (/b, /h, /w).")),
+    };
+
+    let address: u32 = debugger_state.resolve_location(&db_args[2], program_state)?;
+
+    // parse every value before touching memory so a typo doesn't leave a partial write behind
+    let max_value: i64 = (1i64 << (size * 8)) - 1;
+    let min_value: i64 = -(1i64 << (size * 8 - 1));
+    let mut values: Vec<u32> = Vec::new();
+    for arg in &db_args[3..] {
+        let parsed: Option<i64> =
+            if let Some(hex) = arg.strip_prefix("0x").or_else(|| arg.strip_prefix("0X")) {
+                i64::from_str_radix(hex, 16).ok()
+            } else if arg.len() == 3 && arg.starts_with('\'') && arg.ends_with('\'') {
+                Some(i64::from(arg.as_bytes()[1]))
+            } else {
+                arg.parse::<i64>().ok()
+            };
+
+        match parsed {
+            Some(value) if min_value <= value && value <= max_value => {
+                // the low word, so negative values are written in two's complement
+                let [.., a, b, c, d] = value.to_be_bytes();
+                values.push(u32::from_be_bytes([a, b, c, d]));
+            }
+            Some(_) => {
+                return Err(format!(
+                    "{} doesn't fit in {} byte{}.",
+                    arg,
+                    size,
+                    if size == 1 { "" } else { "s" }
+                ))
+            }
+            None => return Err(format!("{arg} is not a valid value.")),
+        }
+    }
+
+    // make sure the whole range is mapped before writing any of it
+    let total: u32 = values.iter().map(|_| size).sum();
+    for i in 0..total {
+        if let Err(e) = program_state.memory.read_byte(address.wrapping_add(i)) {
+            return Err(format!(
+                "Cannot access memory at address 0x{:08x}: {e}",
+                address.wrapping_add(i)
+            ));
+        }
+    }
+
+    let mut byte_address: u32 = address;
+    for value in &values {
+        // most significant byte first - MIPS is big-endian
+        for &byte in &value.to_be_bytes()[(4 - size) as usize..] {
+            if let Err(e) = program_state.memory.set_byte(byte_address, byte) {
+                return Err(format!("{e}"));
+            }
+            byte_address = byte_address.wrapping_add(1);
+        }
+    }
+
+    println!(
+        "Successfully wrote {} byte{} starting at 0x{:08x}.",
+        total,
+        if total == 1 { "" } else { "s" },
+        address
+    );
+    Ok(())
+}
+
+#[allow(clippy::too_many_lines)] // one line of code per line of help
 pub fn help_menu(db_args: Vec<String>) -> Result<(), String> {
     if db_args.len() == 1 {
         println!("help - Display this menu.");
@@ -189,12 +672,29 @@ pub fn help_menu(db_args: Vec<String>) -> Result<(), String> {
         println!("r - Begin execution of program.");
         println!("c - Continue program execution until the next breakpoint.");
         println!("s - Execute only the next instruction.");
+        println!("n - Execute the next instruction, running any function it calls to completion.");
+        println!("finish - Continue until the current function returns.");
+        println!("until [LOC] - Continue until LOC (or, with no LOC, past the current instruction) in the current function.");
+        println!("bt - Print the chain of calls that led to the current instruction.");
         println!("l - Print the entire program. (this functionality will be much improved later)");
         println!("p - Print the value of provided registers and memory addresses at the current place in program execution (please include the dollar sign).");
-        println!("pa - Print value of ALL registers at once.");
+        println!("p [EXPR] - Evaluate an expression, like p *($sp+8) or p nums[3]. (help p for the full syntax)");
+        println!("p/[FMT] - Print registers in FMT (x, d, u, c, or t) just this once.");
+        println!("pa - Print value of ALL registers at once, highlighting any that changed since the last stop.");
+        println!("info cp0 - Print the Coprocessor 0 registers with their fields decoded.");
+        println!("x/[N][FMT][SIZE] [ADDR] - Examine N units of memory at ADDR (an address, label, or any expression like $sp+8).");
+        println!("set mem[/SIZE] [ADDR] [VALUE...] - Write bytes (/b), halfs (/h), or words (/w, the default) into memory at ADDR.");
+        println!("set format [FMT] - Show registers as hex, signed, unsigned, char, or binary.");
         println!("pb - Print all breakpoints.");
-        println!("b [N] - Insert a breakpoint at line number N.");
+        println!("b [LOC] [if COND] - Insert a breakpoint at LOC (a line number, file:line, label, or *address), optionally only stopping when COND holds.");
         println!("del [N] - Delete breakpoint number N.");
+        println!("enable [N...] - Enable breakpoints (all of them if none are given).");
+        println!("disable [N...] - Disable breakpoints without deleting them (all of them if none are given).");
+        println!("ignore [N] [COUNT] - Pass over the next COUNT hits of breakpoint N.");
+        println!("condition [N] [COND] - Change the condition on breakpoint N, or remove it if COND is omitted.");
+        println!("watch [ADDR] [r|w|rw] [SIZE] - Stop when memory at ADDR (an address or label) is read and/or written.");
+        println!("pw - Print all watchpoints.");
+        println!("delw [N] - Delete watchpoint number N.");
         println!("q - Exit (quit) debugger.");
     } else if db_args.len() == 2 {
         match &db_args[1] as &str {
@@ -207,6 +707,24 @@ pub fn help_menu(db_args: Vec<String>) -> Result<(), String> {
             "s" => {
                 println!("Execute only the next instruction.");
             }
+            "n" => {
+                println!("Execute the next instruction. If it's a call (jal or jalr), keep going until the call returns, so the whole call counts as one step.");
+                println!("Breakpoints and watchpoints inside the call still stop execution.");
+            }
+            "finish" => {
+                println!("Continue until the current function returns through jr $ra, then show the return values in $v0 and $v1.");
+            }
+            "until" => {
+                println!("Continue until LOC is reached without descending into calls, or until the current function returns.");
+                println!(
+                    "LOC takes the same forms as b: a line number, file:line, label, or *address."
+                );
+                println!("With no LOC, continue until execution gets past the current instruction. Use this at the bottom of a loop to finish the loop.");
+            }
+            "bt" => {
+                println!("Print a backtrace: the current instruction, then each jal/jalr that is still waiting on its function to return, innermost first.");
+                println!("Each frame is labeled with the function it's in. Calls are tracked as they happen, so frames only appear for calls made while the program ran.");
+            }
             "l" => {
                 println!("When provided no arguments: print the first ten lines of the program. Then, print the next 10, and so forth.");
                 println!("When provided a line number (positive integer): print 9 lines around the given line number.");
@@ -215,19 +733,78 @@ pub fn help_menu(db_args: Vec<String>) -> Result<(), String> {
             "p" => {
                 println!("Print the value stored in the provided registers ($) and/or memory addresses (#).");
                 println!("Please provide memory addresses in hexadecimal.");
+                println!("p/FMT shows registers in FMT instead of the display format: x (hex), d (signed), u (unsigned), c (character), or t (binary).");
+                println!("Example: p/d $t0 $hi");
+                println!("Anything else is evaluated as an expression, which may use:");
+                println!("  registers ($t0, $8, $pc, $hi, $lo), numbers (12, 0x1c, 'A'), and labels, which stand for their addresses");
+                println!("  arithmetic and bitwise operators (+ - * / % << >> & | ^ ~), comparisons (== != < <= > >=), and logic (&& || !)");
+                println!("  *ADDR for the word at ADDR, word[ADDR], half[ADDR], or byte[ADDR] for other sizes, and LABEL[N] for the Nth word of an array");
+                println!("  casts like (half*) to change what * and [N] read");
+                println!(
+                    "Examples: p *($sp+8)    p word[array+4*$t0]    p *(word*)($fp-4)    p nums[3]"
+                );
             }
             "pa" => {
-                println!("Print each register and the value stored therein.");
+                println!("Print each register and the value stored therein, in the display format (see set format).");
+                println!("Registers that changed since execution last stopped are highlighted, or marked with * when output isn't a terminal.");
+            }
+            "info" => {
+                println!("info cp0 - Print the Coprocessor 0 registers (Status, Cause, EPC, EBase), with each of their fields decoded by name.");
+            }
+            "x" => {
+                println!("Examine memory, starting at an address (0x10010000), a label (fibs), or any expression (see help p), like $sp+8 or fibs+4*$t0.");
+                println!("N is the number of units to show (default 1).");
+                println!("FMT is x (hex, the default), d (signed decimal), u (unsigned decimal), c (character), or s (null-terminated string).");
+                println!("SIZE is b (byte), h (half), or w (word, the default). Characters are always shown a byte at a time.");
+                println!("Example: x/12dw fibs");
+            }
+            "set" => {
+                println!("set mem[/SIZE] [ADDR] [VALUE...] - Write values into memory starting at ADDR, which accepts the same forms as x.");
+                println!("SIZE is b (byte), h (half), or w (word, the default). Multiple values are written one after another.");
+                println!("Values may be decimal (-5), hexadecimal (0xff), or a character ('A').");
+                println!("set format [FMT] - Change how p and pa show registers: hex (the default), signed, unsigned, char, or binary.");
             }
             "pb" => {
                 println!("Print all user-created breakpoints. (This does not include break instructions that already existed in the code.)");
+                println!("Each breakpoint is listed with whether it's enabled, its address and line, how many times it has been hit, and its condition and ignore count.");
             }
             "b" => {
-                println!("Insert a breakpoint at the line number provided. Note that this line will be executed before the break occurs.");
+                println!("Insert a breakpoint. Execution stops before the instruction at the breakpoint runs.");
+                println!("The location may be a line number (20), a file and line number (fib.asm:20), a label (compute), or an address (*0x400010).");
+                println!(
+                    "Add \"if COND\" to only stop when COND holds, e.g. b compute if $t1 == 3"
+                );
+                println!("Conditions are expressions (see help p), and hold whenever they're nonzero: $t0 == 5, *counter >= 10 && $a0 != 0, nums[$t1] & 1.");
+            }
+            "enable" => {
+                println!("Re-enable the breakpoints with the associated numbers, or every breakpoint if none are given.");
+            }
+            "disable" => {
+                println!("Disable the breakpoints with the associated numbers, or every breakpoint if none are given. Disabled breakpoints stay in pb and can be enabled again.");
+            }
+            "ignore" => {
+                println!("Pass over the next COUNT hits of the breakpoint with the associated number. Ignored hits still count toward its hit counter.");
+            }
+            "condition" => {
+                println!("Give the breakpoint with the associated number a new condition (same syntax as b ... if COND), or make it unconditional if no condition is given.");
             }
             "del" => {
                 println!("Delete the breakpoint with the associated number. (run pb to find out which number the desired breakpoint has)");
             }
+            "watch" => {
+                println!("Stop execution whenever the watched memory is accessed, then report the old and new value along with the responsible instruction.");
+                println!("ADDR may be a hexadecimal address (0x10010000), a decimal address, or a label (fibs).");
+                println!(
+                    "The optional access type is r (reads), w (writes, the default), or rw (both)."
+                );
+                println!("The optional size is in bytes. It defaults to the size of the label if one was given, otherwise 4 (one word).");
+            }
+            "pw" => {
+                println!("Print all watchpoints along with their address, size, and access type.");
+            }
+            "delw" => {
+                println!("Delete the watchpoint with the associated number. (run pw to find out which number the desired watchpoint has)");
+            }
             "help" => {
                 println!("you're funny");
             }
@@ -240,4 +817,4 @@ pub fn help_menu(db_args: Vec<String>) -> Result<(), String> {
         };
     }
     Ok(())
-}
\ No newline at end of file
+}
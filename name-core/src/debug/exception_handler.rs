@@ -17,12 +17,25 @@ pub fn handle_exception(
     lineinfo: &Vec<LineInfo>,
     debugger_state: &mut DebuggerState,
 ) {
+    if let Err(e) = try_handle_exception(program_state, os, lineinfo, debugger_state) {
+        panic!("{e}");
+    }
+}
+
+/// Handles an exception like handle_exception, but gives back the error an exception the program can't recover from
+/// would have crashed the emulator with.
+pub fn try_handle_exception(
+    program_state: &mut ProgramState,
+    os: &mut OperatingSystem,
+    lineinfo: &Vec<LineInfo>,
+    debugger_state: &mut DebuggerState,
+) -> Result<(), String> {
     // In order to invoke this function, certain values (like exception_level == 1) are already assumed.
 
     // Attempt to recognize the exception that occurred
     let exception_type = match ExceptionType::try_from(program_state.cp0.get_exc_code()) {
         Ok(exc_type) => exc_type,
-        Err(e) => panic!("{e}"),
+        Err(e) => return Err(e.to_string()),
     };
 
     // Retrieve necessary values
@@ -33,32 +46,57 @@ pub fn handle_exception(
     // Match on exception type to either error out or handle appropriately
     match exception_type {
         ExceptionType::AddressExceptionLoad => {
+            // a thread returning from its entry jumps somewhere it can't fetch from, on purpose
+            match os.handle_thread_return(program_state) {
+                Some(Ok(())) => {
+                    program_state.recover_from_exception();
+                    return Ok(());
+                }
+                Some(Err(e)) => {
+                    return Err(generate_err(
+                        lineinfo,
+                        epc,
+                        &e,
+                        &program_state.call_stack,
+                        &debugger_state.symbols,
+                    ))
+                }
+                None => {}
+            }
             // TODO: Detect difference between instructions like bad lw and bad/misaligned pc
-            panic!("{}", generate_err(lineinfo, epc, "Illegal address provided for load/fetch; misaligned, unreachable, or unowned address."));
+            return Err(generate_err(lineinfo, epc, "Illegal address provided for load/fetch; misaligned, unreachable, or unowned address.", &program_state.call_stack, &debugger_state.symbols));
         }
         ExceptionType::AddressExceptionStore => {
-            panic!("{}", generate_err(lineinfo, epc, "Illegal address provided on store operation; misaligned, unreachable, or unowned address."));
+            return Err(generate_err(lineinfo, epc, "Illegal address provided on store operation; misaligned, unreachable, or unowned address.", &program_state.call_stack, &debugger_state.symbols));
         }
         ExceptionType::BusFetch => {
-            panic!("{}", generate_err(
+            return Err(generate_err(
                 lineinfo,
                 epc,
                 "Failed to interpret instruction as word; Unrecognized bytes in ELF .text space.",
+                &program_state.call_stack,
+                &debugger_state.symbols,
             ));
         }
         ExceptionType::BusLoadStore => {
-            panic!(
-                "{}",
-                generate_err(lineinfo, epc, "Failed to store data in given address.")
-            );
+            return Err(generate_err(
+                lineinfo,
+                epc,
+                "Failed to store data in given address.",
+                &program_state.call_stack,
+                &debugger_state.symbols,
+            ));
         }
         ExceptionType::Syscall => {
             // Invoke the syscall handler on program state
             if let Err(e) = os.handle_syscall(program_state) {
-                panic!(
-                    "{}",
-                    generate_err(lineinfo, epc, &format!("Failed to handle a syscall: {e}"))
-                )
+                return Err(generate_err(
+                    lineinfo,
+                    epc,
+                    &format!("Failed to handle a syscall: {e}"),
+                    &program_state.call_stack,
+                    &debugger_state.symbols,
+                ));
             }
         }
         ExceptionType::Breakpoint => {
@@ -67,52 +105,53 @@ pub fn handle_exception(
                 // debugger is running.
                 os.handle_breakpoint(program_state, lineinfo, debugger_state);
             } else {
-                panic!("Break not recognized outside of debug mode. To run in debug mode, pass -d as a command line argument.");
+                return Err("Break not recognized outside of debug mode. To run in debug mode, pass -d as a command line argument.".to_string());
             }
         }
         ExceptionType::ReservedInstruction => {
-            panic!(
-                "{}",
-                generate_err(
-                    lineinfo,
-                    epc,
-                    "Unrecognized bytes in ELF at program counter.",
-                )
-            );
+            return Err(generate_err(
+                lineinfo,
+                epc,
+                "Unrecognized bytes in ELF at program counter.",
+                &program_state.call_stack,
+                &debugger_state.symbols,
+            ));
         }
         ExceptionType::CoprocessorUnusable => {
-            panic!(
-                "{}",
-                generate_err(
-                    lineinfo,
-                    epc,
-                    "Attempted to access a coprocessor without correct operating mode.",
-                )
-            );
+            return Err(generate_err(
+                lineinfo,
+                epc,
+                "Attempted to access a coprocessor without correct operating mode.",
+                &program_state.call_stack,
+                &debugger_state.symbols,
+            ));
         }
         ExceptionType::ArithmeticOverflow => {
             // TODO: Differentiate between these
-            panic!(
-                "{}",
-                generate_err(
-                    lineinfo,
-                    epc,
-                    "Arithmetic overflow, underflow, or divide by zero detected on instruction.",
-                )
-            );
+            return Err(generate_err(
+                lineinfo,
+                epc,
+                "Arithmetic overflow, underflow, or divide by zero detected on instruction.",
+                &program_state.call_stack,
+                &debugger_state.symbols,
+            ));
         }
         ExceptionType::Trap => {
-            todo!("Not sure how we want trap to work yet.");
+            return Err("not yet implemented: Not sure how we want trap to work yet.".to_string());
         }
         ExceptionType::FloatingPoint => {
             // Will be more useful once cp1 is implemented
-            panic!(
-                "{}",
-                generate_err(lineinfo, epc, "Floating point exception occurred.")
-            );
+            return Err(generate_err(
+                lineinfo,
+                epc,
+                "Floating point exception occurred.",
+                &program_state.call_stack,
+                &debugger_state.symbols,
+            ));
         }
     }
 
     // If the exception did not cause a crash, reset program state to reflect that execution will continue as normal
     program_state.recover_from_exception();
+    Ok(())
 }
@@ -0,0 +1,555 @@
+use crate::constants::REGISTERS;
+use crate::structs::{ProgramState, Symbol};
+
+/// Operators that take a single operand.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum UnaryOperator {
+    Negate,
+    BitwiseNot,
+    LogicalNot,
+}
+
+/// Operators that take two operands. Division, remainder, and comparisons treat values as signed.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BinaryOperator {
+    Multiply,
+    Divide,
+    Remainder,
+    Add,
+    Subtract,
+    ShiftLeft,
+    ShiftRight,
+    Less,
+    LessEqual,
+    Greater,
+    GreaterEqual,
+    Equal,
+    NotEqual,
+    BitwiseAnd,
+    BitwiseXor,
+    BitwiseOr,
+    LogicalAnd,
+    LogicalOr,
+}
+
+/// An expression the debugger can evaluate, like `$t0 + 4`, `*($sp+8)`, `word[array+4*$t0]`, or `nums[3]`.
+/// Labels stand for their addresses and are resolved when the expression is parsed.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expression {
+    Literal(u32),
+    Register(usize),
+    Pc,
+    Hi,
+    Lo,
+    /// The size-byte value stored at the address the inner expression evaluates to.
+    Memory {
+        size: u32,
+        address: Box<Expression>,
+    },
+    /// A pointer cast like `(half*)`: the value is unchanged, but dereferencing or indexing it reads size bytes.
+    Cast {
+        size: u32,
+        inner: Box<Expression>,
+    },
+    Unary(UnaryOperator, Box<Expression>),
+    Binary(BinaryOperator, Box<Expression>, Box<Expression>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Number(u32),
+    Register(String),
+    Identifier(String),
+    Operator(&'static str),
+}
+
+// Two-character operators come first so that "<=" isn't read as "<".
+const OPERATORS: [&str; 24] = [
+    "<<", ">>", "<=", ">=", "==", "!=", "&&", "||", "+", "-", "*", "/", "%", "&", "|", "^", "~",
+    "!", "<", ">", "(", ")", "[", "]",
+];
+
+/// The sizes that can be named in casts and in word[...]-style accesses.
+fn type_size(name: &str) -> Option<u32> {
+    match name {
+        "word" => Some(4),
+        "half" => Some(2),
+        "byte" => Some(1),
+        _ => None,
+    }
+}
+
+/// Binds each binary operator to its precedence, as in C: higher binds tighter.
+fn binary_operator(token: &Token) -> Option<(BinaryOperator, u8)> {
+    let operator: &str = match token {
+        Token::Operator(operator) => operator,
+        _ => return None,
+    };
+
+    Some(match operator {
+        "*" => (BinaryOperator::Multiply, 10),
+        "/" => (BinaryOperator::Divide, 10),
+        "%" => (BinaryOperator::Remainder, 10),
+        "+" => (BinaryOperator::Add, 9),
+        "-" => (BinaryOperator::Subtract, 9),
+        "<<" => (BinaryOperator::ShiftLeft, 8),
+        ">>" => (BinaryOperator::ShiftRight, 8),
+        "<" => (BinaryOperator::Less, 7),
+        "<=" => (BinaryOperator::LessEqual, 7),
+        ">" => (BinaryOperator::Greater, 7),
+        ">=" => (BinaryOperator::GreaterEqual, 7),
+        "==" => (BinaryOperator::Equal, 6),
+        "!=" => (BinaryOperator::NotEqual, 6),
+        "&" => (BinaryOperator::BitwiseAnd, 5),
+        "^" => (BinaryOperator::BitwiseXor, 4),
+        "|" => (BinaryOperator::BitwiseOr, 3),
+        "&&" => (BinaryOperator::LogicalAnd, 2),
+        "||" => (BinaryOperator::LogicalOr, 1),
+        _ => return None,
+    })
+}
+
+/// Parses decimal and 0x-prefixed hexadecimal numbers.
+fn parse_number(text: &str) -> Option<u32> {
+    match text.strip_prefix("0x").or_else(|| text.strip_prefix("0X")) {
+        Some(hex) => u32::from_str_radix(hex, 16).ok(),
+        None => text.parse::<u32>().ok(),
+    }
+}
+
+fn tokenize(text: &str) -> Result<Vec<Token>, String> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut tokens: Vec<Token> = Vec::new();
+    let mut i: usize = 0;
+
+    let is_word_char = |c: char| c.is_ascii_alphanumeric() || c == '_' || c == '.';
+
+    while i < chars.len() {
+        let c: char = chars[i];
+
+        if c.is_whitespace() {
+            i += 1;
+        } else if c.is_ascii_digit() {
+            let start: usize = i;
+            while i < chars.len() && chars[i].is_ascii_alphanumeric() {
+                i += 1;
+            }
+            let number: String = chars[start..i].iter().collect();
+            match parse_number(&number) {
+                Some(value) => tokens.push(Token::Number(value)),
+                None => return Err(format!("{number} is not a valid number.")),
+            }
+        } else if c == '$' || is_word_char(c) {
+            let start: usize = i;
+            i += 1;
+            while i < chars.len() && is_word_char(chars[i]) {
+                i += 1;
+            }
+            let word: String = chars[start..i].iter().collect();
+            tokens.push(match c {
+                '$' => Token::Register(word),
+                _ => Token::Identifier(word),
+            });
+        } else if c == '\'' {
+            // character literals, with the same escapes as MIPS strings
+            let (value, length): (char, usize) = match (chars.get(i + 1), chars.get(i + 2)) {
+                (Some('\\'), Some(escaped)) => (
+                    match escaped {
+                        'n' => '\n',
+                        't' => '\t',
+                        'r' => '\r',
+                        '0' => '\0',
+                        other => *other,
+                    },
+                    4,
+                ),
+                (Some(literal), _) => (*literal, 3),
+                (None, _) => return Err(format!("Unterminated character in {text}.")),
+            };
+            if chars.get(i + length - 1) != Some(&'\'') {
+                return Err(format!("Unterminated character in {text}."));
+            }
+            tokens.push(Token::Number(u32::from(value)));
+            i += length;
+        } else {
+            let rest: String = chars[i..].iter().collect();
+            match OPERATORS
+                .iter()
+                .find(|operator| rest.starts_with(**operator))
+            {
+                Some(operator) => {
+                    tokens.push(Token::Operator(operator));
+                    i += operator.len();
+                }
+                None => return Err(format!("Unexpected character {c} in {text}.")),
+            }
+        }
+    }
+
+    Ok(tokens)
+}
+
+struct Parser<'a> {
+    tokens: Vec<Token>,
+    position: usize,
+    symbols: &'a [Symbol],
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    fn peek_operator(&self, offset: usize, operator: &str) -> bool {
+        matches!(self.tokens.get(self.position + offset), Some(Token::Operator(candidate)) if *candidate == operator)
+    }
+
+    fn expect(&mut self, operator: &str) -> Result<(), String> {
+        if self.peek_operator(0, operator) {
+            self.position += 1;
+            Ok(())
+        } else {
+            Err(format!("Expected {operator}."))
+        }
+    }
+
+    /// The size of a type named at the given offset, if there is one.
+    fn type_at(&self, offset: usize) -> Option<u32> {
+        match self.tokens.get(self.position + offset) {
+            Some(Token::Identifier(name)) => type_size(name),
+            _ => None,
+        }
+    }
+
+    /// Parses operators binding at least as tightly as `min_precedence`.
+    fn parse_binary(&mut self, min_precedence: u8) -> Result<Expression, String> {
+        let mut lhs: Expression = self.parse_unary()?;
+
+        while let Some((operator, precedence)) = self.peek().and_then(binary_operator) {
+            if precedence < min_precedence {
+                break;
+            }
+            self.position += 1;
+            let rhs: Expression = self.parse_binary(precedence + 1)?;
+            lhs = Expression::Binary(operator, Box::new(lhs), Box::new(rhs));
+        }
+
+        Ok(lhs)
+    }
+
+    fn parse_unary(&mut self) -> Result<Expression, String> {
+        let unary = |operator: UnaryOperator, parser: &mut Self| -> Result<Expression, String> {
+            parser.position += 1;
+            Ok(Expression::Unary(operator, Box::new(parser.parse_unary()?)))
+        };
+
+        if self.peek_operator(0, "-") {
+            return unary(UnaryOperator::Negate, self);
+        }
+        if self.peek_operator(0, "~") {
+            return unary(UnaryOperator::BitwiseNot, self);
+        }
+        if self.peek_operator(0, "!") {
+            return unary(UnaryOperator::LogicalNot, self);
+        }
+
+        if self.peek_operator(0, "*") {
+            self.position += 1;
+            let address: Expression = self.parse_unary()?;
+            return Ok(Expression::Memory {
+                size: address.element_size(),
+                address: Box::new(address),
+            });
+        }
+
+        // (word*), (half*), or (byte*)
+        if self.peek_operator(0, "(") && self.peek_operator(2, "*") && self.peek_operator(3, ")") {
+            if let Some(size) = self.type_at(1) {
+                self.position += 4;
+                return Ok(Expression::Cast {
+                    size,
+                    inner: Box::new(self.parse_unary()?),
+                });
+            }
+        }
+
+        self.parse_postfix()
+    }
+
+    fn parse_postfix(&mut self) -> Result<Expression, String> {
+        let mut expression: Expression = match self.type_at(0) {
+            // word[...], half[...], or byte[...]
+            Some(size) if self.peek_operator(1, "[") => {
+                self.position += 2;
+                let address: Expression = self.parse_binary(0)?;
+                self.expect("]")?;
+                Expression::Memory {
+                    size,
+                    address: Box::new(address),
+                }
+            }
+            _ => self.parse_primary()?,
+        };
+
+        // indexing steps through memory an element at a time, like a C array
+        while self.peek_operator(0, "[") {
+            self.position += 1;
+            let index: Expression = self.parse_binary(0)?;
+            self.expect("]")?;
+
+            let size: u32 = expression.element_size();
+            expression = Expression::Memory {
+                size,
+                address: Box::new(Expression::Binary(
+                    BinaryOperator::Add,
+                    Box::new(expression),
+                    Box::new(Expression::Binary(
+                        BinaryOperator::Multiply,
+                        Box::new(index),
+                        Box::new(Expression::Literal(size)),
+                    )),
+                )),
+            };
+        }
+
+        Ok(expression)
+    }
+
+    fn parse_primary(&mut self) -> Result<Expression, String> {
+        let token: Token = match self.peek() {
+            Some(token) => token.clone(),
+            None => return Err("Expected a value at the end of the expression.".to_string()),
+        };
+        self.position += 1;
+
+        match token {
+            Token::Number(value) => Ok(Expression::Literal(value)),
+            Token::Register(name) => parse_register(&name),
+            Token::Identifier(name) => match self.symbols.iter().find(|sym| sym.identifier == name)
+            {
+                Some(sym) => Ok(Expression::Literal(sym.value)),
+                None => Err(format!(
+                    "{name} is not a register, number, or label in this program."
+                )),
+            },
+            Token::Operator("(") => {
+                let inner: Expression = self.parse_binary(0)?;
+                self.expect(")")?;
+                Ok(inner)
+            }
+            Token::Operator(operator) => Err(format!("Unexpected {operator}.")),
+        }
+    }
+}
+
+/// Both $t0 and $8 are accepted, along with $pc, $hi, and $lo.
+fn parse_register(name: &str) -> Result<Expression, String> {
+    match name {
+        "$pc" => return Ok(Expression::Pc),
+        "$hi" => return Ok(Expression::Hi),
+        "$lo" => return Ok(Expression::Lo),
+        _ => {}
+    }
+
+    if let Some(idx) = REGISTERS.iter().position(|&reg| reg == name) {
+        return Ok(Expression::Register(idx));
+    }
+
+    match name[1..].parse::<usize>() {
+        Ok(idx) if idx < REGISTERS.len() => Ok(Expression::Register(idx)),
+        _ => Err(format!("{name} is not a valid register.")),
+    }
+}
+
+impl Expression {
+    /// Parses an expression, resolving labels against the program's symbols.
+    ///
+    /// # Errors
+    ///
+    /// Fails on malformed input or a name that isn't a register or label.
+    pub fn parse(text: &str, symbols: &[Symbol]) -> Result<Expression, String> {
+        let mut parser: Parser = Parser {
+            tokens: tokenize(text)?,
+            position: 0,
+            symbols,
+        };
+
+        if parser.tokens.is_empty() {
+            return Err("Expected an expression.".to_string());
+        }
+
+        let expression: Expression = parser.parse_binary(0)?;
+        match parser.peek() {
+            None => Ok(expression),
+            Some(_) => Err(format!("Unexpected trailing input in {}.", text.trim())),
+        }
+    }
+
+    /// How many bytes dereferencing or indexing this expression reads: words, unless it was cast.
+    fn element_size(&self) -> u32 {
+        match self {
+            Expression::Cast { size, .. } => *size,
+            _ => 4,
+        }
+    }
+
+    /// Evaluates the expression against the current registers and memory.
+    ///
+    /// # Errors
+    ///
+    /// Fails on unreadable memory or division by zero.
+    pub fn evaluate(&self, program_state: &ProgramState) -> Result<u32, String> {
+        match self {
+            Expression::Literal(value) => Ok(*value),
+            Expression::Register(idx) => Ok(program_state.cpu.general_purpose_registers[*idx]),
+            Expression::Pc => Ok(program_state.cpu.pc),
+            Expression::Hi => Ok(program_state.cpu.hi),
+            Expression::Lo => Ok(program_state.cpu.lo),
+            Expression::Memory { size, address } => {
+                let address: u32 = address.evaluate(program_state)?;
+                let mut value: u32 = 0;
+                // most significant byte first - MIPS is big-endian
+                for i in 0..*size {
+                    match program_state.memory.read_byte(address.wrapping_add(i)) {
+                        Ok(byte) => value = (value << 8) | u32::from(byte),
+                        Err(e) => return Err(format!("Could not read 0x{address:08x}: {e}")),
+                    }
+                }
+                Ok(value)
+            }
+            Expression::Cast { inner, .. } => inner.evaluate(program_state),
+            Expression::Unary(operator, inner) => {
+                let value: u32 = inner.evaluate(program_state)?;
+                Ok(match operator {
+                    UnaryOperator::Negate => value.wrapping_neg(),
+                    UnaryOperator::BitwiseNot => !value,
+                    UnaryOperator::LogicalNot => u32::from(value == 0),
+                })
+            }
+            Expression::Binary(operator, lhs, rhs) => {
+                let lhs: u32 = lhs.evaluate(program_state)?;
+
+                // the right-hand side of && and || is only looked at when it matters
+                match operator {
+                    BinaryOperator::LogicalAnd if lhs == 0 => return Ok(0),
+                    BinaryOperator::LogicalOr if lhs != 0 => return Ok(1),
+                    _ => {}
+                }
+
+                let rhs: u32 = rhs.evaluate(program_state)?;
+                let (signed_lhs, signed_rhs) = (lhs.cast_signed(), rhs.cast_signed());
+
+                Ok(match operator {
+                    BinaryOperator::Multiply => lhs.wrapping_mul(rhs),
+                    BinaryOperator::Divide | BinaryOperator::Remainder if rhs == 0 => {
+                        return Err("Division by zero.".to_string())
+                    }
+                    BinaryOperator::Divide => signed_lhs.wrapping_div(signed_rhs).cast_unsigned(),
+                    BinaryOperator::Remainder => {
+                        signed_lhs.wrapping_rem(signed_rhs).cast_unsigned()
+                    }
+                    BinaryOperator::Add => lhs.wrapping_add(rhs),
+                    BinaryOperator::Subtract => lhs.wrapping_sub(rhs),
+                    BinaryOperator::ShiftLeft => lhs.checked_shl(rhs).unwrap_or(0),
+                    BinaryOperator::ShiftRight => lhs.checked_shr(rhs).unwrap_or(0),
+                    BinaryOperator::Less => u32::from(signed_lhs < signed_rhs),
+                    BinaryOperator::LessEqual => u32::from(signed_lhs <= signed_rhs),
+                    BinaryOperator::Greater => u32::from(signed_lhs > signed_rhs),
+                    BinaryOperator::GreaterEqual => u32::from(signed_lhs >= signed_rhs),
+                    BinaryOperator::Equal => u32::from(lhs == rhs),
+                    BinaryOperator::NotEqual => u32::from(lhs != rhs),
+                    BinaryOperator::BitwiseAnd => lhs & rhs,
+                    BinaryOperator::BitwiseXor => lhs ^ rhs,
+                    BinaryOperator::BitwiseOr => lhs | rhs,
+                    BinaryOperator::LogicalAnd | BinaryOperator::LogicalOr => u32::from(rhs != 0),
+                })
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::structs::{Memory, Processor, Section, Visibility};
+
+    fn nums_symbol() -> Symbol {
+        Symbol {
+            symbol_type: 1,
+            identifier: "nums".to_string(),
+            value: 0x1001_0000,
+            size: 16,
+            visibility: Visibility::Local,
+            section: Section::Data,
+        }
+    }
+
+    #[test]
+    fn test_parse_expression() {
+        let symbols = vec![nums_symbol()];
+
+        assert_eq!(
+            Expression::parse("$t0 + 4 * 2", &symbols),
+            Ok(Expression::Binary(
+                BinaryOperator::Add,
+                Box::new(Expression::Register(8)),
+                Box::new(Expression::Binary(
+                    BinaryOperator::Multiply,
+                    Box::new(Expression::Literal(4)),
+                    Box::new(Expression::Literal(2)),
+                )),
+            ))
+        );
+
+        assert_eq!(
+            Expression::parse("*(half*)nums", &symbols),
+            Ok(Expression::Memory {
+                size: 2,
+                address: Box::new(Expression::Cast {
+                    size: 2,
+                    inner: Box::new(Expression::Literal(0x1001_0000)),
+                }),
+            })
+        );
+
+        assert!(Expression::parse("$t10", &symbols).is_err());
+        assert!(Expression::parse("nowhere + 1", &symbols).is_err());
+        assert!(Expression::parse("(1 + 2", &symbols).is_err());
+        assert!(Expression::parse("1 2", &symbols).is_err());
+        assert!(Expression::parse("", &symbols).is_err());
+    }
+
+    #[test]
+    fn test_evaluate_expression() {
+        let symbols = vec![nums_symbol()];
+        let mut program_state = ProgramState::new(
+            Processor::new(0x0040_0000),
+            Memory::new(
+                vec![0, 0, 0, 1, 0, 0, 0, 2, 0, 0, 0, 3, 0xFF, 0xFF, 0xFF, 0xFE],
+                vec![0; 4],
+            ),
+        );
+        program_state.cpu.general_purpose_registers[8] = 2; // $t0
+        program_state.cpu.general_purpose_registers[29] = 0x1001_0000; // $sp
+
+        let evaluate = |text: &str| {
+            Expression::parse(text, &symbols)
+                .unwrap()
+                .evaluate(&program_state)
+        };
+
+        assert_eq!(evaluate("nums[3]"), Ok(0xFFFF_FFFE));
+        assert_eq!(evaluate("word[nums+4*$t0]"), Ok(3));
+        assert_eq!(evaluate("*($sp+4)"), Ok(2));
+        assert_eq!(evaluate("*(word*)($sp+8)"), Ok(3));
+        assert_eq!(evaluate("((byte*)nums)[15]"), Ok(0xFE));
+        assert_eq!(evaluate("half[nums + 12]"), Ok(0xFFFF));
+        assert_eq!(evaluate("nums[3] / $t0"), Ok((-1i32).cast_unsigned()));
+        assert_eq!(evaluate("-1 < 0 && ~0 == 0xffffffff"), Ok(1));
+        assert_eq!(evaluate("1 << 4 | 3 ^ 1"), Ok(18));
+        assert_eq!(evaluate("'A' + 1"), Ok(66));
+        assert_eq!(evaluate("$zero || !$t0"), Ok(0));
+        assert!(evaluate("1 / $zero").is_err());
+        assert!(evaluate("*$zero").is_err());
+    }
+}
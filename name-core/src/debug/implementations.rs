@@ -1,60 +1,214 @@
-// use crate::constants::MIPS_ADDRESS_ALIGNMENT;
+use crate::constants::MIPS_ADDRESS_ALIGNMENT;
 // use std::collections::HashMap;
-use crate::debug::debug_utils::{Breakpoint, DebuggerState};
-use crate::structs::{LineInfo, ProgramState};
+use crate::debug::condition::Condition;
+use crate::debug::debug_utils::{Breakpoint, DebuggerState, DisplayFormat, WatchType, Watchpoint};
+use crate::debug::debugger_methods::escape_byte;
+use crate::debug::expression::Expression;
+use crate::debug::simulator_helpers::find_lineinfo;
+use crate::instruction::{AccessType, MemoryAccess};
+use crate::structs::{LineInfo, Memory, ProgramState, Section, Symbol};
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::path::Path;
 
 impl Breakpoint {
     pub fn new(
-        bp_num: usize,
         line_address: u32,
         lineinfo: &Vec<LineInfo>,
-        program_state: &mut ProgramState,
+        program_state: &ProgramState,
     ) -> Result<Self, String> {
-        let old_instr = match program_state.insert_breakpoint(line_address, bp_num) {
-            Ok(instr) => instr,
-            Err(_e) => {
-                return Err(format!("Attempted breakpoint insertion at invalid address 0x{:#08x} - please stick to .text.", line_address))
-            }
-        };
+        if !line_address.is_multiple_of(MIPS_ADDRESS_ALIGNMENT) {
+            return Err(format!(
+                "Address 0x{:08x} is not word-aligned, so no instruction starts there.",
+                line_address
+            ));
+        }
+
+        if !program_state.memory.allows_execution_of(line_address) {
+            return Err(format!("Attempted breakpoint insertion at invalid address 0x{:08x} - please stick to .text.", line_address));
+        }
 
         let bp = Breakpoint {
             // bp_num,
-            line_num: {
-                match lineinfo
-                    .iter()
-                    .find(|&line| line.start_address == line_address)
-                {
-                    Some(line) => line.line_number,
-                    None => {
-                        panic!(
-                            "Breakpoint not found in memory. (Something has gone seriously wrong.)"
-                        );
-                    }
-                }
+            // the line that generated the instruction; 0 if the line information doesn't cover this address.
+            line_num: match find_lineinfo(lineinfo, line_address) {
+                Some(line) => line.line_number,
+                None => 0,
             },
             address: line_address,
-            replaced_instruction: old_instr,
-            already_executed: false,
+            enabled: true,
+            condition: None,
+            ignore_count: 0,
+            hit_count: 0,
         };
 
         Ok(bp)
     }
+}
+
+impl DisplayFormat {
+    /// Parses a format from its name (hex, signed, unsigned, char, binary) or its p/ letter (x, d, u, c, t).
+    pub fn parse(text: &str) -> Result<Self, String> {
+        match text {
+            "x" | "hex" => Ok(DisplayFormat::Hex),
+            "d" | "signed" => Ok(DisplayFormat::Signed),
+            "u" | "unsigned" => Ok(DisplayFormat::Unsigned),
+            "c" | "char" => Ok(DisplayFormat::Char),
+            "t" | "binary" => Ok(DisplayFormat::Binary),
+            _ => Err(format!(
+                "{} is not a valid format (hex, signed, unsigned, char, binary).",
+                text
+            )),
+        }
+    }
 
-    pub fn already_executed(&self) -> bool {
-        return self.already_executed;
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DisplayFormat::Hex => "hex",
+            DisplayFormat::Signed => "signed",
+            DisplayFormat::Unsigned => "unsigned",
+            DisplayFormat::Char => "char",
+            DisplayFormat::Binary => "binary",
+        }
     }
 
-    pub fn flip_execution_status(&mut self) {
-        self.already_executed = !self.already_executed;
+    /// Shows a register's value in this format. Chars are taken from the low byte.
+    pub fn format(&self, value: u32) -> String {
+        match self {
+            DisplayFormat::Hex => format!("{:08x}", value),
+            DisplayFormat::Signed => (value as i32).to_string(),
+            DisplayFormat::Unsigned => value.to_string(),
+            DisplayFormat::Char => format!("{} '{}'", value as u8, escape_byte(value as u8)),
+            DisplayFormat::Binary => format!("{:032b}", value),
+        }
+    }
+}
+
+impl WatchType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            WatchType::Read => "r",
+            WatchType::Write => "w",
+            WatchType::ReadWrite => "rw",
+        }
+    }
+
+    /// Checks whether an access of the given type should trigger a watchpoint of this type.
+    pub fn watches(&self, access_type: AccessType) -> bool {
+        match (self, access_type) {
+            (WatchType::ReadWrite, _) => true,
+            (WatchType::Read, AccessType::Read) => true,
+            (WatchType::Write, AccessType::Write) => true,
+            _ => false,
+        }
+    }
+}
+
+impl Watchpoint {
+    /// Grabs the current contents of the watched region. Bytes that can't be read (e.g. stack that hasn't been grown yet) are None.
+    pub fn snapshot(&self, memory: &Memory) -> Vec<Option<u8>> {
+        (0..self.size)
+            .map(|i| memory.read_byte(self.address.wrapping_add(i)).ok())
+            .collect()
+    }
+}
+
+/// Formats bytes pulled from memory for the watchpoint report.
+/// Byte, half, and word-sized values are shown as big-endian hex integers; anything else is shown byte by byte.
+fn format_bytes(bytes: &[Option<u8>]) -> String {
+    if bytes.iter().any(|b| b.is_none()) {
+        return "(unmapped)".to_string();
+    }
+
+    let bytes: Vec<u8> = bytes.iter().map(|b| b.unwrap_or(0)).collect();
+    match bytes.len() {
+        1 | 2 | 4 => {
+            let value: u32 = bytes.iter().fold(0, |acc, &b| (acc << 8) | b as u32);
+            format!("0x{:0width$x}", value, width = bytes.len() * 2)
+        }
+        _ => bytes
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect::<Vec<String>>()
+            .join(" "),
     }
 }
 
 impl DebuggerState {
     pub fn new() -> Self {
         DebuggerState {
-            global_bp_num: 0,
-            breakpoints: Vec::<Breakpoint>::new(),
+            breakpoints: BTreeMap::<usize, Breakpoint>::new(),
+            breakpoint_addresses: HashMap::<u32, usize>::new(),
+            next_bp_num: 0,
             global_list_loc: 5,
+            watchpoints: Vec::<Watchpoint>::new(),
+            next_wp_num: 0,
+            symbols: Vec::<Symbol>::new(),
+            script: VecDeque::<String>::new(),
+            batch: false,
+            stopped_at_breakpoint: None,
+            display_format: DisplayFormat::Hex,
+            registers_at_last_stop: None,
+        }
+    }
+
+    /// Remembers the registers as they are now, so that once execution stops again pa can point out what changed.
+    pub fn remember_registers(&mut self, program_state: &ProgramState) {
+        self.registers_at_last_stop = Some(program_state.cpu.clone());
+    }
+
+    /// Queues up the commands in a debugger script, one per line.
+    /// Blank lines and lines starting with # are skipped.
+    pub fn load_script(&mut self, contents: &str) {
+        self.script.extend(
+            contents
+                .lines()
+                .map(|line| line.trim())
+                .filter(|line| !line.is_empty() && !line.starts_with('#'))
+                .map(|line| line.to_string()),
+        );
+    }
+
+    /// Looks up a label from .symtab by name.
+    pub fn find_symbol(&self, name: &str) -> Option<&Symbol> {
+        self.symbols.iter().find(|sym| sym.identifier == name)
+    }
+
+    /// Turns a debugger argument into an address.
+    /// Accepts hexadecimal (0x prefix), decimal, or the name of a label.
+    pub fn resolve_address(&self, arg: &str) -> Result<u32, String> {
+        if let Some(hex) = arg.strip_prefix("0x").or_else(|| arg.strip_prefix("0X")) {
+            return u32::from_str_radix(hex, 16)
+                .map_err(|_| format!("{} is not a valid hexadecimal address.", arg));
+        }
+
+        if let Ok(address) = arg.parse::<u32>() {
+            return Ok(address);
+        }
+
+        match self.find_symbol(arg) {
+            Some(sym) => Ok(sym.value),
+            None => Err(format!(
+                "{} is neither an address nor a label in this program.",
+                arg
+            )),
+        }
+    }
+
+    /// Like resolve_address, but takes any debugger expression: $sp, $sp+8, fibs+4*$t0, *($fp-4), and so on.
+    pub fn resolve_location(&self, arg: &str, program_state: &ProgramState) -> Result<u32, String> {
+        Expression::parse(arg, &self.symbols)?.evaluate(program_state)
+    }
+
+    /// Describes an address relative to the label it falls in, e.g. <fibs+8>, for the examine command.
+    pub fn describe_address(&self, address: u32) -> Option<String> {
+        let symbol: &Symbol = self.symbols.iter().find(|sym| {
+            sym.value == address
+                || (sym.value < address && address < sym.value.saturating_add(sym.size))
+        })?;
+
+        match address - symbol.value {
+            0 => Some(format!("<{}>", symbol.identifier)),
+            offset => Some(format!("<{}+{}>", symbol.identifier, offset)),
         }
     }
 
@@ -62,11 +216,28 @@ impl DebuggerState {
 
     /// Prints all breakpoints that have been created. Invoked by "pb" in the CLI.
     pub fn print_all_breakpoints(&self) -> Result<(), String> {
-        println!("BP_NUM: LINE_NUM");
-        // for (_address, bp) in &self.breakpoints {
-        // for bp in &self.breakpoints {
-        for bp_num in 0..self.breakpoints.len() {
-            println!("{:>6}: {}", bp_num, self.breakpoints[bp_num].line_num);
+        println!("BP_NUM: ENABLED  ADDRESS     LINE_NUM  HITS  CONDITION");
+        for (bp_num, bp) in &self.breakpoints {
+            let mut details: Vec<String> = Vec::new();
+            if let Some(condition) = &bp.condition {
+                details.push(format!("if {}", condition.text));
+            }
+            if bp.ignore_count > 0 {
+                details.push(format!("ignore next {} hits", bp.ignore_count));
+            }
+
+            println!(
+                "{:>6}: {:<7}  0x{:08x}  {:>8}  {:>4}  {}",
+                bp_num,
+                if bp.enabled { "y" } else { "n" },
+                bp.address,
+                match bp.line_num {
+                    0 => "?".to_string(),
+                    line_num => line_num.to_string(),
+                },
+                bp.hit_count,
+                details.join(", ")
+            );
         }
         return Ok(());
     }
@@ -100,77 +271,478 @@ impl DebuggerState {
         }
     }
 
-    /// Adds a breakpoint at the given line number. Invoked by "b" in the CLI.
-    pub fn add_breakpoint(
-        &mut self,
-        lineinfo: &Vec<LineInfo>,
-        db_args: &Vec<String>,
-        program_state: &mut ProgramState,
-    ) -> Result<(), String> {
-        if db_args.len() != 2 {
+    /// Adds a watchpoint on a region of memory. Invoked by "watch" in the CLI.
+    /// Usage: watch <addr|label> [r|w|rw] [size]
+    pub fn add_watchpoint(&mut self, db_args: &Vec<String>) -> Result<(), String> {
+        if db_args.len() < 2 || db_args.len() > 4 {
             return Err(format!(
-                "b expects 1 argument, received {}",
+                "watch expects 1 to 3 arguments, received {}",
                 db_args.len() - 1
             ));
         }
 
-        if self.breakpoints.len() > ((1 << 20) - 1) {
+        let address: u32 = self.resolve_address(&db_args[1])?;
+
+        // Labels remember their size, so watching an array label watches the whole array by default.
+        let label: Option<String> = self.find_symbol(&db_args[1]).map(|_| db_args[1].clone());
+        let mut size: u32 = match self.find_symbol(&db_args[1]) {
+            Some(sym) if sym.size > 0 => sym.size,
+            _ => 4,
+        };
+        let mut watch_type: WatchType = WatchType::Write;
+
+        // The optional arguments may come in either order.
+        for arg in &db_args[2..] {
+            match arg.as_str() {
+                "r" => watch_type = WatchType::Read,
+                "w" => watch_type = WatchType::Write,
+                "rw" => watch_type = WatchType::ReadWrite,
+                _ => match arg.parse::<u32>() {
+                    Ok(0) => return Err("Watchpoint size must be at least 1 byte.".to_string()),
+                    Ok(n) => size = n,
+                    Err(_) => {
+                        return Err(format!(
+                            "{} is not a valid watch type (r, w, rw) or size in bytes.",
+                            arg
+                        ))
+                    }
+                },
+            }
+        }
+
+        let wp_num: usize = self.next_wp_num;
+        self.next_wp_num += 1;
+        self.watchpoints.push(Watchpoint {
+            wp_num,
+            address,
+            size,
+            watch_type,
+            label,
+        });
+
+        println!(
+            "Successfully added watchpoint {} ({}) on 0x{:08x} ({} byte{}).",
+            wp_num,
+            watch_type.as_str(),
+            address,
+            size,
+            if size == 1 { "" } else { "s" }
+        );
+        Ok(())
+    }
+
+    /// Removes a watchpoint. Invoked by "delw" in the CLI.
+    pub fn remove_watchpoint(&mut self, db_args: &Vec<String>) -> Result<(), String> {
+        if db_args.len() != 2 {
             return Err(format!(
-                "Reached maximum number of breakpoints - cannot add any more."
+                "delw expects 1 argument, received {}",
+                db_args.len() - 1
             ));
         }
 
-        // grab line number and do error handling as necessary
-        let line_num: u32 = match db_args[1].parse() {
-            Ok(ln) => ln,
-            Err(_) => return Err("b takes 32-bit unsigned int as input".to_string()),
+        let wp_num: usize = match db_args[1].parse() {
+            Ok(num) => num,
+            Err(_) => return Err("delw takes an unsigned int as input".to_string()),
         };
 
-        if line_num > lineinfo.len().try_into().unwrap() {
-            // something like that
-            return Err(format!("{} exceeds number of lines in program.", line_num));
+        match self.watchpoints.iter().position(|wp| wp.wp_num == wp_num) {
+            Some(idx) => self.watchpoints.remove(idx),
+            None => return Err(format!("Watchpoint {} not found.", wp_num)),
+        };
+        println!("Successfully removed watchpoint {}.", wp_num);
+        Ok(())
+    }
+
+    /// Prints all watchpoints that have been created. Invoked by "pw" in the CLI.
+    pub fn print_all_watchpoints(&self) -> Result<(), String> {
+        println!("WP_NUM: ADDRESS     SIZE  TYPE");
+        for wp in &self.watchpoints {
+            println!(
+                "{:>6}: 0x{:08x}  {:>4}  {:<4}{}",
+                wp.wp_num,
+                wp.address,
+                wp.size,
+                wp.watch_type.as_str(),
+                match &wp.label {
+                    Some(label) => format!(" ({})", label),
+                    None => String::new(),
+                }
+            );
         }
+        Ok(())
+    }
 
-        // get the line address associated with the line number
-        let line_address: u32 = match lineinfo.iter().find(|line| line.line_number == line_num) {
-            Some(ln) => ln.start_address,
-            None => {
-                return Err(format!(
-                    "Line number {} not found in memory. Something has gone very wrong.",
-                    line_num
-                ))
+    /// Captures the current contents of every watched region. Taken by db_step before executing an instruction.
+    pub fn snapshot_watchpoints(&self, memory: &Memory) -> Vec<Vec<Option<u8>>> {
+        self.watchpoints
+            .iter()
+            .map(|wp| wp.snapshot(memory))
+            .collect()
+    }
+
+    /// Checks every watchpoint after an instruction has executed and reports the ones that fired.
+    /// A watchpoint fires if the instruction's load/store touched the region, or (for write watchpoints)
+    /// if the region's contents changed some other way, like a syscall writing into a buffer.
+    /// Returns whether any watchpoint fired.
+    pub fn check_watchpoints(
+        &self,
+        lineinfo: &Vec<LineInfo>,
+        program_state: &ProgramState,
+        pc: u32,
+        mnemonic: &str,
+        access: Option<MemoryAccess>,
+        snapshots: &Vec<Vec<Option<u8>>>,
+    ) -> bool {
+        let mut triggered = false;
+
+        for (idx, wp) in self.watchpoints.iter().enumerate() {
+            let old_contents: &Vec<Option<u8>> = &snapshots[idx];
+            let new_contents: Vec<Option<u8>> = wp.snapshot(&program_state.memory);
+
+            let accessed: Option<MemoryAccess> = match access {
+                Some(acc)
+                    if acc.overlaps(wp.address, wp.size)
+                        && wp.watch_type.watches(acc.access_type) =>
+                {
+                    Some(acc)
+                }
+                _ => None,
+            };
+            let changed: bool =
+                wp.watch_type.watches(AccessType::Write) && *old_contents != new_contents;
+
+            if accessed.is_none() && !changed {
+                continue;
             }
-        };
+            triggered = true;
 
-        let new_bp =
-            match Breakpoint::new(self.global_bp_num, line_address, lineinfo, program_state) {
-                Ok(bp) => bp,
-                Err(e) => return Err(format!("{e}")),
+            // Narrow the report down to the bytes that were actually touched.
+            let (start, end): (usize, usize) = match accessed {
+                Some(acc) => (
+                    acc.address.saturating_sub(wp.address) as usize,
+                    std::cmp::min(
+                        (acc.address as u64 + acc.size as u64 - wp.address as u64) as usize,
+                        wp.size as usize,
+                    ),
+                ),
+                None => {
+                    let differs = |i: &usize| old_contents[*i] != new_contents[*i];
+                    let first = (0..new_contents.len()).find(differs).unwrap_or(0);
+                    let last = (0..new_contents.len()).rev().find(differs).unwrap_or(0);
+                    (first, last + 1)
+                }
             };
 
-        self.breakpoints.insert(self.global_bp_num as usize, new_bp);
+            let location: String = match find_lineinfo(lineinfo, pc) {
+                Some(line) => format!("line {}: {}", line.line_number, line.content.trim()),
+                None => "no line information".to_string(),
+            };
+            let verb: &str = match accessed {
+                Some(MemoryAccess {
+                    access_type: AccessType::Read,
+                    ..
+                }) => "read",
+                _ => "written",
+            };
+
+            println!(
+                "Watchpoint {} ({}) triggered: 0x{:08x} {} by {} at pc 0x{:08x} ({})",
+                wp.wp_num,
+                wp.watch_type.as_str(),
+                wp.address.wrapping_add(start as u32),
+                verb,
+                mnemonic,
+                pc,
+                location
+            );
+
+            if verb == "read" {
+                println!(" - Value: {}", format_bytes(&new_contents[start..end]));
+            } else {
+                println!(" - Old value: {}", format_bytes(&old_contents[start..end]));
+                println!(" - New value: {}", format_bytes(&new_contents[start..end]));
+            }
+        }
 
-        // find the next empty space in the breakpoint vector
-        while let Some(_) = self.breakpoints.get(self.global_bp_num as usize) {
-            self.global_bp_num += 1;
+        triggered
+    }
+
+    /// Turns the target of a "b" command into the address of an instruction.
+    /// Accepts a line number (20), a file and line number (fib.asm:20), a label (loop), or an address (*0x400010).
+    pub fn resolve_breakpoint_target(
+        &self,
+        lineinfo: &Vec<LineInfo>,
+        target: &str,
+    ) -> Result<u32, String> {
+        if let Some(address) = target.strip_prefix('*') {
+            return self.resolve_address(address);
         }
 
+        // file:line
+        if let Some((file, line)) = target.rsplit_once(':') {
+            let line_num: u32 = match line.parse() {
+                Ok(ln) => ln,
+                Err(_) => return Err(format!("{} is not a valid line number.", line)),
+            };
+
+            // Only the file's name is recorded, so a path to the file is fine too.
+            let file_name: &str = match Path::new(file).file_name() {
+                Some(name) => name.to_str().unwrap_or(file),
+                None => file,
+            };
+            if !lineinfo.iter().any(|line| line.file_name == file_name) {
+                return Err(format!("No source file named {} in this program.", file));
+            }
+
+            return match lineinfo
+                .iter()
+                .find(|line| line.file_name == file_name && line.line_number == line_num)
+            {
+                Some(line) => self.line_address(line),
+                None => Err(format!("{} exceeds number of lines in {}.", line_num, file)),
+            };
+        }
+
+        // line number
+        if let Ok(line_num) = target.parse::<u32>() {
+            return match lineinfo.iter().find(|line| line.line_number == line_num) {
+                Some(line) => self.line_address(line),
+                None => Err(format!("{} exceeds number of lines in program.", line_num)),
+            };
+        }
+
+        // label
+        match self.find_symbol(target) {
+            Some(Symbol {
+                section: Section::Text,
+                value,
+                ..
+            }) => Ok(*value),
+            Some(_) => Err(format!(
+                "{} is not a label in .text. (Use watch to stop when data is accessed.)",
+                target
+            )),
+            None => Err(format!(
+                "{} is not a line number, label, or *address in this program.",
+                target
+            )),
+        }
+    }
+
+    /// Gets the address of the first instruction a line generated (or the next instruction, if the line generated none).
+    fn line_address(&self, line: &LineInfo) -> Result<u32, String> {
+        // .data lines (and anything before .text) have no address.
+        if line.start_address == 0 {
+            return Err(format!(
+                "Line {} is not in .text, so no instruction will ever be executed there.",
+                line.line_number
+            ));
+        }
+        Ok(line.start_address)
+    }
+
+    /// Adds a breakpoint. Invoked by "b" in the CLI.
+    /// Usage: b <line|file:line|label|*addr> [if <condition>]
+    pub fn add_breakpoint(
+        &mut self,
+        lineinfo: &Vec<LineInfo>,
+        db_args: &Vec<String>,
+        program_state: &mut ProgramState,
+    ) -> Result<(), String> {
+        if db_args.len() < 2 {
+            return Err(format!(
+                "b expects at least 1 argument, received {}",
+                db_args.len() - 1
+            ));
+        }
+
+        // anything after the target must be a condition
+        let condition: Option<Condition> = match db_args.get(2).map(|arg| arg.as_str()) {
+            None => None,
+            Some("if") => Some(Condition::parse(&db_args[3..].join(" "), &self.symbols)?),
+            Some(arg) => {
+                return Err(format!(
+                    "Expected \"if\" after the breakpoint location, found {}",
+                    arg
+                ))
+            }
+        };
+
+        // get the address associated with whatever the user asked for
+        let line_address: u32 = self.resolve_breakpoint_target(lineinfo, &db_args[1])?;
+
+        let bp_num: usize =
+            self.insert_breakpoint_at(lineinfo, line_address, condition, program_state)?;
+
         // play around with this if you're getting weird pc related logic errors.
         // program_state.cpu.pc = program_state.cpu.pc - MIPS_ADDRESS_ALIGNMENT;
 
         println!(
-            "Successfully added breakpoint {} at line {}.",
-            self.global_bp_num, line_num
+            "Successfully added breakpoint {} at 0x{:08x} (line {}).",
+            bp_num, line_address, self.breakpoints[&bp_num].line_num
         );
         Ok(())
     }
 
-    /// Zoinks a breakpoint. Invoked by "del" in the CLI.
-    pub fn remove_breakpoint(
+    /// Does the work of add_breakpoint once the address is known. Returns the new breakpoint's number.
+    pub fn insert_breakpoint_at(
+        &mut self,
+        lineinfo: &Vec<LineInfo>,
+        line_address: u32,
+        condition: Option<Condition>,
+        program_state: &ProgramState,
+    ) -> Result<usize, String> {
+        // only one breakpoint per address, so a hit always belongs to exactly one of them
+        if let Some(existing) = self.breakpoint_at(line_address) {
+            return Err(format!(
+                "Breakpoint {} already exists at 0x{:08x}.",
+                existing, line_address
+            ));
+        }
+
+        let mut new_bp = match Breakpoint::new(line_address, lineinfo, program_state) {
+            Ok(bp) => bp,
+            Err(e) => return Err(format!("{e}")),
+        };
+        new_bp.condition = condition;
+
+        let bp_num: usize = self.next_bp_num;
+        self.next_bp_num += 1;
+        self.breakpoints.insert(bp_num, new_bp);
+        self.breakpoint_addresses.insert(line_address, bp_num);
+
+        Ok(bp_num)
+    }
+
+    /// Finds the number of the breakpoint at an address, if there is one.
+    pub fn breakpoint_at(&self, address: u32) -> Option<usize> {
+        self.breakpoint_addresses.get(&address).copied()
+    }
+
+    /// Records a hit on a breakpoint and decides whether execution should actually stop there.
+    /// Disabled breakpoints and breakpoints whose condition is false don't count as hits.
+    /// Hits are counted even while the ignore count is being used up.
+    pub fn should_stop_at(&mut self, bp_num: usize, program_state: &ProgramState) -> bool {
+        let bp: &mut Breakpoint = match self.breakpoints.get_mut(&bp_num) {
+            Some(bp) => bp,
+            None => return false,
+        };
+        if !bp.enabled {
+            return false;
+        }
+
+        if let Some(condition) = &bp.condition {
+            match condition.evaluate(program_state) {
+                Ok(true) => {}
+                Ok(false) => return false,
+                Err(e) => {
+                    // a condition that can't be evaluated stops execution so the user can fix it
+                    eprintln!(
+                        "Error in condition for breakpoint {} ({}): {e}",
+                        bp_num, condition.text
+                    );
+                    return true;
+                }
+            }
+        }
+
+        bp.hit_count += 1;
+
+        if bp.ignore_count > 0 {
+            bp.ignore_count -= 1;
+            return false;
+        }
+
+        true
+    }
+
+    /// Enables or disables breakpoints without deleting them. Invoked by "enable" and "disable" in the CLI.
+    /// With no arguments, every breakpoint is affected.
+    pub fn set_breakpoints_enabled(
         &mut self,
         db_args: &Vec<String>,
-        program_state: &mut ProgramState,
+        enabled: bool,
     ) -> Result<(), String> {
+        let bp_nums: Vec<usize> = if db_args.len() == 1 {
+            self.breakpoints.keys().copied().collect()
+        } else {
+            let mut bp_nums: Vec<usize> = Vec::new();
+            for arg in &db_args[1..] {
+                match arg.parse::<usize>() {
+                    Ok(num) if self.breakpoints.contains_key(&num) => bp_nums.push(num),
+                    Ok(num) => return Err(format!("Breakpoint {} not found.", num)),
+                    Err(_) => return Err(format!("{} takes unsigned ints as input", db_args[0])),
+                }
+            }
+            bp_nums
+        };
+
+        for bp_num in bp_nums {
+            self.breakpoint_mut(bp_num)?.enabled = enabled;
+            println!(
+                "Breakpoint {} {}.",
+                bp_num,
+                if enabled { "enabled" } else { "disabled" }
+            );
+        }
+        Ok(())
+    }
+
+    /// Skips the next COUNT hits of a breakpoint. Invoked by "ignore" in the CLI.
+    pub fn ignore_breakpoint(&mut self, db_args: &Vec<String>) -> Result<(), String> {
+        if db_args.len() != 3 {
+            return Err(format!(
+                "ignore expects 2 arguments, received {}",
+                db_args.len() - 1
+            ));
+        }
+
+        let (bp_num, count): (usize, u32) = match (db_args[1].parse(), db_args[2].parse()) {
+            (Ok(bp_num), Ok(count)) => (bp_num, count),
+            _ => return Err("ignore takes two unsigned ints as input".to_string()),
+        };
+
+        self.breakpoint_mut(bp_num)?.ignore_count = count;
+        println!("Will ignore next {} hits of breakpoint {}.", count, bp_num);
+        Ok(())
+    }
+
+    /// Replaces (or with no expression, removes) the condition on a breakpoint. Invoked by "condition" in the CLI.
+    pub fn set_breakpoint_condition(&mut self, db_args: &Vec<String>) -> Result<(), String> {
+        if db_args.len() < 2 {
+            return Err(format!(
+                "condition expects at least 1 argument, received {}",
+                db_args.len() - 1
+            ));
+        }
+
+        let bp_num: usize = match db_args[1].parse() {
+            Ok(num) => num,
+            Err(_) => return Err("condition takes an unsigned int as its first input".to_string()),
+        };
+
+        let symbols: &Vec<Symbol> = &self.symbols;
+        let bp: &mut Breakpoint = match self.breakpoints.get_mut(&bp_num) {
+            Some(bp) => bp,
+            None => return Err(format!("Breakpoint {bp_num} not found.")),
+        };
+
+        if db_args.len() == 2 {
+            bp.condition = None;
+            println!("Breakpoint {} is now unconditional.", bp_num);
+        } else {
+            let condition: Condition = Condition::parse(&db_args[2..].join(" "), symbols)?;
+            println!(
+                "Breakpoint {} will stop only if {}.",
+                bp_num, condition.text
+            );
+            bp.condition = Some(condition);
+        }
+        Ok(())
+    }
+
+    /// Zoinks a breakpoint. Invoked by "del" in the CLI.
+    pub fn remove_breakpoint(&mut self, db_args: &Vec<String>) -> Result<(), String> {
         if db_args.len() != 2 {
             return Err(format!(
                 "del expects 1 argument, received {}",
@@ -185,36 +757,25 @@ impl DebuggerState {
             }
         };
 
-        let removed_breakpoint = match self.breakpoints.get(bp_num) {
+        self.delete_breakpoint(bp_num)
+    }
+
+    /// Does the work of remove_breakpoint: forgets the breakpoint.
+    pub fn delete_breakpoint(&mut self, bp_num: usize) -> Result<(), String> {
+        // remove the breakpoint from the universe of discourse; the other breakpoints keep their numbers
+        let bp: Breakpoint = match self.breakpoints.remove(&bp_num) {
             Some(bp) => bp,
-            None => return Err(format!("Breakpoint {} not found.", bp_num)),
+            None => return Err(format!("Breakpoint {bp_num} not found.")),
         };
+        self.breakpoint_addresses.remove(&bp.address);
+        Ok(())
+    }
 
-        // replace the instruction
-        let mut i = 0;
-        while i < 4 {
-            // Shift/mask value to get correct byte
-            let new_byte: u8 = ((removed_breakpoint.replaced_instruction >> (i * 8)) & 0xFF) as u8;
-            // Write it to correct location
-            match program_state
-                .memory
-                .set_byte(removed_breakpoint.address + (3 - i), new_byte)
-            {
-                Ok(_) => (),
-                Err(e) => {
-                    return Err(format!("{e}"));
-                }
-            }
-            i += 1;
-        }
-
-        // remove the breakpoint from the universe of discourse
-        self.breakpoints.remove(bp_num);
-
-        // the index at bp_num is now empty. point our number there
-        if self.global_bp_num > bp_num {
-            self.global_bp_num = bp_num;
+    /// Looks up a breakpoint by number for the commands that change one.
+    fn breakpoint_mut(&mut self, bp_num: usize) -> Result<&mut Breakpoint, String> {
+        match self.breakpoints.get_mut(&bp_num) {
+            Some(bp) => Ok(bp),
+            None => Err(format!("Breakpoint {bp_num} not found.")),
         }
-        Ok(())
     }
 }
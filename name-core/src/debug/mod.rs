@@ -1,5 +1,7 @@
+pub mod condition;
 pub mod debug_utils;
 pub mod debugger_methods;
+pub mod expression;
 pub mod exception_handler;
 pub mod fetch;
 pub mod implementations;
@@ -1,7 +1,7 @@
 use crate::elf_utils::find_target_section_index;
 
 use crate::elf_def::Elf;
-use crate::structs::LineInfo;
+use crate::structs::{CallFrame, LineInfo, Section, Symbol};
 
 // Extract section .text and section .data from the ELF
 pub fn extract_loadable_sections(elf: &Elf) -> (Vec<u8>, Vec<u8>) {
@@ -27,23 +27,79 @@ pub fn extract_loadable_sections(elf: &Elf) -> (Vec<u8>, Vec<u8>) {
     (data_section, text_section)
 }
 
-pub fn generate_err(lineinfo: &Vec<LineInfo>, address: u32, message: &str) -> String {
-    // Perform an address-based search for the correct line info
-    let found_lineinfo: &LineInfo = match lineinfo
+/// Performs an address-based search for the line of source that generated the instruction at address.
+pub fn find_lineinfo(lineinfo: &Vec<LineInfo>, address: u32) -> Option<&LineInfo> {
+    lineinfo
         .iter()
         .find(|li| (li.start_address <= address) && (address < li.end_address))
-    {
-        Some(info) => info,
+}
+
+/// Names the function containing address. Jump targets from the call stack are exact matches;
+/// otherwise the closest .text label at or before the address is used.
+pub fn function_name(symbols: &[Symbol], address: u32) -> String {
+    symbols
+        .iter()
+        .filter(|sym| matches!(sym.section, Section::Text) && sym.value <= address)
+        .max_by_key(|sym| sym.value)
+        .map(|sym| sym.identifier.clone())
+        .unwrap_or_else(|| "??".to_string())
+}
+
+/// Lays out the shadow call stack innermost-first, one line per frame.
+/// Frame #0 is where execution currently is; every frame after it is the call site that is waiting on the one before.
+pub fn format_backtrace(
+    lineinfo: &Vec<LineInfo>,
+    symbols: &[Symbol],
+    call_stack: &[CallFrame],
+    pc: u32,
+) -> Vec<String> {
+    let mut addresses: Vec<u32> = vec![pc];
+    addresses.extend(call_stack.iter().rev().map(|frame| frame.call_site));
+
+    addresses
+        .iter()
+        .enumerate()
+        .map(|(depth, &address)| {
+            // the frame that called into this depth knows exactly which function we're in
+            let function: String = match call_stack.len().checked_sub(depth + 1) {
+                Some(idx) => function_name(symbols, call_stack[idx].function_address),
+                None => function_name(symbols, address),
+            };
+            let location: String = match find_lineinfo(lineinfo, address) {
+                Some(info) => format!(" (line {}: {})", info.line_number, info.content.trim()),
+                None => String::new(),
+            };
+            format!("#{:<3}0x{:08x} in {}{}", depth, address, function, location)
+        })
+        .collect()
+}
+
+pub fn generate_err(
+    lineinfo: &Vec<LineInfo>,
+    address: u32,
+    message: &str,
+    call_stack: &[CallFrame],
+    symbols: &[Symbol],
+) -> String {
+    // Perform an address-based search for the correct line info
+    let report: String = match find_lineinfo(lineinfo, address) {
+        // If lineinfo was retrieved, print a well-formed error message
+        Some(found_lineinfo) => format!(
+            "[*] At pc 0x{:x}:\n - {}: {}\n - {}",
+            address,
+            found_lineinfo.line_number,
+            found_lineinfo.content.trim(),
+            message,
+        ),
         // If no lineinfo was found, just give a general message
-        None => return format!("[*] At pc 0x{:8x}:\n - {}", address, message),
+        None => format!("[*] At pc 0x{:8x}:\n - {}", address, message),
     };
 
-    // If lineinfo was retrieved, print a well-formed error message
-    return format!(
-        "[*] At pc 0x{:x}:\n - {}: {}\n - {}",
-        address,
-        found_lineinfo.line_number,
-        found_lineinfo.content.trim(),
-        message,
-    );
+    // Only worth showing how we got here if we're inside a function
+    if call_stack.is_empty() {
+        return report;
+    }
+
+    let backtrace: Vec<String> = format_backtrace(lineinfo, symbols, call_stack, address);
+    format!("{}\n - Backtrace:\n   {}", report, backtrace.join("\n   "))
 }
@@ -164,6 +164,7 @@ pub const PF_R: u32 = 0x4; // Read
 pub const STT_OBJECT: u8 = 1;
 pub const STT_FUNC: u8 = 2;
 pub const SH_ENTSIZE_SYMTAB: u32 = 16;
+pub const SHN_UNDEF: u16 = 0; // st_shndx of a symbol referenced but not defined here
 
 // Section header consts
 
@@ -5,7 +5,9 @@ use std::{fs, io::Write, path::PathBuf, vec::Vec};
 
 use crate::constants::{MIPS_ADDRESS_ALIGNMENT, MIPS_DATA_START_ADDR, MIPS_TEXT_START_ADDR};
 use crate::elf_def::*;
-use crate::structs::{LineInfo, Section, Symbol, Visibility}; // Used for ELF sections
+use crate::structs::{
+    LineInfo, Section, Symbol, Visibility, LINE_SECTION_HEADER, LINE_SECTION_VERSION,
+}; // Used for ELF sections
 
 // Macros - had to learn somehow!
 
@@ -328,7 +330,9 @@ pub fn create_new_elf(sections: Vec<Vec<u8>>, elf_type: ElfType, create_shstrtab
     // Craft final sections
     let mut final_sections: Vec<Vec<u8>> = sections.clone();
     
-    final_sections.push(shstrtab_section);  // Is empty if create_shstrtab is false
+    if create_shstrtab {
+        final_sections.push(shstrtab_section);  // Otherwise the caller's last section already is the .shstrtab
+    }
 
     // Final step is to create the final Elf struct
     return Elf {
@@ -390,6 +394,17 @@ pub fn extract_symbol_table_to_sections(symbol_table: Vec<Symbol>) -> (Vec<u8>,
 
 // This function creates a new file with the passed name and writes all bytes into an Elf object
 pub fn write_elf_to_file(file_name: &PathBuf, et_rel: &Elf) -> Result<(), String> {
+    let file_bytes: Vec<u8> = elf_to_bytes(et_rel);
+
+    // Write file bytes to output file
+    let mut f: fs::File = fs::File::create(file_name).expect("Unable to write file");
+    f.write_all(&file_bytes).expect("Unable to write data.");
+
+    Ok(())
+}
+
+// Lays an Elf object out as the bytes of a file. Reading them back with read_bytes_to_elf gives the Elf the next tool expects.
+pub fn elf_to_bytes(et_rel: &Elf) -> Vec<u8> {
     // Declare file_bytes vector to push all these file bytes onto
     // Concatenate all bytes in file header
     let mut file_bytes: Vec<u8> = et_rel.file_header.to_bytes().to_vec();
@@ -409,11 +424,7 @@ pub fn write_elf_to_file(file_name: &PathBuf, et_rel: &Elf) -> Result<(), String
         file_bytes.extend_from_slice(&entry.to_bytes());
     }
 
-    // Write file bytes to output file
-    let mut f: fs::File = fs::File::create(file_name).expect("Unable to write file");
-    f.write_all(&file_bytes).expect("Unable to write data.");
-
-    Ok(())
+    file_bytes
 }
 
 /*
@@ -468,12 +479,24 @@ pub fn read_bytes_to_elf(file_contents: Vec<u8>) -> Result<Elf, String> {
         );
     }
 
-    Ok(Elf {
+    let elf: Elf = Elf {
         file_header: elf_header,
         program_header_table: program_header_table,
         sections: sections,
         section_header_table: section_header_table,
-    })
+    };
+
+    // A .line in another format would otherwise be misread without a word
+    if let Some(shstrtab) = elf
+        .sections
+        .get((elf.file_header.e_shstrndx as usize).wrapping_sub(1))
+    {
+        if let Some(idx) = find_target_section_index(&elf.section_header_table, shstrtab, ".line") {
+            deserialize_line_info(&elf.sections[idx])?;
+        }
+    }
+
+    Ok(elf)
 }
 
 fn parse_elf_header(expected_bytes: &[u8]) -> Result<Elf32Header, String> {
@@ -604,7 +627,42 @@ pub fn extract_lineinfo(elf: &Elf) -> Vec<LineInfo> {
         None => unreachable!(),
     };
 
-    deserialize_line_info(&elf.sections[idx])
+    // read_bytes_to_elf has already turned away a .line this can't read
+    deserialize_line_info(&elf.sections[idx]).unwrap_or_else(|e| unreachable!("{e}"))
+}
+
+/// Reconstructs the symbols in .symtab so that tools like the debugger can refer to labels by name.
+/// The reserved null symbol is skipped.
+pub fn extract_symbols(elf: &Elf) -> Vec<Symbol> {
+    let shstrtab = &elf.sections[elf.file_header.e_shstrndx as usize - 1];
+    let (symtab_idx, strtab_idx) = match (
+        find_target_section_index(&elf.section_header_table, shstrtab, ".symtab"),
+        find_target_section_index(&elf.section_header_table, shstrtab, ".strtab"),
+    ) {
+        (Some(symtab_idx), Some(strtab_idx)) => (symtab_idx, strtab_idx),
+        _ => return vec![],
+    };
+
+    parse_elf_symbols(&elf.sections[symtab_idx])
+        .iter()
+        .filter(|sym| sym.st_name != 0)
+        .map(|sym| Symbol {
+            symbol_type: sym.get_type(),
+            identifier: sym.get_linked_name(&elf.sections[strtab_idx]),
+            value: sym.st_value,
+            size: sym.st_size,
+            visibility: match sym.get_bind() {
+                1 => Visibility::Global,
+                2 => Visibility::Weak,
+                _ => Visibility::Local,
+            },
+            section: match sym.st_shndx {
+                1 => Section::Text,
+                2 => Section::Data,
+                _ => Section::Null,
+            },
+        })
+        .collect()
 }
 
 pub fn find_global_symbol_address(
@@ -658,9 +716,23 @@ impl Elf {
     }
 }
 
-fn deserialize_line_info(data: &Vec<u8>) -> Vec<LineInfo> {
+/// Reconstructs the LineInfo entries serialized into section .line, after checking its header says it's in a format
+/// this version can read. An empty section has no lines.
+pub fn deserialize_line_info(data: &Vec<u8>) -> Result<Vec<LineInfo>, String> {
+    let mut cursor = match data.strip_prefix(&LINE_SECTION_HEADER[..]) {
+        Some(entries) => entries,
+        None if data.is_empty() => return Ok(vec![]),
+        None if data[0] == LINE_SECTION_HEADER[0] => {
+            return Err(format!(
+                "Section .line is in version {} of its format, but only version {LINE_SECTION_VERSION} can be read. Reassemble the program.",
+                data.get(1).copied().unwrap_or(0)
+            ))
+        }
+        None => {
+            return Err("Section .line was written before its format had a version. Reassemble the program.".to_string())
+        }
+    };
     let mut result = Vec::new();
-    let mut cursor = &data[..];
 
     while !cursor.is_empty() {
         // Find the null terminator (0 byte) to extract the string
@@ -671,6 +743,16 @@ fn deserialize_line_info(data: &Vec<u8>) -> Vec<LineInfo> {
             // Move cursor past the null terminator and string
             cursor = &cursor[pos + 1..];
 
+            // The file name is null-terminated just like the content
+            let file_name = match cursor.iter().position(|&c| c == 0) {
+                Some(pos) => {
+                    let file_name = String::from_utf8_lossy(&cursor[..pos]).to_string();
+                    cursor = &cursor[pos + 1..];
+                    file_name
+                }
+                None => break,
+            };
+
             // Ensure we have at least 12 bytes remaining for three u32 values
             if cursor.len() < 12 {
                 break;
@@ -687,6 +769,7 @@ fn deserialize_line_info(data: &Vec<u8>) -> Vec<LineInfo> {
             // Add the deserialized LineInfo to the result
             result.push(LineInfo {
                 content,
+                file_name,
                 line_number,
                 start_address,
                 end_address,
@@ -697,5 +780,43 @@ fn deserialize_line_info(data: &Vec<u8>) -> Vec<LineInfo> {
         }
     }
 
-    result
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_line_info_round_trip() {
+        let line: LineInfo = LineInfo {
+            content: "addi $t0, $t0, 1".to_string(),
+            file_name: "fib.asm".to_string(),
+            line_number: 7,
+            start_address: 0x0040_0010,
+            end_address: 0x0040_0014,
+        };
+        let mut section: Vec<u8> = LINE_SECTION_HEADER.to_vec();
+        section.extend(line.to_bytes());
+
+        let lines: Vec<LineInfo> = deserialize_line_info(&section).unwrap();
+        assert_eq!(lines.len(), 1);
+        assert_eq!(lines[0].file_name, "fib.asm");
+        assert_eq!(lines[0].start_address, 0x0040_0010);
+        assert!(deserialize_line_info(&vec![]).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_line_info_rejects_other_formats() {
+        // before the format had a version, a section started with the first line's content
+        let old: Vec<u8> = b"main:\0\0\0\0\0\x01\0\0\0\0\0\0\0\0".to_vec();
+        assert!(deserialize_line_info(&old)
+            .unwrap_err()
+            .contains("before its format had a version"));
+
+        let newer: Vec<u8> = vec![LINE_SECTION_HEADER[0], LINE_SECTION_VERSION + 1];
+        assert!(deserialize_line_info(&newer)
+            .unwrap_err()
+            .contains("only version 2 can be read"));
+    }
 }
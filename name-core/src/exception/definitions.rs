@@ -3,7 +3,7 @@
 // The implementation here was derived entirely from this document: https://s3-eu-west-1.amazonaws.com/downloads-mips/documents/MD00090-2B-MIPS32PRA-AFP-06.02.pdf
 
 // This enum contains all the exceptions we could generate.
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum ExceptionType {
     // Interrupt,
     // TlbMod,
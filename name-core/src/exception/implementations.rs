@@ -95,7 +95,7 @@ impl Coprocessor0 {
         };
 
         // Clear the specified field
-        self.registers[reg] &= !mask;
+        self.registers[reg] &= !(mask << range.start);
 
         // Set the specified field
         self.registers[reg] |= (value & mask) << range.start;
@@ -107,6 +107,7 @@ getset!(current_mode, Register::Status, 3..5);
 getset!(exception_level, Register::Status, 0..1);
 getset!(exc_code, Register::Cause, 2..7);
 getset!(epc, Register::EPC, 0..32);
+getset!(cpu_num, Register::EBase, 0..10);
 
 #[cfg(test)]
 mod tests {
@@ -125,4 +126,13 @@ mod tests {
         cop0.registers[12] |= 0xABAB << 5;
         assert_eq!(cop0.get_bit_field(Register::Status, 5..(5 + 4 * 4)), 0xABAB);
     }
+
+    #[test]
+    fn test_set_bit_field_replaces_old_value() {
+        let mut cop0 = Coprocessor0::default();
+        // a syscall followed by a bad fetch
+        cop0.set_exc_code(0x08);
+        cop0.set_exc_code(0x04);
+        assert_eq!(cop0.get_exc_code(), 0x04);
+    }
 }
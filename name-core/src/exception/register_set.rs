@@ -1,4 +1,8 @@
+use super::constants::{KERNEL_MODE, USER_MODE};
+use super::definitions::ExceptionType;
 use super::registers::Register;
+use std::ops::Range;
+
 /// The table contained in this file defines the details for every register NAME needs to use in Coprocessor 0.
 /// This simplifies the Register and Select field representation,
 /// as the MIPS standard has overloaded meanings for certain registers in certain contexts.
@@ -10,6 +14,61 @@ pub struct Cp0RegisterInformation {
     It is largely unused, except for certain instructions.
     It is included for now. */
     _select: usize,
+    /// The bit fields worth showing to a user, in the order they should be shown.
+    fields: &'static [Cp0Field],
+}
+
+/// A named bit field inside a Coprocessor 0 register.
+pub struct Cp0Field {
+    pub name: &'static str,
+    pub bits: Range<usize>,
+    /// Spells out what a value in this field means, for fields where the number alone isn't very telling.
+    pub describe: Option<fn(u32) -> String>,
+}
+
+impl Cp0RegisterInformation {
+    pub fn name(&self) -> Register {
+        self.name
+    }
+
+    pub fn register(&self) -> usize {
+        self.register
+    }
+
+    pub fn fields(&self) -> &'static [Cp0Field] {
+        self.fields
+    }
+}
+
+impl Cp0Field {
+    /// Pulls this field's value out of the whole register.
+    pub fn extract(&self, register_value: u32) -> u32 {
+        let mask: u32 = if self.bits.len() >= 32 {
+            u32::MAX
+        } else {
+            (1u32 << self.bits.len()) - 1
+        };
+        (register_value >> self.bits.start) & mask
+    }
+}
+
+fn describe_mode(value: u32) -> String {
+    match value {
+        KERNEL_MODE => "kernel".to_string(),
+        USER_MODE => "user".to_string(),
+        _ => "supervisor".to_string(),
+    }
+}
+
+fn describe_exc_code(value: u32) -> String {
+    // 0 would be an interrupt, which NAME never raises
+    if value == 0 {
+        return "none".to_string();
+    }
+    match ExceptionType::try_from(value) {
+        Ok(exception_type) => format!("{:?}", exception_type),
+        Err(_) => "unknown".to_string(),
+    }
 }
 
 /// This helper function allows for quick translation from a Register to a usize.
@@ -26,15 +85,44 @@ pub const CP0_REGISTER_INFO: &[Cp0RegisterInformation] = &[
         name: Register::Status,
         register: 12,
         _select: 0,
+        fields: &[
+            Cp0Field {
+                name: "KSU",
+                bits: 3..5,
+                describe: Some(describe_mode),
+            },
+            Cp0Field {
+                name: "EXL",
+                bits: 0..1,
+                describe: None,
+            },
+        ],
     },
     Cp0RegisterInformation {
         name: Register::Cause,
         register: 13,
         _select: 0,
+        fields: &[Cp0Field {
+            name: "ExcCode",
+            bits: 2..7,
+            describe: Some(describe_exc_code),
+        }],
     },
     Cp0RegisterInformation {
         name: Register::EPC,
         register: 14,
         _select: 0,
+        fields: &[],
+    },
+    // Selects aren't kept apart, so register 15 holds EBase rather than PRId.
+    Cp0RegisterInformation {
+        name: Register::EBase,
+        register: 15,
+        _select: 1,
+        fields: &[Cp0Field {
+            name: "CPUNum",
+            bits: 0..10,
+            describe: None,
+        }],
     },
 ];
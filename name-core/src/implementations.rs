@@ -1,23 +1,32 @@
-use crate::constants::{REGISTERS, MIPS_TEXT_START_ADDR};
+use crate::constants::{
+    MIPS_ADDRESS_ALIGNMENT, MIPS_STACK_END_ADDR, MIPS_TEXT_START_ADDR, REGISTERS,
+};
+use crate::debug::debug_utils::{DebuggerState, DisplayFormat};
+use crate::instruction::RawInstruction;
 use crate::structs::{
-    Coprocessor0, Processor, ProgramState, /*, OperatingSystem*/
+    CallFrame, Coprocessor0, Processor, ProgramState, Register, /*, OperatingSystem*/
 };
 // use crate::instruction::instruction_set;
+use std::io::{stdout, IsTerminal};
 
 impl Default for Processor {
     fn default() -> Self {
-        Self {
-            pc: MIPS_TEXT_START_ADDR,
-            general_purpose_registers: [0u32; 32],
-        }
+        Processor::new(MIPS_TEXT_START_ADDR)
     }
 }
 
 impl Processor {
     pub fn new(entry: u32) -> Self {
+        let mut general_purpose_registers: [u32; 32] = [0; 32];
+        // $sp starts at the top of the stack so programs can push right away
+        general_purpose_registers[Register::Sp as usize] = MIPS_STACK_END_ADDR;
+
         Processor {
             pc: entry,
-            general_purpose_registers: [0; 32],
+            general_purpose_registers,
+            hi: 0,
+            lo: 0,
+            linked_address: None,
         }
     }
 }
@@ -30,60 +39,23 @@ impl Coprocessor0 {
             debug_mode: false,
         }
     }
-}
-
-impl ProgramState {
-    pub fn insert_breakpoint(&mut self, address: u32, bp_num: usize) -> Result<u32, String> {
-        // least vulnerable code ever
-
-        if !self.memory.allows_execution_of(address) {
-            return Err(format!(" - Address 0x{:x} is out of bounds.", address));
-        }
-
-        // get offset from address so we can manipulate the data in memory
-
-        let mut old_instruction_word: [u8; 4] = [0; 4];
-
-        // craft the break instruction (i.e. stick what we need to into `code`)
-        // TODO: for the love of God make this fit more with the codebase
-        let break_inst: u32 = ((bp_num as u32) << 6) | 0b001101;
-
-        // stick the crafted break instruction into memory
-        // and in the process, grab the old instruction
-        for i in 0..4 {
-            // this looks like gobbledygook, but here's what it's doing:
-            // take the last 8 bits in the break instruction.
-            // make that into a byte and store it in the data.
-            // shift the instruction to the right so that we can take the second-to-last byte.
-            // so on so forth
-            let break_inst_byte: u8 = (break_inst >> (24 - 8 * i)) as u8;
-            // nab the original instruction that was there before to be returned
-            old_instruction_word[i] = match self.memory.read_byte(address + i as u32) {
-                Ok(byte) => byte,
-                Err(e) => {
-                    return Err(format!("{e}"));
-                }
-            };
-            // replace it with the break instruction
-            match self.memory.set_byte(address + i as u32, break_inst_byte) {
-                Ok(_) => continue,
-                Err(e) => {
-                    return Err(format!("{e}"));
-                }
-            };
-        }
-
-        let mut old_instruction: u32 = 0;
-        for i in 0..4 {
-            old_instruction |= ((old_instruction_word[i] as u32) << (24 - 8 * i)) as u32;
-            // println!("{:x}", old_instruction_word[i]);
-        }
 
-        Ok(old_instruction)
+    /// Coprocessor 0 for one core of several, with the core's number in EBase.
+    pub fn for_core(cpu_num: u32) -> Self {
+        let mut cp0: Coprocessor0 = Coprocessor0::new();
+        cp0.set_cpu_num(cpu_num);
+        cp0
     }
+}
 
+impl ProgramState {
     /// Prints the values of all registers at once. Invoked by "pa" in the CLI.
-    pub fn print_all_registers(&mut self, db_args: &Vec<String>) -> Result<(), String> {
+    /// Registers that changed since execution last stopped are highlighted (or marked with * when not on a terminal).
+    pub fn print_all_registers(
+        &mut self,
+        debugger_state: &DebuggerState,
+        db_args: &Vec<String>,
+    ) -> Result<(), String> {
         if db_args.len() > 1 {
             // this outputs a lot so make sure the user actually meant to type pa and not pb or p or something
             // made it > so we can use this function to do register_dump()
@@ -93,25 +65,171 @@ impl ProgramState {
             ));
         }
 
-        println!("{:>5}: {:08x}", "$pc", self.cpu.pc);
+        let format: DisplayFormat = debugger_state.display_format;
+        let previous: Option<&Processor> = debugger_state.registers_at_last_stop.as_ref();
+        let highlight: bool = stdout().is_terminal();
 
-        // for register in Register.values() {
-        for register in REGISTERS {
-            // change this to loop through the enum in name-core::structs instead?
-            let idx: usize = REGISTERS.iter().position(|&x| x == register).unwrap();
-            println!(
-                "{:>5}: {:08x}",
+        let print_register = |name: &str, value: u32, old_value: Option<u32>| {
+            let text: String = format.format(value);
+            match old_value {
+                Some(old_value) if old_value != value && highlight => {
+                    println!("{:>5}: \x1b[1;33m{}\x1b[0m", name, text)
+                }
+                Some(old_value) if old_value != value => println!("{:>5}: {} *", name, text),
+                _ => println!("{:>5}: {}", name, text),
+            }
+        };
+
+        print_register("$pc", self.cpu.pc, previous.map(|cpu| cpu.pc));
+        for (idx, register) in REGISTERS.iter().enumerate() {
+            print_register(
                 register,
-                self.cpu.general_purpose_registers[idx] // register, program_state.cpu.general_purpose_registers[register]
+                self.cpu.general_purpose_registers[idx],
+                previous.map(|cpu| cpu.general_purpose_registers[idx]),
             );
         }
+        print_register("$hi", self.cpu.hi, previous.map(|cpu| cpu.hi));
+        print_register("$lo", self.cpu.lo, previous.map(|cpu| cpu.lo));
         Ok(())
     }
 
-    pub fn register_dump(&mut self) {
-        match self.print_all_registers(&Vec::new()) {
+    /// Keeps the shadow call stack in sync after an instruction has executed.
+    /// address is where the instruction was fetched from; the pc should already reflect the instruction's effects.
+    pub fn update_call_stack(&mut self, instruction: RawInstruction, address: u32) {
+        // a call that faulted never happened
+        if self.is_exception() {
+            return;
+        }
+
+        if instruction.is_call() {
+            self.call_stack.push(CallFrame {
+                call_site: address,
+                function_address: self.cpu.pc,
+                return_address: address.wrapping_add(MIPS_ADDRESS_ALIGNMENT),
+            });
+        } else if instruction.is_return() {
+            // Usually this is the innermost frame, but if a function skipped its jr $ra (say, by jumping out)
+            // the frames it abandoned are discarded along with it. A return nobody called is ignored.
+            if let Some(idx) = self
+                .call_stack
+                .iter()
+                .rposition(|frame| frame.return_address == self.cpu.pc)
+            {
+                self.call_stack.truncate(idx);
+            }
+        }
+    }
+
+    pub fn register_dump(&mut self, debugger_state: &DebuggerState) {
+        match self.print_all_registers(debugger_state, &Vec::new()) {
             Ok(_) => {}
             Err(e) => eprintln!("{e}"),
         };
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constants::MIPS_DATA_START_ADDR;
+    use crate::debug::debug_utils::single_step;
+    use crate::structs::{Memory, MemoryPermissions};
+
+    #[test]
+    fn test_update_call_stack() {
+        let mut program_state = ProgramState::new(
+            Processor::new(MIPS_TEXT_START_ADDR),
+            Memory::new(vec![], vec![0; 4]),
+        );
+        let jal: RawInstruction = RawInstruction::new(0x0C10_0008);
+        let jr_ra: RawInstruction = RawInstruction::new(0x03E0_0008);
+
+        // main calls f at 0x00400020, which calls itself from 0x00400030
+        program_state.cpu.pc = 0x00400020;
+        program_state.update_call_stack(jal, 0x00400004);
+        program_state.update_call_stack(jal, 0x00400030);
+        assert_eq!(program_state.call_stack.len(), 2);
+        assert_eq!(
+            program_state.call_stack[1],
+            CallFrame {
+                call_site: 0x00400030,
+                function_address: 0x00400020,
+                return_address: 0x00400034,
+            }
+        );
+
+        // the inner call returns
+        program_state.cpu.pc = 0x00400034;
+        program_state.update_call_stack(jr_ra, 0x00400040);
+        assert_eq!(program_state.call_stack.len(), 1);
+
+        // a return to somewhere nobody called from leaves the stack alone
+        program_state.cpu.pc = 0x00400100;
+        program_state.update_call_stack(jr_ra, 0x00400040);
+        assert_eq!(program_state.call_stack.len(), 1);
+
+        program_state.cpu.pc = 0x00400008;
+        program_state.update_call_stack(jr_ra, 0x00400040);
+        assert!(program_state.call_stack.is_empty());
+    }
+
+    #[test]
+    fn test_self_modifying_code() {
+        // sw $t0, 8($t1); nop; nop, where the second nop gets replaced by addi $t2, $zero, 7
+        let text: Vec<u8> = vec![0xAD, 0x28, 0x00, 0x08, 0, 0, 0, 0, 0, 0, 0, 0];
+        let run = |permissions: MemoryPermissions| {
+            let mut memory: Memory = Memory::new(vec![], text.clone());
+            memory.layout.permissions = permissions;
+            let mut program_state =
+                ProgramState::new(Processor::new(MIPS_TEXT_START_ADDR), memory);
+            program_state.cpu.general_purpose_registers[8] = 0x200A_0007;
+            program_state.cpu.general_purpose_registers[9] = MIPS_TEXT_START_ADDR;
+            for _ in 0..3 {
+                if !program_state.is_exception() {
+                    single_step(&Vec::new(), &mut program_state);
+                }
+            }
+            program_state
+        };
+
+        let refused = run(MemoryPermissions::default());
+        assert!(refused.is_exception());
+        assert_eq!(refused.memory.text[8..], [0, 0, 0, 0]);
+
+        let patched = run(MemoryPermissions {
+            writable_text: true,
+            ..Default::default()
+        });
+        assert!(!patched.is_exception());
+        assert_eq!(patched.cpu.general_purpose_registers[10], 7);
+    }
+
+    #[test]
+    fn test_executable_data() {
+        // jr $t1 into .data, which holds addi $t2, $zero, 9
+        let run = |permissions: MemoryPermissions| {
+            let mut memory: Memory =
+                Memory::new(vec![0x20, 0x0A, 0x00, 0x09], vec![0x01, 0x20, 0x00, 0x08]);
+            memory.layout.permissions = permissions;
+            let mut program_state =
+                ProgramState::new(Processor::new(MIPS_TEXT_START_ADDR), memory);
+            program_state.cpu.general_purpose_registers[9] = MIPS_DATA_START_ADDR;
+            for _ in 0..2 {
+                if !program_state.is_exception() {
+                    single_step(&Vec::new(), &mut program_state);
+                }
+            }
+            program_state
+        };
+
+        assert!(run(MemoryPermissions::default()).is_exception());
+
+        let jitted = run(MemoryPermissions {
+            executable_data: true,
+            ..Default::default()
+        });
+        assert!(!jitted.is_exception());
+        assert_eq!(jitted.cpu.general_purpose_registers[10], 9);
+        assert!(!jitted.memory.allows_execution_of(MIPS_DATA_START_ADDR + 4));
+    }
+}
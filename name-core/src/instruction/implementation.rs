@@ -89,6 +89,66 @@ pub fn break_instr(program_state: &mut ProgramState, _args: RArgs) -> () {
     program_state.set_exception(ExceptionType::Breakpoint);
 }
 
+// 0x10 - mfhi
+pub fn mfhi(program_state: &mut ProgramState, args: RArgs) -> () {
+    program_state.cpu.general_purpose_registers[args.rd as usize] = program_state.cpu.hi;
+}
+
+// 0x11 - mthi
+pub fn mthi(program_state: &mut ProgramState, args: RArgs) -> () {
+    program_state.cpu.hi = program_state.cpu.general_purpose_registers[args.rs as usize];
+}
+
+// 0x12 - mflo
+pub fn mflo(program_state: &mut ProgramState, args: RArgs) -> () {
+    program_state.cpu.general_purpose_registers[args.rd as usize] = program_state.cpu.lo;
+}
+
+// 0x13 - mtlo
+pub fn mtlo(program_state: &mut ProgramState, args: RArgs) -> () {
+    program_state.cpu.lo = program_state.cpu.general_purpose_registers[args.rs as usize];
+}
+
+// 0x18 - mult
+pub fn mult(program_state: &mut ProgramState, args: RArgs) -> () {
+    let product: i64 =
+        i64::from(program_state.cpu.general_purpose_registers[args.rs as usize] as i32)
+            * i64::from(program_state.cpu.general_purpose_registers[args.rt as usize] as i32);
+    program_state.cpu.hi = (product >> 32) as u32;
+    program_state.cpu.lo = product as u32;
+}
+
+// 0x19 - multu
+pub fn multu(program_state: &mut ProgramState, args: RArgs) -> () {
+    let product: u64 = u64::from(program_state.cpu.general_purpose_registers[args.rs as usize])
+        * u64::from(program_state.cpu.general_purpose_registers[args.rt as usize]);
+    program_state.cpu.hi = (product >> 32) as u32;
+    program_state.cpu.lo = product as u32;
+}
+
+// 0x1A - div
+pub fn div(program_state: &mut ProgramState, args: RArgs) -> () {
+    let dividend: i32 = program_state.cpu.general_purpose_registers[args.rs as usize] as i32;
+    let divisor: i32 = program_state.cpu.general_purpose_registers[args.rt as usize] as i32;
+
+    // Dividing by zero doesn't trap; HI and LO are just left as they were.
+    if divisor != 0 {
+        program_state.cpu.lo = dividend.wrapping_div(divisor) as u32;
+        program_state.cpu.hi = dividend.wrapping_rem(divisor) as u32;
+    }
+}
+
+// 0x1B - divu
+pub fn divu(program_state: &mut ProgramState, args: RArgs) -> () {
+    let dividend: u32 = program_state.cpu.general_purpose_registers[args.rs as usize];
+    let divisor: u32 = program_state.cpu.general_purpose_registers[args.rt as usize];
+
+    if divisor != 0 {
+        program_state.cpu.lo = dividend / divisor;
+        program_state.cpu.hi = dividend % divisor;
+    }
+}
+
 // 0x20 - add
 pub fn add(program_state: &mut ProgramState, args: RArgs) -> () {
     program_state.cpu.general_purpose_registers[args.rd as usize] =
@@ -318,8 +378,7 @@ pub fn addi(program_state: &mut ProgramState, args: IArgs) -> () {
 pub fn addiu(program_state: &mut ProgramState, args: IArgs) -> () {
     program_state.cpu.general_purpose_registers[args.rt as usize] =
         program_state.cpu.general_purpose_registers[args.rs as usize]
-            .overflowing_add(args.imm)
-            .0;
+            .wrapping_add(args.imm as i16 as i32 as u32);
 }
 
 // 0x0C - andi
@@ -349,27 +408,25 @@ pub fn lui(program_state: &mut ProgramState, args: IArgs) -> () {
 // 0x20 - lb
 pub fn lb(program_state: &mut ProgramState, args: IArgs) -> () {
     let temp: u32 = (program_state.cpu.general_purpose_registers[args.rs as usize] as i32
-        + args.imm as i32) as u32;
+        + args.imm as i16 as i32) as u32;
 
     if !program_state.memory.allows_read_from(temp) {
         // TODO: Use a function which sets the proper values in cp0 for us
         program_state.set_exception(ExceptionType::AddressExceptionLoad);
         return;
     }
-    let return_byte: u8 = match program_state.memory.read_byte(temp) {
-        Ok(b) => b,
-        Err(_) => {
-            program_state.set_exception(ExceptionType::AddressExceptionLoad);
-            return;
+    match program_state.load(temp, 1) {
+        Ok(byte) => {
+            program_state.cpu.general_purpose_registers[args.rt as usize] = byte as u8 as i8 as u32
         }
-    };
-    program_state.cpu.general_purpose_registers[args.rt as usize] = return_byte as i8 as u32;
+        Err(_) => program_state.set_exception(ExceptionType::AddressExceptionLoad),
+    }
 }
 
 // 0x21 - lh
 pub fn lh(program_state: &mut ProgramState, args: IArgs) -> () {
     let temp = (program_state.cpu.general_purpose_registers[args.rs as usize] as i32
-        + args.imm as i32) as u32;
+        + args.imm as i16 as i32) as u32;
 
     if temp % 2 != 0 {
         program_state.set_exception(ExceptionType::AddressExceptionLoad);
@@ -384,21 +441,19 @@ pub fn lh(program_state: &mut ProgramState, args: IArgs) -> () {
     }
 
     // Checks passed. Load half.
-    let mut result_half: u16 = 0;
-    for offset in 0..2 {
-        match program_state.memory.read_byte(temp + offset) {
-            Ok(b) => result_half |= (b as u16) << (8 - (offset * 8)),
-            Err(_) => program_state.set_exception(ExceptionType::AddressExceptionLoad),
+    match program_state.load(temp, 2) {
+        Ok(half) => {
+            program_state.cpu.general_purpose_registers[args.rt as usize] =
+                half as u16 as i16 as u32
         }
+        Err(_) => program_state.set_exception(ExceptionType::AddressExceptionLoad),
     }
-
-    program_state.cpu.general_purpose_registers[args.rt as usize] = result_half as i16 as u32;
 }
 
 // 0x23 - lw
 pub fn lw(program_state: &mut ProgramState, args: IArgs) -> () {
     let temp = (program_state.cpu.general_purpose_registers[args.rs as usize] as i32
-        + args.imm as i32) as u32;
+        + args.imm as i16 as i32) as u32;
 
     if temp % 4 != 0 {
         program_state.set_exception(ExceptionType::AddressExceptionLoad);
@@ -413,41 +468,32 @@ pub fn lw(program_state: &mut ProgramState, args: IArgs) -> () {
     }
 
     // Checks passed. Load word.
-    let mut result_word: u32 = 0;
-    for offset in 0..4 {
-        match program_state.memory.read_byte(temp + offset) {
-            Ok(b) => result_word |= (b as u32) << (24 - (offset * 8)),
-            Err(_) => program_state.set_exception(ExceptionType::AddressExceptionLoad),
-        }
+    match program_state.load(temp, 4) {
+        Ok(word) => program_state.cpu.general_purpose_registers[args.rt as usize] = word,
+        Err(_) => program_state.set_exception(ExceptionType::AddressExceptionLoad),
     }
-
-    program_state.cpu.general_purpose_registers[args.rt as usize] = result_word;
 }
 
 // 0x24 - lbu
 pub fn lbu(program_state: &mut ProgramState, args: IArgs) -> () {
     let temp: u32 = (program_state.cpu.general_purpose_registers[args.rs as usize] as i32
-        + args.imm as i32) as u32;
+        + args.imm as i16 as i32) as u32;
 
     if !program_state.memory.allows_read_from(temp) {
         // TODO: Use a function which sets the proper values in cp0 for us
         program_state.set_exception(ExceptionType::AddressExceptionLoad);
         return;
     }
-    let return_byte: u8 = match program_state.memory.read_byte(temp) {
-        Ok(b) => b,
-        Err(_) => {
-            program_state.set_exception(ExceptionType::AddressExceptionLoad);
-            return;
-        }
-    };
-    program_state.cpu.general_purpose_registers[args.rt as usize] = return_byte as u32;
+    match program_state.load(temp, 1) {
+        Ok(byte) => program_state.cpu.general_purpose_registers[args.rt as usize] = byte,
+        Err(_) => program_state.set_exception(ExceptionType::AddressExceptionLoad),
+    }
 }
 
 // 0x25 - lhu
 pub fn lhu(program_state: &mut ProgramState, args: IArgs) -> () {
     let temp = (program_state.cpu.general_purpose_registers[args.rs as usize] as i32
-        + args.imm as i32) as u32;
+        + args.imm as i16 as i32) as u32;
 
     if temp % 2 != 0 {
         program_state.set_exception(ExceptionType::AddressExceptionLoad);
@@ -462,30 +508,26 @@ pub fn lhu(program_state: &mut ProgramState, args: IArgs) -> () {
     }
 
     // Checks passed. Load half.
-    let mut result_half: u16 = 0;
-    for offset in 0..2 {
-        match program_state.memory.read_byte(temp + offset) {
-            Ok(b) => result_half |= (b as u16) << (8 - (offset * 8)),
-            Err(_) => program_state.set_exception(ExceptionType::AddressExceptionLoad),
-        }
+    match program_state.load(temp, 2) {
+        Ok(half) => program_state.cpu.general_purpose_registers[args.rt as usize] = half,
+        Err(_) => program_state.set_exception(ExceptionType::AddressExceptionLoad),
     }
-
-    program_state.cpu.general_purpose_registers[args.rt as usize] = result_half as u32;
 }
 
 // 0x28 - sb
 pub fn sb(program_state: &mut ProgramState, args: IArgs) -> () {
     let temp = (program_state.cpu.general_purpose_registers[args.rs as usize] as i32
-        + args.imm as i32) as u32;
+        + args.imm as i16 as i32) as u32;
 
     if !program_state.memory.allows_write_to(temp) {
         program_state.set_exception(ExceptionType::AddressExceptionStore);
         return;
     }
 
-    match program_state.memory.set_byte(
+    match program_state.store(
         temp,
-        program_state.cpu.general_purpose_registers[args.rt as usize] as u8,
+        1,
+        program_state.cpu.general_purpose_registers[args.rt as usize],
     ) {
         Ok(_) => (),
         Err(_) => program_state.set_exception(ExceptionType::AddressExceptionStore),
@@ -495,7 +537,7 @@ pub fn sb(program_state: &mut ProgramState, args: IArgs) -> () {
 // 0x29 - sh
 pub fn sh(program_state: &mut ProgramState, args: IArgs) -> () {
     let temp = (program_state.cpu.general_purpose_registers[args.rs as usize] as i32
-        + args.imm as i32) as u32;
+        + args.imm as i16 as i32) as u32;
 
     if temp % 2 != 0 {
         program_state.set_exception(ExceptionType::AddressExceptionStore);
@@ -513,25 +555,16 @@ pub fn sh(program_state: &mut ProgramState, args: IArgs) -> () {
     let value: u32 = program_state.cpu.general_purpose_registers[args.rt as usize];
 
     // Checks passed. Store half.
-    for offset in 0..2 {
-        // Shift/mask value to get correct byte
-        let new_byte: u8 = ((value >> (offset * 8)) & 0xFF) as u8;
-        // Write it to correct location
-        match program_state.memory.set_byte(temp + (1 - offset), new_byte) {
-            Ok(_) => (),
-            Err(_) => {
-                // If write failed, trigger an exception
-                program_state.set_exception(ExceptionType::AddressExceptionStore);
-                return;
-            }
-        }
+    if program_state.store(temp, 2, value).is_err() {
+        // If write failed, trigger an exception
+        program_state.set_exception(ExceptionType::AddressExceptionStore);
     }
 }
 
 // 0x2b - sw
 pub fn sw(program_state: &mut ProgramState, args: IArgs) -> () {
     let temp = (program_state.cpu.general_purpose_registers[args.rs as usize] as i32
-        + args.imm as i32) as u32;
+        + args.imm as i16 as i32) as u32;
 
     if temp % 4 != 0 {
         program_state.set_exception(ExceptionType::AddressExceptionStore);
@@ -549,17 +582,174 @@ pub fn sw(program_state: &mut ProgramState, args: IArgs) -> () {
     let value: u32 = program_state.cpu.general_purpose_registers[args.rt as usize];
 
     // Checks passed. Store word.
-    for offset in 0..4 {
-        // Shift/mask value to get correct byte
-        let new_byte: u8 = ((value >> (offset * 8)) & 0xFF) as u8;
-        // Write it to correct location
-        match program_state.memory.set_byte(temp + (3 - offset), new_byte) {
-            Ok(_) => (),
-            Err(_) => {
-                // If write failed, trigger an exception
-                program_state.set_exception(ExceptionType::AddressExceptionStore);
-                return;
-            }
-        }
+    if program_state.store(temp, 4, value).is_err() {
+        // If write failed, trigger an exception
+        program_state.set_exception(ExceptionType::AddressExceptionStore);
+    }
+}
+
+// 0x30 - ll
+pub fn ll(program_state: &mut ProgramState, args: IArgs) -> () {
+    let temp = (program_state.cpu.general_purpose_registers[args.rs as usize] as i32
+        + args.imm as i16 as i32) as u32;
+
+    lw(program_state, args);
+    if !program_state.is_exception() {
+        program_state.cpu.linked_address = Some(temp);
+    }
+}
+
+// 0x38 - sc
+pub fn sc(program_state: &mut ProgramState, args: IArgs) -> () {
+    let temp = (program_state.cpu.general_purpose_registers[args.rs as usize] as i32
+        + args.imm as i16 as i32) as u32;
+
+    if temp % 4 != 0 {
+        program_state.set_exception(ExceptionType::AddressExceptionStore);
+        return;
+    }
+
+    // The link is used up either way. If another core wrote the word since the ll, it's already gone.
+    if program_state.cpu.linked_address.take() != Some(temp) {
+        program_state.cpu.general_purpose_registers[args.rt as usize] = 0;
+        return;
+    }
+
+    let rt: usize = args.rt as usize;
+    sw(program_state, args);
+    if !program_state.is_exception() {
+        program_state.cpu.general_purpose_registers[rt] = 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::instruction::RawInstruction;
+    use crate::structs::{Memory, Processor};
+
+    fn i_args(raw: u32) -> IArgs {
+        IArgs::from(RawInstruction::new(raw))
+    }
+
+    fn r_args(raw: u32) -> RArgs {
+        RArgs::from(RawInstruction::new(raw))
+    }
+
+    /// Runs one instruction on $t0 and $t1, giving back HI and LO.
+    fn hi_lo(instruction: fn(&mut ProgramState, RArgs), raw: u32, t0: u32, t1: u32) -> (u32, u32) {
+        let mut program_state = ProgramState::default();
+        program_state.cpu.general_purpose_registers[8] = t0;
+        program_state.cpu.general_purpose_registers[9] = t1;
+        (program_state.cpu.hi, program_state.cpu.lo) = (0xAAAA_AAAA, 0x5555_5555);
+        instruction(&mut program_state, r_args(raw));
+        assert!(!program_state.is_exception());
+        (program_state.cpu.hi, program_state.cpu.lo)
+    }
+
+    #[test]
+    fn test_addiu() {
+        let mut program_state = ProgramState::default();
+
+        // addiu $t0, $t1, -4 sign-extends its immediate
+        program_state.cpu.general_purpose_registers[9] = 16;
+        addiu(&mut program_state, i_args(0x2528_FFFC));
+        assert_eq!(program_state.cpu.general_purpose_registers[8], 12);
+
+        // addiu $t0, $t1, 1 wraps around instead of trapping
+        program_state.cpu.general_purpose_registers[9] = u32::MAX;
+        addiu(&mut program_state, i_args(0x2528_0001));
+        assert_eq!(program_state.cpu.general_purpose_registers[8], 0);
+        assert!(!program_state.is_exception());
+    }
+
+    #[test]
+    fn test_loads_and_stores_with_negative_offsets() {
+        let mut program_state =
+            ProgramState::new(Processor::default(), Memory::new(vec![0; 16], vec![]));
+        let registers = &mut program_state.cpu.general_purpose_registers;
+        registers[8] = program_state.memory.layout.data_base + 8; // $t0
+        registers[9] = 0xFFFF_FF80; // $t1
+
+        // sw $t1, -4($t0) / lw $t2, -4($t0)
+        sw(&mut program_state, i_args(0xAD09_FFFC));
+        assert_eq!(program_state.memory.data[4..8], [0xFF, 0xFF, 0xFF, 0x80]);
+        lw(&mut program_state, i_args(0x8D0A_FFFC));
+        assert_eq!(program_state.cpu.general_purpose_registers[10], 0xFFFF_FF80);
+
+        // sh $t1, -6($t0) / lh $t2, -6($t0) / lhu $t2, -6($t0)
+        sh(&mut program_state, i_args(0xA509_FFFA));
+        assert_eq!(program_state.memory.data[2..4], [0xFF, 0x80]);
+        lh(&mut program_state, i_args(0x850A_FFFA));
+        assert_eq!(program_state.cpu.general_purpose_registers[10], 0xFFFF_FF80);
+        lhu(&mut program_state, i_args(0x950A_FFFA));
+        assert_eq!(program_state.cpu.general_purpose_registers[10], 0xFF80);
+
+        // sb $t1, -7($t0) / lb $t2, -7($t0) / lbu $t2, -7($t0)
+        sb(&mut program_state, i_args(0xA109_FFF9));
+        assert_eq!(program_state.memory.data[1], 0x80);
+        lb(&mut program_state, i_args(0x810A_FFF9));
+        assert_eq!(program_state.cpu.general_purpose_registers[10], 0xFFFF_FF80);
+        lbu(&mut program_state, i_args(0x910A_FFF9));
+        assert_eq!(program_state.cpu.general_purpose_registers[10], 0x80);
+
+        assert!(!program_state.is_exception());
+    }
+
+    #[test]
+    fn test_multiply() {
+        // mult $t0, $t1 / multu $t0, $t1: -3 * 2^30, then the same bits unsigned
+        assert_eq!(
+            hi_lo(mult, 0x0109_0018, 0xFFFF_FFFD, 0x4000_0000),
+            (0xFFFF_FFFF, 0x4000_0000)
+        );
+        assert_eq!(
+            hi_lo(multu, 0x0109_0019, 0xFFFF_FFFD, 0x4000_0000),
+            (0x3FFF_FFFF, 0x4000_0000)
+        );
+        assert_eq!(
+            hi_lo(mult, 0x0109_0018, 0x8000_0000, 0x8000_0000),
+            (0x4000_0000, 0)
+        );
+    }
+
+    #[test]
+    fn test_divide() {
+        // div $t0, $t1 truncates toward zero, with the remainder taking the dividend's sign
+        assert_eq!(
+            hi_lo(div, 0x0109_001A, (-7i32).cast_unsigned(), 2),
+            ((-1i32).cast_unsigned(), (-3i32).cast_unsigned())
+        );
+        // divu $t0, $t1 treats the same bits as unsigned
+        assert_eq!(
+            hi_lo(divu, 0x0109_001B, (-7i32).cast_unsigned(), 2),
+            (1, 0x7FFF_FFFC)
+        );
+        // i32::MIN / -1 overflows; it wraps instead of trapping
+        assert_eq!(
+            hi_lo(div, 0x0109_001A, 0x8000_0000, u32::MAX),
+            (0, 0x8000_0000)
+        );
+        // dividing by zero doesn't trap, and leaves HI and LO alone
+        assert_eq!(hi_lo(div, 0x0109_001A, 7, 0), (0xAAAA_AAAA, 0x5555_5555));
+        assert_eq!(hi_lo(divu, 0x0109_001B, 7, 0), (0xAAAA_AAAA, 0x5555_5555));
+    }
+
+    #[test]
+    fn test_move_to_and_from_hi_lo() {
+        let mut program_state = ProgramState::default();
+        program_state.cpu.general_purpose_registers[8] = 12; // $t0
+        program_state.cpu.general_purpose_registers[9] = 34; // $t1
+
+        // mthi $t0 / mtlo $t1
+        mthi(&mut program_state, r_args(0x0100_0011));
+        mtlo(&mut program_state, r_args(0x0120_0013));
+        assert_eq!((program_state.cpu.hi, program_state.cpu.lo), (12, 34));
+
+        // mfhi $t2 / mflo $t3
+        mfhi(&mut program_state, r_args(0x0000_5010));
+        mflo(&mut program_state, r_args(0x0000_5812));
+        assert_eq!(program_state.cpu.general_purpose_registers[10], 12);
+        assert_eq!(program_state.cpu.general_purpose_registers[11], 34);
     }
 }
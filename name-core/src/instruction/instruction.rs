@@ -68,6 +68,134 @@ impl RawInstruction {
             base
         }
     }
+
+    /// Checks whether this instruction calls a function (jal or jalr).
+    pub fn is_call(self) -> bool {
+        self.get_opcode() == 0x03 || (self.get_opcode() == 0x00 && self.get_funct() == 0x09)
+    }
+
+    /// Checks whether this instruction returns from a function (jr $ra).
+    pub fn is_return(self) -> bool {
+        self.get_opcode() == 0x00 && self.get_funct() == 0x08 && self.get_rs() == 31
+    }
+
+    /// Checks whether this instruction is a conditional branch (beq, bne, blez, bgtz, or a REGIMM branch).
+    pub fn is_branch(self) -> bool {
+        matches!(self.get_opcode(), 0x04..=0x07) || self.is_regimm()
+    }
+
+    /// Checks whether this instruction always transfers control (j, jal, jr, or jalr).
+    pub fn is_jump(self) -> bool {
+        self.is_jtype() || (self.get_opcode() == 0x00 && matches!(self.get_funct(), 0x08 | 0x09))
+    }
+
+    /// Lists the general purpose registers this instruction reads. $0 is left out, since it never changes.
+    /// A syscall reads $v0 for the service number and $a0-$a2 for its arguments.
+    pub fn source_registers(self) -> Vec<u32> {
+        let registers: Vec<u32> = match self.get_opcode() {
+            0x00 => match self.get_funct() {
+                // sll, srl
+                0x00 | 0x02 => vec![self.get_rt()],
+                // jr, jalr
+                0x08 | 0x09 => vec![self.get_rs()],
+                // syscall
+                0x0C => vec![2, 4, 5, 6],
+                // break, mfhi, mflo
+                0x0D | 0x10 | 0x12 => vec![],
+                _ => vec![self.get_rs(), self.get_rt()],
+            },
+            // j, jal, lui
+            0x02 | 0x03 | 0x0F => vec![],
+            // beq, bne, stores
+            0x04 | 0x05 | 0x28..=0x2E => vec![self.get_rs(), self.get_rt()],
+            _ => vec![self.get_rs()],
+        };
+
+        registers.into_iter().filter(|&r| r != 0).collect()
+    }
+
+    /// Gives the general purpose register this instruction writes, if it writes one other than $0.
+    pub fn destination_register(self) -> Option<u32> {
+        let register: u32 = match self.get_opcode() {
+            0x00 => match self.get_funct() {
+                // jr, break
+                0x08 | 0x0D => return None,
+                // syscall results come back in $v0
+                0x0C => 2,
+                _ => self.get_rd(),
+            },
+            // jal
+            0x03 => 31,
+            // j, branches, stores
+            0x01 | 0x02 | 0x04..=0x07 | 0x28..=0x2E => return None,
+            _ => self.get_rt(),
+        };
+
+        Some(register).filter(|&r| r != 0)
+    }
+
+    /// Determines which memory this instruction would touch if it were executed with the given register values.
+    /// Only loads and stores access memory directly; everything else returns None.
+    pub fn get_memory_access(self, registers: &[u32; 32]) -> Option<MemoryAccess> {
+        let (size, access_type) = match self.get_opcode() {
+            // lb, lbu
+            0x20 | 0x24 => (1, AccessType::Read),
+            // lh, lhu
+            0x21 | 0x25 => (2, AccessType::Read),
+            // lw, ll
+            0x23 | 0x30 => (4, AccessType::Read),
+            // sb
+            0x28 => (1, AccessType::Write),
+            // sh
+            0x29 => (2, AccessType::Write),
+            // sw, sc (even when it fails)
+            0x2B | 0x38 => (4, AccessType::Write),
+            _ => return None,
+        };
+
+        // The effective address is base + sign-extended offset, exactly as the load/store implementations compute it.
+        let address = registers[self.get_rs() as usize]
+            .wrapping_add(self.get_immediate() as i16 as i32 as u32);
+
+        Some(MemoryAccess {
+            address,
+            size,
+            access_type,
+        })
+    }
+
+    /// Checks whether this is an sc that failed, going by the registers it left behind.
+    /// A failed sc puts 0 in rt and doesn't store anything, even though `get_memory_access` says it would.
+    #[must_use]
+    pub fn is_failed_sc(self, registers: &[u32; 32]) -> bool {
+        // sc
+        self.get_opcode() == 0x38 && registers[self.get_rt() as usize] == 0
+    }
+}
+
+/// Whether a memory access reads or writes.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AccessType {
+    Read,
+    Write,
+}
+
+/// A single load or store, described by the first byte touched and the number of bytes touched.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MemoryAccess {
+    pub address: u32,
+    pub size: u32,
+    pub access_type: AccessType,
+}
+
+impl MemoryAccess {
+    /// Checks whether this access touches any byte in [start, start + size).
+    pub fn overlaps(&self, start: u32, size: u32) -> bool {
+        let access_end = self.address as u64 + self.size as u64;
+        let range_end = start as u64 + size as u64;
+
+        (self.address as u64) < range_end && (start as u64) < access_end
+    }
 }
 
 pub struct IArgs {
@@ -123,3 +251,123 @@ impl From<RawInstruction> for RArgs {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_get_memory_access() {
+        let mut registers = [0u32; 32];
+        registers[29] = 0x7ffffe00;
+
+        // sw $ra, -4($sp)
+        let store = RawInstruction::new(0xAFBF_FFFC).get_memory_access(&registers);
+        assert_eq!(
+            store,
+            Some(MemoryAccess {
+                address: 0x7ffffdfc,
+                size: 4,
+                access_type: AccessType::Write,
+            })
+        );
+
+        // lbu $t0, 3($sp)
+        let load = RawInstruction::new(0x93A8_0003).get_memory_access(&registers);
+        assert_eq!(
+            load,
+            Some(MemoryAccess {
+                address: 0x7ffffe03,
+                size: 1,
+                access_type: AccessType::Read,
+            })
+        );
+
+        // add $t0, $t1, $t2 doesn't touch memory
+        assert_eq!(
+            RawInstruction::new(0x012A_4020).get_memory_access(&registers),
+            None
+        );
+    }
+
+    #[test]
+    fn test_is_failed_sc() {
+        let mut registers = [0u32; 32];
+
+        // sc $t0, 0($a0) left 0 in $t0
+        assert!(RawInstruction::new(0xE088_0000).is_failed_sc(&registers));
+        registers[8] = 1;
+        assert!(!RawInstruction::new(0xE088_0000).is_failed_sc(&registers));
+
+        // sw $t0, 0($a0) always stores
+        registers[8] = 0;
+        assert!(!RawInstruction::new(0xAC88_0000).is_failed_sc(&registers));
+    }
+
+    #[test]
+    fn test_is_call_and_is_return() {
+        // jal 0x00400000
+        assert!(RawInstruction::new(0x0C10_0000).is_call());
+        // jalr $t0
+        assert!(RawInstruction::new(0x0100_F809).is_call());
+        // jr $ra
+        assert!(RawInstruction::new(0x03E0_0008).is_return());
+        // jr $t0 jumps, but doesn't return
+        assert!(!RawInstruction::new(0x0100_0008).is_return());
+        // j 0x00400000
+        assert!(!RawInstruction::new(0x0810_0000).is_call());
+    }
+
+    #[test]
+    fn test_is_branch_and_is_jump() {
+        // beq $t0, $t1, 4
+        assert!(RawInstruction::new(0x1109_0004).is_branch());
+        // bgtz $t0, -2
+        assert!(RawInstruction::new(0x1D00_FFFE).is_branch());
+        // jal 0x00400000
+        assert!(RawInstruction::new(0x0C10_0000).is_jump());
+        // jr $ra
+        assert!(RawInstruction::new(0x03E0_0008).is_jump());
+        // add $t0, $t1, $t2 is neither
+        assert!(!RawInstruction::new(0x012A_4020).is_branch());
+        assert!(!RawInstruction::new(0x012A_4020).is_jump());
+    }
+
+    #[test]
+    fn test_source_and_destination_registers() {
+        // add $t0, $t1, $t2
+        let add = RawInstruction::new(0x012A_4020);
+        assert_eq!(add.source_registers(), vec![9, 10]);
+        assert_eq!(add.destination_register(), Some(8));
+
+        // sw $ra, -4($sp)
+        let store = RawInstruction::new(0xAFBF_FFFC);
+        assert_eq!(store.source_registers(), vec![29, 31]);
+        assert_eq!(store.destination_register(), None);
+
+        // lw $t3, 0($t0)
+        let load = RawInstruction::new(0x8D0B_0000);
+        assert_eq!(load.source_registers(), vec![8]);
+        assert_eq!(load.destination_register(), Some(11));
+
+        // jal 0x00400000
+        assert_eq!(RawInstruction::new(0x0C10_0000).destination_register(), Some(31));
+        // nop reads and writes only $0
+        assert_eq!(RawInstruction::new(0).source_registers(), Vec::<u32>::new());
+        assert_eq!(RawInstruction::new(0).destination_register(), None);
+    }
+
+    #[test]
+    fn test_memory_access_overlaps() {
+        let access = MemoryAccess {
+            address: 0x10010004,
+            size: 4,
+            access_type: AccessType::Read,
+        };
+
+        assert!(access.overlaps(0x10010000, 8));
+        assert!(access.overlaps(0x10010007, 1));
+        assert!(!access.overlaps(0x10010000, 4));
+        assert!(!access.overlaps(0x10010008, 4));
+    }
+}
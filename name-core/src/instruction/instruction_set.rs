@@ -134,6 +134,26 @@ pub static INSTRUCTION_SET: LazyLock<Vec<InstructionInformation>> = LazyLock::ne
             alt_args: None,
             relocation_type: None,
         },
+        InstructionInformation {
+            mnemonic: "div",
+            op_code: 0x00,
+            funct_code: Some(0x1A),
+            implementation: wrap_imp(implementation::div),
+            instruction_type: InstructionType::RType,
+            args: &[ArgumentType::Rs, ArgumentType::Rt],
+            alt_args: None,
+            relocation_type: None,
+        },
+        InstructionInformation {
+            mnemonic: "divu",
+            op_code: 0x00,
+            funct_code: Some(0x1B),
+            implementation: wrap_imp(implementation::divu),
+            instruction_type: InstructionType::RType,
+            args: &[ArgumentType::Rs, ArgumentType::Rt],
+            alt_args: None,
+            relocation_type: None,
+        },
         InstructionInformation {
             mnemonic: "j",
             op_code: 0x02,
@@ -230,6 +250,20 @@ pub static INSTRUCTION_SET: LazyLock<Vec<InstructionInformation>> = LazyLock::ne
             ]),
             relocation_type: None,
         },
+        InstructionInformation {
+            mnemonic: "ll",
+            op_code: 0x30,
+            funct_code: None,
+            implementation: wrap_imp(implementation::ll),
+            instruction_type: InstructionType::IType,
+            args: &[ArgumentType::Rt, ArgumentType::Immediate, ArgumentType::Rs],
+            alt_args: Some(&[
+                &[ArgumentType::Rt, ArgumentType::Rs],
+                &[ArgumentType::Rt, ArgumentType::Identifier, ArgumentType::Rs],
+                &[ArgumentType::Rt, ArgumentType::Identifier],
+            ]),
+            relocation_type: None,
+        },
         InstructionInformation {
             mnemonic: "lui",
             op_code: 0x0F,
@@ -254,26 +288,66 @@ pub static INSTRUCTION_SET: LazyLock<Vec<InstructionInformation>> = LazyLock::ne
             ]),
             relocation_type: None,
         },
-        /*
-          Instruction::InstructionInformation {
+        InstructionInformation {
             mnemonic: "mfhi",
             op_code: 0x00,
+            funct_code: Some(0x10),
+            implementation: wrap_imp(implementation::mfhi),
             instruction_type: InstructionType::RType,
-            opcode: None,
-            funct_code: Some(16),
             args: &[ArgumentType::Rd],
             alt_args: None,
+            relocation_type: None,
         },
-          Instruction::InstructionInformation {
+        InstructionInformation {
             mnemonic: "mflo",
             op_code: 0x00,
+            funct_code: Some(0x12),
+            implementation: wrap_imp(implementation::mflo),
             instruction_type: InstructionType::RType,
-            opcode: None,
-            funct_code: Some(18),
             args: &[ArgumentType::Rd],
             alt_args: None,
+            relocation_type: None,
+        },
+        InstructionInformation {
+            mnemonic: "mthi",
+            op_code: 0x00,
+            funct_code: Some(0x11),
+            implementation: wrap_imp(implementation::mthi),
+            instruction_type: InstructionType::RType,
+            args: &[ArgumentType::Rs],
+            alt_args: None,
+            relocation_type: None,
+        },
+        InstructionInformation {
+            mnemonic: "mtlo",
+            op_code: 0x00,
+            funct_code: Some(0x13),
+            implementation: wrap_imp(implementation::mtlo),
+            instruction_type: InstructionType::RType,
+            args: &[ArgumentType::Rs],
+            alt_args: None,
+            relocation_type: None,
+        },
+        InstructionInformation {
+            mnemonic: "mult",
+            op_code: 0x00,
+            funct_code: Some(0x18),
+            implementation: wrap_imp(implementation::mult),
+            instruction_type: InstructionType::RType,
+            args: &[ArgumentType::Rs, ArgumentType::Rt],
+            alt_args: None,
+            relocation_type: None,
+        },
+        InstructionInformation {
+            mnemonic: "multu",
+            op_code: 0x00,
+            funct_code: Some(0x19),
+            implementation: wrap_imp(implementation::multu),
+            instruction_type: InstructionType::RType,
+            args: &[ArgumentType::Rs, ArgumentType::Rt],
+            alt_args: None,
+            relocation_type: None,
         },
-        */
         InstructionInformation {
             mnemonic: "nor",
             op_code: 0x00,
@@ -328,6 +402,20 @@ pub static INSTRUCTION_SET: LazyLock<Vec<InstructionInformation>> = LazyLock::ne
             ]),
             relocation_type: None,
         },
+        InstructionInformation {
+            mnemonic: "sc",
+            op_code: 0x38,
+            funct_code: None,
+            implementation: wrap_imp(implementation::sc),
+            instruction_type: InstructionType::IType,
+            args: &[ArgumentType::Rt, ArgumentType::Immediate, ArgumentType::Rs],
+            alt_args: Some(&[
+                &[ArgumentType::Rt, ArgumentType::Rs],
+                &[ArgumentType::Rt, ArgumentType::Identifier, ArgumentType::Rs],
+                &[ArgumentType::Rt, ArgumentType::Identifier],
+            ]),
+            relocation_type: None,
+        },
         InstructionInformation {
             mnemonic: "sh",
             op_code: 0x29,
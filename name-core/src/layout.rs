@@ -0,0 +1,454 @@
+//! A memory layout says where each segment goes, how big it may get, and what the program may do with it.
+//! The assembler, the linker, and the emulator all have to agree on one, so each of them takes the same --layout:
+//! either the name of a preset or a TOML file like this one, where anything left out comes from the preset.
+//!
+//! ```toml
+//! preset = "mars-compact-text-at-0"
+//!
+//! [stack]
+//! top = 0x3ffc
+//! size = 0x400
+//!
+//! [permissions]
+//! writable_text = true
+//! ```
+use serde::Deserialize;
+
+use crate::constants::{
+    MIPS_ADDRESS_ALIGNMENT, MIPS_DATA_START_ADDR, MIPS_HEAP_START_ADDR, MIPS_STACK_END_ADDR,
+    MIPS_STACK_MAX_SIZE, MIPS_TEXT_START_ADDR,
+};
+use crate::elf_def::{Elf, Elf32SectionHeader};
+use crate::elf_utils::find_target_section_index;
+use crate::structs::MemoryPermissions;
+
+/// The preset used when no layout is given.
+pub const DEFAULT_PRESET: &str = "name";
+
+/// Every preset, with what it's for.
+pub const PRESETS: [(&str, &str); 4] = [
+    (
+        "name",
+        "NAME's own layout: .text at 0x00400000, the stack just below 0x7ffffe00",
+    ),
+    (
+        "mars-default",
+        "MARS \"Default\": like NAME's, but $sp starts at 0x7fffeffc",
+    ),
+    (
+        "mars-compact-data-at-0",
+        "MARS \"Compact, Data at Address 0\": everything below 0x4000, .data first",
+    ),
+    (
+        "mars-compact-text-at-0",
+        "MARS \"Compact, Text at Address 0\": everything below 0x4000, .text first",
+    ),
+];
+
+// Where create_new_elf puts .data and .text among the program headers.
+const DATA_PROGRAM_HEADER: usize = 0;
+const TEXT_PROGRAM_HEADER: usize = 1;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MemoryLayout {
+    pub text_base: u32,
+    pub text_size: u32, // the most .text may hold
+    pub data_base: u32,
+    pub data_size: u32,
+    pub heap_base: u32,
+    pub heap_size: u32,
+    pub stack_top: u32,  // where $sp starts; the stack grows down from here
+    pub stack_size: u32, // how far below stack_top the stack may grow
+    pub permissions: MemoryPermissions,
+}
+
+impl Default for MemoryLayout {
+    fn default() -> Self {
+        MemoryLayout {
+            text_base: MIPS_TEXT_START_ADDR,
+            text_size: MIPS_DATA_START_ADDR - MIPS_TEXT_START_ADDR,
+            data_base: MIPS_DATA_START_ADDR,
+            data_size: MIPS_HEAP_START_ADDR - MIPS_DATA_START_ADDR,
+            heap_base: MIPS_HEAP_START_ADDR,
+            heap_size: MIPS_STACK_END_ADDR - MIPS_STACK_MAX_SIZE - MIPS_HEAP_START_ADDR,
+            stack_top: MIPS_STACK_END_ADDR,
+            stack_size: MIPS_STACK_MAX_SIZE,
+            permissions: MemoryPermissions::default(),
+        }
+    }
+}
+
+/// What a layout file may contain. Everything is optional so a file only has to give what differs from its preset.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct LayoutFile {
+    preset: Option<String>,
+    text: Option<SegmentFile>,
+    data: Option<SegmentFile>,
+    heap: Option<SegmentFile>,
+    stack: Option<StackFile>,
+    permissions: Option<PermissionsFile>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SegmentFile {
+    base: Option<u32>,
+    size: Option<u32>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct StackFile {
+    top: Option<u32>,
+    size: Option<u32>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct PermissionsFile {
+    writable_text: Option<bool>,
+    executable_data: Option<bool>,
+}
+
+impl MemoryLayout {
+    /// Looks up a preset by name.
+    #[must_use]
+    pub fn preset(name: &str) -> Option<Self> {
+        let default: MemoryLayout = MemoryLayout::default();
+        match name {
+            "name" => Some(default),
+            "mars-default" => Some(MemoryLayout {
+                heap_size: 0x7fff_effc - MIPS_STACK_MAX_SIZE - MIPS_HEAP_START_ADDR,
+                stack_top: 0x7fff_effc,
+                ..default
+            }),
+            // the heap and stack share 0x2000-0x2ffc, so each gets half
+            "mars-compact-data-at-0" => Some(MemoryLayout {
+                text_base: 0x0000_3000,
+                text_size: 0x0000_1000,
+                data_base: 0x0000_0000,
+                data_size: 0x0000_2000,
+                heap_base: 0x0000_2000,
+                heap_size: 0x0000_0800,
+                stack_top: 0x0000_2ffc,
+                stack_size: 0x0000_07fc,
+                ..default
+            }),
+            // likewise for the heap and stack in 0x3000-0x3ffc
+            "mars-compact-text-at-0" => Some(MemoryLayout {
+                text_base: 0x0000_0000,
+                text_size: 0x0000_1000,
+                data_base: 0x0000_2000,
+                data_size: 0x0000_1000,
+                heap_base: 0x0000_3000,
+                heap_size: 0x0000_0800,
+                stack_top: 0x0000_3ffc,
+                stack_size: 0x0000_07fc,
+                ..default
+            }),
+            _ => None,
+        }
+    }
+
+    /// Reads a layout from the text of a TOML file.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the TOML is malformed, names an unknown preset, or describes a layout that doesn't validate.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let file: LayoutFile =
+            toml::from_str(text).map_err(|e| format!("Invalid memory layout: {e}"))?;
+
+        let preset: &str = file.preset.as_deref().unwrap_or(DEFAULT_PRESET);
+        let mut layout: MemoryLayout =
+            MemoryLayout::preset(preset).ok_or_else(unknown_preset(preset))?;
+
+        let text = file.text.unwrap_or_default();
+        layout.text_base = text.base.unwrap_or(layout.text_base);
+        layout.text_size = text.size.unwrap_or(layout.text_size);
+
+        let data = file.data.unwrap_or_default();
+        layout.data_base = data.base.unwrap_or(layout.data_base);
+        layout.data_size = data.size.unwrap_or(layout.data_size);
+
+        let heap = file.heap.unwrap_or_default();
+        layout.heap_base = heap.base.unwrap_or(layout.heap_base);
+        layout.heap_size = heap.size.unwrap_or(layout.heap_size);
+
+        let stack = file.stack.unwrap_or_default();
+        layout.stack_top = stack.top.unwrap_or(layout.stack_top);
+        layout.stack_size = stack.size.unwrap_or(layout.stack_size);
+
+        let permissions = file.permissions.unwrap_or_default();
+        layout.permissions.writable_text = permissions
+            .writable_text
+            .unwrap_or(layout.permissions.writable_text);
+        layout.permissions.executable_data = permissions
+            .executable_data
+            .unwrap_or(layout.permissions.executable_data);
+
+        layout.validate()?;
+        Ok(layout)
+    }
+
+    /// Gets a layout from what was given on the command line: a preset's name, or else the path of a TOML file.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the spec is no preset and no readable file, or if the file doesn't parse.
+    pub fn load(spec: &str) -> Result<Self, String> {
+        if let Some(layout) = MemoryLayout::preset(spec) {
+            return Ok(layout);
+        }
+
+        match std::fs::read_to_string(spec) {
+            Ok(text) => MemoryLayout::parse(&text).map_err(|e| format!("{spec}: {e}")),
+            Err(e) => Err(format!(
+                "{spec} is neither a layout preset ({}) nor a file that can be read: {e}",
+                preset_names()
+            )),
+        }
+    }
+
+    /// Checks that the segments are word-aligned and don't overlap or wrap around.
+    ///
+    /// # Errors
+    ///
+    /// Describes the first segment found misplaced.
+    pub fn validate(&self) -> Result<(), String> {
+        let segments: [(&str, u64, u64); 4] = [
+            (
+                ".text",
+                u64::from(self.text_base),
+                u64::from(self.text_base) + u64::from(self.text_size),
+            ),
+            (
+                ".data",
+                u64::from(self.data_base),
+                u64::from(self.data_base) + u64::from(self.data_size),
+            ),
+            (
+                "the heap",
+                u64::from(self.heap_base),
+                u64::from(self.heap_base) + u64::from(self.heap_size),
+            ),
+            // the stack includes its top
+            (
+                "the stack",
+                u64::from(self.stack_top)
+                    - u64::from(self.stack_size).min(u64::from(self.stack_top)),
+                u64::from(self.stack_top) + 1,
+            ),
+        ];
+
+        if self.stack_size > self.stack_top {
+            return Err(format!(
+                "A stack of 0x{:x} bytes doesn't fit below 0x{:08x}.",
+                self.stack_size, self.stack_top
+            ));
+        }
+
+        for (name, base, end) in segments {
+            if !base.is_multiple_of(u64::from(MIPS_ADDRESS_ALIGNMENT)) {
+                return Err(format!(
+                    "{name} starts at 0x{base:08x}, which is not word-aligned."
+                ));
+            }
+            if end > 1 << 32 {
+                return Err(format!("{name} runs past the end of memory."));
+            }
+        }
+        if !self.stack_top.is_multiple_of(MIPS_ADDRESS_ALIGNMENT) {
+            return Err(format!(
+                "The stack's top, 0x{:08x}, is not word-aligned.",
+                self.stack_top
+            ));
+        }
+
+        for (i, (name, base, end)) in segments.iter().enumerate() {
+            for (other, other_base, other_end) in &segments[i + 1..] {
+                if base < other_end && other_base < end {
+                    return Err(format!(
+                        "{name} (0x{base:08x}-0x{:08x}) overlaps {other} (0x{other_base:08x}-0x{:08x}).",
+                        end - 1,
+                        other_end - 1
+                    ));
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// The lowest address the stack may grow down to.
+    #[must_use]
+    pub fn stack_limit(&self) -> u32 {
+        self.stack_top - self.stack_size
+    }
+
+    /// Points an ELF's .text and .data at this layout's bases. The entry point is left to whoever made the ELF.
+    pub fn apply_to(&self, elf: &mut Elf) {
+        for (program_header, section, base) in [
+            (TEXT_PROGRAM_HEADER, ".text", self.text_base),
+            (DATA_PROGRAM_HEADER, ".data", self.data_base),
+        ] {
+            if let Some(header) = elf.program_header_table.get_mut(program_header) {
+                header.p_vaddr = base;
+                header.p_paddr = base;
+            }
+            if let Some(index) = section_index(elf, section) {
+                elf.section_header_table[index].sh_addr = base;
+            }
+        }
+    }
+
+    /// Checks that an ELF was built for this layout: its segments start at the right places and fit.
+    ///
+    /// # Errors
+    ///
+    /// Says which segment is in the wrong place or too big, naming the ELF as `what`.
+    pub fn check(&self, elf: &Elf, what: &str) -> Result<(), String> {
+        let (text_base, data_base) = segment_bases(elf);
+        if (text_base, data_base) != (self.text_base, self.data_base) {
+            return Err(format!(
+                "{what} puts .text at 0x{text_base:08x} and .data at 0x{data_base:08x}, but the memory layout puts them at 0x{:08x} and 0x{:08x}. Give every tool the same --layout.",
+                self.text_base, self.data_base
+            ));
+        }
+
+        let (text_size, data_size) = segment_sizes(elf);
+        self.check_sizes(text_size, data_size)
+            .map_err(|e| format!("{what}: {e}"))
+    }
+
+    /// Checks that segments of these sizes fit in the layout.
+    ///
+    /// # Errors
+    ///
+    /// Says which segment doesn't fit.
+    pub fn check_sizes(&self, text_size: u32, data_size: u32) -> Result<(), String> {
+        if text_size > self.text_size {
+            return Err(format!(
+                ".text takes 0x{text_size:x} bytes, but the memory layout only has room for 0x{:x}.",
+                self.text_size
+            ));
+        }
+        if data_size > self.data_size {
+            return Err(format!(
+                ".data takes 0x{data_size:x} bytes, but the memory layout only has room for 0x{:x}.",
+                self.data_size
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Where an ELF puts .text and .data.
+#[must_use]
+pub fn segment_bases(elf: &Elf) -> (u32, u32) {
+    let address = |section: &str| section_header(elf, section).map_or(0, |header| header.sh_addr);
+    (address(".text"), address(".data"))
+}
+
+fn segment_sizes(elf: &Elf) -> (u32, u32) {
+    let size = |section: &str| section_header(elf, section).map_or(0, |header| header.sh_size);
+    (size(".text"), size(".data"))
+}
+
+fn section_header<'a>(elf: &'a Elf, section: &str) -> Option<&'a Elf32SectionHeader> {
+    section_index(elf, section).map(|index| &elf.section_header_table[index])
+}
+
+/// Finds a section header by name. ELFs read from a file have lost their null header and ones still being built
+/// haven't, so the header's position can't be trusted.
+fn section_index(elf: &Elf, section: &str) -> Option<usize> {
+    let shstrtab: &Vec<u8> = elf
+        .sections
+        .get((elf.file_header.e_shstrndx as usize).checked_sub(1)?)?;
+    find_target_section_index(&elf.section_header_table, shstrtab, section)
+}
+
+fn preset_names() -> String {
+    PRESETS.map(|(name, _)| name).join(", ")
+}
+
+fn unknown_preset(name: &str) -> impl FnOnce() -> String + '_ {
+    move || {
+        format!(
+            "{name} is not a layout preset; use one of {}.",
+            preset_names()
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::elf_def::ElfType;
+    use crate::elf_utils::create_new_elf;
+
+    #[test]
+    fn test_presets_are_valid() {
+        for (name, _) in PRESETS {
+            let layout: MemoryLayout = MemoryLayout::preset(name).expect("every preset exists");
+            assert_eq!(layout.validate(), Ok(()), "{name}");
+        }
+        assert_eq!(
+            MemoryLayout::preset(DEFAULT_PRESET),
+            Some(MemoryLayout::default())
+        );
+    }
+
+    #[test]
+    fn test_parse_layout() {
+        let layout: MemoryLayout = MemoryLayout::parse(
+            "preset = \"mars-compact-text-at-0\"\n\
+             [stack]\n\
+             size = 0x400\n\
+             [permissions]\n\
+             writable_text = true\n",
+        )
+        .unwrap();
+        assert_eq!(layout.text_base, 0);
+        assert_eq!(layout.stack_top, 0x3ffc);
+        assert_eq!(layout.stack_size, 0x400);
+        assert!(layout.permissions.writable_text);
+        assert!(!layout.permissions.executable_data);
+
+        // no preset means NAME's own layout
+        let layout: MemoryLayout = MemoryLayout::parse("[text]\nbase = 0x1000\n").unwrap();
+        assert_eq!(layout.text_base, 0x1000);
+        assert_eq!(layout.data_base, MIPS_DATA_START_ADDR);
+    }
+
+    #[test]
+    fn test_parse_bad_layouts() {
+        let error = |text: &str| MemoryLayout::parse(text).unwrap_err();
+
+        assert!(error("preset = \"spim\"").contains("not a layout preset"));
+        assert!(error("[text]\nbase = 0x402\n").contains("not word-aligned"));
+        assert!(error("[text]\nstart = 0\n").starts_with("Invalid memory layout"));
+        assert!(error("[data]\nbase = 0x00400100\n")
+            .contains(".text (0x00400000-0x1000ffff) overlaps .data"));
+        assert!(error("[stack]\ntop = 0x100\nsize = 0x200\n").contains("doesn't fit below"));
+    }
+
+    #[test]
+    fn test_apply_to_elf() {
+        let layout: MemoryLayout = MemoryLayout::preset("mars-compact-data-at-0").unwrap();
+        let sections: Vec<Vec<u8>> = vec![vec![0; 8], vec![0; 12], vec![], vec![], vec![], vec![]];
+        let mut elf: Elf = create_new_elf(sections, ElfType::Relocatable, true);
+        layout.apply_to(&mut elf);
+        assert_eq!(segment_bases(&elf), (0x3000, 0));
+        assert_eq!(layout.check(&elf, "The test ELF"), Ok(()));
+
+        // reading an ELF back from a file drops the null section header
+        elf.section_header_table.remove(0);
+        assert_eq!(segment_bases(&elf), (0x3000, 0));
+        assert!(MemoryLayout::default()
+            .check(&elf, "The test ELF")
+            .unwrap_err()
+            .contains("Give every tool the same --layout."));
+    }
+}
@@ -5,6 +5,11 @@ pub mod elf_utils;
 pub mod exception;
 pub mod implementations;
 pub mod instruction;
+pub mod layout;
+pub mod machine;
+pub mod narrate;
+pub mod observer;
 pub mod structs;
 pub mod syscalls;
+pub mod threads;
 pub mod traits;
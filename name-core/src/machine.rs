@@ -0,0 +1,330 @@
+//! A whole emulated machine behind one struct, for running programs from other Rust code (an autograder, a web
+//! backend) without going through name-emu. Load an ELF, hand it input, run it for as long as you like, then look at
+//! its registers, memory, and output:
+//!
+//! ```ignore
+//! let mut machine: Machine = Machine::load_elf(&elf);
+//! machine.set_input(Cursor::new(b"5\n".to_vec()));
+//! assert_eq!(machine.run_until(1_000_000)?, Stop::Exited);
+//! assert_eq!(machine.take_output(), b"120".to_vec());
+//! ```
+//!
+//! Nothing touches stdin or stdout unless asked to: the program reads from empty input and its output is held until
+//! it's taken. Errors that would crash name-emu, like a bad address or an unknown syscall, come back as Err instead.
+
+use std::io::{empty, BufRead, Write};
+
+use crate::debug::debug_utils::{single_step, DebuggerState};
+use crate::debug::exception_handler::try_handle_exception;
+use crate::debug::simulator_helpers::extract_loadable_sections;
+use crate::elf_def::Elf;
+use crate::elf_utils::{extract_lineinfo, extract_symbols};
+use crate::exception::definitions::ExceptionType;
+use crate::layout::{segment_bases, MemoryLayout};
+use crate::observer::Observer;
+use crate::structs::{
+    LineInfo, Memory, OperatingSystem, Processor, ProgramState, Register, Visibility,
+};
+
+/// Why a machine stopped running.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Stop {
+    Exited,       // the program used the exit syscall, or every thread finished
+    Breakpoint,   // a break instruction; running again carries on after it
+    LimitReached, // it ran as many instructions as it was allowed to
+    Returned,     // execution jumped to the address given to return_to
+}
+
+/// Sets up the simulation environment from the information in an ELF.
+/// .text and .data go wherever the ELF says; everything else comes from the default layout.
+#[must_use]
+pub fn load_program(elf: &Elf) -> (Vec<LineInfo>, ProgramState) {
+    let (text_base, data_base) = segment_bases(elf);
+    let layout: MemoryLayout = MemoryLayout {
+        text_base,
+        data_base,
+        ..Default::default()
+    };
+    place_program(elf, layout)
+}
+
+/// Sets up the simulation environment for a memory layout, which the ELF has to have been linked for.
+///
+/// # Errors
+///
+/// Fails if the ELF doesn't fit the layout.
+pub fn load_program_with_layout(
+    elf: &Elf,
+    layout: MemoryLayout,
+) -> Result<(Vec<LineInfo>, ProgramState), String> {
+    layout.check(elf, "The program")?;
+    Ok(place_program(elf, layout))
+}
+
+fn place_program(elf: &Elf, layout: MemoryLayout) -> (Vec<LineInfo>, ProgramState) {
+    let mut cpu: Processor = Processor::new(elf.file_header.e_entry);
+    cpu.general_purpose_registers[Register::Sp as usize] = layout.stack_top;
+
+    let (data, text) = extract_loadable_sections(elf);
+
+    let lineinfo: Vec<LineInfo> = extract_lineinfo(elf);
+
+    let mut memory: Memory = Memory::new(data, text);
+    memory.layout = layout;
+
+    // Create program state
+    (lineinfo, ProgramState::new(cpu, memory))
+}
+
+pub struct Machine {
+    pub program_state: ProgramState,
+    pub operating_system: OperatingSystem,
+    lineinfo: Vec<LineInfo>,
+    debugger_state: DebuggerState, // only the symbols, for naming functions in errors
+    instructions: u64,
+    return_address: Option<u32>,
+}
+
+impl Machine {
+    /// Loads a program. .text and .data go wherever the ELF says; everything else comes from the default layout.
+    #[must_use]
+    pub fn load_elf(elf: &Elf) -> Self {
+        Machine::new(elf, load_program(elf))
+    }
+
+    /// Loads a program for a memory layout, which the ELF has to have been linked for.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the ELF doesn't fit the layout.
+    pub fn load_elf_with_layout(elf: &Elf, layout: MemoryLayout) -> Result<Self, String> {
+        Ok(Machine::new(elf, load_program_with_layout(elf, layout)?))
+    }
+
+    fn new(elf: &Elf, (lineinfo, program_state): (Vec<LineInfo>, ProgramState)) -> Self {
+        let mut operating_system: OperatingSystem = OperatingSystem::new();
+        operating_system.set_program_input(empty());
+        operating_system.capture_program_output();
+
+        let mut debugger_state: DebuggerState = DebuggerState::new();
+        debugger_state.symbols = extract_symbols(elf);
+
+        Machine {
+            program_state,
+            operating_system,
+            lineinfo,
+            debugger_state,
+            instructions: 0,
+            return_address: None,
+        }
+    }
+
+    /// Gives the program something to read, like a Cursor over a string.
+    pub fn set_input<R: BufRead + 'static>(&mut self, input: R) {
+        self.operating_system.set_program_input(input);
+    }
+
+    /// Sends the program's output straight to a writer instead of holding on to it.
+    pub fn set_output<W: Write + 'static>(&mut self, output: W) {
+        self.operating_system.set_program_output(output);
+    }
+
+    /// Hands over whatever the program has printed since the last call. Empty once the output goes to a writer.
+    pub fn take_output(&mut self) -> Vec<u8> {
+        self.operating_system.take_program_output()
+    }
+
+    /// Has an observer watch everything the program does from here on.
+    pub fn add_observer<O: Observer + 'static>(&mut self, observer: O) {
+        self.program_state.observers.add(Box::new(observer));
+    }
+
+    #[must_use]
+    pub fn is_running(&self) -> bool {
+        self.program_state.should_continue_execution
+    }
+
+    /// What the program gave `SysExitValue` when it exited, or 0.
+    #[must_use]
+    pub fn exit_code(&self) -> u32 {
+        self.operating_system.exit_code()
+    }
+
+    /// How many instructions have run so far.
+    #[must_use]
+    pub fn instructions(&self) -> u64 {
+        self.instructions
+    }
+
+    #[must_use]
+    pub fn lineinfo(&self) -> &[LineInfo] {
+        &self.lineinfo
+    }
+
+    /// Looks up where a label is, like main.
+    #[must_use]
+    pub fn symbol(&self, name: &str) -> Option<u32> {
+        self.debugger_state
+            .symbols
+            .iter()
+            .find(|symbol| symbol.identifier == name)
+            .map(|symbol| symbol.value)
+    }
+
+    /// Whether a label was made global with .globl, so code outside its own file may use it.
+    #[must_use]
+    pub fn is_global(&self, name: &str) -> bool {
+        self.debugger_state.symbols.iter().any(|symbol| {
+            symbol.identifier == name && matches!(symbol.visibility, Visibility::Global)
+        })
+    }
+
+    /// Has jumping to address stop the machine with `Stop::Returned` instead of crashing it. Nothing should be loaded
+    /// there; it's for calling a single function with its $ra set to address.
+    pub fn return_to(&mut self, address: u32) {
+        self.return_address = Some(address);
+    }
+
+    /// Executes one instruction. Gives back why the machine stopped if it did; an error stops it for good.
+    ///
+    /// # Errors
+    ///
+    /// Fails with whatever went wrong that the program couldn't handle itself.
+    pub fn step(&mut self) -> Result<Option<Stop>, String> {
+        if !self.is_running() {
+            return Ok(Some(Stop::Exited));
+        }
+        if self.return_address == Some(self.pc()) {
+            return Ok(Some(Stop::Returned));
+        }
+
+        // the break instruction the machine stopped at has been reported; carry on after it
+        let breakpoint: u32 = ExceptionType::Breakpoint.into();
+        if self.program_state.is_exception() && self.program_state.cp0.get_exc_code() == breakpoint
+        {
+            self.program_state.recover_from_exception();
+        }
+
+        single_step(&self.lineinfo, &mut self.program_state);
+        self.instructions += 1;
+
+        if self.program_state.is_exception() {
+            if self.program_state.cp0.get_exc_code() == breakpoint {
+                return Ok(Some(Stop::Breakpoint));
+            }
+            // jumping to the return address can't fetch anything, which is the point
+            if let Some(address) = self.return_address.filter(|&address| address == self.pc()) {
+                self.program_state.recover_from_exception();
+                self.program_state.cpu.pc = address;
+                return Ok(Some(Stop::Returned));
+            }
+            if let Err(e) = try_handle_exception(
+                &mut self.program_state,
+                &mut self.operating_system,
+                &self.lineinfo,
+                &mut self.debugger_state,
+            ) {
+                self.program_state.should_continue_execution = false;
+                return Err(e);
+            }
+        }
+        self.operating_system.tick(&mut self.program_state);
+
+        if self.is_running() {
+            Ok(None)
+        } else {
+            Ok(Some(Stop::Exited))
+        }
+    }
+
+    /// Runs until the program stops, or for at most limit instructions.
+    ///
+    /// # Errors
+    ///
+    /// Fails the same way as [`Machine::step`].
+    pub fn run_until(&mut self, limit: u64) -> Result<Stop, String> {
+        for _ in 0..limit {
+            if let Some(stop) = self.step()? {
+                return Ok(stop);
+            }
+        }
+        if self.is_running() {
+            Ok(Stop::LimitReached)
+        } else {
+            Ok(Stop::Exited)
+        }
+    }
+
+    #[must_use]
+    pub fn pc(&self) -> u32 {
+        self.program_state.cpu.pc
+    }
+
+    pub fn set_pc(&mut self, pc: u32) {
+        self.program_state.cpu.pc = pc;
+    }
+
+    #[must_use]
+    pub fn register(&self, register: Register) -> u32 {
+        self.program_state.cpu.general_purpose_registers[register as usize]
+    }
+
+    /// Writes to $zero are ignored, as they are on the hardware.
+    pub fn set_register(&mut self, register: Register, value: u32) {
+        if !matches!(register, Register::Zero) {
+            self.program_state.cpu.general_purpose_registers[register as usize] = value;
+        }
+    }
+
+    /// Reads length bytes starting at address.
+    ///
+    /// # Errors
+    ///
+    /// Fails on the first byte that can't be read.
+    pub fn read_memory(&self, address: u32, length: u32) -> Result<Vec<u8>, String> {
+        (0..length)
+            .map(|offset| {
+                let byte_address: u32 = address.wrapping_add(offset);
+                self.program_state
+                    .memory
+                    .read_byte(byte_address)
+                    .map_err(|e| format!("Couldn't read 0x{byte_address:08x}: {e}"))
+            })
+            .collect()
+    }
+
+    /// Writes bytes starting at address. Anything the program could write can be written, as well as .text.
+    ///
+    /// # Errors
+    ///
+    /// Fails on the first byte that can't be written; the bytes before it stay written.
+    pub fn write_memory(&mut self, address: u32, bytes: &[u8]) -> Result<(), String> {
+        for (offset, &byte) in (0u32..).zip(bytes) {
+            let byte_address: u32 = address.wrapping_add(offset);
+            self.program_state
+                .memory
+                .set_byte(byte_address, byte)
+                .map_err(|e| format!("Couldn't write 0x{byte_address:08x}: {e}"))?;
+        }
+        Ok(())
+    }
+
+    /// Reads the big-endian word at address.
+    ///
+    /// # Errors
+    ///
+    /// Fails if any of its bytes can't be read.
+    pub fn read_word(&self, address: u32) -> Result<u32, String> {
+        let bytes: Vec<u8> = self.read_memory(address, 4)?;
+        Ok(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    /// Writes a big-endian word at address.
+    ///
+    /// # Errors
+    ///
+    /// Fails if any of its bytes can't be written.
+    pub fn write_word(&mut self, address: u32, value: u32) -> Result<(), String> {
+        self.write_memory(address, &value.to_be_bytes())
+    }
+}
@@ -0,0 +1,55 @@
+//! The assembler and linker describe everything they do on stdout, which is handy from the command line but not when
+//! another tool is running them in-process. Those tools can turn it off while they run them.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+static QUIETERS: AtomicUsize = AtomicUsize::new(0); // how many QuietGuards are alive
+
+/// Keeps the tools quiet for as long as it's held. Builds running side by side each hold their own, so the
+/// description only comes back once every one of them is done.
+#[must_use = "the tools are only quiet while the guard is held"]
+pub struct QuietGuard(());
+
+impl Drop for QuietGuard {
+    fn drop(&mut self) {
+        QUIETERS.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+/// Stops the tools' description of what they're doing until the guard is dropped.
+pub fn quiet() -> QuietGuard {
+    QUIETERS.fetch_add(1, Ordering::Relaxed);
+    QuietGuard(())
+}
+
+#[must_use]
+pub fn is_quiet() -> bool {
+    QUIETERS.load(Ordering::Relaxed) > 0
+}
+
+/// Prints like println, unless the tools have been told to be quiet.
+#[macro_export]
+macro_rules! narrate {
+    ($($arg:tt)*) => {
+        if !$crate::narrate::is_quiet() {
+            println!($($arg)*);
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_quiet_guard() {
+        assert!(!is_quiet());
+        let outer: QuietGuard = quiet();
+        let inner: QuietGuard = quiet();
+        drop(inner);
+        // still quiet while the outer guard is held
+        assert!(is_quiet());
+        drop(outer);
+        assert!(!is_quiet());
+    }
+}
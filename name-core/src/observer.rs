@@ -0,0 +1,144 @@
+//! Hooks into execution for tools built outside name-core, like coverage, cache models, tracers, or grading checks.
+//! Observers are added to a `ProgramState` and hear about everything `single_step` does, however the program is being
+//! run (normally, in the debugger, or through a Machine). They only watch; the program state they're shown can't be
+//! changed through them.
+//!
+//! Memory accesses are everything the program does to its memory: one callback per load or store with the whole value,
+//! and one per byte for syscalls that read or print strings. The debugger and a Machine looking at memory aren't
+//! reported. Syscalls are reported before they're serviced.
+
+use std::cell::RefCell;
+use std::fmt;
+use std::rc::Rc;
+
+use crate::exception::definitions::ExceptionType;
+use crate::instruction::information::InstructionInformation;
+use crate::instruction::{MemoryAccess, RawInstruction};
+use crate::structs::ProgramState;
+
+/// An instruction that has just finished executing.
+#[derive(Debug, Clone, Copy)]
+pub struct ExecutedInstruction {
+    pub address: u32,
+    pub instruction: RawInstruction,
+    pub next_pc: u32, // where execution went afterwards; tells taken branches apart
+    pub memory_access: Option<MemoryAccess>, // the load or store it made, unless it faulted or was a failed sc
+}
+
+impl ExecutedInstruction {
+    /// Checks whether execution went somewhere other than the following instruction.
+    #[must_use]
+    pub fn redirected(&self) -> bool {
+        self.next_pc != self.address.wrapping_add(4)
+    }
+}
+
+/// Callbacks for whatever an observer cares about; the rest do nothing.
+pub trait Observer {
+    /// The instruction at address is about to run. The program state is as it was before.
+    fn before_execute(
+        &mut self,
+        _address: u32,
+        _instruction: RawInstruction,
+        _program_state: &ProgramState,
+    ) {
+    }
+
+    /// An instruction ran, even if it raised an exception. The program state is as it left it.
+    fn after_execute(
+        &mut self,
+        _executed: &ExecutedInstruction,
+        _information: &InstructionInformation,
+        _program_state: &ProgramState,
+    ) {
+    }
+
+    /// The program read value, which is access.size bytes wide.
+    fn memory_read(&mut self, _access: MemoryAccess, _value: u32, _program_state: &ProgramState) {}
+
+    /// The program wrote value, which is access.size bytes wide.
+    fn memory_write(&mut self, _access: MemoryAccess, _value: u32, _program_state: &ProgramState) {}
+
+    /// A syscall is about to be serviced; its number is in $v0.
+    fn syscall(&mut self, _number: u32, _program_state: &ProgramState) {}
+
+    /// An exception was raised, syscalls and breaks included. EPC has the address of the instruction responsible.
+    fn exception(&mut self, _exception: ExceptionType, _program_state: &ProgramState) {}
+}
+
+/// Lets the caller keep hold of an observer it has handed to a `ProgramState`, to look at what it saw afterwards.
+impl<T: Observer> Observer for Rc<RefCell<T>> {
+    fn before_execute(
+        &mut self,
+        address: u32,
+        instruction: RawInstruction,
+        program_state: &ProgramState,
+    ) {
+        self.borrow_mut()
+            .before_execute(address, instruction, program_state);
+    }
+
+    fn after_execute(
+        &mut self,
+        executed: &ExecutedInstruction,
+        information: &InstructionInformation,
+        program_state: &ProgramState,
+    ) {
+        self.borrow_mut()
+            .after_execute(executed, information, program_state);
+    }
+
+    fn memory_read(&mut self, access: MemoryAccess, value: u32, program_state: &ProgramState) {
+        self.borrow_mut().memory_read(access, value, program_state);
+    }
+
+    fn memory_write(&mut self, access: MemoryAccess, value: u32, program_state: &ProgramState) {
+        self.borrow_mut().memory_write(access, value, program_state);
+    }
+
+    fn syscall(&mut self, number: u32, program_state: &ProgramState) {
+        self.borrow_mut().syscall(number, program_state);
+    }
+
+    fn exception(&mut self, exception: ExceptionType, program_state: &ProgramState) {
+        self.borrow_mut().exception(exception, program_state);
+    }
+}
+
+/// The observers attached to a `ProgramState`, called in the order they were added.
+#[derive(Default)]
+pub struct Observers(Vec<Box<dyn Observer>>);
+
+impl fmt::Debug for Observers {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Observers({})", self.0.len())
+    }
+}
+
+impl Observers {
+    pub fn add(&mut self, observer: Box<dyn Observer>) {
+        self.0.push(observer);
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Detaches every observer.
+    pub fn take(&mut self) -> Vec<Box<dyn Observer>> {
+        std::mem::take(&mut self.0)
+    }
+}
+
+/// Shows something to every observer. They're taken out of the program state for the call, so they can look at it.
+pub(crate) fn notify(
+    program_state: &mut ProgramState,
+    mut callback: impl FnMut(&mut dyn Observer, &ProgramState),
+) {
+    let mut observers: Observers = std::mem::take(&mut program_state.observers);
+    for observer in &mut observers.0 {
+        callback(observer.as_mut(), program_state);
+    }
+    program_state.observers = observers;
+}
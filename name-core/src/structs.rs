@@ -1,15 +1,19 @@
 /// These are the key structs on which NAME operates along with their associated implementations.
 /// It's gonna be quite a few definitions, so buckle up.
 use std::{
+    collections::HashMap,
     fmt,
-    io::{/*self,*/ stdin, stdout, Stdin, Stdout, Write},
+    io::{/*self,*/ stdin, stdout, BufRead, Stdin, Stdout, Write},
 };
 
 use crate::{
-    constants::{
-        MIPS_ADDRESS_ALIGNMENT, MIPS_DATA_START_ADDR, MIPS_HEAP_START_ADDR, MIPS_STACK_END_ADDR,
-        MIPS_TEXT_START_ADDR,
-    }, debug::{debug_utils::*, debugger_methods::* /* implementations::* */}, exception::constants::EXCEPTION_BEING_HANDLED, syscalls::*
+    debug::{debug_utils::*, debugger_methods::* /* implementations::* */},
+    exception::constants::EXCEPTION_BEING_HANDLED,
+    instruction::{AccessType, MemoryAccess},
+    layout::MemoryLayout,
+    observer::{notify, Observers},
+    syscalls::*,
+    threads::{ThreadOptions, Threads},
 };
 
 /// Symbol is used for assembly -> ELF, ET_REL -> ET_EXEC, and ELF -> ProgramState construction.
@@ -25,10 +29,13 @@ pub struct Symbol {
 }
 
 /// The processor holds the general-purpose registers along with $sp, $gp, etc.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Processor {
     pub pc: u32,
     pub general_purpose_registers: [u32; 32],
+    pub hi: u32, // results of multiplication and division
+    pub lo: u32,
+    pub linked_address: Option<u32>, // the word ll last linked; sc only stores there while nothing else has written it
 }
 
 /// Coprocessor 0 is for communication with the OS. Look in name-core/exception for more.
@@ -39,26 +46,73 @@ pub struct Coprocessor0 {
 }
 
 /// Memory is a conglomerate of program text, program data, the heap, the stack, and other segments.
-/// There exist predefined offsets for each of these segments in 32-bit MIPS:
-///  - reserved space from 0x00000000 to 0x3fffffff;
-///  - section .text begins at 0x40000000 in memory;
-///  - section .data begins at 0x10010000 in memory;
-///  - heap begins at 0x10040000 in memory;
-///  - stack begins at 0x7ffffe00 in memory (and grows downward);
-///  - kernel data begins at 0x90000000 in memory;
-///  - mem-mapped I/O begins at 0xffff0000 in memory.
-/// The Memory struct relies on address translation for proper use. Each segment is represented as a Vec<u8>.
+/// Where each segment starts and how large it may grow comes from the MemoryLayout in the layout field, which has to
+/// match the one the program was linked for. The stack grows downward from the layout's stack_top.
+/// Addresses outside every segment are reserved and can't be read or written.
+/// The Memory struct relies on address translation for proper use. Each segment but the stack is represented as a Vec<u8>.
 #[derive(Debug)]
 pub struct Memory {
     pub text: Vec<u8>,
     pub data: Vec<u8>,
     pub heap: Vec<u8>,
-    pub stack: Vec<u8>,
+    pub stack: Stack,
+    pub layout: MemoryLayout,
     // These fields may be used later:
     // pub kernel: Vec<u8>,
     // pub mmio: Vec<u8>,
 }
 
+const STACK_PAGE_SIZE: usize = 0x1000; // The stack is allocated this many bytes at a time
+
+/// The stack, indexed by how far below `stack_top` an address is. It may grow a long way, so it's only allocated a page
+/// at a time where it's actually written. Everything between `stack_top` and the deepest byte written reads as part of
+/// the stack, with the bytes that were never written reading as 0.
+#[derive(Debug, Default)]
+pub struct Stack {
+    pages: HashMap<usize, Box<[u8; STACK_PAGE_SIZE]>>,
+    len: usize, // one past the deepest offset written
+}
+
+impl Stack {
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    #[must_use]
+    pub fn get(&self, offset: usize) -> Option<u8> {
+        if offset >= self.len {
+            return None;
+        }
+        Some(
+            self.pages
+                .get(&(offset / STACK_PAGE_SIZE))
+                .map_or(0, |page| page[offset % STACK_PAGE_SIZE]),
+        )
+    }
+
+    pub fn set(&mut self, offset: usize, value: u8) {
+        self.pages
+            .entry(offset / STACK_PAGE_SIZE)
+            .or_insert_with(|| Box::new([0; STACK_PAGE_SIZE]))[offset % STACK_PAGE_SIZE] = value;
+        self.len = self.len.max(offset + 1);
+    }
+}
+
+/// Protections beyond the usual ones, which are off unless asked for (e.g. to demonstrate code injection or a JIT).
+/// Instructions are fetched from memory every time they run and breakpoints are kept by address,
+/// so code that's been written over takes effect the next time it's reached and keeps its breakpoints.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct MemoryPermissions {
+    pub writable_text: bool,   // stores may change .text
+    pub executable_data: bool, // .data, the heap, and the stack can be run as code
+}
+
 /// Error type for reading from memory / address translation
 #[derive(Debug)]
 pub enum MemoryError {
@@ -89,7 +143,8 @@ impl Default for Memory {
             data: Vec::new(),
             text: Vec::new(),
             heap: Vec::new(),
-            stack: Vec::new(),
+            stack: Stack::default(),
+            layout: MemoryLayout::default(),
         }
     }
 }
@@ -102,7 +157,8 @@ impl Memory {
             text: text,
             data: data,
             heap: Vec::new(),
-            stack: Vec::new(),
+            stack: Stack::default(),
+            layout: MemoryLayout::default(),
         }
     }
 
@@ -110,41 +166,41 @@ impl Memory {
     /// read_byte reads a byte, performing address translation.
     pub fn read_byte(&self, address: u32) -> Result<u8, MemoryError> {
         // Obtain values for segment boundaries:
-        let text_end: u32 = MIPS_TEXT_START_ADDR + self.text.len() as u32;
-        let data_end: u32 = MIPS_DATA_START_ADDR + self.data.len() as u32;
-        let heap_end: u32 = MIPS_HEAP_START_ADDR + self.heap.len() as u32; // This one MUST be calculated on the fly
-        let stack_start: u32 = MIPS_STACK_END_ADDR - self.stack.len() as u32; // Similarly, this must be calculated on the fly
-                                                                              // Match on the address to find the correct segment to read from and ensure the offset is within proper bounds of segment
+        let text_end: u32 = self.layout.text_base + self.text.len() as u32;
+        let data_end: u32 = self.layout.data_base + self.data.len() as u32;
+        let heap_end: u32 = self.layout.heap_base + self.heap.len() as u32; // This one MUST be calculated on the fly
+        let stack_start: u32 = self.layout.stack_top - self.stack.len() as u32; // Similarly, this must be calculated on the fly
+
+        // Match on the address to find the correct segment to read from and ensure the offset is within proper bounds of segment
         match address {
-            addr if MIPS_TEXT_START_ADDR <= addr && addr <= text_end => {
+            addr if self.layout.text_base <= addr && addr <= text_end => {
                 // This pipeline either returns the obtained byte or an appropriate error.
                 // It works the same way for every match arm.
                 return self
                     .text
-                    .get((address - MIPS_TEXT_START_ADDR) as usize)
+                    .get((address - self.layout.text_base) as usize)
                     .copied()
                     .ok_or_else(|| MemoryError::TextTranslationError);
             }
-            addr if MIPS_DATA_START_ADDR <= addr && addr <= data_end => {
+            addr if self.layout.data_base <= addr && addr <= data_end => {
                 return self
                     .data
-                    .get((address - MIPS_DATA_START_ADDR) as usize)
+                    .get((address - self.layout.data_base) as usize)
                     .copied()
                     .ok_or_else(|| MemoryError::DataTranslationError);
             }
-            addr if MIPS_HEAP_START_ADDR <= addr && addr <= heap_end => {
+            addr if self.layout.heap_base <= addr && addr <= heap_end => {
                 return self
                     .heap
-                    .get((address - MIPS_HEAP_START_ADDR) as usize)
+                    .get((address - self.layout.heap_base) as usize)
                     .copied()
                     .ok_or_else(|| MemoryError::HeapTranslationError);
             }
             // Note that the stack is most likely to be error-prone as it works differently. (i.e. look here first)
-            addr if stack_start <= addr && addr <= MIPS_STACK_END_ADDR => {
+            addr if stack_start <= addr && addr <= self.layout.stack_top => {
                 return self
                     .stack
-                    .get((MIPS_STACK_END_ADDR - address) as usize)
-                    .copied()
+                    .get((self.layout.stack_top - address) as usize)
                     .ok_or_else(|| MemoryError::StackTranslationError);
             }
             // Other areas in memory are not yet necessary to match on but can be added quickly
@@ -159,15 +215,15 @@ impl Memory {
     /// set_byte performs address translation on the provided address and sets the value at that address to value.
     pub fn set_byte(&mut self, address: u32, value: u8) -> Result<(), MemoryError> {
         // Obtain values for segment boundaries:
-        let text_end: u32 = MIPS_TEXT_START_ADDR + self.text.len() as u32;
-        let data_end: u32 = MIPS_DATA_START_ADDR + self.data.len() as u32;
-        let heap_end: u32 = MIPS_HEAP_START_ADDR + self.heap.len() as u32;
-        let stack_start: u32 = MIPS_STACK_END_ADDR - self.stack.len() as u32;
+        let text_end: u32 = self.layout.text_base + self.text.len() as u32;
+        let data_end: u32 = self.layout.data_base + self.data.len() as u32;
+        let heap_end: u32 = self.layout.heap_base + self.heap.len() as u32;
+        let stack_limit: u32 = self.layout.stack_limit();
         // Match on the address to find the correct segment to read from and ensure the offset is within proper bounds of segment
         match address {
-            addr if MIPS_TEXT_START_ADDR <= addr && addr <= text_end => {
+            addr if self.layout.text_base <= addr && addr <= text_end => {
                 // This offset check is redundant.
-                let offset: usize = (address - MIPS_TEXT_START_ADDR) as usize;
+                let offset: usize = (address - self.layout.text_base) as usize;
                 if offset < self.text.len() {
                     self.text[offset] = value;
                     return Ok(());
@@ -175,8 +231,8 @@ impl Memory {
                     return Err(MemoryError::TextTranslationError);
                 }
             }
-            addr if MIPS_DATA_START_ADDR <= addr && addr <= data_end => {
-                let offset: usize = (address - MIPS_DATA_START_ADDR) as usize;
+            addr if self.layout.data_base <= addr && addr <= data_end => {
+                let offset: usize = (address - self.layout.data_base) as usize;
                 if offset < self.data.len() {
                     self.data[offset] = value;
                     return Ok(());
@@ -184,8 +240,8 @@ impl Memory {
                     return Err(MemoryError::TextTranslationError);
                 }
             }
-            addr if MIPS_HEAP_START_ADDR <= addr && addr <= heap_end => {
-                let offset: usize = (address - MIPS_HEAP_START_ADDR) as usize;
+            addr if self.layout.heap_base <= addr && addr <= heap_end => {
+                let offset: usize = (address - self.layout.heap_base) as usize;
                 if offset < self.heap.len() {
                     self.heap[offset] = value;
                     return Ok(());
@@ -194,14 +250,11 @@ impl Memory {
                 }
             }
             // Note that the stack is most likely to be error-prone as it works differently. (i.e. look here first)
-            addr if stack_start <= addr && addr <= MIPS_STACK_END_ADDR => {
-                let offset: usize = (MIPS_STACK_END_ADDR - address) as usize;
-                if offset < self.stack.len() {
-                    self.stack[offset] = value;
-                    return Ok(());
-                } else {
-                    return Err(MemoryError::TextTranslationError);
-                }
+            // The stack grows on demand: writing below the current bottom of the stack (within the limit) extends it.
+            addr if stack_limit <= addr && addr <= self.layout.stack_top => {
+                self.stack
+                    .set((self.layout.stack_top - address) as usize, value);
+                return Ok(());
             }
             // Other areas in memory are not yet necessary to match on but can be added quickly
             _ => {
@@ -212,44 +265,61 @@ impl Memory {
     }
 
     /// This function checks that the provided address falls within a section that allows execution.
+    /// Everything writable is also executable if the permissions allow executing data.
     pub fn allows_execution_of(&self, address: u32) -> bool {
-        let text_end: u32 = MIPS_TEXT_START_ADDR + self.text.len() as u32;
+        let text_end: u32 = self.layout.text_base + self.text.len() as u32;
 
-        // This will require more sophisticated checks in the future when self-modifying code is optionally allowed.
-        return MIPS_TEXT_START_ADDR <= address && address < text_end;
+        return (self.layout.text_base <= address && address < text_end)
+            || (self.layout.permissions.executable_data && self.allows_read_from(address));
     }
 
     /// This function checks if the provided address can be written to.
+    /// .text is only writable if the permissions say so.
     pub fn allows_write_to(&self, address: u32) -> bool {
-        let data_end: u32 = MIPS_DATA_START_ADDR + self.text.len() as u32;
-        let heap_end: u32 = MIPS_HEAP_START_ADDR + self.heap.len() as u32;
-        let stack_start: u32 = MIPS_STACK_END_ADDR - self.stack.len() as u32;
-
-        return (MIPS_DATA_START_ADDR <= address && address < data_end)
-            || (MIPS_HEAP_START_ADDR <= address && address < heap_end)
-            || (stack_start <= address && address < MIPS_STACK_END_ADDR);
+        let text_end: u32 = self.layout.text_base + self.text.len() as u32;
+        let data_end: u32 = self.layout.data_base + self.data.len() as u32;
+        let heap_end: u32 = self.layout.heap_base + self.heap.len() as u32;
+        // Writes may land anywhere the stack is allowed to grow into.
+        let stack_limit: u32 = self.layout.stack_limit();
+
+        return (self.layout.permissions.writable_text
+            && self.layout.text_base <= address
+            && address < text_end)
+            || (self.layout.data_base <= address && address < data_end)
+            || (self.layout.heap_base <= address && address < heap_end)
+            || (stack_limit <= address && address < self.layout.stack_top);
     }
 
     /// This function checks if the provided address can be read from.
     pub fn allows_read_from(&self, address: u32) -> bool {
-        let text_end: u32 = MIPS_TEXT_START_ADDR + self.text.len() as u32;
-        let data_end: u32 = MIPS_DATA_START_ADDR + self.data.len() as u32;
-        let heap_end: u32 = MIPS_HEAP_START_ADDR + self.heap.len() as u32;
-        let stack_start: u32 = MIPS_STACK_END_ADDR - self.stack.len() as u32;
-
-        return (MIPS_TEXT_START_ADDR <= address && address < text_end)
-            || (MIPS_DATA_START_ADDR <= address && address < data_end)
-            || (MIPS_HEAP_START_ADDR <= address && address < heap_end)
-            || (stack_start <= address && address < MIPS_STACK_END_ADDR);
+        let text_end: u32 = self.layout.text_base + self.text.len() as u32;
+        let data_end: u32 = self.layout.data_base + self.data.len() as u32;
+        let heap_end: u32 = self.layout.heap_base + self.heap.len() as u32;
+        let stack_start: u32 = self.layout.stack_top - self.stack.len() as u32;
+
+        return (self.layout.text_base <= address && address < text_end)
+            || (self.layout.data_base <= address && address < data_end)
+            || (self.layout.heap_base <= address && address < heap_end)
+            || (stack_start <= address && address < self.layout.stack_top);
     }
 }
 
+/// A frame in the shadow call stack. Pushed when a jal/jalr executes and popped when the matching jr $ra does.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CallFrame {
+    pub call_site: u32,        // address of the jal/jalr
+    pub function_address: u32, // where the call went
+    pub return_address: u32,   // where the function is expected to come back to
+}
+
 #[derive(Debug, Default)]
 pub struct ProgramState {
     pub should_continue_execution: bool,
     pub cpu: Processor,
     pub cp0: Coprocessor0,
     pub memory: Memory,
+    pub call_stack: Vec<CallFrame>, // innermost call last
+    pub observers: Observers,
 }
 
 impl ProgramState {
@@ -259,12 +329,58 @@ impl ProgramState {
             cpu: cpu,
             cp0: Coprocessor0::new(),
             memory: memory,
+            call_stack: Vec::new(),
+            observers: Observers::default(),
         }
     }
 
     pub fn is_exception(&self) -> bool {
         return self.cp0.get_exception_level() == EXCEPTION_BEING_HANDLED;
     }
+
+    /// Reads size bytes (at most four) as a big-endian value on the program's behalf and tells observers about it.
+    /// Every load, and every syscall that reads the program's memory, goes through here.
+    ///
+    /// # Errors
+    /// Fails if any of the bytes isn't in memory.
+    pub fn load(&mut self, address: u32, size: u32) -> Result<u32, MemoryError> {
+        let mut value: u32 = 0;
+        for offset in 0..size {
+            value = value << 8 | u32::from(self.memory.read_byte(address.wrapping_add(offset))?);
+        }
+        self.notify_access(address, size, AccessType::Read, value);
+        Ok(value)
+    }
+
+    /// Writes the low size bytes (at most four) of value big-endian on the program's behalf and tells observers about
+    /// it. Every store, and every syscall that writes the program's memory, goes through here.
+    ///
+    /// # Errors
+    /// Fails if any of the bytes isn't in memory; the ones before it have been written by then.
+    pub fn store(&mut self, address: u32, size: u32, value: u32) -> Result<(), MemoryError> {
+        let mut stored: u32 = 0;
+        for (offset, &byte) in (0..size).zip(&value.to_be_bytes()[4 - size as usize..]) {
+            self.memory.set_byte(address.wrapping_add(offset), byte)?;
+            stored = stored << 8 | u32::from(byte);
+        }
+        self.notify_access(address, size, AccessType::Write, stored);
+        Ok(())
+    }
+
+    fn notify_access(&mut self, address: u32, size: u32, access_type: AccessType, value: u32) {
+        if self.observers.is_empty() {
+            return;
+        }
+        let access: MemoryAccess = MemoryAccess {
+            address,
+            size,
+            access_type,
+        };
+        notify(self, |observer, state| match access_type {
+            AccessType::Read => observer.memory_read(access, value, state),
+            AccessType::Write => observer.memory_write(access, value, state),
+        });
+    }
 }
 
 /// Enumeration of general-purpose register set for simplicity in instructions
@@ -322,10 +438,18 @@ pub enum Section {
     Data,
 }
 
+/// The version of section .line's format. Version 2 added each line's file name.
+pub const LINE_SECTION_VERSION: u8 = 2;
+
+/// Section .line starts with these bytes, then its entries. A .line from before the format had a version starts with a
+/// line's content instead, and 0xff can't begin UTF-8 text, so the two can't be mixed up.
+pub const LINE_SECTION_HEADER: [u8; 2] = [0xff, LINE_SECTION_VERSION];
+
 /// The definition for section .line
 #[derive(Debug)]
 pub struct LineInfo {
     pub content: String,
+    pub file_name: String, // the source file the line came from, so lines from different modules can be told apart
     pub line_number: u32,
     pub start_address: u32,
     pub end_address: u32,
@@ -336,6 +460,8 @@ impl LineInfo {
     pub fn to_bytes(&self) -> Vec<u8> {
         let mut bytes = self.content.as_bytes().to_vec();
         bytes.push(b'\0');
+        bytes.extend_from_slice(self.file_name.as_bytes());
+        bytes.push(b'\0');
 
         bytes.extend_from_slice(&self.line_number.to_be_bytes());
         bytes.extend_from_slice(&self.start_address.to_be_bytes());
@@ -347,10 +473,28 @@ impl LineInfo {
 
 /// Handler for outside world. Operating System interprets syscalls.
 /// Still WIP, will grow to include other non processor peripheries (which can interact through MMIO)
-#[derive(Debug)]
 pub struct OperatingSystem {
     stdin: Stdin,
     stdout: Stdout,
+    program_input: Option<Box<dyn BufRead>>, // read by the program's syscalls in place of stdin, so stdin is left to the debugger
+    program_output: Option<Box<dyn Write>>, // written to by the program's syscalls in place of stdout
+    captured_output: Option<Vec<u8>>, // the program's output, held for whoever is driving the emulator instead of going to stdout
+    threads: Threads,
+    exit_code: u32, // what the program gave SysExitValue; 0 if it exited any other way
+}
+
+impl fmt::Debug for OperatingSystem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("OperatingSystem")
+            .field("stdin", &self.stdin)
+            .field("stdout", &self.stdout)
+            .field("program_input", &self.program_input.is_some())
+            .field("program_output", &self.program_output.is_some())
+            .field("captured_output", &self.captured_output)
+            .field("threads", &self.threads)
+            .field("exit_code", &self.exit_code)
+            .finish()
+    }
 }
 
 impl OperatingSystem {
@@ -358,6 +502,62 @@ impl OperatingSystem {
         OperatingSystem {
             stdin: stdin(),
             stdout: stdout(),
+            program_input: None,
+            program_output: None,
+            captured_output: None,
+            threads: Threads::default(),
+            exit_code: 0,
+        }
+    }
+
+    /// Sets how the program's threads are scheduled. Has to happen before the program creates any.
+    pub fn set_thread_options(&mut self, options: ThreadOptions) {
+        self.threads = Threads::new(options);
+    }
+
+    pub fn threads(&self) -> &Threads {
+        &self.threads
+    }
+
+    /// The program's exit code, as it gave it to SysExitValue.
+    pub fn exit_code(&self) -> u32 {
+        self.exit_code
+    }
+
+    /// Lets the scheduler know an instruction has run, so it can preempt the running thread.
+    pub fn tick(&mut self, program_state: &mut ProgramState) {
+        self.threads.tick(program_state);
+    }
+
+    /// Exits the running thread if it has just returned from its entry. Gives back None if it hasn't.
+    pub fn handle_thread_return(
+        &mut self,
+        program_state: &mut ProgramState,
+    ) -> Option<Result<(), String>> {
+        self.threads.returned(program_state)
+    }
+
+    /// Has the program read its input from somewhere other than stdin.
+    pub fn set_program_input<R: BufRead + 'static>(&mut self, input: R) {
+        self.program_input = Some(Box::new(input));
+    }
+
+    /// Has the program print somewhere other than stdout. Output stops being captured.
+    pub fn set_program_output<W: Write + 'static>(&mut self, output: W) {
+        self.program_output = Some(Box::new(output));
+        self.captured_output = None;
+    }
+
+    /// Holds on to everything the program prints instead of writing it to stdout. Collect it with take_program_output.
+    pub fn capture_program_output(&mut self) {
+        self.captured_output = Some(Vec::new());
+    }
+
+    /// Hands over whatever the program has printed since the last call. Empty unless output is being captured.
+    pub fn take_program_output(&mut self) -> Vec<u8> {
+        match &mut self.captured_output {
+            Some(output) => std::mem::take(output),
+            None => Vec::new(),
         }
     }
 
@@ -367,13 +567,51 @@ impl OperatingSystem {
         let syscall_num: usize =
             program_state.cpu.general_purpose_registers[Register::V0 as usize] as usize;
 
+        // stdin and stdout are only locked if they're actually going to be used
+        let mut stdin_lock;
+        let mut stdout_lock;
+        let mut input: &mut dyn BufRead = match &mut self.program_input {
+            Some(input) => input,
+            None => {
+                stdin_lock = self.stdin.lock();
+                &mut stdin_lock
+            }
+        };
+        let mut output: &mut dyn Write = match (&mut self.captured_output, &mut self.program_output)
+        {
+            (Some(output), _) => output,
+            (None, Some(output)) => output,
+            (None, None) => {
+                stdout_lock = self.stdout.lock();
+                &mut stdout_lock
+            }
+        };
+
         match syscall_num {
-            0x01 => sys_print_int(program_state, &mut self.stdout.lock()),
-            0x04 => sys_print_string(program_state, &mut self.stdout.lock()),
-            0x05 => sys_read_int(program_state, &mut self.stdin.lock()),
+            0x01 => sys_print_int(program_state, &mut output),
+            0x04 => sys_print_string(program_state, &mut output),
+            0x05 => sys_read_int(program_state, &mut input),
+            0x08 => sys_read_string(program_state, &mut input),
             0x0A => sys_exit(program_state),
-            0x0B => sys_print_char(program_state, &mut self.stdout.lock()),
-            0x0C => sys_read_char(program_state, &mut self.stdin.lock()),
+            0x0B => sys_print_char(program_state, &mut output),
+            0x0C => sys_read_char(program_state, &mut input),
+            0x11 => sys_exit_value(program_state).map(|code| self.exit_code = code),
+            0x64 => self.threads.create(program_state),
+            0x65 => {
+                self.threads.yield_thread(program_state);
+                Ok(())
+            }
+            0x66 => self.threads.join(program_state),
+            0x67 => {
+                let value: u32 = program_state.cpu.general_purpose_registers[Register::A0 as usize];
+                self.threads.exit(program_state, value)
+            }
+            0x68 => self.threads.create_mutex(program_state),
+            0x69 => self.threads.lock(program_state),
+            0x6A => self.threads.unlock(program_state),
+            0x6B => self.threads.create_semaphore(program_state),
+            0x6C => self.threads.wait(program_state),
+            0x6D => self.threads.post(program_state),
             _ => Err(format!("{} is not a recognized syscall.", syscall_num)),
         }
     }
@@ -384,39 +622,11 @@ impl OperatingSystem {
         lineinfo: &Vec<LineInfo>,
         debugger_state: &mut DebuggerState,
     ) -> () {
-        /* Needs to do the following:
-         * Transfer control to the user
-         *      Register dump (pretty pa)
-         *      Type in a letter to get a hex dump of .data
-         * Note that cp0 should have flags for whether user ran c or s
-         * Idea: simply replace the instruction on bp.line_num with break
-         *      when done, rereplace the instruction and decrement pc by 4 :jadCensored:
-         * Use the code in the break instruction to match injectively (:nerd:) to the instruction you replaced
-         */
-
-        // program_state.cpu.pc -= MIPS_ADDRESS_ALIGNMENT;
-
-        // grab the breakpoint number and other breakpoint information in one fell swoop
-        let bp_tuple = match debugger_state
-            .breakpoints
-            .iter()
-            .enumerate()
-            .find(|bp| bp.1.address == program_state.cpu.pc - MIPS_ADDRESS_ALIGNMENT)
-        {
-            Some(toupee) => toupee,
-            None => {
-                panic!("Breakpoint not found in breakpoint vector. (How.)");
-            }
-        };
+        // debugger breakpoints never reach memory, so this is a break instruction written into the program
+        println!("Breakpoint reached.");
+        program_state.register_dump(debugger_state);
 
-        println!(
-            "Breakpoint {} at line {} reached.",
-            bp_tuple.0, bp_tuple.1.line_num
-        );
-        program_state.register_dump();
-
-        // program counter is now pointing to the instruction AFTER the breakpoint
-        // once we begin executing code again, execute the breakpoint's replaced_instruction instead of the actual breakpoint
+        // program counter is now pointing to the instruction AFTER the break
 
         if program_state.cp0.is_debug_mode() {
             // terminate existing debugger process????
@@ -441,18 +651,38 @@ impl OperatingSystem {
         program_state: &mut ProgramState,
         debugger_state: &mut DebuggerState,
     ) -> Result<(), String> {
-        println!("Welcome to the NAME CLI debugger.");
-        println!("For a list of commands, type \"help\".");
+        // batch mode only prints what the commands print
+        if !debugger_state.batch {
+            println!("Welcome to the NAME CLI debugger.");
+            println!("For a list of commands, type \"help\".");
+        }
 
         loop {
-            print!("(name-db) ");
-            self.stdout.flush().expect("Failed to flush stdout");
+            if !debugger_state.batch {
+                print!("(name-db) ");
+                self.stdout.flush().expect("Failed to flush stdout");
+            }
 
-            // take in the command and split it up into arguments
-            let mut user_input = String::new();
-            match self.stdin.read_line(&mut user_input) {
-                Ok(_) => {}
-                Err(e) => eprintln!("stdin error: {e}"),
+            // take in the command (from the script first, if there is one) and split it up into arguments
+            let user_input: String = match debugger_state.script.pop_front() {
+                Some(command) => {
+                    if !debugger_state.batch {
+                        // echo it so the transcript reads as if it were typed
+                        println!("{command}");
+                    }
+                    command
+                }
+                None if debugger_state.batch => return Ok(()),
+                None => {
+                    let mut user_input = String::new();
+                    match self.stdin.read_line(&mut user_input) {
+                        // nothing left to read; treat it like q rather than spinning forever
+                        Ok(0) => return Ok(()),
+                        Ok(_) => {}
+                        Err(e) => eprintln!("stdin error: {e}"),
+                    };
+                    user_input
+                }
             };
             let db_args: Vec<String> = user_input
                 .trim()
@@ -468,40 +698,61 @@ impl OperatingSystem {
                 "q" => return Ok(()),
                 "exit" => return Ok(()),
                 "quit" => return Ok(()),
-                "r" => match continuously_execute(lineinfo, program_state, self, debugger_state) {
+                "r" | "c" | "s" | "n" | "finish" | "until" => {
+                    // whatever runs next, the registers as they are now are what pa compares against at the next stop
+                    debugger_state.remember_registers(program_state);
+
+                    let result: Result<(), StopReason> = match db_args[0].as_str() {
+                        "r" | "c" => {
+                            continuously_execute(lineinfo, program_state, self, debugger_state)
+                        }
+                        "s" => db_step(lineinfo, program_state, self, debugger_state),
+                        "n" => step_over(lineinfo, program_state, self, debugger_state),
+                        "finish" => finish(lineinfo, program_state, self, debugger_state),
+                        _ => run_until(lineinfo, program_state, self, debugger_state, &db_args),
+                    };
+                    if let Err(reason) = result {
+                        if !reason.is_reported() {
+                            eprintln!("{reason}");
+                        }
+                    }
+                }
+                "bt" => match backtrace(lineinfo, program_state, debugger_state) {
                     Ok(_) => continue,
                     Err(e) => eprintln!("{e}"),
                 },
-                "c" => match continuously_execute(lineinfo, program_state, self, debugger_state) {
+                "l" => match list_text(lineinfo, debugger_state, &db_args) {
                     Ok(_) => continue,
                     Err(e) => eprintln!("{e}"),
                 },
-                "s" => match db_step(lineinfo, program_state, self, debugger_state) {
-                    Ok(_) => continue,
-                    Err(e) => {
-                        if e == "Breakpoint reached." {
-                            continue;
-                        } else {
-                            eprintln!("{e}");
-                        }
+                cmd if cmd == "p" || cmd.starts_with("p/") => {
+                    match print_register(program_state, debugger_state, &db_args) {
+                        Ok(_) => continue,
+                        Err(e) => eprintln!("{e}"),
                     }
-                },
-                "l" => match list_text(lineinfo, debugger_state, &db_args) {
+                }
+                "pa" => match program_state.print_all_registers(debugger_state, &db_args) {
                     Ok(_) => continue,
                     Err(e) => eprintln!("{e}"),
                 },
-                "p" => match print_register(program_state, &db_args) {
+                "m" => match modify_register(program_state, &db_args) {
                     Ok(_) => continue,
                     Err(e) => eprintln!("{e}"),
                 },
-                "pa" => match program_state.print_all_registers(&db_args) {
+                "set" => match set_command(program_state, debugger_state, &db_args) {
                     Ok(_) => continue,
                     Err(e) => eprintln!("{e}"),
                 },
-                "m" => match modify_register(program_state, &db_args) {
+                "info" => match info_command(program_state, &db_args) {
                     Ok(_) => continue,
                     Err(e) => eprintln!("{e}"),
                 },
+                cmd if cmd == "x" || cmd.starts_with("x/") => {
+                    match examine_memory(program_state, debugger_state, &db_args) {
+                        Ok(_) => continue,
+                        Err(e) => eprintln!("{e}"),
+                    }
+                }
                 "pb" => match debugger_state.print_all_breakpoints() {
                     Ok(_) => continue,
                     Err(e) => eprintln!("{e}"),
@@ -510,7 +761,35 @@ impl OperatingSystem {
                     Ok(_) => continue,
                     Err(e) => eprintln!("{e}"),
                 },
-                "del" => match debugger_state.remove_breakpoint(&db_args, program_state) {
+                "del" => match debugger_state.remove_breakpoint(&db_args) {
+                    Ok(_) => continue,
+                    Err(e) => eprintln!("{e}"),
+                },
+                "enable" => match debugger_state.set_breakpoints_enabled(&db_args, true) {
+                    Ok(_) => continue,
+                    Err(e) => eprintln!("{e}"),
+                },
+                "disable" => match debugger_state.set_breakpoints_enabled(&db_args, false) {
+                    Ok(_) => continue,
+                    Err(e) => eprintln!("{e}"),
+                },
+                "ignore" => match debugger_state.ignore_breakpoint(&db_args) {
+                    Ok(_) => continue,
+                    Err(e) => eprintln!("{e}"),
+                },
+                "condition" => match debugger_state.set_breakpoint_condition(&db_args) {
+                    Ok(_) => continue,
+                    Err(e) => eprintln!("{e}"),
+                },
+                "watch" => match debugger_state.add_watchpoint(&db_args) {
+                    Ok(_) => continue,
+                    Err(e) => eprintln!("{e}"),
+                },
+                "pw" => match debugger_state.print_all_watchpoints() {
+                    Ok(_) => continue,
+                    Err(e) => eprintln!("{e}"),
+                },
+                "delw" => match debugger_state.remove_watchpoint(&db_args) {
                     Ok(_) => continue,
                     Err(e) => eprintln!("{e}"),
                 },
@@ -519,3 +798,53 @@ impl OperatingSystem {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stack_grows_a_page_at_a_time() {
+        let mut memory = Memory::default();
+        let (stack_top, stack_limit) = (memory.layout.stack_top, memory.layout.stack_limit());
+
+        // a store at the very bottom of the stack only allocates the page it lands in
+        assert!(memory.allows_write_to(stack_limit));
+        memory.set_byte(stack_limit, 0xAB).unwrap();
+        assert_eq!(memory.stack.pages.len(), 1);
+        assert_eq!(memory.read_byte(stack_limit).unwrap(), 0xAB);
+
+        // everything above it is part of the stack now, and reads as 0 where nothing was written
+        assert!(memory.allows_read_from(stack_top - 4));
+        assert_eq!(memory.read_byte(stack_top - 4).unwrap(), 0);
+
+        // nothing below the limit is
+        assert!(!memory.allows_write_to(stack_limit - 1));
+        assert!(memory.set_byte(stack_limit - 1, 0).is_err());
+    }
+
+    #[test]
+    fn test_writes_stop_at_the_end_of_data() {
+        // .text is much longer than .data, which mustn't make any more of .data writable
+        let mut memory = Memory::new(vec![0; 4], vec![0; 64]);
+        let data_base: u32 = memory.layout.data_base;
+
+        assert!(memory.allows_write_to(data_base + 3));
+        assert!(!memory.allows_write_to(data_base + 4));
+        assert!(!memory.allows_write_to(data_base + 32));
+        memory.set_byte(data_base + 3, 1).unwrap();
+        assert_eq!(memory.data, vec![0, 0, 0, 1]);
+    }
+
+    #[test]
+    fn test_stack_reads_stop_at_the_deepest_write() {
+        let mut memory = Memory::default();
+        let stack_top: u32 = memory.layout.stack_top;
+
+        assert!(memory.read_byte(stack_top - 8).is_err());
+        memory.set_byte(stack_top - 8, 7).unwrap();
+        assert_eq!(memory.read_byte(stack_top - 8).unwrap(), 7);
+        assert_eq!(memory.stack.len(), 9);
+        assert!(memory.read_byte(stack_top - 9).is_err());
+    }
+}
@@ -35,10 +35,10 @@ pub fn sys_print_string<W: Write>(
     let mut to_print: Vec<u8> = Vec::new();
 
     loop {
-        let byte = program_state
-            .memory
-            .read_byte(address)
-            .map_err(|e| format!("{e}"))?;
+        let [.., byte] = program_state
+            .load(address, 1)
+            .map_err(|e| format!("{e}"))?
+            .to_be_bytes();
 
         if byte == 0 {
             break;
@@ -86,13 +86,13 @@ pub fn sys_read_string<R: Read>(
     let mut count = 0;
     let mut address = program_state.cpu.general_purpose_registers[A0 as usize];
     let maxlength = program_state.cpu.general_purpose_registers[A1 as usize];
-    while count < maxlength - 1 {
+    while count < maxlength.saturating_sub(1) {
         match sys.read_exact(&mut buf) {
             Ok(_) => (),
             Err(_) => return Err(format!("Failed to access stdin.")),
         };
 
-        match program_state.memory.set_byte(address, buf[0]) {
+        match program_state.store(address, 1, u32::from(buf[0])) {
             Ok(_) => (),
             Err(e) => return Err(format!("Error occurred in read string syscall:\n - {e}")),
         };
@@ -104,7 +104,7 @@ pub fn sys_read_string<R: Read>(
         }
     }
     buf[0] = 0;
-    match program_state.memory.set_byte(address, buf[0]) {
+    match program_state.store(address, 1, u32::from(buf[0])) {
         Ok(_) => (),
         Err(e) => return Err(format!("Error occurred in read string syscall:\n - {e}")),
     };
@@ -118,6 +118,13 @@ pub fn sys_exit(program_state: &mut ProgramState) -> Result<(), String> {
     Ok(())
 }
 
+/// Syscall 17 - SysExitValue
+/// Gives back the exit code the program asked for in $a0.
+pub fn sys_exit_value(program_state: &mut ProgramState) -> Result<u32, String> {
+    program_state.should_continue_execution = false;
+    Ok(program_state.cpu.general_purpose_registers[A0 as usize])
+}
+
 /// Syscall 11 - SysPrintChar
 pub fn sys_print_char<W: Write>(
     program_state: &mut ProgramState,
@@ -236,4 +243,31 @@ mod tests {
 
         assert_eq!(program_state.memory.data[..16], str[..16]);
     }
+
+    #[test]
+    fn test_sys_read_string_limits() {
+        let mut program_state = ProgramState::new(
+            Processor::default(),
+            Memory::new([0xFF; 8].to_vec(), vec![]),
+        );
+        program_state.cpu.general_purpose_registers[A0 as usize] =
+            crate::constants::MIPS_DATA_START_ADDR;
+
+        // a line ends the string, newline included
+        program_state.cpu.general_purpose_registers[A1 as usize] = 8;
+        let mut cursor = Cursor::new("hi\nthere".as_bytes());
+        assert_eq!(Ok(()), sys_read_string(&mut program_state, &mut cursor));
+        assert_eq!(program_state.memory.data[..4], *b"hi\n\0");
+
+        // a buffer of one byte only has room for the terminator, so nothing is read
+        program_state.cpu.general_purpose_registers[A1 as usize] = 1;
+        assert_eq!(Ok(()), sys_read_string(&mut program_state, &mut cursor));
+        assert_eq!(program_state.memory.data[0], 0);
+        assert_eq!(cursor.position(), 3);
+
+        // and one of none doesn't underflow
+        program_state.cpu.general_purpose_registers[A1 as usize] = 0;
+        assert_eq!(Ok(()), sys_read_string(&mut program_state, &mut cursor));
+        assert_eq!(cursor.position(), 3);
+    }
 }
@@ -0,0 +1,633 @@
+//! Green threads, run by the operating system one at a time on the one processor, for concurrency exercises.
+//! Every thread has its own registers and shadow call stack; memory is shared. A thread runs until it yields, blocks,
+//! or exits, or until it has used up its quantum if preemption is on. The next thread is the one that has been ready
+//! longest, or one picked with a seeded generator, so a program always runs the same way under the same options.
+//!
+//! | $v0 | Syscall         | Arguments                                    | Result                   |
+//! |-----|-----------------|----------------------------------------------|--------------------------|
+//! | 100 | `thread_create` | $a0 = entry, $a1 = argument, $a2 = stack top | $v0 = thread id          |
+//! | 101 | `yield`         |                                              |                          |
+//! | 102 | `join`          | $a0 = thread id                              | $v0 = the thread's value |
+//! | 103 | `exit_thread`   | $a0 = value                                  |                          |
+//! | 104 | `mutex_create`  |                                              | $v0 = mutex id           |
+//! | 105 | `mutex_lock`    | $a0 = mutex id                               |                          |
+//! | 106 | `mutex_unlock`  | $a0 = mutex id                               |                          |
+//! | 107 | `sem_create`    | $a0 = initial count                          | $v0 = semaphore id       |
+//! | 108 | `sem_wait`      | $a0 = semaphore id                           |                          |
+//! | 109 | `sem_post`      | $a0 = semaphore id                           |                          |
+//!
+//! A new thread starts with its argument in $a0 and $ra set to `THREAD_RETURN_ADDRESS`, so returning from its entry
+//! exits it with $v0 as its value. The main thread is thread 0. The program ends once every thread has exited,
+//! or as soon as any thread uses the exit syscall.
+
+use std::collections::VecDeque;
+use std::mem::swap;
+
+use crate::structs::{
+    CallFrame, Processor, ProgramState,
+    Register::{Gp, Ra, Sp, A0, A1, A2, V0},
+};
+
+/// Where a thread's entry returns to. Nothing is ever loaded there.
+pub const THREAD_RETURN_ADDRESS: u32 = 0xffff_fff8;
+
+/// How the operating system schedules threads.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ThreadOptions {
+    pub quantum: Option<u32>, // switch threads after this many instructions; without one, threads only switch when they yield or block
+    pub seed: Option<u64>, // pick the next thread at random with this seed, instead of the one that has been ready longest
+}
+
+/// What a thread is waiting for.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Wait {
+    Join(usize),
+    Mutex(usize),
+    Semaphore(usize),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ThreadState {
+    Ready,
+    Running,
+    Waiting(Wait),
+    Finished(u32), // with the value it exited with
+}
+
+/// One thread's own state. The running thread's registers and call stack are in the `ProgramState` instead.
+#[derive(Debug)]
+pub struct Thread {
+    pub cpu: Processor,
+    pub call_stack: Vec<CallFrame>,
+    pub state: ThreadState,
+}
+
+#[derive(Debug, Default)]
+struct Mutex {
+    owner: Option<usize>,
+    waiting: VecDeque<usize>,
+}
+
+#[derive(Debug, Default)]
+struct Semaphore {
+    count: u32,
+    waiting: VecDeque<usize>,
+}
+
+#[derive(Debug)]
+pub struct Threads {
+    options: ThreadOptions,
+    all: Vec<Thread>,
+    current: usize,
+    ready: VecDeque<usize>, // longest ready first
+    mutexes: Vec<Mutex>,
+    semaphores: Vec<Semaphore>,
+    since_switch: u32, // instructions the running thread has executed since it was switched in
+    random_state: u64,
+    pub switches: u64,
+}
+
+impl Default for Threads {
+    fn default() -> Self {
+        Threads::new(ThreadOptions::default())
+    }
+}
+
+impl Threads {
+    /// Starts out with just the main thread, running.
+    #[must_use]
+    pub fn new(options: ThreadOptions) -> Self {
+        Threads {
+            options,
+            all: vec![Thread {
+                cpu: Processor::default(),
+                call_stack: Vec::new(),
+                state: ThreadState::Running,
+            }],
+            current: 0,
+            ready: VecDeque::new(),
+            mutexes: Vec::new(),
+            semaphores: Vec::new(),
+            since_switch: 0,
+            // xorshift gets stuck at zero, so keep the state away from it
+            random_state: options.seed.unwrap_or(0) ^ 0x9E37_79B9_7F4A_7C15,
+            switches: 0,
+        }
+    }
+
+    #[must_use]
+    pub fn threads(&self) -> &[Thread] {
+        &self.all
+    }
+
+    /// The id of the running thread.
+    #[must_use]
+    pub fn current(&self) -> usize {
+        self.current
+    }
+
+    /// Takes the next thread to run off the ready queue.
+    fn pick_next(&mut self) -> Option<usize> {
+        if self.ready.is_empty() {
+            return None;
+        }
+        let index: usize = match self.options.seed {
+            Some(_) => {
+                self.random_state ^= self.random_state << 13;
+                self.random_state ^= self.random_state >> 7;
+                self.random_state ^= self.random_state << 17;
+                usize::try_from(self.random_state % self.ready.len() as u64).unwrap_or_default()
+            }
+            None => 0,
+        };
+        self.ready.remove(index)
+    }
+
+    /// Puts the running thread's registers away and brings in another's. The running thread's state has to have been
+    /// set already.
+    fn switch_to(&mut self, program_state: &mut ProgramState, next: usize) {
+        let previous: &mut Thread = &mut self.all[self.current];
+        swap(&mut previous.cpu, &mut program_state.cpu);
+        swap(&mut previous.call_stack, &mut program_state.call_stack);
+
+        self.current = next;
+        let thread: &mut Thread = &mut self.all[next];
+        thread.state = ThreadState::Running;
+        swap(&mut thread.cpu, &mut program_state.cpu);
+        swap(&mut thread.call_stack, &mut program_state.call_stack);
+
+        // a context switch breaks any ll/sc link, as eret would
+        program_state.cpu.linked_address = None;
+        // returning from the syscall has to land in the new thread
+        if program_state.is_exception() {
+            program_state
+                .cp0
+                .set_epc(program_state.cpu.pc.wrapping_sub(4));
+        }
+
+        self.since_switch = 0;
+        self.switches += 1;
+    }
+
+    fn make_ready(&mut self, thread: usize) {
+        self.all[thread].state = ThreadState::Ready;
+        self.ready.push_back(thread);
+    }
+
+    /// Switches away from a thread that can't go on, ending the program once there's nothing left to run.
+    fn reschedule(&mut self, program_state: &mut ProgramState) -> Result<(), String> {
+        if let Some(next) = self.pick_next() {
+            self.switch_to(program_state, next);
+            return Ok(());
+        }
+
+        let waits: Vec<String> = self
+            .all
+            .iter()
+            .enumerate()
+            .filter_map(|(id, thread)| match thread.state {
+                ThreadState::Waiting(Wait::Join(other)) => {
+                    Some(format!("thread {id} is waiting to join thread {other}"))
+                }
+                ThreadState::Waiting(Wait::Mutex(mutex)) => {
+                    Some(format!("thread {id} is waiting for mutex {mutex}"))
+                }
+                ThreadState::Waiting(Wait::Semaphore(semaphore)) => {
+                    Some(format!("thread {id} is waiting on semaphore {semaphore}"))
+                }
+                _ => None,
+            })
+            .collect();
+
+        if waits.is_empty() {
+            program_state.should_continue_execution = false;
+            Ok(())
+        } else {
+            Err(format!(
+                "Deadlock: every thread is waiting, so none can go on ({}).",
+                waits.join(", ")
+            ))
+        }
+    }
+
+    fn block(&mut self, program_state: &mut ProgramState, wait: Wait) -> Result<(), String> {
+        self.all[self.current].state = ThreadState::Waiting(wait);
+        self.reschedule(program_state)
+    }
+
+    /// Counts an instruction against the running thread's quantum, and switches threads once it's used up.
+    pub fn tick(&mut self, program_state: &mut ProgramState) {
+        let Some(quantum) = self.options.quantum else {
+            return;
+        };
+        if !program_state.should_continue_execution || program_state.is_exception() {
+            return;
+        }
+
+        self.since_switch += 1;
+        if self.since_switch >= quantum {
+            self.yield_thread(program_state);
+            self.since_switch = 0;
+        }
+    }
+
+    /// Syscall 100 - `thread_create`
+    ///
+    /// # Errors
+    ///
+    /// Fails if the entry isn't code or no stack was given.
+    pub fn create(&mut self, program_state: &mut ProgramState) -> Result<(), String> {
+        let registers: &[u32; 32] = &program_state.cpu.general_purpose_registers;
+        let (entry, argument, stack) = (
+            registers[A0 as usize],
+            registers[A1 as usize],
+            registers[A2 as usize],
+        );
+        if !program_state.memory.allows_execution_of(entry) {
+            return Err(format!(
+                "A thread can't start at 0x{entry:08x}, which isn't code."
+            ));
+        }
+        if stack == 0 {
+            return Err("A new thread needs the top of a stack of its own in $a2.".to_string());
+        }
+
+        let mut cpu: Processor = Processor::new(entry);
+        cpu.general_purpose_registers[Sp as usize] = stack;
+        cpu.general_purpose_registers[Gp as usize] = registers[Gp as usize];
+        cpu.general_purpose_registers[A0 as usize] = argument;
+        cpu.general_purpose_registers[Ra as usize] = THREAD_RETURN_ADDRESS;
+
+        let id: u32 = next_id(self.all.len(), "thread")?;
+        self.all.push(Thread {
+            cpu,
+            call_stack: Vec::new(),
+            state: ThreadState::Ready,
+        });
+        self.ready.push_back(self.all.len() - 1);
+
+        program_state.cpu.general_purpose_registers[V0 as usize] = id;
+        Ok(())
+    }
+
+    /// Syscall 101 - yield. Nothing happens if no other thread is ready.
+    pub fn yield_thread(&mut self, program_state: &mut ProgramState) {
+        if let Some(next) = self.pick_next() {
+            let current: usize = self.current;
+            self.make_ready(current);
+            self.switch_to(program_state, next);
+        }
+    }
+
+    /// Syscall 102 - join
+    ///
+    /// # Errors
+    ///
+    /// Fails if the thread doesn't exist or is the one joining, or if waiting for it leaves nothing to run.
+    pub fn join(&mut self, program_state: &mut ProgramState) -> Result<(), String> {
+        let id: usize = program_state.cpu.general_purpose_registers[A0 as usize] as usize;
+        if id >= self.all.len() {
+            return Err(format!("There is no thread {id} to join."));
+        }
+        if id == self.current {
+            return Err(format!("Thread {id} can't join itself."));
+        }
+
+        match self.all[id].state {
+            ThreadState::Finished(value) => {
+                program_state.cpu.general_purpose_registers[V0 as usize] = value;
+                Ok(())
+            }
+            _ => self.block(program_state, Wait::Join(id)),
+        }
+    }
+
+    /// Syscall 103 - `exit_thread`. Also where a thread ends up when it returns from its entry.
+    ///
+    /// # Errors
+    ///
+    /// Fails if every thread left is waiting.
+    pub fn exit(&mut self, program_state: &mut ProgramState, value: u32) -> Result<(), String> {
+        let current: usize = self.current;
+        self.all[current].state = ThreadState::Finished(value);
+
+        for id in 0..self.all.len() {
+            if self.all[id].state == ThreadState::Waiting(Wait::Join(current)) {
+                self.all[id].cpu.general_purpose_registers[V0 as usize] = value;
+                self.make_ready(id);
+            }
+        }
+
+        self.reschedule(program_state)
+    }
+
+    /// Exits a thread that has returned from its entry. Gives back None if that isn't what happened.
+    pub fn returned(&mut self, program_state: &mut ProgramState) -> Option<Result<(), String>> {
+        if self.current == 0 || program_state.cpu.pc != THREAD_RETURN_ADDRESS {
+            return None;
+        }
+        let value: u32 = program_state.cpu.general_purpose_registers[V0 as usize];
+        Some(self.exit(program_state, value))
+    }
+
+    /// Syscall 104 - `mutex_create`
+    ///
+    /// # Errors
+    ///
+    /// Fails if there are already as many mutexes as ids.
+    pub fn create_mutex(&mut self, program_state: &mut ProgramState) -> Result<(), String> {
+        program_state.cpu.general_purpose_registers[V0 as usize] =
+            next_id(self.mutexes.len(), "mutex")?;
+        self.mutexes.push(Mutex::default());
+        Ok(())
+    }
+
+    fn mutex(&mut self, program_state: &ProgramState) -> Result<(usize, &mut Mutex), String> {
+        let id: usize = program_state.cpu.general_purpose_registers[A0 as usize] as usize;
+        match self.mutexes.get_mut(id) {
+            Some(mutex) => Ok((id, mutex)),
+            None => Err(format!("There is no mutex {id}.")),
+        }
+    }
+
+    /// Syscall 105 - `mutex_lock`
+    ///
+    /// # Errors
+    ///
+    /// Fails if the mutex doesn't exist or is already held by this thread, or if waiting for it leaves nothing to run.
+    pub fn lock(&mut self, program_state: &mut ProgramState) -> Result<(), String> {
+        let current: usize = self.current;
+        let (id, mutex) = self.mutex(program_state)?;
+        match mutex.owner {
+            None => {
+                mutex.owner = Some(current);
+                Ok(())
+            }
+            Some(owner) if owner == current => {
+                Err(format!("Thread {current} already holds mutex {id}."))
+            }
+            Some(_) => {
+                mutex.waiting.push_back(current);
+                self.block(program_state, Wait::Mutex(id))
+            }
+        }
+    }
+
+    /// Syscall 106 - `mutex_unlock`. The longest waiting thread gets the mutex next.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the mutex doesn't exist or this thread doesn't hold it.
+    pub fn unlock(&mut self, program_state: &mut ProgramState) -> Result<(), String> {
+        let current: usize = self.current;
+        let (id, mutex) = self.mutex(program_state)?;
+        if mutex.owner != Some(current) {
+            return Err(format!(
+                "Thread {current} can't unlock mutex {id}, which it doesn't hold."
+            ));
+        }
+
+        mutex.owner = mutex.waiting.pop_front();
+        if let Some(waiter) = mutex.owner {
+            self.make_ready(waiter);
+        }
+        Ok(())
+    }
+
+    /// Syscall 107 - `sem_create`
+    ///
+    /// # Errors
+    ///
+    /// Fails if there are already as many semaphores as ids.
+    pub fn create_semaphore(&mut self, program_state: &mut ProgramState) -> Result<(), String> {
+        let count: u32 = program_state.cpu.general_purpose_registers[A0 as usize];
+        program_state.cpu.general_purpose_registers[V0 as usize] =
+            next_id(self.semaphores.len(), "semaphore")?;
+        self.semaphores.push(Semaphore {
+            count,
+            waiting: VecDeque::new(),
+        });
+        Ok(())
+    }
+
+    fn semaphore(
+        &mut self,
+        program_state: &ProgramState,
+    ) -> Result<(usize, &mut Semaphore), String> {
+        let id: usize = program_state.cpu.general_purpose_registers[A0 as usize] as usize;
+        match self.semaphores.get_mut(id) {
+            Some(semaphore) => Ok((id, semaphore)),
+            None => Err(format!("There is no semaphore {id}.")),
+        }
+    }
+
+    /// Syscall 108 - `sem_wait`
+    ///
+    /// # Errors
+    ///
+    /// Fails if the semaphore doesn't exist, or if waiting on it leaves nothing to run.
+    pub fn wait(&mut self, program_state: &mut ProgramState) -> Result<(), String> {
+        let current: usize = self.current;
+        let (id, semaphore) = self.semaphore(program_state)?;
+        if semaphore.count > 0 {
+            semaphore.count -= 1;
+            return Ok(());
+        }
+
+        semaphore.waiting.push_back(current);
+        self.block(program_state, Wait::Semaphore(id))
+    }
+
+    /// Syscall 109 - `sem_post`. A waiting thread takes the count straight away.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the semaphore doesn't exist.
+    pub fn post(&mut self, program_state: &mut ProgramState) -> Result<(), String> {
+        let (_, semaphore) = self.semaphore(program_state)?;
+        match semaphore.waiting.pop_front() {
+            Some(waiter) => self.make_ready(waiter),
+            None => semaphore.count = semaphore.count.saturating_add(1),
+        }
+        Ok(())
+    }
+}
+
+/// The id the next thread, mutex, or semaphore gets, as long as it fits in a register.
+fn next_id(count: usize, what: &str) -> Result<u32, String> {
+    u32::try_from(count).map_err(|_| format!("There is no id left for another {what}."))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constants::MIPS_TEXT_START_ADDR;
+    use crate::structs::Memory;
+
+    fn program_state() -> ProgramState {
+        ProgramState::new(
+            Processor::new(MIPS_TEXT_START_ADDR),
+            Memory::new(vec![], vec![0; 64]),
+        )
+    }
+
+    fn set(program_state: &mut ProgramState, a0: u32, a1: u32, a2: u32) {
+        let registers: &mut [u32; 32] = &mut program_state.cpu.general_purpose_registers;
+        registers[A0 as usize] = a0;
+        registers[A1 as usize] = a1;
+        registers[A2 as usize] = a2;
+    }
+
+    fn v0(program_state: &ProgramState) -> u32 {
+        program_state.cpu.general_purpose_registers[V0 as usize]
+    }
+
+    #[test]
+    fn test_create_join_exit() {
+        let mut program_state: ProgramState = program_state();
+        let mut threads: Threads = Threads::default();
+        let entry: u32 = MIPS_TEXT_START_ADDR + 0x20;
+
+        set(&mut program_state, entry, 42, 0x7fff_0000);
+        assert_eq!(threads.create(&mut program_state), Ok(()));
+        assert_eq!(v0(&program_state), 1);
+        assert_eq!(threads.current(), 0);
+
+        // joining switches to the new thread, which starts out set up for its entry
+        set(&mut program_state, 1, 0, 0);
+        assert_eq!(threads.join(&mut program_state), Ok(()));
+        assert_eq!(threads.current(), 1);
+        let registers: &[u32; 32] = &program_state.cpu.general_purpose_registers;
+        assert_eq!(program_state.cpu.pc, entry);
+        assert_eq!(registers[A0 as usize], 42);
+        assert_eq!(registers[Sp as usize], 0x7fff_0000);
+        assert_eq!(registers[Ra as usize], THREAD_RETURN_ADDRESS);
+
+        // returning from the entry exits with $v0, which the joiner gets
+        program_state.cpu.general_purpose_registers[V0 as usize] = 7;
+        program_state.cpu.pc = THREAD_RETURN_ADDRESS;
+        assert_eq!(threads.returned(&mut program_state), Some(Ok(())));
+        assert_eq!(threads.current(), 0);
+        assert_eq!(v0(&program_state), 7);
+        assert_eq!(threads.threads()[1].state, ThreadState::Finished(7));
+
+        // the main thread returning isn't a thread exiting
+        program_state.cpu.pc = THREAD_RETURN_ADDRESS;
+        assert_eq!(threads.returned(&mut program_state), None);
+
+        set(&mut program_state, 0, 0, 0);
+        assert!(threads.join(&mut program_state).is_err());
+        set(&mut program_state, 5, 0, 0);
+        assert!(threads.join(&mut program_state).is_err());
+        set(&mut program_state, 0x1234, 0, 0x7fff_0000);
+        assert!(threads.create(&mut program_state).is_err());
+
+        assert_eq!(threads.exit(&mut program_state, 0), Ok(()));
+        assert!(!program_state.should_continue_execution);
+    }
+
+    #[test]
+    fn test_mutex_and_deadlock() {
+        let mut program_state: ProgramState = program_state();
+        let mut threads: Threads = Threads::default();
+
+        assert_eq!(threads.create_mutex(&mut program_state), Ok(()));
+        assert_eq!(v0(&program_state), 0);
+        set(&mut program_state, MIPS_TEXT_START_ADDR, 0, 0x7fff_0000);
+        threads.create(&mut program_state).unwrap();
+
+        set(&mut program_state, 0, 0, 0);
+        assert_eq!(threads.lock(&mut program_state), Ok(()));
+        assert!(threads.lock(&mut program_state).is_err());
+
+        // thread 1 blocks on the mutex, and gets it as soon as it's unlocked
+        threads.yield_thread(&mut program_state);
+        assert_eq!(threads.current(), 1);
+        set(&mut program_state, 0, 0, 0);
+        assert_eq!(threads.lock(&mut program_state), Ok(()));
+        assert_eq!(threads.current(), 0);
+        assert_eq!(
+            threads.threads()[1].state,
+            ThreadState::Waiting(Wait::Mutex(0))
+        );
+        assert_eq!(threads.unlock(&mut program_state), Ok(()));
+        assert_eq!(threads.threads()[1].state, ThreadState::Ready);
+        assert!(threads.unlock(&mut program_state).is_err());
+
+        // thread 1 holds the mutex and waits for main, which then waits for the mutex
+        threads.yield_thread(&mut program_state);
+        assert_eq!(threads.current(), 1);
+        set(&mut program_state, 0, 0, 0);
+        threads.join(&mut program_state).unwrap();
+        assert_eq!(threads.current(), 0);
+        assert_eq!(
+            threads.lock(&mut program_state),
+            Err("Deadlock: every thread is waiting, so none can go on (thread 0 is waiting for mutex 0, thread 1 is waiting to join thread 0).".to_string())
+        );
+    }
+
+    #[test]
+    fn test_semaphore() {
+        let mut program_state: ProgramState = program_state();
+        let mut threads: Threads = Threads::default();
+
+        set(&mut program_state, 1, 0, 0);
+        threads.create_semaphore(&mut program_state).unwrap();
+        set(&mut program_state, MIPS_TEXT_START_ADDR, 0, 0x7fff_0000);
+        threads.create(&mut program_state).unwrap();
+
+        set(&mut program_state, 0, 0, 0);
+        assert_eq!(threads.wait(&mut program_state), Ok(()));
+        assert_eq!(threads.current(), 0);
+        assert_eq!(threads.wait(&mut program_state), Ok(()));
+        assert_eq!(threads.current(), 1);
+
+        // posting hands the count straight to the waiting thread
+        set(&mut program_state, 0, 0, 0);
+        assert_eq!(threads.post(&mut program_state), Ok(()));
+        assert_eq!(threads.threads()[0].state, ThreadState::Ready);
+        assert_eq!(threads.wait(&mut program_state), Ok(()));
+        assert_eq!(threads.current(), 0);
+
+        set(&mut program_state, 3, 0, 0);
+        assert!(threads.post(&mut program_state).is_err());
+    }
+
+    #[test]
+    fn test_preemption() {
+        // which thread runs after each preemption
+        let order = |seed: Option<u64>| -> Vec<usize> {
+            let mut program_state: ProgramState = program_state();
+            let mut threads: Threads = Threads::new(ThreadOptions {
+                quantum: Some(2),
+                seed,
+            });
+            for _ in 0..3 {
+                set(&mut program_state, MIPS_TEXT_START_ADDR, 0, 0x7fff_0000);
+                threads.create(&mut program_state).unwrap();
+            }
+            (0..16)
+                .map(|_| {
+                    threads.tick(&mut program_state);
+                    threads.current()
+                })
+                .collect()
+        };
+
+        assert_eq!(
+            order(None),
+            vec![0, 1, 1, 2, 2, 3, 3, 0, 0, 1, 1, 2, 2, 3, 3, 0]
+        );
+        assert_eq!(order(Some(4)), order(Some(4)));
+        assert_ne!(order(Some(4)), order(None));
+
+        // nothing else to run, so nothing switches
+        let mut program_state: ProgramState = program_state();
+        let mut threads: Threads = Threads::new(ThreadOptions {
+            quantum: Some(1),
+            seed: None,
+        });
+        threads.tick(&mut program_state);
+        assert_eq!(threads.switches, 0);
+    }
+}
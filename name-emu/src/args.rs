@@ -2,8 +2,187 @@ use clap::Parser;
 
 #[derive(Debug, Parser)]
 pub struct Cli {
-    pub input_filename: std::path::PathBuf,
+    #[arg(required_unless_present = "dap")]
+    pub input_filename: Option<std::path::PathBuf>,
 
     #[arg(short, long, help = "Enable debug mode")]
     pub debug: bool,
+
+    #[arg(
+        short = 'x',
+        long,
+        value_name = "SCRIPT",
+        help = "Run the debugger commands in SCRIPT before accepting any from stdin (implies --debug)"
+    )]
+    pub script: Option<std::path::PathBuf>,
+
+    #[arg(
+        long,
+        help = "Exit the debugger once the script runs out instead of waiting for more commands (implies --debug)"
+    )]
+    pub batch: bool,
+
+    #[arg(
+        short,
+        long,
+        value_name = "FILE",
+        help = "Give the program FILE as its input instead of stdin"
+    )]
+    pub input: Option<std::path::PathBuf>,
+
+    #[arg(
+        long,
+        help = "Serve the Debug Adapter Protocol over stdin/stdout; the program is given by the client's launch request"
+    )]
+    pub dap: bool,
+
+    #[arg(
+        long,
+        value_name = "TARGET",
+        help = "Serve the GDB remote protocol on TARGET: a port, an address, or - for stdin/stdout"
+    )]
+    pub gdb: Option<String>,
+
+    #[arg(
+        long,
+        conflicts_with_all = ["debug", "script", "batch", "gdb"],
+        help = "Count the instructions the program executes and report them by function and line on stderr"
+    )]
+    pub profile: bool,
+
+    #[arg(
+        long,
+        value_name = "FILE",
+        conflicts_with_all = ["debug", "script", "batch", "gdb"],
+        help = "Also write the profile to FILE as JSON (implies --profile)"
+    )]
+    pub profile_json: Option<std::path::PathBuf>,
+
+    #[arg(
+        long,
+        conflicts_with_all = ["debug", "script", "batch", "gdb"],
+        help = "Model a 5-stage pipeline alongside the run and report its cycles, CPI, and hazards on stderr"
+    )]
+    pub pipeline: bool,
+
+    #[arg(
+        long,
+        conflicts_with_all = ["debug", "script", "batch", "gdb"],
+        help = "Model the pipeline without forwarding (implies --pipeline)"
+    )]
+    pub no_forwarding: bool,
+
+    #[arg(
+        long,
+        value_name = "START,END",
+        conflicts_with_all = ["debug", "script", "batch", "gdb"],
+        help = "Draw the pipeline cycle by cycle for instructions from START up to END, e.g. compute,print (implies --pipeline)"
+    )]
+    pub pipeline_diagram: Option<String>,
+
+    #[arg(
+        long,
+        value_name = "SPEC",
+        conflicts_with_all = ["debug", "script", "batch", "gdb", "icache", "dcache"],
+        help = "Simulate one cache for instructions and data and report its hits and misses on stderr. SPEC is like size=1024,block=16,ways=2,replace=lru|fifo|random,write=back|through,seed=1"
+    )]
+    pub cache: Option<String>,
+
+    #[arg(
+        long,
+        value_name = "SPEC",
+        conflicts_with_all = ["debug", "script", "batch", "gdb"],
+        help = "Simulate an instruction cache (SPEC as for --cache)"
+    )]
+    pub icache: Option<String>,
+
+    #[arg(
+        long,
+        value_name = "SPEC",
+        conflicts_with_all = ["debug", "script", "batch", "gdb"],
+        help = "Simulate a data cache (SPEC as for --cache)"
+    )]
+    pub dcache: Option<String>,
+
+    #[arg(
+        long,
+        value_name = "KIND",
+        conflicts_with_all = ["debug", "script", "batch", "gdb"],
+        help = "Predict every conditional branch with KIND (not-taken, 1bit, 2bit, or gshare[:BITS]) and report its accuracy on stderr; repeat to compare predictors"
+    )]
+    pub branch_predictor: Vec<String>,
+
+    #[arg(
+        long,
+        conflicts_with_all = ["debug", "script", "batch", "gdb"],
+        help = "Check that functions follow the o32 calling convention and report any problems on stderr"
+    )]
+    pub check_conventions: bool,
+
+    #[arg(
+        long,
+        conflicts_with_all = ["debug", "script", "batch", "gdb"],
+        help = "Track which registers and bytes of memory hold defined values and warn on stderr when the program depends on one that doesn't"
+    )]
+    pub memcheck: bool,
+
+    #[arg(
+        long,
+        help = "Let the program store into .text, so it can modify its own code"
+    )]
+    pub writable_text: bool,
+
+    #[arg(
+        long,
+        help = "Let the program run code in .data, on the heap, or on the stack"
+    )]
+    pub executable_data: bool,
+
+    #[arg(
+        long,
+        value_name = "LAYOUT",
+        help = "Lay out memory as the program was linked: a preset (name, mars-default, mars-compact-data-at-0, mars-compact-text-at-0) or a TOML file"
+    )]
+    pub layout: Option<String>,
+
+    #[arg(
+        long,
+        value_name = "N",
+        conflicts_with_all = ["debug", "script", "batch", "gdb", "profile", "profile_json", "pipeline", "no_forwarding", "pipeline_diagram", "cache", "icache", "dcache", "branch_predictor", "check_conventions", "memcheck", "thread_quantum", "thread_seed"],
+        help = "Run the program on N cores sharing its memory; each core starts with its number in $a0 and stops when it returns"
+    )]
+    pub cores: Option<usize>,
+
+    #[arg(
+        long,
+        value_name = "ENTRY",
+        requires = "cores",
+        help = "Start every core but core 0 at ENTRY, a label or address, instead of at the program's entry"
+    )]
+    pub core_entry: Option<String>,
+
+    #[arg(
+        long,
+        value_name = "HOW",
+        requires = "cores",
+        help = "Take turns between cores with round-robin[:QUANTUM] or random[:SEED] (default round-robin:1)"
+    )]
+    pub interleave: Option<String>,
+
+    #[arg(
+        long,
+        value_name = "N",
+        value_parser = clap::value_parser!(u32).range(1..),
+        conflicts_with_all = ["cores"],
+        help = "Preempt the running thread after N instructions, instead of only switching threads when one yields or blocks"
+    )]
+    pub thread_quantum: Option<u32>,
+
+    #[arg(
+        long,
+        value_name = "SEED",
+        conflicts_with_all = ["cores"],
+        help = "Pick the next thread to run at random with SEED, instead of the one that has been ready longest"
+    )]
+    pub thread_seed: Option<u64>,
 }
@@ -0,0 +1,267 @@
+//! Branch predictor models, run alongside the functional emulator. Each conditional branch the program executes is
+//! predicted first and then checked against what the emulator actually did, so predictions never change the program.
+
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Write;
+
+use name_core::debug::simulator_helpers::find_lineinfo;
+use name_core::elf_def::Elf;
+use name_core::elf_utils::extract_lineinfo;
+use name_core::instruction::information::InstructionInformation;
+use name_core::observer::{ExecutedInstruction, Observer};
+use name_core::structs::{LineInfo, ProgramState};
+
+/// The gshare history length used when none is given.
+const DEFAULT_HISTORY_BITS: u32 = 8;
+
+/// Guesses whether the conditional branch at an address will be taken, and learns from the outcome.
+pub trait BranchPredictor {
+    fn name(&self) -> String;
+    fn predict(&self, address: u32) -> bool;
+    fn update(&mut self, address: u32, taken: bool);
+}
+
+/// Always guesses that execution falls through.
+#[derive(Debug, Default)]
+pub struct StaticNotTaken;
+
+impl BranchPredictor for StaticNotTaken {
+    fn name(&self) -> String {
+        "static not-taken".to_string()
+    }
+
+    fn predict(&self, _address: u32) -> bool {
+        false
+    }
+
+    fn update(&mut self, _address: u32, _taken: bool) {}
+}
+
+/// Guesses whatever each branch did last time.
+#[derive(Debug, Default)]
+pub struct OneBit {
+    last_outcome: HashMap<u32, bool>,
+}
+
+impl BranchPredictor for OneBit {
+    fn name(&self) -> String {
+        "1-bit".to_string()
+    }
+
+    fn predict(&self, address: u32) -> bool {
+        self.last_outcome.get(&address).copied().unwrap_or(false)
+    }
+
+    fn update(&mut self, address: u32, taken: bool) {
+        self.last_outcome.insert(address, taken);
+    }
+}
+
+/// A 2-bit saturating counter per branch: 0 and 1 predict not taken, 2 and 3 predict taken.
+/// It takes two wrong guesses in a row to change its mind, so a loop's exit doesn't spoil the next run of the loop.
+#[derive(Debug, Default)]
+pub struct TwoBit {
+    counters: HashMap<u32, u8>,
+}
+
+impl BranchPredictor for TwoBit {
+    fn name(&self) -> String {
+        "2-bit saturating".to_string()
+    }
+
+    fn predict(&self, address: u32) -> bool {
+        self.counters.get(&address).copied().unwrap_or(1) >= 2
+    }
+
+    fn update(&mut self, address: u32, taken: bool) {
+        let counter: &mut u8 = self.counters.entry(address).or_insert(1);
+        *counter = saturate(*counter, taken);
+    }
+}
+
+/// 2-bit counters in a table indexed by the branch address XORed with the outcomes of the last few branches,
+/// so a branch can be predicted differently depending on how the program got to it.
+#[derive(Debug)]
+pub struct Gshare {
+    history_bits: u32,
+    history: u32,
+    counters: Vec<u8>,
+}
+
+impl Gshare {
+    pub fn new(history_bits: u32) -> Self {
+        Gshare {
+            history_bits,
+            history: 0,
+            counters: vec![1; 1 << history_bits],
+        }
+    }
+
+    fn index(&self, address: u32) -> usize {
+        let mask: u32 = (1 << self.history_bits) - 1;
+        (((address >> 2) ^ self.history) & mask) as usize
+    }
+}
+
+impl BranchPredictor for Gshare {
+    fn name(&self) -> String {
+        format!("gshare ({} bits of history)", self.history_bits)
+    }
+
+    fn predict(&self, address: u32) -> bool {
+        self.counters[self.index(address)] >= 2
+    }
+
+    fn update(&mut self, address: u32, taken: bool) {
+        let index: usize = self.index(address);
+        self.counters[index] = saturate(self.counters[index], taken);
+
+        let mask: u32 = (1 << self.history_bits) - 1;
+        self.history = ((self.history << 1) | taken as u32) & mask;
+    }
+}
+
+fn saturate(counter: u8, taken: bool) -> u8 {
+    if taken {
+        (counter + 1).min(3)
+    } else {
+        counter.saturating_sub(1)
+    }
+}
+
+/// Builds a predictor from its name on the command line: not-taken, 1bit, 2bit, or gshare[:HISTORY_BITS].
+pub fn parse_predictor(text: &str) -> Result<Box<dyn BranchPredictor>, String> {
+    let (kind, argument) = match text.split_once(':') {
+        Some((kind, argument)) => (kind, Some(argument)),
+        None => (text, None),
+    };
+
+    match (kind, argument) {
+        ("not-taken", None) => Ok(Box::new(StaticNotTaken)),
+        ("1bit", None) => Ok(Box::new(OneBit::default())),
+        ("2bit", None) => Ok(Box::new(TwoBit::default())),
+        ("gshare", None) => Ok(Box::new(Gshare::new(DEFAULT_HISTORY_BITS))),
+        ("gshare", Some(bits)) => match bits.parse::<u32>() {
+            Ok(bits) if (1..=20).contains(&bits) => Ok(Box::new(Gshare::new(bits))),
+            _ => Err(format!(
+                "{bits} is not a history length; gshare takes 1 to 20 bits."
+            )),
+        },
+        _ => Err(format!(
+            "{text} is not a branch predictor; use not-taken, 1bit, 2bit, or gshare[:BITS]."
+        )),
+    }
+}
+
+/// How one branch site fared.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct BranchSite {
+    pub executed: u64,
+    pub taken: u64,
+    pub correct: u64,
+}
+
+impl BranchSite {
+    pub fn accuracy(&self) -> f64 {
+        match self.executed {
+            0 => 0.0,
+            executed => self.correct as f64 * 100.0 / executed as f64,
+        }
+    }
+}
+
+/// Runs one predictor over every conditional branch the program executes.
+pub struct PredictorModel {
+    lineinfo: Vec<LineInfo>,
+    pub predictor: Box<dyn BranchPredictor>,
+    pub sites: BTreeMap<u32, BranchSite>, // branch address -> outcomes
+}
+
+impl PredictorModel {
+    pub fn new(elf: &Elf, predictor: Box<dyn BranchPredictor>) -> Self {
+        PredictorModel {
+            lineinfo: extract_lineinfo(elf),
+            predictor,
+            sites: BTreeMap::new(),
+        }
+    }
+
+    /// Totals over every branch site.
+    pub fn overall(&self) -> BranchSite {
+        self.sites
+            .values()
+            .fold(BranchSite::default(), |total, site| BranchSite {
+                executed: total.executed + site.executed,
+                taken: total.taken + site.taken,
+                correct: total.correct + site.correct,
+            })
+    }
+
+    /// Lays out the overall accuracy, then each branch site with its source line, most mispredictions first.
+    pub fn report(&self) -> String {
+        let overall: BranchSite = self.overall();
+        let mut report: String = String::new();
+
+        let _ = writeln!(
+            report,
+            "Branch predictor: {}\n  {} branches, {} taken, {} predicted correctly ({:.2}%)",
+            self.predictor.name(),
+            overall.executed,
+            overall.taken,
+            overall.correct,
+            overall.accuracy()
+        );
+
+        let mut sites: Vec<(&u32, &BranchSite)> = self.sites.iter().collect();
+        sites.sort_by(|a, b| {
+            let wrong = |site: &BranchSite| site.executed - site.correct;
+            wrong(b.1).cmp(&wrong(a.1)).then(a.0.cmp(b.0))
+        });
+
+        let _ = writeln!(
+            report,
+            "  {:<10} {:>8} {:>8} {:>8}",
+            "address", "executed", "taken", "correct"
+        );
+        for (address, site) in sites {
+            let source: String = match find_lineinfo(&self.lineinfo, *address) {
+                Some(li) => format!("{}:{}  {}", li.file_name, li.line_number, li.content.trim()),
+                None => "??".to_string(),
+            };
+            let _ = writeln!(
+                report,
+                "  0x{:08x} {:>8} {:>8} {:>8} ({:>6.2}%)  {}",
+                address,
+                site.executed,
+                site.taken,
+                site.correct,
+                site.accuracy(),
+                source
+            );
+        }
+
+        report
+    }
+}
+
+impl Observer for PredictorModel {
+    fn after_execute(
+        &mut self,
+        executed: &ExecutedInstruction,
+        _information: &InstructionInformation,
+        _program_state: &ProgramState,
+    ) {
+        if !executed.instruction.is_branch() {
+            return;
+        }
+
+        let taken: bool = executed.redirected();
+        let predicted: bool = self.predictor.predict(executed.address);
+        self.predictor.update(executed.address, taken);
+
+        let site: &mut BranchSite = self.sites.entry(executed.address).or_default();
+        site.executed += 1;
+        site.taken += taken as u64;
+        site.correct += (predicted == taken) as u64;
+    }
+}
@@ -0,0 +1,489 @@
+//! A cache simulator for the memory hierarchy lab, run alongside the functional emulator like the pipeline model.
+//! Instruction caches see every fetch; data caches see every load and store. Memory contents are never cached for real,
+//! so the program behaves exactly the same with or without caches; only hits and misses are counted.
+//!
+//! Write-back caches allocate a block on a write miss and write it to memory when it's evicted dirty.
+//! Write-through caches send every write to memory and leave the cache alone on a write miss.
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+use name_core::debug::simulator_helpers::find_lineinfo;
+use name_core::elf_def::Elf;
+use name_core::elf_utils::extract_lineinfo;
+use name_core::instruction::information::InstructionInformation;
+use name_core::instruction::instruction::AccessType;
+use name_core::observer::{ExecutedInstruction, Observer};
+use name_core::structs::{LineInfo, ProgramState};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Replacement {
+    Lru,
+    Fifo,
+    Random,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum WritePolicy {
+    WriteBack,
+    WriteThrough,
+}
+
+impl Replacement {
+    pub fn parse(text: &str) -> Result<Self, String> {
+        match text {
+            "lru" => Ok(Replacement::Lru),
+            "fifo" => Ok(Replacement::Fifo),
+            "random" => Ok(Replacement::Random),
+            _ => Err(format!(
+                "{text} is not a replacement policy; use lru, fifo, or random."
+            )),
+        }
+    }
+}
+
+impl WritePolicy {
+    pub fn parse(text: &str) -> Result<Self, String> {
+        match text {
+            "back" => Ok(WritePolicy::WriteBack),
+            "through" => Ok(WritePolicy::WriteThrough),
+            _ => Err(format!(
+                "{text} is not a write policy; use back or through."
+            )),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CacheConfig {
+    pub size: u32,          // bytes of data the cache holds
+    pub block_size: u32,    // bytes per block
+    pub associativity: u32, // blocks per set; size / block_size makes it fully associative
+    pub replacement: Replacement,
+    pub write_policy: WritePolicy,
+    pub seed: u64, // for random replacement, so runs can be repeated
+}
+
+impl Default for CacheConfig {
+    fn default() -> Self {
+        CacheConfig {
+            size: 1024,
+            block_size: 16,
+            associativity: 1,
+            replacement: Replacement::Lru,
+            write_policy: WritePolicy::WriteBack,
+            seed: 1,
+        }
+    }
+}
+
+impl CacheConfig {
+    /// Reads a configuration like `size=1024,block=16,ways=2,replace=lru,write=back`.
+    /// Anything left out keeps its default; `ways=full` makes the cache fully associative.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut config: CacheConfig = CacheConfig::default();
+        let mut fully_associative: bool = false;
+
+        for setting in text.split(',').map(str::trim).filter(|s| !s.is_empty()) {
+            let Some((key, value)) = setting.split_once('=') else {
+                return Err(format!(
+                    "{setting} is not a cache setting; give it as KEY=VALUE."
+                ));
+            };
+            let number = || {
+                value
+                    .parse::<u32>()
+                    .map_err(|_| format!("{value} is not a number (in {setting})."))
+            };
+
+            match key {
+                "size" => config.size = number()?,
+                "block" => config.block_size = number()?,
+                "ways" if value == "full" => fully_associative = true,
+                "ways" => config.associativity = number()?,
+                "replace" => config.replacement = Replacement::parse(value)?,
+                "write" => config.write_policy = WritePolicy::parse(value)?,
+                "seed" => {
+                    config.seed = value
+                        .parse::<u64>()
+                        .map_err(|_| format!("{value} is not a number (in {setting})."))?
+                }
+                _ => {
+                    return Err(format!(
+                    "{key} is not a cache setting; use size, block, ways, replace, write, or seed."
+                ))
+                }
+            }
+        }
+
+        if fully_associative {
+            config.associativity = config.size / config.block_size.max(1);
+        }
+        config.validate()?;
+        Ok(config)
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        if !self.block_size.is_power_of_two() || self.block_size < 4 {
+            return Err(format!(
+                "The block size must be a power of two of at least 4 bytes, not {}.",
+                self.block_size
+            ));
+        }
+        if self.associativity == 0 {
+            return Err("A cache needs at least one way.".to_string());
+        }
+        let set_size: Option<u32> = self.block_size.checked_mul(self.associativity);
+        if self.size == 0 || !set_size.is_some_and(|set_size| self.size.is_multiple_of(set_size)) {
+            return Err(format!(
+                "A {}-byte cache can't be split into {}-way sets of {}-byte blocks.",
+                self.size, self.associativity, self.block_size
+            ));
+        }
+        if !self.sets().is_power_of_two() {
+            return Err(format!(
+                "The number of sets must be a power of two, not {}.",
+                self.sets()
+            ));
+        }
+        Ok(())
+    }
+
+    /// How many sets the cache is split into, or 0 if its blocks and ways don't fit in a u32.
+    pub fn sets(&self) -> u32 {
+        self.block_size
+            .checked_mul(self.associativity)
+            .and_then(|set_size| self.size.checked_div(set_size))
+            .unwrap_or(0)
+    }
+
+    /// Describes the organisation the way a textbook would, e.g. "2-way set associative".
+    pub fn describe(&self) -> String {
+        let organisation: String = if self.associativity == 1 {
+            "direct-mapped".to_string()
+        } else if self.sets() == 1 {
+            "fully associative".to_string()
+        } else {
+            format!("{}-way set associative", self.associativity)
+        };
+        let replacement: &str = match self.replacement {
+            Replacement::Lru => "LRU",
+            Replacement::Fifo => "FIFO",
+            Replacement::Random => "random",
+        };
+        let write_policy: &str = match self.write_policy {
+            WritePolicy::WriteBack => "write-back",
+            WritePolicy::WriteThrough => "write-through",
+        };
+
+        format!(
+            "{} bytes, {}-byte blocks, {organisation}, {replacement}, {write_policy}",
+            self.size, self.block_size
+        )
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct CacheStats {
+    pub reads: u64,
+    pub writes: u64,
+    pub read_hits: u64,
+    pub write_hits: u64,
+    pub evictions: u64,
+    pub memory_writes: u64, // dirty blocks written back, or every write for write-through
+}
+
+impl CacheStats {
+    pub fn accesses(&self) -> u64 {
+        self.reads + self.writes
+    }
+
+    pub fn hits(&self) -> u64 {
+        self.read_hits + self.write_hits
+    }
+
+    pub fn misses(&self) -> u64 {
+        self.accesses() - self.hits()
+    }
+
+    pub fn hit_rate(&self) -> f64 {
+        match self.accesses() {
+            0 => 0.0,
+            accesses => self.hits() as f64 * 100.0 / accesses as f64,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+struct Block {
+    valid: bool,
+    dirty: bool,
+    tag: u32,
+    last_used: u64,
+    filled: u64,
+}
+
+#[derive(Debug)]
+pub struct Cache {
+    pub name: String,
+    pub config: CacheConfig,
+    pub stats: CacheStats,
+    sets: Vec<Vec<Block>>,
+    time: u64,
+    random_state: u64,
+}
+
+impl Cache {
+    pub fn new(name: &str, config: CacheConfig) -> Self {
+        Cache {
+            name: name.to_string(),
+            config,
+            stats: CacheStats::default(),
+            sets: vec![
+                vec![Block::default(); config.associativity as usize];
+                config.sets() as usize
+            ],
+            time: 0,
+            // xorshift gets stuck at zero, so keep the state away from it
+            random_state: config.seed ^ 0x9E37_79B9_7F4A_7C15,
+        }
+    }
+
+    /// Looks up the block holding address, filling it in on a miss if the write policy allows. Returns whether it hit.
+    pub fn access(&mut self, address: u32, write: bool) -> bool {
+        self.time += 1;
+        let block_number: u32 = address / self.config.block_size;
+        let index: usize = (block_number % self.config.sets()) as usize;
+        let tag: u32 = block_number / self.config.sets();
+        let write_through: bool = self.config.write_policy == WritePolicy::WriteThrough;
+
+        if write {
+            self.stats.writes += 1;
+            if write_through {
+                self.stats.memory_writes += 1;
+            }
+        } else {
+            self.stats.reads += 1;
+        }
+
+        let time: u64 = self.time;
+        if let Some(block) = self.sets[index]
+            .iter_mut()
+            .find(|block| block.valid && block.tag == tag)
+        {
+            block.last_used = time;
+            block.dirty |= write && !write_through;
+            if write {
+                self.stats.write_hits += 1;
+            } else {
+                self.stats.read_hits += 1;
+            }
+            return true;
+        }
+
+        // write-through caches don't allocate on a write miss
+        if write && write_through {
+            return false;
+        }
+
+        let way: usize = self.victim(index);
+        let victim: Block = self.sets[index][way];
+        if victim.valid {
+            self.stats.evictions += 1;
+            if victim.dirty {
+                self.stats.memory_writes += 1;
+            }
+        }
+        self.sets[index][way] = Block {
+            valid: true,
+            dirty: write,
+            tag,
+            last_used: time,
+            filled: time,
+        };
+        false
+    }
+
+    /// Picks the way in a set to fill: an empty one if there is one, otherwise whatever the replacement policy says.
+    fn victim(&mut self, index: usize) -> usize {
+        let set: &Vec<Block> = &self.sets[index];
+        if let Some(way) = set.iter().position(|block| !block.valid) {
+            return way;
+        }
+
+        match self.config.replacement {
+            Replacement::Lru => (0..set.len()).min_by_key(|&way| set[way].last_used),
+            Replacement::Fifo => (0..set.len()).min_by_key(|&way| set[way].filled),
+            Replacement::Random => {
+                self.random_state ^= self.random_state << 13;
+                self.random_state ^= self.random_state >> 7;
+                self.random_state ^= self.random_state << 17;
+                Some((self.random_state % self.sets[index].len() as u64) as usize)
+            }
+        }
+        .unwrap_or(0)
+    }
+}
+
+/// Hits and misses caused by the instructions on one source line.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct SiteCounts {
+    pub hits: u64,
+    pub misses: u64,
+}
+
+/// The caches in front of memory: either separate instruction and data caches, or one unified cache for both.
+#[derive(Debug)]
+pub struct CacheHierarchy {
+    lineinfo: Vec<LineInfo>,
+    pub caches: Vec<Cache>,
+    instruction: Option<usize>,            // which cache fetches go to
+    data: Option<usize>,                   // which cache loads and stores go to
+    sites: Vec<BTreeMap<u32, SiteCounts>>, // per cache: instruction address -> counts
+}
+
+impl CacheHierarchy {
+    /// Separate caches; either can be left out.
+    pub fn split(elf: &Elf, instruction: Option<CacheConfig>, data: Option<CacheConfig>) -> Self {
+        let mut hierarchy: CacheHierarchy = CacheHierarchy::empty(elf);
+        if let Some(config) = instruction {
+            hierarchy.instruction = Some(hierarchy.add(Cache::new("I-cache", config)));
+        }
+        if let Some(config) = data {
+            hierarchy.data = Some(hierarchy.add(Cache::new("D-cache", config)));
+        }
+        hierarchy
+    }
+
+    /// One cache for both instructions and data.
+    pub fn unified(elf: &Elf, config: CacheConfig) -> Self {
+        let mut hierarchy: CacheHierarchy = CacheHierarchy::empty(elf);
+        let cache: usize = hierarchy.add(Cache::new("Unified cache", config));
+        hierarchy.instruction = Some(cache);
+        hierarchy.data = Some(cache);
+        hierarchy
+    }
+
+    fn empty(elf: &Elf) -> Self {
+        CacheHierarchy {
+            lineinfo: extract_lineinfo(elf),
+            caches: Vec::new(),
+            instruction: None,
+            data: None,
+            sites: Vec::new(),
+        }
+    }
+
+    fn add(&mut self, cache: Cache) -> usize {
+        self.caches.push(cache);
+        self.sites.push(BTreeMap::new());
+        self.caches.len() - 1
+    }
+
+    fn access(&mut self, cache: usize, pc: u32, address: u32, write: bool) {
+        let hit: bool = self.caches[cache].access(address, write);
+        let counts: &mut SiteCounts = self.sites[cache].entry(pc).or_default();
+        if hit {
+            counts.hits += 1;
+        } else {
+            counts.misses += 1;
+        }
+    }
+
+    /// Hits and misses per source line for one cache, most misses first.
+    pub fn by_line(&self, cache: usize) -> Vec<(Option<&LineInfo>, SiteCounts)> {
+        let mut lines: BTreeMap<(String, u32), (Option<&LineInfo>, u32, SiteCounts)> =
+            BTreeMap::new();
+
+        for (&pc, counts) in &self.sites[cache] {
+            let lineinfo: Option<&LineInfo> = find_lineinfo(&self.lineinfo, pc);
+            let key = match lineinfo {
+                Some(li) => (li.file_name.clone(), li.line_number),
+                None => ("??".to_string(), 0),
+            };
+            let entry = lines
+                .entry(key)
+                .or_insert((lineinfo, pc, SiteCounts::default()));
+            entry.2.hits += counts.hits;
+            entry.2.misses += counts.misses;
+        }
+
+        let mut lines: Vec<(Option<&LineInfo>, u32, SiteCounts)> = lines.into_values().collect();
+        lines.sort_by(|a, b| b.2.misses.cmp(&a.2.misses).then(a.1.cmp(&b.1)));
+        lines
+            .into_iter()
+            .map(|(lineinfo, _, counts)| (lineinfo, counts))
+            .collect()
+    }
+
+    /// Lays out each cache's statistics, then which lines caused its misses.
+    pub fn report(&self) -> String {
+        let mut report: String = String::new();
+
+        for (i, cache) in self.caches.iter().enumerate() {
+            let stats: CacheStats = cache.stats;
+            if i > 0 {
+                report.push('\n');
+            }
+            let _ = writeln!(report, "{} ({}):", cache.name, cache.config.describe());
+            let _ = writeln!(
+                report,
+                "  {:<14} {:>10} ({} reads, {} writes)",
+                "accesses",
+                stats.accesses(),
+                stats.reads,
+                stats.writes
+            );
+            let _ = writeln!(
+                report,
+                "  {:<14} {:>10} ({:.2}%)",
+                "hits",
+                stats.hits(),
+                stats.hit_rate()
+            );
+            let _ = writeln!(report, "  {:<14} {:>10}", "misses", stats.misses());
+            let _ = writeln!(report, "  {:<14} {:>10}", "evictions", stats.evictions);
+            let _ = writeln!(
+                report,
+                "  {:<14} {:>10}",
+                "memory writes", stats.memory_writes
+            );
+
+            let _ = writeln!(report, "  By line:");
+            let _ = writeln!(report, "  {:>10} {:>10}", "misses", "hits");
+            for (lineinfo, counts) in self.by_line(i) {
+                let (location, content) = match lineinfo {
+                    Some(li) => (
+                        format!("{}:{}", li.file_name, li.line_number),
+                        li.content.trim(),
+                    ),
+                    None => ("??".to_string(), ""),
+                };
+                let _ = writeln!(
+                    report,
+                    "  {:>10} {:>10}  {location}  {content}",
+                    counts.misses, counts.hits
+                );
+            }
+        }
+
+        report
+    }
+}
+
+impl Observer for CacheHierarchy {
+    fn after_execute(
+        &mut self,
+        executed: &ExecutedInstruction,
+        _information: &InstructionInformation,
+        _program_state: &ProgramState,
+    ) {
+        if let Some(cache) = self.instruction {
+            self.access(cache, executed.address, executed.address, false);
+        }
+        if let (Some(cache), Some(access)) = (self.data, executed.memory_access) {
+            let write: bool = access.access_type == AccessType::Write;
+            self.access(cache, executed.address, access.address, write);
+        }
+    }
+}
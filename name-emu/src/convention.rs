@@ -0,0 +1,242 @@
+//! A checker for the o32 calling convention, run alongside the functional emulator.
+//! It snapshots the registers at every jal/jalr and compares them when the matching jr $ra comes back, using the same
+//! shadow call stack the debugger's backtraces come from.
+
+use std::collections::HashMap;
+use std::fmt::Write;
+
+use name_core::constants::REGISTERS;
+use name_core::debug::simulator_helpers::{find_lineinfo, format_backtrace, function_name};
+use name_core::elf_def::Elf;
+use name_core::elf_utils::{extract_lineinfo, extract_symbols};
+use name_core::instruction::information::InstructionInformation;
+use name_core::observer::{ExecutedInstruction, Observer};
+use name_core::structs::{CallFrame, LineInfo, ProgramState, Symbol};
+
+/// $s0-$s7, $gp, and $fp: a function has to give these back the way it found them.
+const CALLEE_SAVED: [usize; 10] = [16, 17, 18, 19, 20, 21, 22, 23, 28, 30];
+const SP: usize = 29;
+/// $t0-$t9: a function is free to change these, so a caller can't count on them across a call.
+const TEMPORARIES: [usize; 10] = [8, 9, 10, 11, 12, 13, 14, 15, 24, 25];
+/// $k0 and $k1 belong to the kernel.
+const KERNEL_RESERVED: [usize; 2] = [26, 27];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ViolationKind {
+    NotRestored,
+    StackImbalance,
+    ClobberedRead,
+    KernelRegister,
+}
+
+/// One way the program broke the convention. The same problem at the same place is only reported once, with a count.
+#[derive(Debug, Clone)]
+pub struct Violation {
+    pub kind: ViolationKind,
+    pub address: u32,
+    pub register: usize,
+    pub message: String,
+    pub backtrace: Vec<String>, // the call chain when it first happened, innermost first
+    pub count: u64,
+}
+
+/// A call that hasn't returned yet, with the registers as they were right after the jal.
+#[derive(Debug, Clone)]
+struct SavedFrame {
+    frame: CallFrame,
+    registers: [u32; 32],
+}
+
+pub struct ConventionChecker {
+    lineinfo: Vec<LineInfo>,
+    symbols: Vec<Symbol>,
+    frames: Vec<SavedFrame>,
+    stale: Vec<[Option<u32>; 32]>, // per call depth, the temporaries a callee changed since, and which function it was
+    pub violations: Vec<Violation>,
+    seen: HashMap<(ViolationKind, u32, usize), usize>,
+}
+
+impl ConventionChecker {
+    pub fn new(elf: &Elf) -> Self {
+        ConventionChecker {
+            lineinfo: extract_lineinfo(elf),
+            symbols: extract_symbols(elf),
+            frames: Vec::new(),
+            stale: vec![[None; 32]],
+            violations: Vec::new(),
+            seen: HashMap::new(),
+        }
+    }
+
+    fn report_violation(
+        &mut self,
+        kind: ViolationKind,
+        address: u32,
+        register: usize,
+        message: String,
+        call_stack: &[CallFrame],
+    ) {
+        if let Some(&i) = self.seen.get(&(kind, address, register)) {
+            self.violations[i].count += 1;
+            return;
+        }
+
+        self.seen
+            .insert((kind, address, register), self.violations.len());
+        self.violations.push(Violation {
+            kind,
+            address,
+            register,
+            message,
+            backtrace: format_backtrace(&self.lineinfo, &self.symbols, call_stack, address),
+            count: 1,
+        });
+    }
+
+    /// Compares the registers a function handed back with the ones it was given.
+    fn check_return(&mut self, address: u32, returned: &SavedFrame, registers: &[u32; 32]) {
+        let function: String = function_name(&self.symbols, returned.frame.function_address);
+        // the backtrace still shows the function that's returning
+        let call_stack: Vec<CallFrame> = self
+            .frames
+            .iter()
+            .map(|saved| saved.frame.clone())
+            .chain(std::iter::once(returned.frame.clone()))
+            .collect();
+
+        for register in CALLEE_SAVED {
+            let (before, after) = (returned.registers[register], registers[register]);
+            if before != after {
+                self.report_violation(
+                    ViolationKind::NotRestored,
+                    address,
+                    register,
+                    format!(
+                        "{function} returned without restoring {}: it was 0x{before:08x} when {function} was called and 0x{after:08x} when it returned.",
+                        REGISTERS[register]
+                    ),
+                    &call_stack,
+                );
+            }
+        }
+
+        let (before, after) = (returned.registers[SP], registers[SP]);
+        if before != after {
+            let difference: i64 = after as i64 - before as i64;
+            self.report_violation(
+                ViolationKind::StackImbalance,
+                address,
+                SP,
+                format!(
+                    "{function} left the stack unbalanced: $sp was 0x{before:08x} when {function} was called and 0x{after:08x} when it returned ({difference:+} bytes)."
+                ),
+                &call_stack,
+            );
+        }
+
+        // whatever the callee changed, the caller has to set again before using
+        let depth: usize = self.frames.len();
+        for register in TEMPORARIES {
+            if returned.registers[register] != registers[register] {
+                self.stale[depth][register] = Some(returned.frame.function_address);
+            }
+        }
+    }
+
+    /// Lays out every problem found, in the order they first happened.
+    pub fn report(&self) -> String {
+        let mut report: String = match self.violations.len() {
+            0 => "Calling convention check: no problems found.\n".to_string(),
+            1 => "Calling convention check: 1 problem found.\n".to_string(),
+            n => format!("Calling convention check: {n} problems found.\n"),
+        };
+
+        for violation in &self.violations {
+            let location: String = match find_lineinfo(&self.lineinfo, violation.address) {
+                Some(li) => format!("{}:{}", li.file_name, li.line_number),
+                None => "??".to_string(),
+            };
+            let _ = writeln!(
+                report,
+                "\n{location} (0x{:08x}): {}",
+                violation.address, violation.message
+            );
+            for frame in &violation.backtrace {
+                let _ = writeln!(report, "    {frame}");
+            }
+            if violation.count > 1 {
+                let _ = writeln!(report, "    (happened {} times)", violation.count);
+            }
+        }
+
+        report
+    }
+}
+
+impl Observer for ConventionChecker {
+    fn after_execute(
+        &mut self,
+        executed: &ExecutedInstruction,
+        _information: &InstructionInformation,
+        program_state: &ProgramState,
+    ) {
+        let instruction = executed.instruction;
+        let registers: &[u32; 32] = &program_state.cpu.general_purpose_registers;
+        let call_stack: &[CallFrame] = &program_state.call_stack;
+        let depth: usize = self.frames.len();
+
+        let sources: Vec<u32> = instruction.source_registers();
+        let destination: Option<u32> = instruction.destination_register();
+
+        for register in KERNEL_RESERVED {
+            if sources.contains(&(register as u32)) || destination == Some(register as u32) {
+                self.report_violation(
+                    ViolationKind::KernelRegister,
+                    executed.address,
+                    register,
+                    format!(
+                        "{} is reserved for the kernel and shouldn't be used in user code.",
+                        REGISTERS[register]
+                    ),
+                    call_stack,
+                );
+            }
+        }
+
+        for &register in &sources {
+            if let Some(callee) = self.stale[depth][register as usize].take() {
+                self.report_violation(
+                    ViolationKind::ClobberedRead,
+                    executed.address,
+                    register as usize,
+                    format!(
+                        "{} is read here, but {} changed it and it hasn't been set since.",
+                        REGISTERS[register as usize],
+                        function_name(&self.symbols, callee)
+                    ),
+                    call_stack,
+                );
+            }
+        }
+        if let Some(register) = destination {
+            self.stale[depth][register as usize] = None;
+        }
+
+        // keep in step with the shadow call stack, which already took this instruction into account
+        if instruction.is_call() && call_stack.len() > self.frames.len() {
+            if let Some(frame) = call_stack.last() {
+                self.frames.push(SavedFrame {
+                    frame: frame.clone(),
+                    registers: *registers,
+                });
+                self.stale.push([None; 32]);
+            }
+        } else if instruction.is_return() && call_stack.len() < self.frames.len() {
+            // frames abandoned by jumping out of a function are dropped without being checked
+            let returned: SavedFrame = self.frames[call_stack.len()].clone();
+            self.frames.truncate(call_stack.len());
+            self.stale.truncate(call_stack.len() + 1);
+            self.check_return(executed.address, &returned, registers);
+        }
+    }
+}
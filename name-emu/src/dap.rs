@@ -0,0 +1,706 @@
+//! A Debug Adapter Protocol server, so editors (like the VSCode extension in name-ext) can drive the emulator.
+//! Messages are JSON bodies behind a Content-Length header. Run with --dap to serve over stdin/stdout.
+
+use std::collections::HashMap;
+use std::fs::{read, File};
+use std::io::{empty, BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+
+use serde_json::{json, Value};
+
+use name_core::constants::REGISTERS;
+use name_core::debug::condition::Condition;
+use name_core::debug::debug_utils::{db_step, stopped_address, DebuggerState, StopReason};
+use name_core::debug::simulator_helpers::{find_lineinfo, function_name};
+use name_core::elf_def::Elf;
+use name_core::elf_utils::{extract_symbols, read_bytes_to_elf};
+use name_core::layout::MemoryLayout;
+use name_core::structs::{LineInfo, OperatingSystem, ProgramState, Section};
+use name_core::threads::ThreadOptions;
+
+use name_ld::build::build_with_layout;
+
+use crate::simulator::load_program_with_layout;
+
+const THREAD_ID: u64 = 1; // programs run on a single thread
+const REGISTERS_REFERENCE: u64 = 1;
+const MEMORY_REFERENCE: u64 = 2;
+const FIRST_LABEL_REFERENCE: u64 = 3; // labels in .data bigger than a word can be expanded; one reference per label
+const MAX_LABEL_WORDS: u32 = 1024; // don't send the client an entire heap's worth of words
+
+/// The ways the client can ask execution to proceed.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum RunMode {
+    Continue,
+    Next,
+    StepIn,
+    StepOut,
+}
+
+/// Why execution stopped.
+#[derive(Debug, PartialEq)]
+enum Stop {
+    Step,
+    Breakpoint(String),
+    Watchpoint,
+    Exception(String),
+    Exited,
+}
+
+/// The outgoing half of the connection. Numbers every message it sends.
+struct Connection<W: Write> {
+    output: W,
+    seq: u64,
+}
+
+impl<W: Write> Connection<W> {
+    fn send(&mut self, mut message: Value) -> Result<(), String> {
+        self.seq += 1;
+        message["seq"] = json!(self.seq);
+
+        let body: String = message.to_string();
+        write!(
+            self.output,
+            "Content-Length: {}\r\n\r\n{}",
+            body.len(),
+            body
+        )
+        .map_err(|e| format!("Failed to write to client: {e}"))?;
+        self.output
+            .flush()
+            .map_err(|e| format!("Failed to write to client: {e}"))
+    }
+
+    fn respond(&mut self, request: &Value, result: Result<Value, String>) -> Result<(), String> {
+        let mut response: Value = json!({
+            "type": "response",
+            "request_seq": request["seq"],
+            "command": request["command"],
+            "success": result.is_ok(),
+        });
+        match result {
+            Ok(body) => response["body"] = body,
+            Err(message) => response["message"] = json!(message),
+        }
+        self.send(response)
+    }
+
+    fn event(&mut self, event: &str, body: Value) -> Result<(), String> {
+        self.send(json!({
+            "type": "event",
+            "event": event,
+            "body": body,
+        }))
+    }
+}
+
+/// Reads one message from the client. Returns None once the client hangs up.
+fn read_message<R: BufRead>(input: &mut R) -> Result<Option<Value>, String> {
+    let mut content_length: Option<usize> = None;
+
+    // headers end with a blank line
+    loop {
+        let mut header = String::new();
+        match input.read_line(&mut header) {
+            Ok(0) => return Ok(None),
+            Ok(_) => {}
+            Err(e) => return Err(format!("Failed to read from client: {e}")),
+        }
+
+        let header: &str = header.trim();
+        if header.is_empty() {
+            if content_length.is_some() {
+                break;
+            }
+            continue;
+        }
+        if let Some(length) = header.strip_prefix("Content-Length:") {
+            content_length = match length.trim().parse() {
+                Ok(length) => Some(length),
+                Err(_) => return Err(format!("{} is not a valid Content-Length.", length)),
+            };
+        }
+    }
+
+    let mut body: Vec<u8> = vec![0; content_length.unwrap_or(0)];
+    if let Err(e) = input.read_exact(&mut body) {
+        return Err(format!("Failed to read from client: {e}"));
+    }
+
+    match serde_json::from_slice(&body) {
+        Ok(message) => Ok(Some(message)),
+        Err(e) => Err(format!("Received a malformed message: {e}")),
+    }
+}
+
+/// Everything that belongs to a launched program.
+struct Session {
+    lineinfo: Vec<LineInfo>,
+    program_state: ProgramState,
+    os: OperatingSystem,
+    debugger_state: DebuggerState,
+    program_dir: PathBuf,
+    source_paths: HashMap<String, String>, // file name -> the path the client used for it
+    stop_on_entry: bool,
+    no_debug: bool,
+    finished: bool, // exited or crashed; nothing left to run
+}
+
+impl Session {
+    /// Handles the launch request's arguments: program (the linked executable, or a .asm file to assemble and link
+    /// first), and optionally stopOnEntry, noDebug, and input (a file for the program to read instead of stdin).
+    /// The program has to have been linked for the layout.
+    fn launch(
+        arguments: &Value,
+        layout: MemoryLayout,
+        threads: ThreadOptions,
+    ) -> Result<Session, String> {
+        let program: &str = match arguments["program"].as_str() {
+            Some(program) => program,
+            None => return Err("launch requires the path to a program.".to_string()),
+        };
+
+        let elf: Elf = if Path::new(program)
+            .extension()
+            .is_some_and(|ext| ext == "asm")
+        {
+            build_with_layout(Path::new(program), &layout)?
+        } else {
+            let elf_contents: Vec<u8> = match read(program) {
+                Ok(contents) => contents,
+                Err(e) => return Err(format!("Could not read {}: {e}", program)),
+            };
+            match read_bytes_to_elf(elf_contents) {
+                Ok(elf) => elf,
+                Err(e) => {
+                    return Err(format!(
+                        "{} is neither a NAME executable nor a .asm file: {e}",
+                        program
+                    ))
+                }
+            }
+        };
+
+        let (lineinfo, mut program_state) = load_program_with_layout(&elf, layout)?;
+        program_state.cp0.set_debug_mode(true);
+
+        // stdin and stdout belong to the protocol, so the program gets its own
+        let mut os: OperatingSystem = OperatingSystem::new();
+        os.set_thread_options(threads);
+        os.capture_program_output();
+        match arguments["input"].as_str() {
+            Some(path) => match File::open(path) {
+                Ok(file) => os.set_program_input(BufReader::new(file)),
+                Err(e) => return Err(format!("Could not open input file {}: {e}", path)),
+            },
+            None => os.set_program_input(empty()),
+        }
+
+        let mut debugger_state: DebuggerState = DebuggerState::new();
+        debugger_state.symbols = extract_symbols(&elf);
+
+        Ok(Session {
+            lineinfo,
+            program_state,
+            os,
+            debugger_state,
+            program_dir: Path::new(program)
+                .parent()
+                .map(|dir| dir.to_path_buf())
+                .unwrap_or_default(),
+            source_paths: HashMap::new(),
+            stop_on_entry: arguments["stopOnEntry"].as_bool().unwrap_or(false),
+            no_debug: arguments["noDebug"].as_bool().unwrap_or(false),
+            finished: false,
+        })
+    }
+
+    fn handle<W: Write>(
+        &mut self,
+        connection: &mut Connection<W>,
+        request: &Value,
+        command: &str,
+    ) -> Result<(), String> {
+        let arguments: &Value = &request["arguments"];
+
+        match command {
+            "setBreakpoints" => connection.respond(request, self.set_breakpoints(arguments)),
+            "configurationDone" => {
+                connection.respond(request, Ok(json!({})))?;
+                if self.stop_on_entry && !self.no_debug {
+                    connection.event(
+                        "stopped",
+                        json!({ "reason": "entry", "threadId": THREAD_ID, "allThreadsStopped": true }),
+                    )
+                } else {
+                    self.run(connection, RunMode::Continue)
+                }
+            }
+            "threads" => connection.respond(
+                request,
+                Ok(json!({ "threads": [{ "id": THREAD_ID, "name": "main" }] })),
+            ),
+            "continue" => {
+                connection.respond(request, Ok(json!({ "allThreadsContinued": true })))?;
+                self.run(connection, RunMode::Continue)
+            }
+            "next" => {
+                connection.respond(request, Ok(json!({})))?;
+                self.run(connection, RunMode::Next)
+            }
+            "stepIn" => {
+                connection.respond(request, Ok(json!({})))?;
+                self.run(connection, RunMode::StepIn)
+            }
+            "stepOut" => {
+                connection.respond(request, Ok(json!({})))?;
+                self.run(connection, RunMode::StepOut)
+            }
+            "stackTrace" => connection.respond(request, Ok(self.stack_trace())),
+            "scopes" => connection.respond(
+                request,
+                Ok(json!({ "scopes": [
+                    { "name": "Registers", "variablesReference": REGISTERS_REFERENCE, "expensive": false },
+                    { "name": "Memory", "variablesReference": MEMORY_REFERENCE, "expensive": false },
+                ] })),
+            ),
+            "variables" => {
+                let reference: u64 = arguments["variablesReference"].as_u64().unwrap_or(0);
+                connection.respond(request, self.variables(reference))
+            }
+            _ => connection.respond(request, Err(format!("{} is not supported.", command))),
+        }
+    }
+
+    fn current_address(&self) -> u32 {
+        stopped_address(&self.program_state)
+    }
+
+    /// The file and line of source the current instruction came from.
+    fn current_line(&self) -> Option<(String, u32)> {
+        find_lineinfo(&self.lineinfo, self.current_address())
+            .map(|line| (line.file_name.clone(), line.line_number))
+    }
+
+    /// The path the client knows a source file by, falling back to the file's name next to the program.
+    fn source_path(&self, file_name: &str) -> String {
+        match self.source_paths.get(file_name) {
+            Some(path) => path.clone(),
+            None => self
+                .program_dir
+                .join(file_name)
+                .to_string_lossy()
+                .to_string(),
+        }
+    }
+
+    /// Replaces every breakpoint in one source file. Lines without an instruction slide down to the next line that has one.
+    fn set_breakpoints(&mut self, arguments: &Value) -> Result<Value, String> {
+        let path: &str = match arguments["source"]["path"].as_str() {
+            Some(path) => path,
+            None => return Err("setBreakpoints requires a source path.".to_string()),
+        };
+        let file_name: String = match Path::new(path).file_name() {
+            Some(name) => name.to_string_lossy().to_string(),
+            None => path.to_string(),
+        };
+        self.source_paths
+            .insert(file_name.clone(), path.to_string());
+
+        // clear out the old ones
+        let stale: Vec<usize> = self
+            .debugger_state
+            .breakpoints
+            .iter()
+            .filter(|(_, bp)| {
+                find_lineinfo(&self.lineinfo, bp.address)
+                    .is_some_and(|line| line.file_name == file_name)
+            })
+            .map(|(&bp_num, _)| bp_num)
+            .collect();
+        for bp_num in stale {
+            self.debugger_state.delete_breakpoint(bp_num)?;
+        }
+
+        let requested: Vec<Value> = arguments["breakpoints"]
+            .as_array()
+            .cloned()
+            .unwrap_or_default();
+
+        let mut breakpoints: Vec<Value> = Vec::new();
+        for requested in requested {
+            let line_num: u32 = requested["line"].as_u64().unwrap_or(0) as u32;
+            match self.add_breakpoint(&file_name, line_num, requested["condition"].as_str()) {
+                Ok((bp_num, actual_line)) => breakpoints.push(json!({
+                    "id": bp_num,
+                    "verified": true,
+                    "line": actual_line,
+                })),
+                Err(message) => breakpoints.push(json!({
+                    "verified": false,
+                    "line": line_num,
+                    "message": message,
+                })),
+            }
+        }
+
+        Ok(json!({ "breakpoints": breakpoints }))
+    }
+
+    /// Adds a single breakpoint for set_breakpoints. Returns its number and the line it actually landed on.
+    fn add_breakpoint(
+        &mut self,
+        file_name: &str,
+        line_num: u32,
+        condition: Option<&str>,
+    ) -> Result<(usize, u32), String> {
+        if self.no_debug {
+            return Err("Breakpoints are ignored when running without debugging.".to_string());
+        }
+
+        let line: &LineInfo = match self
+            .lineinfo
+            .iter()
+            .filter(|line| {
+                line.file_name == file_name
+                    && line.line_number >= line_num
+                    && line.start_address != 0
+                    && line.end_address > line.start_address
+            })
+            .min_by_key(|line| line.line_number)
+        {
+            Some(line) => line,
+            None => return Err(format!("No instructions at or after line {}.", line_num)),
+        };
+        let (address, actual_line): (u32, u32) = (line.start_address, line.line_number);
+
+        let condition: Option<Condition> = match condition.filter(|text| !text.trim().is_empty()) {
+            Some(text) => Some(Condition::parse(text, &self.debugger_state.symbols)?),
+            None => None,
+        };
+
+        let bp_num: usize = self.debugger_state.insert_breakpoint_at(
+            &self.lineinfo,
+            address,
+            condition,
+            &self.program_state,
+        )?;
+        Ok((bp_num, actual_line))
+    }
+
+    /// Steps until the run mode is satisfied or something stops execution.
+    fn execute(&mut self, mode: RunMode) -> Stop {
+        let start_line: Option<(String, u32)> = self.current_line();
+        let start_depth: usize = self.program_state.call_stack.len();
+
+        loop {
+            if !self.program_state.should_continue_execution {
+                return Stop::Exited;
+            }
+
+            match db_step(
+                &self.lineinfo,
+                &mut self.program_state,
+                &mut self.os,
+                &mut self.debugger_state,
+            ) {
+                Ok(_) => {}
+                Err(StopReason::Watchpoint) => return Stop::Watchpoint,
+                Err(e @ (StopReason::Breakpoint(_) | StopReason::BreakInstruction)) => {
+                    return Stop::Breakpoint(e.to_string())
+                }
+                // a fatal exception comes with the report the client should show
+                Err(e) => return Stop::Exception(e.to_string()),
+            }
+
+            if !self.program_state.should_continue_execution {
+                return Stop::Exited;
+            }
+
+            let depth: usize = self.program_state.call_stack.len();
+            let line: Option<(String, u32)> = self.current_line();
+            let new_line: bool = line.is_some() && line != start_line;
+
+            let done: bool = match mode {
+                RunMode::Continue => false,
+                RunMode::StepIn => new_line,
+                RunMode::Next => new_line && depth <= start_depth,
+                RunMode::StepOut => depth < start_depth,
+            };
+            if done {
+                return Stop::Step;
+            }
+        }
+    }
+
+    /// Runs the program and tells the client what happened.
+    fn run<W: Write>(
+        &mut self,
+        connection: &mut Connection<W>,
+        mode: RunMode,
+    ) -> Result<(), String> {
+        if self.finished {
+            return connection.event("terminated", json!({}));
+        }
+
+        let stop: Stop = self.execute(mode);
+
+        let output: Vec<u8> = self.os.take_program_output();
+        if !output.is_empty() {
+            connection.event(
+                "output",
+                json!({ "category": "stdout", "output": String::from_utf8_lossy(&output) }),
+            )?;
+        }
+
+        match stop {
+            Stop::Step => connection.event(
+                "stopped",
+                json!({ "reason": "step", "threadId": THREAD_ID, "allThreadsStopped": true }),
+            ),
+            Stop::Breakpoint(description) => connection.event(
+                "stopped",
+                json!({ "reason": "breakpoint", "description": description, "threadId": THREAD_ID, "allThreadsStopped": true }),
+            ),
+            Stop::Watchpoint => connection.event(
+                "stopped",
+                json!({ "reason": "data breakpoint", "threadId": THREAD_ID, "allThreadsStopped": true }),
+            ),
+            Stop::Exception(text) => {
+                // stay stopped so the client can look around, but there's no recovering
+                self.finished = true;
+                connection.event(
+                    "output",
+                    json!({ "category": "stderr", "output": format!("{}\n", text) }),
+                )?;
+                connection.event(
+                    "stopped",
+                    json!({ "reason": "exception", "text": text, "threadId": THREAD_ID, "allThreadsStopped": true }),
+                )
+            }
+            Stop::Exited => {
+                self.finished = true;
+                connection.event("exited", json!({ "exitCode": self.os.exit_code() }))?;
+                connection.event("terminated", json!({}))
+            }
+        }
+    }
+
+    /// One frame for the current instruction and one for each call still waiting to return, innermost first.
+    fn stack_trace(&self) -> Value {
+        let call_stack = &self.program_state.call_stack;
+
+        let mut addresses: Vec<u32> = vec![self.current_address()];
+        addresses.extend(call_stack.iter().rev().map(|frame| frame.call_site));
+
+        let frames: Vec<Value> = addresses
+            .iter()
+            .enumerate()
+            .map(|(depth, &address)| {
+                let name: String = match call_stack.len().checked_sub(depth + 1) {
+                    Some(idx) => function_name(
+                        &self.debugger_state.symbols,
+                        call_stack[idx].function_address,
+                    ),
+                    None => function_name(&self.debugger_state.symbols, address),
+                };
+
+                let mut frame: Value = json!({
+                    "id": depth,
+                    "name": name,
+                    "line": 0,
+                    "column": 0,
+                    "instructionPointerReference": format!("0x{:08x}", address),
+                });
+                if let Some(line) = find_lineinfo(&self.lineinfo, address) {
+                    frame["line"] = json!(line.line_number);
+                    frame["column"] = json!(1);
+                    frame["source"] = json!({
+                        "name": line.file_name,
+                        "path": self.source_path(&line.file_name),
+                    });
+                }
+                frame
+            })
+            .collect();
+
+        json!({ "stackFrames": frames, "totalFrames": addresses.len() })
+    }
+
+    fn variables(&self, reference: u64) -> Result<Value, String> {
+        let variables: Vec<Value> = match reference {
+            REGISTERS_REFERENCE => {
+                let mut variables: Vec<Value> = REGISTERS
+                    .iter()
+                    .zip(self.program_state.cpu.general_purpose_registers.iter())
+                    .map(|(name, &value)| register_variable(name, value))
+                    .collect();
+                variables.push(register_variable("$pc", self.current_address()));
+                variables.push(register_variable("$hi", self.program_state.cpu.hi));
+                variables.push(register_variable("$lo", self.program_state.cpu.lo));
+                variables
+            }
+            MEMORY_REFERENCE => self
+                .data_labels()
+                .iter()
+                .enumerate()
+                .map(|(idx, &(name, address, size))| self.label_variable(idx, name, address, size))
+                .collect(),
+            reference if reference >= FIRST_LABEL_REFERENCE => {
+                let labels = self.data_labels();
+                let (_, address, size) =
+                    match labels.get((reference - FIRST_LABEL_REFERENCE) as usize) {
+                        Some(label) => *label,
+                        None => return Err(format!("No variables for reference {}.", reference)),
+                    };
+                (0..size.div_ceil(4).min(MAX_LABEL_WORDS))
+                    .map(|i| {
+                        let word_address: u32 = address.wrapping_add(i * 4);
+                        json!({
+                            "name": format!("[{}]", i),
+                            "value": self.format_word(word_address),
+                            "memoryReference": format!("0x{:08x}", word_address),
+                            "variablesReference": 0,
+                        })
+                    })
+                    .collect()
+            }
+            _ => return Err(format!("No variables for reference {}.", reference)),
+        };
+
+        Ok(json!({ "variables": variables }))
+    }
+
+    /// The labels in .data as (name, address, size), in address order.
+    fn data_labels(&self) -> Vec<(&str, u32, u32)> {
+        let mut labels: Vec<(&str, u32, u32)> = self
+            .debugger_state
+            .symbols
+            .iter()
+            .filter(|sym| matches!(sym.section, Section::Data))
+            .map(|sym| (sym.identifier.as_str(), sym.value, sym.size))
+            .collect();
+        labels.sort_by_key(|&(_, address, _)| address);
+        labels
+    }
+
+    /// Shows a word-sized (or smaller) label's value directly. Anything bigger can be expanded a word at a time,
+    /// and null-terminated strings are shown as strings.
+    fn label_variable(&self, idx: usize, name: &str, address: u32, size: u32) -> Value {
+        let bytes: Vec<Option<u8>> = (0..size.min(MAX_LABEL_WORDS * 4))
+            .map(|i| {
+                self.program_state
+                    .memory
+                    .read_byte(address.wrapping_add(i))
+                    .ok()
+            })
+            .collect();
+
+        // a word is always a number, but smaller labels are often short strings like " "
+        let value: String = match (size, as_string(&bytes)) {
+            (size, Some(text)) if size != 4 => format!("{:?}", text),
+            (1 | 2 | 4, _) => match bytes.iter().copied().collect::<Option<Vec<u8>>>() {
+                Some(bytes) => {
+                    let value: u32 = bytes.iter().fold(0, |acc, &b| (acc << 8) | b as u32);
+                    format!(
+                        "0x{:0width$x} ({})",
+                        value,
+                        value as i32,
+                        width = bytes.len() * 2
+                    )
+                }
+                None => "(unmapped)".to_string(),
+            },
+            _ => format!("0x{:08x}", address),
+        };
+
+        json!({
+            "name": name,
+            "value": value,
+            "memoryReference": format!("0x{:08x}", address),
+            "variablesReference": if size > 4 { FIRST_LABEL_REFERENCE + idx as u64 } else { 0 },
+        })
+    }
+
+    fn format_word(&self, address: u32) -> String {
+        let mut word: u32 = 0;
+        for i in 0..4 {
+            match self.program_state.memory.read_byte(address.wrapping_add(i)) {
+                Ok(byte) => word = (word << 8) | byte as u32,
+                Err(_) => return "(unmapped)".to_string(),
+            }
+        }
+        format!("0x{:08x} ({})", word, word as i32)
+    }
+}
+
+fn register_variable(name: &str, value: u32) -> Value {
+    json!({
+        "name": name,
+        "value": format!("0x{:08x} ({})", value, value as i32),
+        "variablesReference": 0,
+    })
+}
+
+/// Reads bytes as a null-terminated string, if that's what they look like.
+fn as_string(bytes: &[Option<u8>]) -> Option<String> {
+    let bytes: Vec<u8> = bytes.iter().copied().collect::<Option<Vec<u8>>>()?;
+    let (last, text) = bytes.split_last()?;
+    if *last != 0
+        || text.is_empty()
+        || text
+            .iter()
+            .any(|&b| b == 0 || !(b.is_ascii_graphic() || b.is_ascii_whitespace()))
+    {
+        return None;
+    }
+    Some(String::from_utf8_lossy(text).to_string())
+}
+
+/// Serves the Debug Adapter Protocol until the client disconnects or hangs up. Launched programs are run with the
+/// memory layout and thread options given on the command line.
+pub fn serve<R: BufRead, W: Write>(
+    mut input: R,
+    output: W,
+    layout: MemoryLayout,
+    threads: ThreadOptions,
+) -> Result<(), String> {
+    let mut connection: Connection<W> = Connection { output, seq: 0 };
+    let mut session: Option<Session> = None;
+
+    while let Some(request) = read_message(&mut input)? {
+        let command: &str = request["command"].as_str().unwrap_or("");
+
+        match command {
+            "initialize" => {
+                connection.respond(
+                    &request,
+                    Ok(json!({
+                        "supportsConfigurationDoneRequest": true,
+                        "supportsConditionalBreakpoints": true,
+                    })),
+                )?;
+            }
+            "launch" => match Session::launch(&request["arguments"], layout, threads) {
+                Ok(launched) => {
+                    session = Some(launched);
+                    connection.respond(&request, Ok(json!({})))?;
+                    // breakpoints can only be set once there's a program to set them in
+                    connection.event("initialized", json!({}))?;
+                }
+                Err(e) => connection.respond(&request, Err(e))?,
+            },
+            "disconnect" | "terminate" => {
+                connection.respond(&request, Ok(json!({})))?;
+                return Ok(());
+            }
+            _ => match &mut session {
+                Some(session) => session.handle(&mut connection, &request, command)?,
+                None => connection
+                    .respond(&request, Err("No program has been launched.".to_string()))?,
+            },
+        }
+    }
+
+    Ok(())
+}
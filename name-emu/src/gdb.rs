@@ -0,0 +1,549 @@
+//! A GDB remote serial protocol stub, so gdb-multiarch (and IDEs built on it) can debug programs running in NAME.
+//! Serve over TCP with --gdb 1234 and `target remote :1234`, or over a pipe with `target remote | name-emu prog --gdb -`.
+//! Registers use GDB's default big-endian MIPS32 layout, so no target description is needed.
+
+use std::collections::VecDeque;
+use std::io::{Read, Write};
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::thread;
+
+use name_core::debug::debug_utils::{db_step, stopped_address, DebuggerState, StopReason};
+use name_core::elf_def::Elf;
+use name_core::elf_utils::extract_symbols;
+use name_core::exception::definitions::ExceptionType;
+use name_core::layout::MemoryLayout;
+use name_core::structs::{LineInfo, OperatingSystem, ProgramState};
+
+use crate::simulator::load_program_with_layout;
+
+// GDB's register numbering for MIPS32: the GPRs, then these, then the FPU
+const NUM_REGISTERS: usize = 72;
+const SR_REGISTER: usize = 32;
+const LO_REGISTER: usize = 33;
+const HI_REGISTER: usize = 34;
+const BADVADDR_REGISTER: usize = 35;
+const CAUSE_REGISTER: usize = 36;
+const PC_REGISTER: usize = 37;
+
+// GDB's own signal numbers, which are the same on every target
+const SIGINT: u8 = 2;
+const SIGILL: u8 = 4;
+const SIGTRAP: u8 = 5;
+const SIGFPE: u8 = 8;
+const SIGBUS: u8 = 10;
+const SIGSEGV: u8 = 11;
+const SIGSYS: u8 = 12;
+
+/// One stop-reply's worth of news about the program.
+#[derive(Debug, PartialEq)]
+enum Stop {
+    Signal(u8),
+    Exited,
+}
+
+struct Stub<W: Write> {
+    input: Receiver<Vec<u8>>, // what GDB sends, read on its own thread so an interrupt can be noticed mid-run
+    pending: VecDeque<u8>,
+    output: W,
+    no_ack: bool,
+    lineinfo: Vec<LineInfo>,
+    program_state: ProgramState,
+    os: OperatingSystem,
+    debugger_state: DebuggerState,
+    last_stop: Stop,
+    crashed: Option<u8>, // the signal a fatal exception was reported as; there's no running past one
+}
+
+/// Which signal GDB should be told about for an exception the emulator couldn't recover from.
+fn signal_for(exc_code: u32) -> u8 {
+    match ExceptionType::try_from(exc_code) {
+        Ok(ExceptionType::AddressExceptionLoad) | Ok(ExceptionType::AddressExceptionStore) => {
+            SIGSEGV
+        }
+        Ok(ExceptionType::BusFetch) | Ok(ExceptionType::BusLoadStore) => SIGBUS,
+        Ok(ExceptionType::ReservedInstruction) | Ok(ExceptionType::CoprocessorUnusable) => SIGILL,
+        Ok(ExceptionType::ArithmeticOverflow)
+        | Ok(ExceptionType::Trap)
+        | Ok(ExceptionType::FloatingPoint) => SIGFPE,
+        Ok(ExceptionType::Syscall) => SIGSYS,
+        _ => SIGTRAP,
+    }
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn from_hex(text: &str) -> Option<Vec<u8>> {
+    if !text.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..text.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(text.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+/// Parses the "addr,length" that memory packets start with.
+fn parse_range(text: &str) -> Option<(u32, u32)> {
+    let (address, length) = text.split_once(',')?;
+    Some((
+        u32::from_str_radix(address, 16).ok()?,
+        u32::from_str_radix(length, 16).ok()?,
+    ))
+}
+
+/// Reads everything GDB sends on a thread of its own, passing it along until GDB hangs up.
+fn forward_input<R: Read>(mut input: R, sender: Sender<Vec<u8>>) {
+    let mut buffer = [0u8; 4096];
+    loop {
+        match input.read(&mut buffer) {
+            Ok(0) | Err(_) => return,
+            Ok(n) => {
+                if sender.send(buffer[..n].to_vec()).is_err() {
+                    return;
+                }
+            }
+        }
+    }
+}
+
+impl<W: Write> Stub<W> {
+    fn read_byte(&mut self) -> Option<u8> {
+        while self.pending.is_empty() {
+            self.pending.extend(self.input.recv().ok()?);
+        }
+        self.pending.pop_front()
+    }
+
+    /// Whether GDB has asked for the running program to be stopped, without waiting for it to say anything.
+    fn interrupted(&mut self) -> bool {
+        while let Ok(bytes) = self.input.try_recv() {
+            self.pending.extend(bytes);
+        }
+        match self.pending.iter().position(|&byte| byte == 0x03) {
+            Some(index) => {
+                self.pending.remove(index);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Reads the next packet, acknowledging it. Returns None once GDB hangs up.
+    fn read_packet(&mut self) -> Result<Option<String>, String> {
+        loop {
+            // skip acks and anything else between packets; an interrupt while stopped changes nothing
+            match self.read_byte() {
+                Some(b'$') => {}
+                Some(0x03) => {
+                    self.send_packet(&format!("S{:02x}", SIGTRAP))?;
+                    continue;
+                }
+                Some(_) => continue,
+                None => return Ok(None),
+            }
+
+            let mut data: Vec<u8> = Vec::new();
+            loop {
+                match self.read_byte() {
+                    Some(b'#') => break,
+                    Some(byte) => data.push(byte),
+                    None => return Ok(None),
+                }
+            }
+            let checksum: Option<u8> = match (self.read_byte(), self.read_byte()) {
+                (Some(high), Some(low)) => {
+                    u8::from_str_radix(&String::from_utf8_lossy(&[high, low]), 16).ok()
+                }
+                _ => return Ok(None),
+            };
+
+            let expected: u8 = data.iter().fold(0u8, |sum, &b| sum.wrapping_add(b));
+            if !self.no_ack {
+                let ack: &[u8] = if checksum == Some(expected) {
+                    b"+"
+                } else {
+                    b"-"
+                };
+                self.write(ack)?;
+            }
+            if checksum == Some(expected) || self.no_ack {
+                return Ok(Some(String::from_utf8_lossy(&data).to_string()));
+            }
+        }
+    }
+
+    fn write(&mut self, bytes: &[u8]) -> Result<(), String> {
+        self.output
+            .write_all(bytes)
+            .and_then(|_| self.output.flush())
+            .map_err(|e| format!("Failed to write to GDB: {e}"))
+    }
+
+    /// Sends a packet, resending until GDB acknowledges it.
+    fn send_packet(&mut self, data: &str) -> Result<(), String> {
+        let checksum: u8 = data.bytes().fold(0u8, |sum, b| sum.wrapping_add(b));
+        let packet: String = format!("${}#{:02x}", data, checksum);
+
+        loop {
+            self.write(packet.as_bytes())?;
+            if self.no_ack {
+                return Ok(());
+            }
+            match self.read_byte() {
+                Some(b'+') | None => return Ok(()),
+                _ => continue,
+            }
+        }
+    }
+
+    /// Shows GDB whatever the program printed, as console output.
+    fn send_program_output(&mut self) -> Result<(), String> {
+        let output: Vec<u8> = self.os.take_program_output();
+        if output.is_empty() {
+            return Ok(());
+        }
+        self.send_packet(&format!("O{}", to_hex(&output)))
+    }
+
+    fn stop_reply(&self) -> String {
+        match self.last_stop {
+            Stop::Signal(signal) => format!("S{:02x}", signal),
+            Stop::Exited => format!("W{:02x}", self.os.exit_code() & 0xff),
+        }
+    }
+
+    /// A register's value as GDB wants it, or None if NAME doesn't have it.
+    fn read_register(&self, register: usize) -> Option<u32> {
+        let cp0 = &self.program_state.cp0.registers;
+        match register {
+            0..=31 => Some(self.program_state.cpu.general_purpose_registers[register]),
+            SR_REGISTER => Some(cp0[12]),
+            BADVADDR_REGISTER => Some(cp0[8]),
+            CAUSE_REGISTER => Some(cp0[13]),
+            LO_REGISTER => Some(self.program_state.cpu.lo),
+            HI_REGISTER => Some(self.program_state.cpu.hi),
+            PC_REGISTER => Some(stopped_address(&self.program_state)),
+            // no FPU yet
+            _ => None,
+        }
+    }
+
+    fn write_register(&mut self, register: usize, value: u32) -> bool {
+        match register {
+            // $0 is hardwired
+            0 => true,
+            1..=31 => {
+                self.program_state.cpu.general_purpose_registers[register] = value;
+                true
+            }
+            SR_REGISTER => {
+                self.program_state.cp0.registers[12] = value;
+                true
+            }
+            LO_REGISTER => {
+                self.program_state.cpu.lo = value;
+                true
+            }
+            HI_REGISTER => {
+                self.program_state.cpu.hi = value;
+                true
+            }
+            BADVADDR_REGISTER => {
+                self.program_state.cp0.registers[8] = value;
+                true
+            }
+            CAUSE_REGISTER => {
+                self.program_state.cp0.registers[13] = value;
+                true
+            }
+            PC_REGISTER => {
+                // leave any break instruction in the program behind rather than carrying on after it
+                if self.program_state.is_exception() && self.crashed.is_none() {
+                    self.program_state.recover_from_exception();
+                }
+                self.program_state.cpu.pc = value;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn format_register(&self, register: usize) -> String {
+        match self.read_register(register) {
+            Some(value) => to_hex(&value.to_be_bytes()),
+            None => "xxxxxxxx".to_string(),
+        }
+    }
+
+    fn read_memory(&self, address: u32, length: u32) -> Option<Vec<u8>> {
+        (0..length)
+            .map(|i| {
+                self.program_state
+                    .memory
+                    .read_byte(address.wrapping_add(i))
+                    .ok()
+            })
+            .collect()
+    }
+
+    fn write_memory(&mut self, address: u32, bytes: &[u8]) -> bool {
+        bytes.iter().enumerate().all(|(i, &byte)| {
+            self.program_state
+                .memory
+                .set_byte(address.wrapping_add(i as u32), byte)
+                .is_ok()
+        })
+    }
+
+    /// Runs the program for one instruction or until something stops it.
+    fn resume(&mut self, step: bool) -> Result<(), String> {
+        if let Some(signal) = self.crashed {
+            // nothing more can run; let GDB know the program is gone
+            self.send_packet(&format!("X{:02x}", signal))?;
+            return Ok(());
+        }
+
+        self.last_stop = loop {
+            if !self.program_state.should_continue_execution {
+                break Stop::Exited;
+            }
+
+            match db_step(
+                &self.lineinfo,
+                &mut self.program_state,
+                &mut self.os,
+                &mut self.debugger_state,
+            ) {
+                Ok(_) => {}
+                Err(
+                    StopReason::Breakpoint(_)
+                    | StopReason::BreakInstruction
+                    | StopReason::Watchpoint,
+                ) => break Stop::Signal(SIGTRAP),
+                Err(StopReason::Crashed(report)) => {
+                    // GDB hears about the crash the same way the program's output reaches it
+                    self.send_program_output()?;
+                    self.send_packet(&format!("O{}", to_hex(format!("{report}\n").as_bytes())))?;
+
+                    let signal: u8 = signal_for(self.program_state.cp0.get_exc_code());
+                    self.crashed = Some(signal);
+                    break Stop::Signal(signal);
+                }
+                Err(e) => {
+                    self.send_packet(&format!("O{}", to_hex(format!("{e}\n").as_bytes())))?;
+                    break Stop::Signal(SIGILL);
+                }
+            }
+
+            if !self.program_state.should_continue_execution {
+                break Stop::Exited;
+            }
+            if step {
+                break Stop::Signal(SIGTRAP);
+            }
+            if self.interrupted() {
+                break Stop::Signal(SIGINT);
+            }
+        };
+
+        self.send_program_output()?;
+        let reply: String = self.stop_reply();
+        self.send_packet(&reply)
+    }
+
+    fn handle_query(&mut self, packet: &str) -> String {
+        let name: &str = packet.split([':', ',']).next().unwrap_or(packet);
+        match name {
+            "qSupported" => "PacketSize=4000;QStartNoAckMode+".to_string(),
+            "qAttached" => "1".to_string(),
+            "qC" => "QC1".to_string(),
+            "qfThreadInfo" => "m1".to_string(),
+            "qsThreadInfo" => "l".to_string(),
+            "qSymbol" => "OK".to_string(),
+            _ => String::new(),
+        }
+    }
+
+    fn set_breakpoint(&mut self, arguments: &str, insert: bool) -> String {
+        let mut fields = arguments.split(',');
+        let (kind, address) = match (fields.next(), fields.next()) {
+            (Some(kind), Some(address)) => (kind, u32::from_str_radix(address, 16)),
+            _ => return "E01".to_string(),
+        };
+        // only software breakpoints; an empty reply tells GDB the others aren't supported
+        if kind != "0" {
+            return String::new();
+        }
+        let address: u32 = match address {
+            Ok(address) => address,
+            Err(_) => return "E01".to_string(),
+        };
+
+        let existing: Option<usize> = self.debugger_state.breakpoint_at(address);
+
+        let result: Result<(), String> = match (insert, existing) {
+            (true, Some(_)) | (false, None) => Ok(()),
+            (true, None) => self
+                .debugger_state
+                .insert_breakpoint_at(&self.lineinfo, address, None, &self.program_state)
+                .map(|_| ()),
+            (false, Some(bp_num)) => self.debugger_state.delete_breakpoint(bp_num),
+        };
+
+        match result {
+            Ok(_) => "OK".to_string(),
+            Err(_) => "E01".to_string(),
+        }
+    }
+
+    /// Answers packets until GDB detaches or hangs up.
+    fn serve(&mut self) -> Result<(), String> {
+        while let Some(packet) = self.read_packet()? {
+            let (command, arguments) = packet.split_at(packet.len().min(1));
+
+            let reply: String = match command {
+                "?" => self.stop_reply(),
+                "g" => (0..NUM_REGISTERS)
+                    .map(|r| self.format_register(r))
+                    .collect(),
+                "G" => {
+                    // registers NAME doesn't have come back as the x's "g" sent for them
+                    let values: Option<Vec<Option<u32>>> =
+                        if arguments.len() == NUM_REGISTERS * 8 && arguments.is_ascii() {
+                            (0..NUM_REGISTERS)
+                                .map(
+                                    |register| match &arguments[register * 8..register * 8 + 8] {
+                                        "xxxxxxxx" => Some(None),
+                                        text => from_hex(text).map(|bytes| {
+                                            Some(u32::from_be_bytes([
+                                                bytes[0], bytes[1], bytes[2], bytes[3],
+                                            ]))
+                                        }),
+                                    },
+                                )
+                                .collect()
+                        } else {
+                            None
+                        };
+                    match values {
+                        Some(values) => {
+                            for (register, value) in values.into_iter().enumerate() {
+                                if let Some(value) = value {
+                                    self.write_register(register, value);
+                                }
+                            }
+                            "OK".to_string()
+                        }
+                        None => "E01".to_string(),
+                    }
+                }
+                "p" => match usize::from_str_radix(arguments, 16) {
+                    Ok(register) if register < NUM_REGISTERS => self.format_register(register),
+                    _ => "E01".to_string(),
+                },
+                "P" => {
+                    let parsed = arguments.split_once('=').and_then(|(register, value)| {
+                        Some((
+                            usize::from_str_radix(register, 16).ok()?,
+                            u32::from_str_radix(value, 16).ok()?,
+                        ))
+                    });
+                    match parsed {
+                        Some((register, value)) if self.write_register(register, value) => {
+                            "OK".to_string()
+                        }
+                        _ => "E01".to_string(),
+                    }
+                }
+                "m" => match parse_range(arguments)
+                    .and_then(|(address, length)| self.read_memory(address, length))
+                {
+                    Some(bytes) => to_hex(&bytes),
+                    None => "E01".to_string(),
+                },
+                "M" => {
+                    let parsed = arguments
+                        .split_once(':')
+                        .and_then(|(range, data)| Some((parse_range(range)?, from_hex(data)?)));
+                    match parsed {
+                        Some(((address, length), bytes))
+                            if bytes.len() == length as usize
+                                && self.write_memory(address, &bytes) =>
+                        {
+                            "OK".to_string()
+                        }
+                        _ => "E01".to_string(),
+                    }
+                }
+                "Z" => self.set_breakpoint(arguments, true),
+                "z" => self.set_breakpoint(arguments, false),
+                "s" | "c" => {
+                    // an address means resume from there
+                    if let Ok(address) = u32::from_str_radix(arguments, 16) {
+                        self.write_register(PC_REGISTER, address);
+                    }
+                    self.resume(command == "s")?;
+                    continue;
+                }
+                "H" | "T" => "OK".to_string(),
+                "k" => return Ok(()),
+                "D" => {
+                    self.send_packet("OK")?;
+                    return Ok(());
+                }
+                "q" => self.handle_query(&packet),
+                "Q" if packet == "QStartNoAckMode" => {
+                    self.send_packet("OK")?;
+                    self.no_ack = true;
+                    continue;
+                }
+                "v" if packet.starts_with("vKill") => {
+                    self.send_packet("OK")?;
+                    return Ok(());
+                }
+                // an empty reply means "not supported"
+                _ => String::new(),
+            };
+
+            self.send_packet(&reply)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Serves the GDB remote protocol for a program, laid out in memory as it was linked, until GDB detaches, kills it,
+/// or hangs up. The program's output is shown in GDB's console; its input comes from whatever os was set up to read.
+pub fn serve<R: Read + Send + 'static, W: Write>(
+    input: R,
+    output: W,
+    elf: Elf,
+    layout: MemoryLayout,
+    mut os: OperatingSystem,
+) -> Result<(), String> {
+    let (lineinfo, mut program_state) = load_program_with_layout(&elf, layout)?;
+    program_state.cp0.set_debug_mode(true);
+    os.capture_program_output();
+
+    let mut debugger_state: DebuggerState = DebuggerState::new();
+    debugger_state.symbols = extract_symbols(&elf);
+
+    let (sender, receiver) = channel();
+    thread::spawn(move || forward_input(input, sender));
+
+    let mut stub: Stub<W> = Stub {
+        input: receiver,
+        pending: VecDeque::new(),
+        output,
+        no_ack: false,
+        lineinfo,
+        program_state,
+        os,
+        debugger_state,
+        last_stop: Stop::Signal(SIGTRAP),
+        crashed: None,
+    };
+    stub.serve()
+}
@@ -1,2 +1,11 @@
 pub mod args;
+pub mod branch;
+pub mod cache;
+pub mod convention;
+pub mod dap;
+pub mod gdb;
+pub mod memcheck;
+pub mod pipeline;
+pub mod profile;
 pub mod simulator;
+pub mod smp;
@@ -1,22 +1,283 @@
 use name_emu::args::Cli;
-use name_emu::simulator::simulate;
+use name_emu::branch::{parse_predictor, PredictorModel};
+use name_emu::cache::{CacheConfig, CacheHierarchy};
+use name_emu::convention::ConventionChecker;
+use name_emu::dap::serve;
+use name_emu::gdb;
+use name_emu::memcheck::Memcheck;
+use name_emu::pipeline::{parse_range, Pipeline, PipelineOptions};
+use name_emu::profile::Profile;
+use name_emu::simulator::{load_program, simulate_with_options, SimulatorOptions};
+use name_emu::smp::{simulate_smp, Interleaving};
 
 use name_core::elf_def::Elf;
-use name_core::elf_utils::read_bytes_to_elf;
+use name_core::elf_utils::{extract_symbols, read_bytes_to_elf};
+use name_core::layout::MemoryLayout;
+use name_core::observer::Observers;
+use name_core::structs::OperatingSystem;
+use name_core::threads::ThreadOptions;
 
 use clap::Parser;
 
-use std::fs::read;
+use std::cell::RefCell;
+use std::fmt::Display;
+use std::fs::{read, read_to_string, write, File};
+use std::io::{empty, stdin, stdout, BufReader};
+use std::net::TcpListener;
+use std::rc::Rc;
 
 pub fn main() {
     let args = Cli::parse();
 
-    let elf_contents: Vec<u8> = read(args.input_filename).expect("File not found");
+    let mut memory_layout: MemoryLayout = match args.layout.as_deref().map(MemoryLayout::load) {
+        Some(Ok(layout)) => layout,
+        Some(Err(e)) => fail(e),
+        None => MemoryLayout::default(),
+    };
+    memory_layout.permissions.writable_text |= args.writable_text;
+    memory_layout.permissions.executable_data |= args.executable_data;
+
+    let threads: ThreadOptions = ThreadOptions {
+        quantum: args.thread_quantum,
+        seed: args.thread_seed,
+    };
+
+    // the program to debug comes from the client's launch request
+    if args.dap {
+        if let Err(e) = serve(BufReader::new(stdin()), stdout(), memory_layout, threads) {
+            fail(e);
+        }
+        return;
+    }
+
+    let input_filename = args.input_filename.expect("No input file given");
+    let elf_contents: Vec<u8> = read(input_filename).expect("File not found");
 
     let executable: Elf = match read_bytes_to_elf(elf_contents) {
         Ok(elf) => elf,
         Err(e) => panic!("{}", e),
     };
 
-    let _ = simulate(executable, args.debug);
+    if let Some(target) = args.gdb {
+        // stdin is GDB's, so the program only gets input from a file
+        let mut operating_system: OperatingSystem = OperatingSystem::new();
+        operating_system.set_thread_options(threads);
+        match args.input {
+            Some(path) => operating_system.set_program_input(BufReader::new(
+                File::open(path).expect("Program input file not found"),
+            )),
+            None => operating_system.set_program_input(empty()),
+        }
+
+        let result: Result<(), String> = if target == "-" {
+            gdb::serve(
+                stdin(),
+                stdout(),
+                executable,
+                memory_layout,
+                operating_system,
+            )
+        } else {
+            // a bare port listens locally
+            let address: String = match target.parse::<u16>() {
+                Ok(port) => format!("127.0.0.1:{port}"),
+                Err(_) => target,
+            };
+            let listener: TcpListener = TcpListener::bind(&address)
+                .unwrap_or_else(|e| panic!("Couldn't listen on {address}: {e}"));
+            eprintln!("Waiting for GDB on {address}...");
+
+            match listener.accept() {
+                Ok((stream, _)) => match stream.try_clone() {
+                    Ok(reader) => {
+                        gdb::serve(reader, stream, executable, memory_layout, operating_system)
+                    }
+                    Err(e) => Err(format!("Couldn't set up the GDB connection: {e}")),
+                },
+                Err(e) => Err(format!("Couldn't accept a GDB connection: {e}")),
+            }
+        };
+
+        if let Err(e) = result {
+            fail(e);
+        }
+        return;
+    }
+
+    if let Some(cores) = args.cores {
+        let interleaving: Interleaving = match args.interleave.as_deref().map(Interleaving::parse) {
+            Some(Ok(interleaving)) => interleaving,
+            Some(Err(e)) => fail(e),
+            None => Interleaving::default(),
+        };
+        let options: SimulatorOptions = SimulatorOptions {
+            program_input: args
+                .input
+                .map(|path| File::open(path).expect("Program input file not found")),
+            memory_layout,
+            ..Default::default()
+        };
+
+        match simulate_smp(
+            executable,
+            options,
+            cores,
+            interleaving,
+            args.core_entry.as_deref(),
+        ) {
+            Ok(machine) => eprint!("\n{}", machine.report()),
+            Err(e) => fail(e),
+        }
+        return;
+    }
+
+    let profiling: bool = args.profile || args.profile_json.is_some();
+    let pipelining: bool = args.pipeline || args.no_forwarding || args.pipeline_diagram.is_some();
+    let caching: bool = args.cache.is_some() || args.icache.is_some() || args.dcache.is_some();
+    let predicting: bool = !args.branch_predictor.is_empty();
+    let checking: bool = args.check_conventions;
+    let memchecking: bool = args.memcheck;
+    if profiling || pipelining || caching || predicting || checking || memchecking {
+        let profile: Rc<RefCell<Profile>> = Rc::new(RefCell::new(Profile::new(&executable)));
+
+        let diagram = match &args.pipeline_diagram {
+            Some(range) => {
+                let (_, program_state) = load_program(&executable);
+                match parse_range(range, &extract_symbols(&executable), &program_state) {
+                    Ok(range) => Some(range),
+                    Err(e) => fail(e),
+                }
+            }
+            None => None,
+        };
+        let pipeline: Rc<RefCell<Pipeline>> =
+            Rc::new(RefCell::new(Pipeline::new(PipelineOptions {
+                forwarding: !args.no_forwarding,
+                diagram,
+            })));
+
+        let parse_cache =
+            |spec: &Option<String>| spec.as_deref().map(CacheConfig::parse).transpose();
+        let caches: CacheHierarchy = match (
+            parse_cache(&args.cache),
+            parse_cache(&args.icache),
+            parse_cache(&args.dcache),
+        ) {
+            (Ok(Some(unified)), _, _) => CacheHierarchy::unified(&executable, unified),
+            (Ok(None), Ok(instruction), Ok(data)) => {
+                CacheHierarchy::split(&executable, instruction, data)
+            }
+            (Err(e), _, _) | (_, Err(e), _) | (_, _, Err(e)) => fail(e),
+        };
+
+        let caches: Rc<RefCell<CacheHierarchy>> = Rc::new(RefCell::new(caches));
+
+        let mut predictors: Vec<Rc<RefCell<PredictorModel>>> = Vec::new();
+        for kind in &args.branch_predictor {
+            match parse_predictor(kind) {
+                Ok(predictor) => predictors.push(Rc::new(RefCell::new(PredictorModel::new(
+                    &executable,
+                    predictor,
+                )))),
+                Err(e) => fail(e),
+            }
+        }
+
+        let checker: Rc<RefCell<ConventionChecker>> =
+            Rc::new(RefCell::new(ConventionChecker::new(&executable)));
+        let memcheck: Rc<RefCell<Memcheck>> = Rc::new(RefCell::new(Memcheck::new(&executable)));
+
+        // the models are shared with the run, so their reports can be read once it's over
+        let mut observers: Observers = Observers::default();
+        if profiling {
+            observers.add(Box::new(profile.clone()));
+        }
+        if pipelining {
+            observers.add(Box::new(pipeline.clone()));
+        }
+        if caching {
+            observers.add(Box::new(caches.clone()));
+        }
+        for predictor in &predictors {
+            observers.add(Box::new(predictor.clone()));
+        }
+        if checking {
+            observers.add(Box::new(checker.clone()));
+        }
+        if memchecking {
+            observers.add(Box::new(memcheck.clone()));
+        }
+
+        let options: SimulatorOptions = SimulatorOptions {
+            program_input: args
+                .input
+                .map(|path| File::open(path).expect("Program input file not found")),
+            memory_layout,
+            threads,
+            observers,
+            ..Default::default()
+        };
+
+        let exit_code: u32 = match simulate_with_options(executable, options) {
+            Ok(exit_code) => exit_code,
+            Err(e) => fail(e),
+        };
+
+        if profiling {
+            eprint!("\n{}", profile.borrow().report());
+            if let Some(path) = args.profile_json {
+                let json: String = serde_json::to_string_pretty(&profile.borrow().to_json())
+                    .expect("Profiles are always valid JSON");
+                if let Err(e) = write(&path, json + "\n") {
+                    eprintln!("Couldn't write the profile to {}: {e}", path.display());
+                }
+            }
+        }
+        if pipelining {
+            eprint!("\n{}", pipeline.borrow().report());
+        }
+        if caching {
+            eprint!("\n{}", caches.borrow().report());
+        }
+        for predictor in &predictors {
+            eprint!("\n{}", predictor.borrow().report());
+        }
+        if checking {
+            eprint!("\n{}", checker.borrow().report());
+        }
+        if memchecking {
+            eprint!("\n{}", memcheck.borrow().report());
+        }
+        exit(exit_code);
+    }
+
+    let options: SimulatorOptions = SimulatorOptions {
+        debug: args.debug,
+        script: args
+            .script
+            .map(|path| read_to_string(path).expect("Debugger script not found")),
+        batch: args.batch,
+        program_input: args
+            .input
+            .map(|path| File::open(path).expect("Program input file not found")),
+        memory_layout,
+        threads,
+        ..Default::default()
+    };
+
+    match simulate_with_options(executable, options) {
+        Ok(exit_code) => exit(exit_code),
+        Err(e) => fail(e),
+    }
+}
+
+/// Reports why the program couldn't be run (or couldn't finish) and exits with 1.
+fn fail(message: impl Display) -> ! {
+    eprintln!("{message}");
+    std::process::exit(1)
+}
+
+/// Exits with the code the program gave SysExitValue, so scripts can check it like any other command's.
+fn exit(exit_code: u32) -> ! {
+    std::process::exit(exit_code as i32)
 }
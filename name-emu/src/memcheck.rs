@@ -0,0 +1,390 @@
+//! A Memcheck-style checker for uninitialised values, run alongside the functional emulator.
+//! Every register and every byte of memory carries a "defined" bit. Values computed from undefined ones are undefined
+//! too, so the checker can warn at the point the program actually depends on one: a branch, an address, a syscall
+//! argument, or a load from stack or heap memory nothing has written yet.
+//!
+//! The .data section starts out defined, since the assembler gave it contents. The stack and heap start out undefined.
+//! Whatever a syscall writes to memory (like read_string's buffer) comes from outside the program, so it's defined.
+
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::ops::Range;
+
+use name_core::constants::REGISTERS;
+use name_core::debug::debug_utils::mnemonic_of;
+use name_core::debug::simulator_helpers::{
+    extract_loadable_sections, find_lineinfo, format_backtrace,
+};
+use name_core::elf_def::Elf;
+use name_core::elf_utils::{extract_lineinfo, extract_symbols};
+use name_core::instruction::information::InstructionInformation;
+use name_core::instruction::instruction::{AccessType, MemoryAccess, RawInstruction};
+use name_core::layout::{segment_bases, MemoryLayout};
+use name_core::observer::{ExecutedInstruction, Observer};
+use name_core::structs::{LineInfo, ProgramState, Symbol};
+
+const V0: usize = 2;
+const A0: usize = 4;
+const A1: usize = 5;
+const SP: usize = 29;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum WarningKind {
+    UndefinedLoad,
+    UndefinedBranch,
+    UndefinedAddress,
+    UndefinedSyscallArgument,
+}
+
+/// One place the program depended on an undefined value. Repeats at the same place are counted, not reported again.
+#[derive(Debug, Clone)]
+pub struct Warning {
+    pub kind: WarningKind,
+    pub address: u32,
+    pub message: String,
+    pub backtrace: Vec<String>,
+    pub count: u64,
+}
+
+pub struct Memcheck {
+    lineinfo: Vec<LineInfo>,
+    symbols: Vec<Symbol>,
+    loaded: [Range<u32>; 2], // .text and .data, which the loader fills in
+    registers: [bool; 32],
+    hi: bool,
+    lo: bool,
+    executing: bool, // between an instruction's before and after, when its own stores are checked separately
+    memory: HashMap<u32, bool>, // bytes written since the start; everything else keeps its initial state
+    pub warnings: Vec<Warning>,
+    seen: HashMap<(WarningKind, u32, usize), usize>,
+}
+
+impl Memcheck {
+    pub fn new(elf: &Elf) -> Self {
+        let (data, text) = extract_loadable_sections(elf);
+        let (text_base, data_base) = segment_bases(elf);
+
+        // only what the loader sets up is defined to begin with
+        let mut registers: [bool; 32] = [false; 32];
+        registers[0] = true;
+        registers[SP] = true;
+
+        Memcheck {
+            lineinfo: extract_lineinfo(elf),
+            symbols: extract_symbols(elf),
+            loaded: [
+                text_base..text_base + text.len() as u32,
+                data_base..data_base + data.len() as u32,
+            ],
+            registers,
+            hi: false,
+            lo: false,
+            executing: false,
+            memory: HashMap::new(),
+            warnings: Vec::new(),
+            seen: HashMap::new(),
+        }
+    }
+
+    /// Checks whether a byte of memory holds a defined value.
+    pub fn is_defined(&self, address: u32) -> bool {
+        match self.memory.get(&address) {
+            Some(&defined) => defined,
+            None => self.loaded.iter().any(|range| range.contains(&address)),
+        }
+    }
+
+    pub fn register_is_defined(&self, register: usize) -> bool {
+        self.registers[register]
+    }
+
+    fn warn(
+        &mut self,
+        kind: WarningKind,
+        executed: &ExecutedInstruction,
+        register: usize,
+        message: String,
+        program_state: &ProgramState,
+    ) {
+        if let Some(&i) = self.seen.get(&(kind, executed.address, register)) {
+            self.warnings[i].count += 1;
+            return;
+        }
+
+        self.seen
+            .insert((kind, executed.address, register), self.warnings.len());
+        self.warnings.push(Warning {
+            kind,
+            address: executed.address,
+            message,
+            backtrace: format_backtrace(
+                &self.lineinfo,
+                &self.symbols,
+                &program_state.call_stack,
+                executed.address,
+            ),
+            count: 1,
+        });
+    }
+
+    /// Names the part of memory an address is in, relative to $sp for the stack.
+    fn describe_location(address: u32, sp: u32, layout: &MemoryLayout) -> String {
+        if (layout.stack_limit()..=layout.stack_top).contains(&address) {
+            let offset: i64 = address as i64 - sp as i64;
+            match offset {
+                0 => "on the stack, at $sp".to_string(),
+                o if o > 0 => format!("on the stack, {o} bytes above $sp"),
+                o => format!("on the stack, {} bytes below $sp", -o),
+            }
+        } else if (layout.heap_base..layout.heap_base + layout.heap_size).contains(&address) {
+            "on the heap".to_string()
+        } else {
+            "in the data section".to_string()
+        }
+    }
+
+    /// Lays out every warning, in the order they first happened.
+    pub fn report(&self) -> String {
+        let mut report: String = match self.warnings.len() {
+            0 => "Memcheck: no uses of undefined values found.\n".to_string(),
+            1 => "Memcheck: 1 use of an undefined value found.\n".to_string(),
+            n => format!("Memcheck: {n} uses of undefined values found.\n"),
+        };
+
+        for warning in &self.warnings {
+            let location: String = match find_lineinfo(&self.lineinfo, warning.address) {
+                Some(li) => format!("{}:{}", li.file_name, li.line_number),
+                None => "??".to_string(),
+            };
+            let _ = writeln!(
+                report,
+                "\n{location} (0x{:08x}): {}",
+                warning.address, warning.message
+            );
+            for frame in &warning.backtrace {
+                let _ = writeln!(report, "    {frame}");
+            }
+            if warning.count > 1 {
+                let _ = writeln!(report, "    (happened {} times)", warning.count);
+            }
+        }
+
+        report
+    }
+
+    /// Warns about any of the registers that are undefined, saying what they were used for.
+    fn check_registers(
+        &mut self,
+        kind: WarningKind,
+        executed: &ExecutedInstruction,
+        registers: &[u32],
+        purpose: &str,
+        program_state: &ProgramState,
+    ) {
+        for &register in registers {
+            let register: usize = register as usize;
+            if !self.registers[register] {
+                self.warn(
+                    kind,
+                    executed,
+                    register,
+                    format!("{purpose} {}, which is undefined.", REGISTERS[register]),
+                    program_state,
+                );
+            }
+        }
+    }
+
+    fn check_syscall(&mut self, executed: &ExecutedInstruction, program_state: &ProgramState) {
+        let kind: WarningKind = WarningKind::UndefinedSyscallArgument;
+        if !self.registers[V0] {
+            self.check_registers(
+                kind,
+                executed,
+                &[V0 as u32],
+                "The syscall number comes from",
+                program_state,
+            );
+            return;
+        }
+
+        match program_state.cpu.general_purpose_registers[V0] {
+            // print int, print string, print char
+            1 | 4 | 11 => self.check_registers(
+                kind,
+                executed,
+                &[A0 as u32],
+                "The syscall prints",
+                program_state,
+            ),
+            // read int, read char
+            5 | 12 => self.registers[V0] = true,
+            // read string fills in the buffer, however much of it is typed
+            8 => {
+                self.check_registers(
+                    kind,
+                    executed,
+                    &[A0 as u32, A1 as u32],
+                    "The syscall's buffer is described by",
+                    program_state,
+                );
+                let registers: &[u32; 32] = &program_state.cpu.general_purpose_registers;
+                let (buffer, length) = (registers[A0], registers[A1]);
+                for offset in 0..length {
+                    self.memory.insert(buffer.wrapping_add(offset), true);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Follows values through HI and LO. Gives back whether the instruction was one that uses them.
+    fn check_hi_lo(&mut self, executed: &ExecutedInstruction) -> bool {
+        let instruction = executed.instruction;
+        if instruction.get_opcode() != 0x00 {
+            return false;
+        }
+
+        let rs: bool = self.registers[instruction.get_rs() as usize];
+        let rt: bool = self.registers[instruction.get_rt() as usize];
+        match instruction.get_funct() {
+            // mfhi, mflo
+            funct @ (0x10 | 0x12) => {
+                let defined: bool = match funct {
+                    0x10 => self.hi,
+                    _ => self.lo,
+                };
+                if let Some(destination) = instruction.destination_register() {
+                    self.registers[destination as usize] = defined;
+                }
+            }
+            // mthi, mtlo
+            0x11 => self.hi = rs,
+            0x13 => self.lo = rs,
+            // mult, multu, div, divu
+            0x18..=0x1B => {
+                self.hi = rs && rt;
+                self.lo = rs && rt;
+            }
+            _ => return false,
+        }
+        true
+    }
+
+    fn check_memory(
+        &mut self,
+        executed: &ExecutedInstruction,
+        access: MemoryAccess,
+        program_state: &ProgramState,
+    ) {
+        let instruction = executed.instruction;
+        let base: usize = instruction.get_rs() as usize;
+        let target: usize = instruction.get_rt() as usize;
+        let mnemonic: &str = mnemonic_of(instruction).unwrap_or("??");
+
+        self.check_registers(
+            WarningKind::UndefinedAddress,
+            executed,
+            &[base as u32],
+            &format!("The address {mnemonic} uses is computed from"),
+            program_state,
+        );
+
+        let bytes = access.address..access.address.wrapping_add(access.size);
+        match access.access_type {
+            AccessType::Read => {
+                let defined: bool = bytes.clone().all(|byte| self.is_defined(byte));
+                if !defined && self.registers[base] {
+                    let sp: u32 = program_state.cpu.general_purpose_registers[SP];
+                    self.warn(
+                        WarningKind::UndefinedLoad,
+                        executed,
+                        0,
+                        format!(
+                            "{mnemonic} reads 0x{:08x} ({}), which nothing has written yet.",
+                            access.address,
+                            Memcheck::describe_location(
+                                access.address,
+                                sp,
+                                &program_state.memory.layout
+                            )
+                        ),
+                        program_state,
+                    );
+                }
+                if target != 0 {
+                    self.registers[target] = defined && self.registers[base];
+                }
+            }
+            AccessType::Write => {
+                let defined: bool = self.registers[target] && self.registers[base];
+                for byte in bytes {
+                    self.memory.insert(byte, defined);
+                }
+            }
+        }
+    }
+}
+
+impl Observer for Memcheck {
+    fn before_execute(
+        &mut self,
+        _address: u32,
+        _instruction: RawInstruction,
+        _program_state: &ProgramState,
+    ) {
+        self.executing = true;
+    }
+
+    fn after_execute(
+        &mut self,
+        executed: &ExecutedInstruction,
+        information: &InstructionInformation,
+        program_state: &ProgramState,
+    ) {
+        self.executing = false;
+        let instruction = executed.instruction;
+        let sources: Vec<u32> = instruction.source_registers();
+
+        if let Some(access) = executed.memory_access {
+            self.check_memory(executed, access, program_state);
+            return;
+        }
+
+        if instruction.get_opcode() == 0x00 && instruction.get_funct() == 0x0C {
+            self.check_syscall(executed, program_state);
+            return;
+        }
+
+        if self.check_hi_lo(executed) {
+            return;
+        }
+
+        if instruction.is_branch() || instruction.is_jump() {
+            self.check_registers(
+                WarningKind::UndefinedBranch,
+                executed,
+                &sources,
+                &format!("Where {} goes depends on", information.mnemonic),
+                program_state,
+            );
+        }
+
+        // calls always leave a good return address, whatever they jumped through
+        if let Some(destination) = instruction.destination_register() {
+            self.registers[destination as usize] = instruction.is_call()
+                || sources
+                    .iter()
+                    .all(|&register| self.registers[register as usize]);
+        }
+    }
+    /// Syscalls write memory outside of any instruction.
+    fn memory_write(&mut self, access: MemoryAccess, _value: u32, _program_state: &ProgramState) {
+        if !self.executing {
+            for offset in 0..access.size {
+                self.memory
+                    .insert(access.address.wrapping_add(offset), true);
+            }
+        }
+    }
+}
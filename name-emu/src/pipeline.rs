@@ -0,0 +1,306 @@
+//! A timing model of the classic 5-stage MIPS pipeline (IF, ID, EX, MEM, WB), run alongside the functional emulator.
+//! The emulator decides what the program does; the model only works out how many cycles a pipelined processor would take.
+//!
+//! The model assumes:
+//! - registers are written in the first half of WB and read in the second half of ID;
+//! - with forwarding, results reach EX from the EX/MEM and MEM/WB latches, so only a load followed by a use stalls;
+//! - without forwarding, a dependent instruction waits in ID until its operands are written back;
+//! - fetch predicts not taken, and branches and jumps resolve in EX, so a taken one flushes the two instructions behind it.
+
+use std::fmt::Write;
+use std::ops::Range;
+
+use name_core::debug::expression::Expression;
+use name_core::instruction::information::InstructionInformation;
+use name_core::instruction::instruction::AccessType;
+use name_core::observer::{ExecutedInstruction, Observer};
+use name_core::structs::{ProgramState, Symbol};
+
+/// The most rows one pipeline diagram gets before a new one is started.
+const DIAGRAM_ROWS: usize = 16;
+
+#[derive(Debug, Clone, Default)]
+pub struct PipelineOptions {
+    pub forwarding: bool,
+    pub diagram: Option<Range<u32>>, // instructions in this range get drawn cycle by cycle
+}
+
+/// Totals for a whole run.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct PipelineStats {
+    pub instructions: u64,
+    pub cycles: u64,
+    pub data_hazards: u64, // instructions that read a register before it was written back
+    pub data_stalls: u64,
+    pub load_use_stalls: u64, // the data stalls spent waiting on a load
+    pub control_hazards: u64, // taken branches and jumps
+    pub flushed: u64,
+}
+
+impl PipelineStats {
+    pub fn cpi(&self) -> f64 {
+        match self.instructions {
+            0 => 0.0,
+            instructions => self.cycles as f64 / instructions as f64,
+        }
+    }
+}
+
+/// When an instruction was in each stage. An instruction can sit in IF and ID for several cycles while the pipeline stalls;
+/// MEM and WB always follow EX directly.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StageTiming {
+    pub fetch: u64,  // first cycle in IF
+    pub decode: u64, // first cycle in ID
+    pub execute: u64,
+}
+
+impl StageTiming {
+    pub fn write_back(&self) -> u64 {
+        self.execute + 2
+    }
+
+    /// Names the stage the instruction was in during a cycle.
+    pub fn stage_at(&self, cycle: u64) -> Option<&'static str> {
+        match cycle {
+            c if c < self.fetch => None,
+            c if c < self.decode => Some("IF"),
+            c if c < self.execute => Some("ID"),
+            c if c == self.execute => Some("EX"),
+            c if c == self.execute + 1 => Some("MEM"),
+            c if c == self.execute + 2 => Some("WB"),
+            _ => None,
+        }
+    }
+}
+
+/// The instruction that will next write a register, and when its value becomes usable.
+#[derive(Debug, Clone, Copy)]
+struct Producer {
+    timing: StageTiming,
+    is_load: bool,
+}
+
+#[derive(Debug, Clone)]
+struct DiagramRow {
+    address: u32,
+    mnemonic: &'static str,
+    timing: StageTiming,
+}
+
+#[derive(Debug, Default)]
+pub struct Pipeline {
+    options: PipelineOptions,
+    pub stats: PipelineStats,
+    producers: [Option<Producer>; 32],
+    last: Option<StageTiming>,
+    fetch_after: u64, // the earliest cycle fetch can restart after a taken branch or jump
+    diagrams: Vec<Vec<DiagramRow>>,
+    current_diagram: Vec<DiagramRow>,
+}
+
+impl Pipeline {
+    pub fn new(options: PipelineOptions) -> Self {
+        Pipeline {
+            options,
+            ..Default::default()
+        }
+    }
+
+    /// Works out when the next instruction in program order goes through each stage.
+    fn schedule(&mut self, executed: &ExecutedInstruction) -> StageTiming {
+        let instruction = executed.instruction;
+
+        // the next instruction is fetched once the one before it moves on to ID
+        let fetch: u64 = match self.last {
+            Some(last) => last.decode.max(self.fetch_after),
+            None => 1,
+        };
+        // and it leaves IF once the one before it leaves ID
+        let decode: u64 = match self.last {
+            Some(last) => fetch.max(last.execute - 1) + 1,
+            None => fetch + 1,
+        };
+        let in_order: u64 = decode + 1;
+
+        // wait in ID until every operand can be had
+        let mut execute: u64 = in_order;
+        let mut waiting_on_load: bool = false;
+        let mut hazard: bool = false;
+        for register in instruction.source_registers() {
+            let Some(producer) = self.producers[register as usize] else {
+                continue;
+            };
+            if producer.timing.write_back() <= decode {
+                continue;
+            }
+            hazard = true;
+
+            let ready: u64 = match (self.options.forwarding, producer.is_load) {
+                (true, true) => producer.timing.execute + 2, // from MEM/WB
+                (true, false) => producer.timing.execute + 1, // from EX/MEM
+                (false, _) => producer.timing.write_back() + 1,
+            };
+            if ready > execute {
+                execute = ready;
+                waiting_on_load = producer.is_load;
+            }
+        }
+
+        if hazard {
+            self.stats.data_hazards += 1;
+        }
+        self.stats.data_stalls += execute - in_order;
+        if waiting_on_load {
+            self.stats.load_use_stalls += execute - in_order;
+        }
+
+        StageTiming {
+            fetch,
+            decode,
+            execute,
+        }
+    }
+
+    fn end_diagram(&mut self) {
+        if !self.current_diagram.is_empty() {
+            let rows: Vec<DiagramRow> = std::mem::take(&mut self.current_diagram);
+            self.diagrams.push(rows);
+        }
+    }
+
+    /// Lays out the totals, then any pipeline diagrams that were asked for.
+    pub fn report(&self) -> String {
+        let stats: PipelineStats = self.stats;
+        let mut report: String = String::new();
+
+        let forwarding: &str = if self.options.forwarding { "on" } else { "off" };
+        let _ = writeln!(report, "Pipeline (5 stages, forwarding {forwarding}):");
+        let _ = writeln!(
+            report,
+            "  {:<20} {:>10}",
+            "instructions", stats.instructions
+        );
+        let _ = writeln!(report, "  {:<20} {:>10}", "cycles", stats.cycles);
+        let _ = writeln!(report, "  {:<20} {:>10.3}", "CPI", stats.cpi());
+        let _ = writeln!(
+            report,
+            "  {:<20} {:>10}",
+            "data hazards", stats.data_hazards
+        );
+        let _ = writeln!(
+            report,
+            "  {:<20} {:>10} ({} load-use)",
+            "data stall cycles", stats.data_stalls, stats.load_use_stalls
+        );
+        let _ = writeln!(
+            report,
+            "  {:<20} {:>10}",
+            "control hazards", stats.control_hazards
+        );
+        let _ = writeln!(report, "  {:<20} {:>10}", "flushed cycles", stats.flushed);
+
+        let pending = (!self.current_diagram.is_empty()).then_some(&self.current_diagram);
+        for rows in self.diagrams.iter().chain(pending) {
+            report.push('\n');
+            report.push_str(&draw_diagram(rows));
+        }
+
+        report
+    }
+}
+
+impl Observer for Pipeline {
+    fn after_execute(
+        &mut self,
+        executed: &ExecutedInstruction,
+        information: &InstructionInformation,
+        _program_state: &ProgramState,
+    ) {
+        let instruction = executed.instruction;
+        let timing: StageTiming = self.schedule(executed);
+
+        if let Some(register) = instruction.destination_register() {
+            self.producers[register as usize] = Some(Producer {
+                timing,
+                is_load: executed
+                    .memory_access
+                    .is_some_and(|access| access.access_type == AccessType::Read),
+            });
+        }
+
+        // the two instructions fetched behind a taken branch or jump were the wrong ones
+        if (instruction.is_branch() || instruction.is_jump()) && executed.redirected() {
+            self.stats.control_hazards += 1;
+            self.stats.flushed += 2;
+            self.fetch_after = timing.execute + 1;
+        }
+
+        self.stats.instructions += 1;
+        self.stats.cycles = timing.write_back();
+        self.last = Some(timing);
+
+        let drawn: bool = self
+            .options
+            .diagram
+            .as_ref()
+            .is_some_and(|range| range.contains(&executed.address));
+        if drawn {
+            self.current_diagram.push(DiagramRow {
+                address: executed.address,
+                mnemonic: information.mnemonic,
+                timing,
+            });
+        }
+        if !drawn || self.current_diagram.len() == DIAGRAM_ROWS {
+            self.end_diagram();
+        }
+    }
+}
+
+/// Draws one row per instruction and one column per cycle, like the diagrams in Patterson and Hennessy.
+/// A stage repeated across several cycles means the instruction stalled there.
+fn draw_diagram(rows: &[DiagramRow]) -> String {
+    let first: u64 = rows.first().map_or(0, |row| row.timing.fetch);
+    let last: u64 = rows.last().map_or(0, |row| row.timing.write_back());
+    let width: usize = (last.to_string().len() + 1).max(4);
+
+    let mut diagram: String = format!("{:<19}", "cycle");
+    for cycle in first..=last {
+        let _ = write!(diagram, "{:<width$}", cycle);
+    }
+    diagram = diagram.trim_end().to_string() + "\n";
+
+    for row in rows {
+        let mut line: String = format!("0x{:08x} {:<8}", row.address, row.mnemonic);
+        for cycle in first..=last {
+            let _ = write!(line, "{:<width$}", row.timing.stage_at(cycle).unwrap_or(""));
+        }
+        diagram.push_str(line.trim_end());
+        diagram.push('\n');
+    }
+
+    diagram
+}
+
+/// Reads a diagram range like `compute,print` or `0x00400024,0x00400040`. The end is exclusive.
+/// Either end can be any debugger expression that doesn't need registers or memory.
+pub fn parse_range(
+    text: &str,
+    symbols: &[Symbol],
+    program_state: &ProgramState,
+) -> Result<Range<u32>, String> {
+    let Some((start, end)) = text.split_once(',') else {
+        return Err(format!("{text} is not a range; give it as START,END."));
+    };
+
+    let start: u32 = Expression::parse(start.trim(), symbols)?.evaluate(program_state)?;
+    let end: u32 = Expression::parse(end.trim(), symbols)?.evaluate(program_state)?;
+    if end <= start {
+        return Err(format!(
+            "The range 0x{start:08x},0x{end:08x} is empty; the end is exclusive."
+        ));
+    }
+
+    Ok(start..end)
+}
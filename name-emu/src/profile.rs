@@ -0,0 +1,237 @@
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+use name_core::debug::simulator_helpers::{find_lineinfo, function_name};
+use name_core::elf_def::Elf;
+use name_core::elf_utils::{extract_lineinfo, extract_symbols};
+use name_core::instruction::information::InstructionInformation;
+use name_core::instruction::instruction::AccessType;
+use name_core::observer::{ExecutedInstruction, Observer};
+use name_core::structs::{LineInfo, ProgramState, Symbol};
+
+use serde_json::{json, Value};
+
+/// How many executed instructions fell into each kind.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct InstructionMix {
+    pub alu: u64,
+    pub load: u64,
+    pub store: u64,
+    pub branch_taken: u64,
+    pub branch_not_taken: u64,
+    pub jump: u64,
+    pub syscall: u64, // break traps the same way, so it's counted here too
+}
+
+impl InstructionMix {
+    /// The label and count of each kind, in the order they're reported.
+    pub fn entries(&self) -> [(&'static str, u64); 7] {
+        [
+            ("alu", self.alu),
+            ("load", self.load),
+            ("store", self.store),
+            ("branch_taken", self.branch_taken),
+            ("branch_not_taken", self.branch_not_taken),
+            ("jump", self.jump),
+            ("syscall", self.syscall),
+        ]
+    }
+}
+
+/// Executions of a single source line, summed over every address it assembled to.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LineCount {
+    pub file_name: String,
+    pub line_number: u32,
+    pub content: String,
+    pub address: u32, // the first address of the line, used to break ties
+    pub count: u64,
+}
+
+/// Dynamic instruction counts for one run of a program. Filled in by the simulator under --profile.
+#[derive(Debug, Default)]
+pub struct Profile {
+    lineinfo: Vec<LineInfo>,
+    symbols: Vec<Symbol>,
+    pub executions: BTreeMap<u32, u64>, // text address -> times executed
+    pub mix: InstructionMix,
+}
+
+impl Profile {
+    pub fn new(elf: &Elf) -> Self {
+        Profile {
+            lineinfo: extract_lineinfo(elf),
+            symbols: extract_symbols(elf),
+            ..Default::default()
+        }
+    }
+
+    /// The total number of instructions executed.
+    pub fn total(&self) -> u64 {
+        self.executions.values().sum()
+    }
+
+    /// Execution counts per source line, hottest first.
+    pub fn by_line(&self) -> Vec<LineCount> {
+        let mut lines: BTreeMap<(String, u32), LineCount> = BTreeMap::new();
+
+        for (&address, &count) in &self.executions {
+            let (file_name, line_number, content) = match find_lineinfo(&self.lineinfo, address) {
+                Some(li) => (li.file_name.clone(), li.line_number, li.content.clone()),
+                None => ("??".to_string(), 0, String::new()),
+            };
+
+            lines
+                .entry((file_name.clone(), line_number))
+                .or_insert(LineCount {
+                    file_name,
+                    line_number,
+                    content,
+                    address,
+                    count: 0,
+                })
+                .count += count;
+        }
+
+        let mut lines: Vec<LineCount> = lines.into_values().collect();
+        lines.sort_by(|a, b| b.count.cmp(&a.count).then(a.address.cmp(&b.address)));
+        lines
+    }
+
+    /// Execution counts per function, hottest first. An address belongs to the closest .text label before it.
+    pub fn by_function(&self) -> Vec<(String, u64)> {
+        let mut functions: BTreeMap<String, (u32, u64)> = BTreeMap::new();
+
+        for (&address, &count) in &self.executions {
+            let entry = functions
+                .entry(function_name(&self.symbols, address))
+                .or_insert((address, 0));
+            entry.1 += count;
+        }
+
+        let mut functions: Vec<(String, u32, u64)> = functions
+            .into_iter()
+            .map(|(name, (address, count))| (name, address, count))
+            .collect();
+        functions.sort_by(|a, b| b.2.cmp(&a.2).then(a.1.cmp(&b.1)));
+        functions
+            .into_iter()
+            .map(|(name, _, count)| (name, count))
+            .collect()
+    }
+
+    /// Lays out the profile for a person: the instruction mix, then functions and lines from hottest to coldest.
+    pub fn report(&self) -> String {
+        let total: u64 = self.total();
+        let percent = |count: u64| {
+            if total == 0 {
+                0.0
+            } else {
+                count as f64 * 100.0 / total as f64
+            }
+        };
+
+        let mut report: String = String::new();
+        let _ = writeln!(report, "{total} instructions executed");
+
+        let _ = writeln!(report, "\nInstruction mix:");
+        for (kind, count) in self.mix.entries() {
+            let _ = writeln!(
+                report,
+                "  {:<18} {:>10} {:>6.2}%",
+                kind.replace('_', " "),
+                count,
+                percent(count)
+            );
+        }
+
+        let _ = writeln!(report, "\nBy function:");
+        for (name, count) in self.by_function() {
+            let _ = writeln!(report, "  {:>10} {:>6.2}%  {}", count, percent(count), name);
+        }
+
+        let _ = writeln!(report, "\nBy line:");
+        for line in self.by_line() {
+            let _ = writeln!(
+                report,
+                "  {:>10} {:>6.2}%  {}:{}  {}",
+                line.count,
+                percent(line.count),
+                line.file_name,
+                line.line_number,
+                line.content.trim()
+            );
+        }
+
+        report
+    }
+
+    /// The same information as the report, for tools to read. Lists are sorted the same way.
+    pub fn to_json(&self) -> Value {
+        let mix: serde_json::Map<String, Value> = self
+            .mix
+            .entries()
+            .iter()
+            .map(|(kind, count)| (kind.to_string(), json!(count)))
+            .collect();
+
+        json!({
+            "total": self.total(),
+            "mix": mix,
+            "functions": self
+                .by_function()
+                .into_iter()
+                .map(|(name, count)| json!({ "name": name, "count": count }))
+                .collect::<Vec<Value>>(),
+            "lines": self
+                .by_line()
+                .into_iter()
+                .map(|line| json!({
+                    "file": line.file_name,
+                    "line": line.line_number,
+                    "source": line.content.trim(),
+                    "address": line.address,
+                    "count": line.count,
+                }))
+                .collect::<Vec<Value>>(),
+            "addresses": self
+                .executions
+                .iter()
+                .map(|(address, count)| json!({ "address": address, "count": count }))
+                .collect::<Vec<Value>>(),
+        })
+    }
+}
+
+impl Observer for Profile {
+    /// Counts one executed instruction.
+    fn after_execute(
+        &mut self,
+        executed: &ExecutedInstruction,
+        _information: &InstructionInformation,
+        _program_state: &ProgramState,
+    ) {
+        let instruction = executed.instruction;
+        *self.executions.entry(executed.address).or_insert(0) += 1;
+
+        let counter: &mut u64 = if instruction.is_branch() {
+            if executed.redirected() {
+                &mut self.mix.branch_taken
+            } else {
+                &mut self.mix.branch_not_taken
+            }
+        } else if instruction.is_jump() {
+            &mut self.mix.jump
+        } else if instruction.get_opcode() == 0x00 && matches!(instruction.get_funct(), 0x0C | 0x0D)
+        {
+            &mut self.mix.syscall
+        } else {
+            match executed.memory_access {
+                Some(access) if access.access_type == AccessType::Read => &mut self.mix.load,
+                Some(_) => &mut self.mix.store,
+                None => &mut self.mix.alu,
+            }
+        };
+        *counter += 1;
+    }
+}
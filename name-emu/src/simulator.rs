@@ -1,56 +1,114 @@
+use std::fs::File;
+use std::io::BufReader;
+
 use name_core::debug::exception_handler::handle_exception;
-use name_core::debug::simulator_helpers::extract_loadable_sections;
 
 use name_core::debug::debug_utils::{single_step, DebuggerState};
 
 use name_core::elf_def::Elf;
-use name_core::elf_utils::extract_lineinfo;
-use name_core::structs::{LineInfo, Memory, OperatingSystem, Processor, ProgramState};
+use name_core::elf_utils::extract_symbols;
+use name_core::layout::MemoryLayout;
+use name_core::observer::Observers;
+use name_core::structs::{LineInfo, OperatingSystem, ProgramState};
+use name_core::threads::ThreadOptions;
+
+// loading lives with the Machine, which needs it too
+pub use name_core::machine::{load_program, load_program_with_layout};
 
-pub fn simulate(elf: Elf, debug: bool) -> Result<(), String> {
-    // Set up simulation environment from information in ELF
-    let cpu: Processor = Processor::new(elf.file_header.e_entry);
+/// Everything about a run that doesn't come from the ELF itself.
+#[derive(Debug, Default)]
+pub struct SimulatorOptions {
+    pub debug: bool,
+    pub script: Option<String>,      // debugger commands, one per line
+    pub batch: bool,                 // exit the debugger once the script is used up
+    pub program_input: Option<File>, // what the program reads in place of stdin
+    pub memory_layout: MemoryLayout, // has to match the one the program was linked for
+    pub threads: ThreadOptions,      // how the program's green threads are scheduled
+    pub observers: Observers,        // watch the run, like the profiler or the cache model
+}
 
-    let (data, text) = extract_loadable_sections(&elf);
+/// Runs a program, giving back its exit code.
+pub fn simulate(elf: Elf, debug: bool) -> Result<u32, String> {
+    simulate_with_options(
+        elf,
+        SimulatorOptions {
+            debug,
+            ..Default::default()
+        },
+    )
+}
 
-    let lineinfo: Vec<LineInfo> = extract_lineinfo(&elf);
+/// Sets up an operating system that gives the program the requested input, or stdin if there isn't any,
+/// and schedules its threads as asked.
+pub(crate) fn new_operating_system(
+    program_input: Option<File>,
+    threads: ThreadOptions,
+) -> OperatingSystem {
+    let mut operating_system: OperatingSystem = OperatingSystem::new();
+    operating_system.set_thread_options(threads);
+    if let Some(input) = program_input {
+        operating_system.set_program_input(BufReader::new(input));
+    }
+    operating_system
+}
 
-    let memory: Memory = Memory::new(data, text);
+/// Runs a program normally or in the debugger, giving back its exit code.
+pub fn simulate_with_options(elf: Elf, options: SimulatorOptions) -> Result<u32, String> {
+    let debug: bool = options.debug || options.script.is_some() || options.batch;
 
-    // Create program state
-    let mut program_state: ProgramState = ProgramState::new(cpu, memory);
+    let (lineinfo, mut program_state) = load_program_with_layout(&elf, options.memory_layout)?;
     program_state.cp0.set_debug_mode(debug);
+    program_state.observers = options.observers;
 
     // Setup a new operating system
-    let mut operating_system: OperatingSystem = OperatingSystem::new();
+    let mut operating_system: OperatingSystem =
+        new_operating_system(options.program_input, options.threads);
 
     if program_state.cp0.is_debug_mode() {
         // Invoke the cli debugger if the user asked for it
         // When VSCode extension is implemented, add a flag here to determine whether to
         // run the CLI debugger right away or to engage in soon-to-be-defined behavior
         // depending on whether the user ran this from the command line or from the nice little VSCode button
-        return operating_system.cli_debugger(
+        let mut debugger_state: DebuggerState = DebuggerState::new();
+        debugger_state.symbols = extract_symbols(&elf);
+        if let Some(script) = &options.script {
+            debugger_state.load_script(script);
+        }
+        debugger_state.batch = options.batch;
+
+        operating_system.cli_debugger(&lineinfo, &mut program_state, &mut debugger_state)?;
+    } else {
+        // Not debugging, but fatal errors still name functions in their backtraces
+        let mut debugger_state: DebuggerState = DebuggerState::new();
+        debugger_state.symbols = extract_symbols(&elf);
+
+        run(
             &lineinfo,
             &mut program_state,
-            &mut DebuggerState::new(),
+            &mut operating_system,
+            &mut debugger_state,
         );
-    } else {
-        // Begin fetch/decode/execute cycle to run program normally
-        while program_state.should_continue_execution {
-            // Run the next instruction
-            single_step(&lineinfo, &mut program_state);
-            // If an exception occurred, handle it
-            if program_state.is_exception() {
-                handle_exception(
-                    &mut program_state,
-                    &mut operating_system,
-                    &lineinfo,
-                    &mut DebuggerState::new(),
-                );
-                if program_state.cp0.is_debug_mode() {}  // ooops you have to put the cd in the computer
-            }
-        }
     }
 
-    Ok(())
-}
\ No newline at end of file
+    Ok(operating_system.exit_code())
+}
+
+/// The fetch/decode/execute cycle for running a program normally.
+fn run(
+    lineinfo: &Vec<LineInfo>,
+    program_state: &mut ProgramState,
+    operating_system: &mut OperatingSystem,
+    debugger_state: &mut DebuggerState,
+) {
+    while program_state.should_continue_execution {
+        // Run the next instruction
+        single_step(lineinfo, program_state);
+
+        // If an exception occurred, handle it
+        if program_state.is_exception() {
+            handle_exception(program_state, operating_system, lineinfo, debugger_state);
+        }
+
+        operating_system.tick(program_state);
+    }
+}
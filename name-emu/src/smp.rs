@@ -0,0 +1,350 @@
+//! Several cores running one program out of the same memory, for demonstrating races and locks on real MIPS code.
+//! Every core has its own registers, Coprocessor 0 (with its number in EBase.CPUNum), shadow call stack, and ll/sc link,
+//! while memory and the operating system are shared. The cores take turns one instruction at a time, and whose turn it
+//! is gets decided here instead of by the host: round-robin every few instructions, or picked with a seeded generator.
+//! Either way, running the same program the same way always interleaves it the same way.
+//!
+//! Core 0 starts at the program's entry. The others are parked until started at an entry of their own, with their
+//! number in $a0, a share of the stack, and $ra set to HALT_ADDRESS, so returning from the entry stops the core.
+//! An exit syscall on any core ends the whole program.
+
+use std::cell::RefCell;
+use std::mem::{swap, take};
+use std::rc::Rc;
+
+use name_core::debug::debug_utils::{single_step, DebuggerState};
+use name_core::debug::exception_handler::handle_exception;
+use name_core::debug::expression::Expression;
+use name_core::elf_def::Elf;
+use name_core::elf_utils::extract_symbols;
+use name_core::instruction::instruction::MemoryAccess;
+use name_core::observer::Observer;
+use name_core::structs::{
+    CallFrame, Coprocessor0, LineInfo, OperatingSystem, Processor, ProgramState, Register,
+};
+
+use crate::simulator::{load_program_with_layout, new_operating_system, SimulatorOptions};
+
+/// Where a started core's entry returns to. Nothing is ever loaded there.
+pub const HALT_ADDRESS: u32 = 0xffff_fffc;
+/// EBase.CPUNum is 10 bits wide.
+pub const MAX_CORES: usize = 1024;
+
+/// How the cores take turns.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Interleaving {
+    RoundRobin { quantum: u32 }, // each core runs this many instructions before the next one gets a go
+    Random { seed: u64 },        // before every instruction, any running core may be picked
+}
+
+impl Default for Interleaving {
+    fn default() -> Self {
+        Interleaving::RoundRobin { quantum: 1 }
+    }
+}
+
+impl Interleaving {
+    /// Reads an interleaving like round-robin, round-robin:4, random, or random:42.
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let (kind, parameter) = match spec.split_once(':') {
+            Some((kind, parameter)) => (kind.trim(), Some(parameter.trim())),
+            None => (spec.trim(), None),
+        };
+        let number = |default: u64| match parameter {
+            Some(text) => text
+                .parse::<u64>()
+                .map_err(|_| format!("{text} is not a number (in {spec}).")),
+            None => Ok(default),
+        };
+
+        match kind {
+            "round-robin" => match u32::try_from(number(1)?) {
+                Ok(0) | Err(_) => Err(format!(
+                    "A round-robin quantum has to be between 1 and {} instructions.",
+                    u32::MAX
+                )),
+                Ok(quantum) => Ok(Interleaving::RoundRobin { quantum }),
+            },
+            "random" => Ok(Interleaving::Random { seed: number(1)? }),
+            _ => Err(format!(
+                "{kind} is not a way to interleave cores; use round-robin[:QUANTUM] or random[:SEED]."
+            )),
+        }
+    }
+}
+
+/// One core's own state. It's only in the shared ProgramState while the core is executing an instruction.
+#[derive(Debug)]
+pub struct Core {
+    pub cpu: Processor,
+    pub cp0: Coprocessor0,
+    pub call_stack: Vec<CallFrame>,
+    pub running: bool,     // started, and hasn't returned yet
+    pub instructions: u64, // how many it has executed
+}
+
+/// Collects the program's writes to memory, so the cores' links can be broken by whatever wrote over them.
+/// Syscalls that write the program's memory (like read_string) are heard about the same way stores are.
+#[derive(Debug, Default)]
+struct Writes(Vec<MemoryAccess>);
+
+impl Observer for Writes {
+    fn memory_write(&mut self, access: MemoryAccess, _value: u32, _program_state: &ProgramState) {
+        self.0.push(access);
+    }
+}
+
+pub struct Multiprocessor {
+    pub program_state: ProgramState, // memory, and the registers of whichever core is executing
+    cores: Vec<Core>,
+    writes: Rc<RefCell<Writes>>, // what the instruction being executed wrote
+    interleaving: Interleaving,
+    current: usize,
+    turn_left: u32,
+    random_state: u64,
+}
+
+impl Multiprocessor {
+    /// Sets up cores around a loaded program. Core 0 takes over the registers the program was loaded with.
+    pub fn new(
+        mut program_state: ProgramState,
+        cores: usize,
+        interleaving: Interleaving,
+    ) -> Result<Self, String> {
+        if !(1..=MAX_CORES).contains(&cores) {
+            return Err(format!(
+                "There can be between 1 and {MAX_CORES} cores, not {cores}."
+            ));
+        }
+
+        let cores: Vec<Core> = (0..cores)
+            .map(|number| {
+                let mut core: Core = Core {
+                    cpu: Processor::new(HALT_ADDRESS),
+                    cp0: Coprocessor0::for_core(number as u32),
+                    call_stack: Vec::new(),
+                    running: number == 0,
+                    instructions: 0,
+                };
+                if number == 0 {
+                    core.cp0.set_debug_mode(program_state.cp0.is_debug_mode());
+                    swap(&mut core.cpu, &mut program_state.cpu);
+                    swap(&mut core.call_stack, &mut program_state.call_stack);
+                }
+                core
+            })
+            .collect();
+
+        let turn_left: u32 = match interleaving {
+            Interleaving::RoundRobin { quantum } => quantum,
+            Interleaving::Random { .. } => 0,
+        };
+        let random_state: u64 = match interleaving {
+            // xorshift gets stuck at zero, so keep the state away from it
+            Interleaving::Random { seed } => seed ^ 0x9E37_79B9_7F4A_7C15,
+            Interleaving::RoundRobin { .. } => 0,
+        };
+
+        let writes: Rc<RefCell<Writes>> = Rc::default();
+        program_state.observers.add(Box::new(Rc::clone(&writes)));
+
+        Ok(Multiprocessor {
+            program_state,
+            cores,
+            writes,
+            interleaving,
+            current: 0,
+            turn_left,
+            random_state,
+        })
+    }
+
+    pub fn cores(&self) -> &[Core] {
+        &self.cores
+    }
+
+    /// Starts a parked core at entry with its number in $a0. Every core gets an equal share of the stack, in order
+    /// from the top, so core 0 keeps the part it starts in.
+    pub fn start_core(&mut self, number: usize, entry: u32) -> Result<(), String> {
+        let count: usize = self.cores.len();
+        if number == 0 || number >= count {
+            return Err(match count {
+                1 => "There's only core 0, which is already running.".to_string(),
+                _ => format!(
+                    "There is no core {number} to start; the others are numbered 1 to {}.",
+                    count - 1
+                ),
+            });
+        }
+        if self.cores[number].running {
+            return Err(format!("Core {number} is already running."));
+        }
+
+        let layout = &self.program_state.memory.layout;
+        let share: u32 = (layout.stack_size / count as u32) & !7;
+
+        let core: &mut Core = &mut self.cores[number];
+        core.cpu = Processor::new(entry);
+        core.cpu.general_purpose_registers[Register::Sp as usize] =
+            layout.stack_top - share * number as u32;
+        core.cpu.general_purpose_registers[Register::A0 as usize] = number as u32;
+        core.cpu.general_purpose_registers[Register::Ra as usize] = HALT_ADDRESS;
+        core.call_stack.clear();
+        core.running = true;
+        Ok(())
+    }
+
+    /// Picks the core that runs next, or None when every core is done.
+    fn next_core(&mut self) -> Option<usize> {
+        let running: Vec<usize> = (0..self.cores.len())
+            .filter(|&number| self.cores[number].running)
+            .collect();
+        if running.is_empty() {
+            return None;
+        }
+
+        match self.interleaving {
+            Interleaving::RoundRobin { quantum } => {
+                if self.turn_left == 0 || !self.cores[self.current].running {
+                    self.current = running
+                        .iter()
+                        .copied()
+                        .find(|&number| number > self.current)
+                        .unwrap_or(running[0]);
+                    self.turn_left = quantum;
+                }
+                self.turn_left -= 1;
+            }
+            Interleaving::Random { .. } => {
+                self.random_state ^= self.random_state << 13;
+                self.random_state ^= self.random_state >> 7;
+                self.random_state ^= self.random_state << 17;
+                self.current = running[(self.random_state % running.len() as u64) as usize];
+            }
+        }
+
+        Some(self.current)
+    }
+
+    /// Trades a core's own state with what's in the shared ProgramState.
+    fn swap_core(&mut self, number: usize) {
+        let core: &mut Core = &mut self.cores[number];
+        swap(&mut core.cpu, &mut self.program_state.cpu);
+        swap(&mut core.cp0, &mut self.program_state.cp0);
+        swap(&mut core.call_stack, &mut self.program_state.call_stack);
+    }
+
+    /// Executes one instruction on whichever core's turn it is. Returns false once there's nothing left to run.
+    pub fn step(
+        &mut self,
+        lineinfo: &Vec<LineInfo>,
+        operating_system: &mut OperatingSystem,
+        debugger_state: &mut DebuggerState,
+    ) -> bool {
+        if !self.program_state.should_continue_execution {
+            return false;
+        }
+        let Some(number) = self.next_core() else {
+            return false;
+        };
+
+        self.swap_core(number);
+        let program_state: &mut ProgramState = &mut self.program_state;
+
+        single_step(lineinfo, program_state);
+        self.cores[number].instructions += 1;
+
+        if program_state.is_exception() {
+            if program_state.cpu.pc == HALT_ADDRESS {
+                // the core returned from its entry
+                program_state.recover_from_exception();
+                program_state.cpu.pc = HALT_ADDRESS;
+                self.cores[number].running = false;
+            } else {
+                handle_exception(program_state, operating_system, lineinfo, debugger_state);
+            }
+        }
+
+        self.swap_core(number);
+        let writes: Vec<MemoryAccess> = take(&mut self.writes.borrow_mut().0);
+        for access in writes {
+            self.break_links(number, access);
+        }
+        true
+    }
+
+    /// Whatever another core stores over a word it has linked with ll makes its next sc fail.
+    fn break_links(&mut self, writer: usize, access: MemoryAccess) {
+        let written = access.address..access.address.wrapping_add(access.size);
+        for (number, core) in self.cores.iter_mut().enumerate() {
+            if number == writer {
+                continue;
+            }
+            if let Some(linked) = core.cpu.linked_address {
+                if linked < written.end && written.start < linked.wrapping_add(4) {
+                    core.cpu.linked_address = None;
+                }
+            }
+        }
+    }
+
+    /// Says how many instructions each core executed, which shows how the interleaving went.
+    pub fn report(&self) -> String {
+        let how: String = match self.interleaving {
+            Interleaving::RoundRobin { quantum: 1 } => {
+                "round-robin, switching every instruction".to_string()
+            }
+            Interleaving::RoundRobin { quantum } => {
+                format!("round-robin, switching every {quantum} instructions")
+            }
+            Interleaving::Random { seed } => format!("random, seed {seed}"),
+        };
+        let mut report: String = format!("Cores: {} ({how})\n", self.cores.len());
+        for (number, core) in self.cores.iter().enumerate() {
+            report += &format!("  core {number}: {} instructions\n", core.instructions);
+        }
+        report
+    }
+
+    /// Runs until the program exits or every core has returned.
+    pub fn run(
+        &mut self,
+        lineinfo: &Vec<LineInfo>,
+        operating_system: &mut OperatingSystem,
+        debugger_state: &mut DebuggerState,
+    ) {
+        while self.step(lineinfo, operating_system, debugger_state) {}
+    }
+}
+
+/// Runs a program on several cores. Secondary cores all start at entry (any debugger expression, like a label), or at
+/// the program's own entry if there isn't one. Gives back the machine so the caller can look at how it ended up.
+pub fn simulate_smp(
+    elf: Elf,
+    options: SimulatorOptions,
+    cores: usize,
+    interleaving: Interleaving,
+    entry: Option<&str>,
+) -> Result<Multiprocessor, String> {
+    let (lineinfo, program_state) = load_program_with_layout(&elf, options.memory_layout)?;
+    let mut operating_system: OperatingSystem =
+        new_operating_system(options.program_input, options.threads);
+
+    let mut debugger_state: DebuggerState = DebuggerState::new();
+    debugger_state.symbols = extract_symbols(&elf);
+
+    let entry: u32 = match entry {
+        Some(entry) => {
+            Expression::parse(entry, &debugger_state.symbols)?.evaluate(&program_state)?
+        }
+        None => elf.file_header.e_entry,
+    };
+
+    let mut machine: Multiprocessor = Multiprocessor::new(program_state, cores, interleaving)?;
+    for number in 1..cores {
+        machine.start_core(number, entry)?;
+    }
+    machine.run(&lineinfo, &mut operating_system, &mut debugger_state);
+
+    Ok(machine)
+}
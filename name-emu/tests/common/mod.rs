@@ -0,0 +1,93 @@
+//! What the integration tests share: the sample programs, built in memory the way name-as and name-ld would build
+//! them, so the tests don't depend on the other crates' tests having left executables behind, and programs the tests
+//! write themselves. name-test's tests use it too.
+#![allow(dead_code)] // each test uses only some of these
+
+use std::fs::{create_dir_all, remove_dir_all, write};
+use std::path::{Path, PathBuf};
+
+use name_core::elf_def::Elf;
+use name_core::elf_utils::write_elf_to_file;
+use name_ld::build::build;
+
+/// The directory the sample programs' sources are in.
+pub fn samples() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .parent()
+        .expect("[*] FATAL: No parent directory found (did you clone the entire repo?)")
+        .join("tests")
+        .join("samples")
+}
+
+/// Assembles and links a sample program, like fib for samples/fib.asm.
+pub fn sample(name: &str) -> Elf {
+    build(&samples().join(format!("{name}.asm")))
+        .unwrap_or_else(|e| panic!("[*] FATAL: {name}.asm didn't build:\n{e}"))
+}
+
+pub fn fib() -> Elf {
+    sample("fib")
+}
+
+/// Builds a sample program into an executable for tests that run it by path. The file is named after the test, so
+/// tests running at the same time never share one.
+pub fn sample_file(name: &str, test: &str) -> PathBuf {
+    let path: PathBuf = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join(test);
+    write_elf_to_file(&path, &sample(name))
+        .unwrap_or_else(|e| panic!("[*] FATAL: Couldn't write {name} for {test}: {e}"));
+    path
+}
+
+/// A directory for one test's files. It's removed when it's dropped, so a failing assertion doesn't leave it behind.
+pub struct TempDir(PathBuf);
+
+impl TempDir {
+    /// The directory is named after the test, so tests running at the same time never share one.
+    pub fn new(test: &str) -> Self {
+        let directory: PathBuf = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join(test);
+        create_dir_all(&directory)
+            .unwrap_or_else(|e| panic!("[*] FATAL: Couldn't make a directory for {test}: {e}"));
+        TempDir(directory)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.0
+    }
+
+    /// Writes a file into the directory, giving back where it went.
+    pub fn write(&self, name: &str, contents: &str) -> PathBuf {
+        let path: PathBuf = self.0.join(name);
+        write(&path, contents)
+            .unwrap_or_else(|e| panic!("[*] FATAL: Couldn't write {}: {e}", path.display()));
+        path
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = remove_dir_all(&self.0);
+    }
+}
+
+/// A program built from assembly source by a test. Its executable is in a directory of its own, which goes away
+/// with it.
+pub struct BuiltSample {
+    pub elf: Elf,
+    pub path: PathBuf,
+    _directory: TempDir,
+}
+
+/// Assembles, links, and writes out a program for a test, which can then run it by path or load its ELF.
+pub fn build_sample(asm: &str, name: &str) -> BuiltSample {
+    let directory: TempDir = TempDir::new(name);
+    let elf: Elf = build(&directory.write(&format!("{name}.asm"), asm))
+        .unwrap_or_else(|e| panic!("[*] FATAL: {name} didn't build:\n{e}"));
+    let path: PathBuf = directory.path().join(name);
+    write_elf_to_file(&path, &elf)
+        .unwrap_or_else(|e| panic!("[*] FATAL: Couldn't write {name}: {e}"));
+    BuiltSample {
+        elf,
+        path,
+        _directory: directory,
+    }
+}
@@ -0,0 +1,48 @@
+mod common;
+
+use std::process::{Command, Output};
+
+#[test]
+fn exit_code_test() {
+    let program: common::BuiltSample = common::build_sample(
+        "    .text\nmain:\n    li $a0, 7\n    li $v0, 1\n    syscall\n    li $a0, 3\n    li $v0, 17\n    syscall\n",
+        "exit-code",
+    );
+
+    let output: Output = Command::new(env!("CARGO_BIN_EXE_name-emu"))
+        .arg(&program.path)
+        .output()
+        .unwrap();
+
+    // the program's exit code is the emulator's, as it would be for a native program
+    assert_eq!(output.status.code(), Some(3));
+    assert_eq!(output.stdout, b"7");
+}
+
+#[test]
+fn exit_code_failure_test() {
+    let program: common::BuiltSample = common::build_sample(
+        "    .text\nmain:\n    li $v0, 10\n    syscall\n",
+        "exit-code-failure",
+    );
+    let run = |arguments: &[&str]| -> Output {
+        Command::new(env!("CARGO_BIN_EXE_name-emu"))
+            .args(arguments)
+            .arg(&program.path)
+            .output()
+            .unwrap()
+    };
+
+    // a layout that can't be read, or that the program wasn't linked for, fails like any other command that went
+    // wrong, whether the program would have run plainly or with a model watching
+    for arguments in [
+        &["--layout", "no-such-layout.toml"][..],
+        &["--layout", "mars-compact-text-at-0"],
+        &["--layout", "mars-compact-text-at-0", "--profile"],
+    ] {
+        let output: Output = run(arguments);
+        assert_eq!(output.status.code(), Some(1), "{arguments:?}");
+        assert!(!output.stderr.is_empty());
+    }
+    assert_eq!(run(&[]).status.code(), Some(0));
+}
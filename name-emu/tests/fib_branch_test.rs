@@ -0,0 +1,90 @@
+mod common;
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use name_emu::branch::{parse_predictor, BranchPredictor, BranchSite, PredictorModel};
+use name_emu::profile::Profile;
+use name_emu::simulator::{simulate_with_options, SimulatorOptions};
+
+/// Counts how many of the outcomes the predictor gets right at one address.
+fn correct(predictor: &mut dyn BranchPredictor, outcomes: &[bool]) -> usize {
+    outcomes
+        .iter()
+        .filter(|&&taken| {
+            let predicted: bool = predictor.predict(0x00400010);
+            predictor.update(0x00400010, taken);
+            predicted == taken
+        })
+        .count()
+}
+
+#[test]
+fn branch_predictor_test() {
+    // a loop branch that's taken three times, then falls through, run three times over
+    let loops: Vec<bool> = [true, true, true, false].repeat(3);
+
+    assert_eq!(
+        correct(&mut *parse_predictor("not-taken").unwrap(), &loops),
+        3
+    );
+    // 1-bit misses each exit and the first iteration of each run
+    assert_eq!(correct(&mut *parse_predictor("1bit").unwrap(), &loops), 6);
+    // 2-bit only misses each exit, once it has warmed up
+    assert_eq!(correct(&mut *parse_predictor("2bit").unwrap(), &loops), 8);
+
+    // history lets gshare learn a pattern a per-branch counter can't
+    let alternating: Vec<bool> = [true, false].repeat(50);
+    let two_bit: usize = correct(&mut *parse_predictor("2bit").unwrap(), &alternating);
+    let gshare: usize = correct(&mut *parse_predictor("gshare:2").unwrap(), &alternating);
+    assert!(
+        gshare > 90 && two_bit < 60,
+        "gshare {gshare}, 2-bit {two_bit}"
+    );
+
+    assert!(parse_predictor("gshare:0").is_err());
+    assert!(parse_predictor("3bit").is_err());
+}
+
+#[test]
+fn fib_branch_test() {
+    let elf = common::fib();
+
+    let not_taken = Rc::new(RefCell::new(PredictorModel::new(
+        &elf,
+        parse_predictor("not-taken").unwrap(),
+    )));
+    let two_bit = Rc::new(RefCell::new(PredictorModel::new(
+        &elf,
+        parse_predictor("2bit").unwrap(),
+    )));
+    let profile: Rc<RefCell<Profile>> = Rc::new(RefCell::new(Profile::new(&elf)));
+    let mut options: SimulatorOptions = SimulatorOptions::default();
+    options.observers.add(Box::new(not_taken.clone()));
+    options.observers.add(Box::new(two_bit.clone()));
+    options.observers.add(Box::new(profile.clone()));
+    simulate_with_options(elf, options).expect("fib failed");
+    let (not_taken, two_bit, profile) = (not_taken.borrow(), two_bit.borrow(), profile.borrow());
+
+    // predicting doesn't change what runs
+    assert_eq!(profile.total(), 212);
+    assert_eq!(
+        two_bit.overall().executed,
+        profile.mix.branch_taken + profile.mix.branch_not_taken
+    );
+
+    assert_eq!(
+        not_taken.overall(),
+        BranchSite {
+            executed: 22,
+            taken: 20,
+            correct: 2,
+        }
+    );
+    assert_eq!(two_bit.overall().correct, 18);
+
+    // compute's loop branch, on fib.asm line 27
+    assert_eq!(two_bit.sites[&0x0040003c].executed, 10);
+    let report: String = two_bit.report();
+    assert!(report.contains("fib.asm:27"), "{report}");
+}
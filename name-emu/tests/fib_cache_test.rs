@@ -0,0 +1,129 @@
+mod common;
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use name_emu::cache::{Cache, CacheConfig, CacheHierarchy, Replacement, WritePolicy};
+use name_emu::simulator::{simulate_with_options, SimulatorOptions};
+
+fn cache(spec: &str) -> Cache {
+    Cache::new("test", CacheConfig::parse(spec).expect("Bad cache spec"))
+}
+
+#[test]
+fn cache_config_test() {
+    let config: CacheConfig =
+        CacheConfig::parse("size=256,block=8,ways=2,replace=fifo,write=through").unwrap();
+    assert_eq!(config.sets(), 16);
+    assert_eq!(config.replacement, Replacement::Fifo);
+    assert_eq!(config.write_policy, WritePolicy::WriteThrough);
+    assert_eq!(
+        config.describe(),
+        "256 bytes, 8-byte blocks, 2-way set associative, FIFO, write-through"
+    );
+
+    assert_eq!(CacheConfig::parse("size=64,ways=full").unwrap().sets(), 1);
+    assert!(CacheConfig::parse("block=12").is_err());
+    assert!(CacheConfig::parse("size=48").is_err());
+    assert!(CacheConfig::parse("size=1024,block=65536,ways=65536").is_err());
+    assert!(CacheConfig::parse("replace=mru").is_err());
+}
+
+#[test]
+fn cache_replacement_test() {
+    // two blocks that map to the same set of a direct-mapped cache keep evicting each other
+    let mut direct: Cache = cache("size=64,block=16");
+    for _ in 0..3 {
+        assert!(!direct.access(0x10010000, false));
+        assert!(!direct.access(0x10010040, false));
+    }
+    assert_eq!(direct.stats.evictions, 5);
+
+    // with two ways they both fit; a third block evicts whichever policy says
+    let mut lru: Cache = cache("size=32,block=16,ways=2,replace=lru");
+    let mut fifo: Cache = cache("size=32,block=16,ways=2,replace=fifo");
+    for cache in [&mut lru, &mut fifo] {
+        cache.access(0x0, false);
+        cache.access(0x10, false);
+        assert!(cache.access(0x0, false));
+        cache.access(0x20, false);
+    }
+    // LRU threw out 0x10, FIFO threw out 0x0
+    assert!(lru.access(0x0, false));
+    assert!(!fifo.access(0x0, false));
+
+    // the same seed picks the same victims
+    let pattern = |cache: &mut Cache| {
+        (0..64u32)
+            .map(|i| cache.access((i * 7 % 12) * 16, false))
+            .collect::<Vec<bool>>()
+    };
+    assert_eq!(
+        pattern(&mut cache(
+            "size=64,block=16,ways=full,replace=random,seed=3"
+        )),
+        pattern(&mut cache(
+            "size=64,block=16,ways=full,replace=random,seed=3"
+        ))
+    );
+}
+
+#[test]
+fn cache_write_policy_test() {
+    let mut back: Cache = cache("size=16,block=16,write=back");
+    assert!(!back.access(0x0, true));
+    assert!(back.access(0x4, true));
+    assert_eq!(back.stats.memory_writes, 0);
+    // evicting the dirty block writes it back
+    back.access(0x10, false);
+    assert_eq!(back.stats.memory_writes, 1);
+
+    let mut through: Cache = cache("size=16,block=16,write=through");
+    // no allocation on a write miss
+    assert!(!through.access(0x0, true));
+    assert!(!through.access(0x0, false));
+    assert!(through.access(0x0, true));
+    assert_eq!(through.stats.memory_writes, 2);
+}
+
+#[test]
+fn fib_cache_test() {
+    let elf = common::fib();
+
+    let config: CacheConfig = CacheConfig::parse("size=64,block=16").unwrap();
+    let split = Rc::new(RefCell::new(CacheHierarchy::split(
+        &elf,
+        Some(config),
+        Some(config),
+    )));
+    let unified = Rc::new(RefCell::new(CacheHierarchy::unified(&elf, config)));
+    let mut options: SimulatorOptions = SimulatorOptions::default();
+    options.observers.add(Box::new(split.clone()));
+    options.observers.add(Box::new(unified.clone()));
+    simulate_with_options(elf, options).expect("fib failed");
+    let (split, unified) = (split.borrow(), unified.borrow());
+
+    let instruction: &Cache = &split.caches[0];
+    let data: &Cache = &split.caches[1];
+    assert_eq!(instruction.stats.accesses(), 212);
+    assert_eq!(data.stats.reads, 33);
+    assert_eq!(data.stats.writes, 12);
+
+    // one cache sees everything, and has the two streams fighting over it
+    assert_eq!(unified.caches[0].stats.accesses(), 212 + 45);
+    assert!(unified.caches[0].stats.misses() > instruction.stats.misses() + data.stats.misses());
+
+    // every miss is blamed on some line
+    let blamed: u64 = split
+        .by_line(1)
+        .iter()
+        .map(|(_, counts)| counts.misses)
+        .sum();
+    assert_eq!(blamed, data.stats.misses());
+
+    let report: String = split.report();
+    assert!(
+        report.starts_with("I-cache (64 bytes, 16-byte blocks, direct-mapped, LRU, write-back):\n")
+    );
+    assert!(report.contains("\nD-cache ("), "{report}");
+}
@@ -0,0 +1,109 @@
+mod common;
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use name_core::debug::debug_utils::information_of;
+use name_core::elf_def::Elf;
+use name_core::instruction::instruction::RawInstruction;
+use name_core::observer::{ExecutedInstruction, Observer};
+use name_core::structs::{CallFrame, ProgramState};
+
+use name_emu::convention::{ConventionChecker, ViolationKind};
+use name_emu::simulator::{simulate_with_options, SimulatorOptions};
+
+fn step(checker: &mut ConventionChecker, state: &ProgramState, address: u32, word: u32) {
+    let instruction: RawInstruction = RawInstruction::new(word);
+    checker.after_execute(
+        &ExecutedInstruction {
+            address,
+            instruction,
+            next_pc: state.cpu.pc,
+            memory_access: None,
+        },
+        information_of(instruction).expect("not an instruction"),
+        state,
+    );
+}
+
+#[test]
+fn convention_violation_test() {
+    let mut checker: ConventionChecker = ConventionChecker::new(&common::fib());
+    let mut state: ProgramState = ProgramState::default();
+
+    // call print from line 30 twice; each time it trashes $s0, $sp, and $t0
+    for _ in 0..2 {
+        state.cpu.general_purpose_registers[8] = 5;
+        state.cpu.general_purpose_registers[16] = 1;
+        state.cpu.general_purpose_registers[29] = 0x7ffffe00;
+
+        // jal print
+        state.cpu.pc = 0x00400058;
+        state.call_stack.push(CallFrame {
+            call_site: 0x0040004c,
+            function_address: 0x00400058,
+            return_address: 0x00400050,
+        });
+        step(&mut checker, &state, 0x0040004c, 0x0C10_0016);
+
+        state.cpu.general_purpose_registers[8] = 7;
+        state.cpu.general_purpose_registers[16] = 42;
+        state.cpu.general_purpose_registers[29] -= 8;
+
+        // jr $ra
+        state.cpu.pc = 0x00400050;
+        state.call_stack.pop();
+        step(&mut checker, &state, 0x00400098, 0x03E0_0008);
+
+        // add $a0, $t0, $zero
+        state.cpu.pc = 0x00400054;
+        step(&mut checker, &state, 0x00400050, 0x0100_2020);
+    }
+
+    // add $k0, $a0, $zero
+    step(&mut checker, &state, 0x00400054, 0x0080_D020);
+
+    let kinds: Vec<ViolationKind> = checker.violations.iter().map(|v| v.kind).collect();
+    assert_eq!(
+        kinds,
+        vec![
+            ViolationKind::NotRestored,
+            ViolationKind::StackImbalance,
+            ViolationKind::ClobberedRead,
+            ViolationKind::KernelRegister,
+        ]
+    );
+    assert!(checker.violations[..3].iter().all(|v| v.count == 2));
+
+    // returns are blamed on the jr, with the call that got there
+    let not_restored = &checker.violations[0];
+    assert!(
+        not_restored.message.contains("$s0"),
+        "{}",
+        not_restored.message
+    );
+    assert_eq!(not_restored.backtrace.len(), 2);
+    assert!(not_restored.backtrace[0].contains("in print (line 54"));
+    assert!(not_restored.backtrace[1].contains("line 30"));
+
+    assert!(checker.violations[1].message.contains("(-8 bytes)"));
+    assert!(checker.violations[2]
+        .message
+        .starts_with("$t0 is read here, but print changed it"));
+
+    let report: String = checker.report();
+    assert!(report.starts_with("Calling convention check: 4 problems found.\n"));
+    assert!(report.contains("(happened 2 times)"));
+}
+
+#[test]
+fn fib_convention_test() {
+    let elf: Elf = common::fib();
+    let checker = Rc::new(RefCell::new(ConventionChecker::new(&elf)));
+    let mut options: SimulatorOptions = SimulatorOptions::default();
+    options.observers.add(Box::new(checker.clone()));
+    simulate_with_options(elf, options).expect("fib failed");
+    let checker = checker.borrow();
+
+    assert!(checker.violations.is_empty(), "{}", checker.report());
+}
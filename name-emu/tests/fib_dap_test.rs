@@ -0,0 +1,303 @@
+mod common;
+
+use std::io::Cursor;
+use std::path::PathBuf;
+
+use serde_json::{json, Value};
+
+use name_core::layout::MemoryLayout;
+use name_core::threads::ThreadOptions;
+
+use name_emu::dap;
+
+/// Frames requests the way a client would send them.
+fn requests(messages: &[Value]) -> Vec<u8> {
+    let mut bytes: Vec<u8> = Vec::new();
+    for (seq, message) in messages.iter().enumerate() {
+        let mut message: Value = message.clone();
+        message["seq"] = json!(seq + 1);
+        message["type"] = json!("request");
+        let body: String = message.to_string();
+        bytes.extend(format!("Content-Length: {}\r\n\r\n{}", body.len(), body).into_bytes());
+    }
+    bytes
+}
+
+/// Splits the server's output back into messages.
+fn responses(mut bytes: &[u8]) -> Vec<Value> {
+    let mut messages: Vec<Value> = Vec::new();
+    while !bytes.is_empty() {
+        let text: String = String::from_utf8_lossy(bytes).to_string();
+        let header_end: usize = text.find("\r\n\r\n").expect("Missing header");
+        let length: usize = text["Content-Length: ".len()..header_end]
+            .parse()
+            .expect("Bad Content-Length");
+        let body: &[u8] = &bytes[header_end + 4..header_end + 4 + length];
+        messages.push(serde_json::from_slice(body).expect("Malformed message"));
+        bytes = &bytes[header_end + 4 + length..];
+    }
+    messages
+}
+
+#[test]
+fn fib_dap_test() {
+    let program: String = common::sample_file("fib", "fib_dap_test")
+        .to_string_lossy()
+        .to_string();
+    let source: String = common::samples()
+        .join("fib.asm")
+        .to_string_lossy()
+        .to_string();
+
+    let input: Vec<u8> = requests(&[
+        json!({ "command": "initialize", "arguments": { "adapterID": "vsname" } }),
+        json!({ "command": "launch", "arguments": { "program": program } }),
+        // line 39 is .text, so the breakpoint slides down to print on line 40
+        json!({ "command": "setBreakpoints", "arguments": {
+            "source": { "path": source },
+            "breakpoints": [{ "line": 39 }, { "line": 1000 }],
+        } }),
+        json!({ "command": "configurationDone" }),
+        json!({ "command": "stackTrace", "arguments": { "threadId": 1 } }),
+        json!({ "command": "variables", "arguments": { "variablesReference": 1 } }),
+        json!({ "command": "variables", "arguments": { "variablesReference": 2 } }),
+        json!({ "command": "next", "arguments": { "threadId": 1 } }),
+        json!({ "command": "stepOut", "arguments": { "threadId": 1 } }),
+        json!({ "command": "continue", "arguments": { "threadId": 1 } }),
+        json!({ "command": "disconnect" }),
+    ]);
+    let mut output: Vec<u8> = Vec::new();
+
+    dap::serve(
+        Cursor::new(input),
+        &mut output,
+        MemoryLayout::default(),
+        ThreadOptions::default(),
+    )
+    .expect("DAP session failed");
+    let messages: Vec<Value> = responses(&output);
+
+    // every request got a successful response
+    let failed: Vec<&Value> = messages
+        .iter()
+        .filter(|m| m["type"] == "response" && m["success"] != true)
+        .collect();
+    assert!(failed.is_empty(), "{:?}", failed);
+
+    let response = |command: &str| -> &Value {
+        messages
+            .iter()
+            .find(|m| m["type"] == "response" && m["command"] == command)
+            .expect("Missing response")
+    };
+    let events: Vec<&Value> = messages.iter().filter(|m| m["type"] == "event").collect();
+
+    // the client only sends breakpoints after initialized, which has to wait for the program
+    let launched: Option<usize> = messages
+        .iter()
+        .position(|m| m["type"] == "response" && m["command"] == "launch");
+    let initialized: Option<usize> = messages.iter().position(|m| m["event"] == "initialized");
+    assert!(launched.is_some() && launched < initialized);
+
+    let breakpoints: &Value = &response("setBreakpoints")["body"]["breakpoints"];
+    assert_eq!(breakpoints[0]["verified"], true);
+    assert_eq!(breakpoints[0]["line"], 40);
+    assert_eq!(breakpoints[1]["verified"], false);
+
+    // stopped on the breakpoint inside print, which was called from line 30
+    let frames: &Value = &response("stackTrace")["body"]["stackFrames"];
+    assert_eq!(frames[0]["name"], "print");
+    assert_eq!(frames[0]["line"], 40);
+    assert_eq!(frames[0]["source"]["path"], json!(source));
+    assert_eq!(frames[1]["line"], 30);
+
+    let registers: &Value = &messages
+        .iter()
+        .find(|m| m["command"] == "variables")
+        .expect("Missing registers")["body"]["variables"];
+    assert_eq!(registers[13]["name"], "$t5");
+    assert_eq!(registers[13]["value"], "0x0000000c (12)");
+    assert_eq!(registers[32]["name"], "$pc");
+    assert_eq!(registers[32]["value"], "0x00400058 (4194392)");
+
+    let memory: &Value = &messages
+        .iter()
+        .filter(|m| m["command"] == "variables")
+        .nth(1)
+        .expect("Missing memory")["body"]["variables"];
+    assert!(memory
+        .as_array()
+        .unwrap()
+        .iter()
+        .any(|v| v["name"] == "size" && v["value"] == "0x0000000c (12)"));
+    assert!(memory
+        .as_array()
+        .unwrap()
+        .iter()
+        .any(|v| v["name"] == "space" && v["value"] == "\" \""));
+
+    let reasons: Vec<&Value> = events
+        .iter()
+        .filter(|e| e["event"] == "stopped")
+        .map(|e| &e["body"]["reason"])
+        .collect();
+    assert_eq!(reasons, vec!["breakpoint", "step", "step"]);
+
+    // print writes everything before stepOut brings us back
+    let output_text: String = events
+        .iter()
+        .filter(|e| e["event"] == "output")
+        .map(|e| e["body"]["output"].as_str().unwrap_or(""))
+        .collect();
+    assert_eq!(
+        output_text,
+        "The Fibonacci numbers are:\n1 1 2 3 5 8 13 21 34 55 89 144 "
+    );
+
+    assert!(events.iter().any(|e| e["event"] == "exited"));
+    assert_eq!(events.last().unwrap()["event"], "terminated");
+}
+
+#[test]
+fn fib_dap_source_test() {
+    let source: String = common::samples()
+        .join("fib.asm")
+        .to_string_lossy()
+        .to_string();
+
+    // the editor launches the file it has open, which gets assembled and linked on the spot
+    let input: Vec<u8> = requests(&[
+        json!({ "command": "initialize", "arguments": { "adapterID": "vsname" } }),
+        json!({ "command": "launch", "arguments": { "program": source } }),
+        json!({ "command": "setBreakpoints", "arguments": {
+            "source": { "path": source },
+            "breakpoints": [{ "line": 40 }],
+        } }),
+        json!({ "command": "configurationDone" }),
+        json!({ "command": "continue", "arguments": { "threadId": 1 } }),
+        json!({ "command": "disconnect" }),
+    ]);
+    let mut output: Vec<u8> = Vec::new();
+    dap::serve(
+        Cursor::new(input),
+        &mut output,
+        MemoryLayout::default(),
+        ThreadOptions::default(),
+    )
+    .expect("DAP session failed");
+    let messages: Vec<Value> = responses(&output);
+
+    let failed: Vec<&Value> = messages
+        .iter()
+        .filter(|m| m["type"] == "response" && m["success"] != true)
+        .collect();
+    assert!(failed.is_empty(), "{:?}", failed);
+
+    let reasons: Vec<&Value> = messages
+        .iter()
+        .filter(|m| m["event"] == "stopped")
+        .map(|m| &m["body"]["reason"])
+        .collect();
+    assert_eq!(reasons, vec!["breakpoint"]);
+    assert!(messages.iter().any(|m| m["event"] == "exited"));
+}
+
+#[test]
+fn fib_dap_layout_test() {
+    let program: PathBuf = common::sample_file("fib", "fib_dap_layout_test");
+
+    let input: Vec<u8> = requests(&[
+        json!({ "command": "initialize", "arguments": { "adapterID": "vsname" } }),
+        json!({ "command": "launch", "arguments": { "program": program } }),
+        json!({ "command": "disconnect" }),
+    ]);
+    let mut output: Vec<u8> = Vec::new();
+
+    // fib was linked for the default layout
+    dap::serve(
+        Cursor::new(input),
+        &mut output,
+        MemoryLayout::preset("mars-compact-text-at-0").unwrap(),
+        ThreadOptions::default(),
+    )
+    .expect("DAP session failed");
+    let messages: Vec<Value> = responses(&output);
+
+    let launch: &Value = messages
+        .iter()
+        .find(|m| m["type"] == "response" && m["command"] == "launch")
+        .expect("Missing response");
+    assert_eq!(launch["success"], false);
+    assert!(launch["message"]
+        .as_str()
+        .unwrap()
+        .contains("Give every tool the same --layout."));
+}
+
+#[test]
+fn dap_exit_code_test() {
+    let program: common::BuiltSample = common::build_sample(
+        "    .text\nmain:\n    li $a0, 3\n    li $v0, 17\n    syscall\n",
+        "dap-exit-code",
+    );
+
+    let input: Vec<u8> = requests(&[
+        json!({ "command": "initialize", "arguments": { "adapterID": "vsname" } }),
+        json!({ "command": "launch", "arguments": { "program": program.path } }),
+        json!({ "command": "configurationDone" }),
+        json!({ "command": "disconnect" }),
+    ]);
+    let mut output: Vec<u8> = Vec::new();
+    dap::serve(
+        Cursor::new(input),
+        &mut output,
+        MemoryLayout::default(),
+        ThreadOptions::default(),
+    )
+    .expect("DAP session failed");
+
+    let messages: Vec<Value> = responses(&output);
+    let exited: &Value = messages
+        .iter()
+        .find(|m| m["type"] == "event" && m["event"] == "exited")
+        .expect("Missing exited event");
+    assert_eq!(exited["body"]["exitCode"], 3);
+}
+
+#[test]
+fn dap_crash_test() {
+    let program: common::BuiltSample = common::build_sample(
+        "    .text\nmain:\n    lw $t0, 1($zero)\n    li $v0, 10\n    syscall\n",
+        "dap-crash",
+    );
+
+    let input: Vec<u8> = requests(&[
+        json!({ "command": "initialize", "arguments": { "adapterID": "vsname" } }),
+        json!({ "command": "launch", "arguments": { "program": program.path } }),
+        json!({ "command": "configurationDone" }),
+        json!({ "command": "continue", "arguments": { "threadId": 1 } }),
+        json!({ "command": "disconnect" }),
+    ]);
+    let mut output: Vec<u8> = Vec::new();
+    dap::serve(
+        Cursor::new(input),
+        &mut output,
+        MemoryLayout::default(),
+        ThreadOptions::default(),
+    )
+    .expect("DAP session failed");
+
+    // the crash is reported as a stop rather than taking the adapter down with it
+    let messages: Vec<Value> = responses(&output);
+    let stopped: &Value = messages
+        .iter()
+        .find(|m| m["type"] == "event" && m["event"] == "stopped")
+        .expect("Missing stopped event");
+    assert_eq!(stopped["body"]["reason"], "exception");
+    let text: &str = stopped["body"]["text"].as_str().unwrap();
+    assert!(text.contains("Illegal address"), "{text}");
+
+    assert!(messages
+        .iter()
+        .any(|m| m["type"] == "event" && m["event"] == "terminated"));
+}
@@ -1,44 +1,164 @@
-use std::fs::read;
+mod common;
+
+use std::fs::write;
 use std::path::PathBuf;
+use std::process::Command;
+
+#[test]
+fn fib_db_test() {
+    let input_fn: PathBuf = common::sample_file("fib", "fib_db_test");
+
+    // stop on the way into print, look around, then let it finish
+    let script_fn: PathBuf = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("fib_db_test.ndb");
+    write(
+        &script_fn,
+        "# fib_db_test\nb print\nr\nx/12dw fibs\nx/xw print\nbt\np $t5\np/d $t5\np/d fibs[11] + *(word*)($a0+4)\ninfo cp0\nc\n",
+    )
+    .expect("[*] FATAL: Couldn't write the debugger script");
+
+    let output = Command::new(env!("CARGO_BIN_EXE_name-emu"))
+        .arg(&input_fn)
+        .arg("-x")
+        .arg(&script_fn)
+        .arg("--batch")
+        .output()
+        .expect("[*] FATAL: Couldn't run name-emu");
+
+    let stdout: String = String::from_utf8_lossy(&output.stdout).to_string();
+    let stderr: String = String::from_utf8_lossy(&output.stderr).to_string();
+
+    assert!(output.status.success(), "{stderr}");
+    assert!(stdout.contains("Successfully added breakpoint 0"));
+    assert!(stderr.contains("Breakpoint 0 at line 40 reached. (hit 1 time)"));
+
+    // the array is filled in by the time print is called
+    assert!(stdout.contains("0x10010000 <fibs>:  1  1  2  3"));
+    assert!(stdout.contains("0x10010020 <fibs+32>:  34  55  89  144"));
+
+    // breakpoints don't touch the program's memory
+    assert!(stdout.contains("0x00400058 <print>:  0x00044020"));
+
+    // print was called from line 30
+    assert!(stdout.contains("#0  0x00400058 in print (line 40"));
+    assert!(stdout.contains("#1  0x0040004c in"));
+
+    assert!(stdout.contains("Value in register 13 is 0000000c"));
+    assert!(stdout.contains("Value in register 13 is 12\n"));
+    assert!(stdout.contains("fibs[11] + *(word*)($a0+4) = 145\n"));
+    assert!(stdout.contains("Cause (13): 0x00000000\n    ExcCode: 0 (none)"));
+
+    // batch mode never shows the prompt
+    assert!(!stdout.contains("(name-db)"));
+    assert!(stdout.ends_with("The Fibonacci numbers are:\n1 1 2 3 5 8 13 21 34 55 89 144 "));
+}
 
-// use assert_cmd::Command;
+/// Runs fib under a debugger script, giving back its stdout and stderr.
+fn debug_fib(name: &str, script: &str) -> (String, String) {
+    let input_fn: PathBuf = common::sample_file("fib", name);
+    let script_fn: PathBuf = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join(format!("{name}.ndb"));
+    write(&script_fn, script).expect("[*] FATAL: Couldn't write the debugger script");
 
-use name_core::elf_def::Elf;
-use name_core::elf_utils::read_bytes_to_elf;
+    let output = Command::new(env!("CARGO_BIN_EXE_name-emu"))
+        .arg(&input_fn)
+        .arg("-x")
+        .arg(&script_fn)
+        .arg("--batch")
+        .output()
+        .expect("[*] FATAL: Couldn't run name-emu");
+    (
+        String::from_utf8_lossy(&output.stdout).to_string(),
+        String::from_utf8_lossy(&output.stderr).to_string(),
+    )
+}
 
-// use name_emu::simulator;
+#[test]
+fn fib_db_stepping_test() {
+    // n runs the whole call to print, which returns to line 31
+    let (stdout, _) = debug_fib("fib_db_next_test", "b 30\nr\nn\nc\n");
+    assert!(stdout.ends_with(
+        "1 1 2 3 5 8 13 21 34 55 89 144 Stopped at 0x00400050 (line 31: li   \t$v0, SysExit     # system call for exit)\n"
+    ), "{stdout}");
+
+    // a breakpoint inside the call stops n, and finish takes it the rest of the way
+    let (stdout, stderr) = debug_fib(
+        "fib_db_finish_test",
+        "finish\nb 30\nb print\nr\nn\nfinish\n",
+    );
+    assert!(
+        stderr.contains("finish isn't meaningful in the outermost frame."),
+        "{stderr}"
+    );
+    assert!(
+        stderr.contains("Breakpoint 1 at line 40 reached. (hit 1 time)"),
+        "{stderr}"
+    );
+    assert!(
+        stdout.contains("Stopped at 0x00400050 (line 31"),
+        "{stdout}"
+    );
+    assert!(
+        stdout.ends_with("Returned with $v0 = 4 (0x00000004), $v1 = 0 (0x00000000)\n"),
+        "{stdout}"
+    );
+}
 
 #[test]
-fn fib_db_test() {
-    // please note that this actually isn't a valid test rn
-    // i'll fix it later maybe hopefully question marka
-    let base_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
-        .parent()
-        .expect("[*] FATAL: No parent directory found (did you clone the entire repo?)")
-        .join("tests")
-        .join("samples");
-
-    let input_fn: PathBuf = base_path.join("fib");
-
-    let elf_contents: Vec<u8> =
-        read(input_fn).expect("[*] FATAL: NAME cannot run files that don't exist...");
-
-    let _executable: Elf = match read_bytes_to_elf(elf_contents) {
-        Ok(elf) => elf,
-        Err(e) => panic!("{}", e),
-    };
-
-    assert_eq!(1 + 1, 2);
-    // todo compliance check executable (format, existence, etc.)
-    // removing this test right now because it causes test suite to hang
-    // let simulator_result = simulator::simulate(executable, true);
-
-    // // i'm going to be so honest you need to feed the commands in yourself for now
-    //
-    // match simulator_result {
-    //     Ok(_) => {}
-    //     Err(e) => panic!("{e}"),
-    // }
-    //
-    // // NOTE: most important test case ever.
+fn fib_db_watchpoint_test() {
+    let (stdout, stderr) = debug_fib(
+        "fib_db_watchpoint_test",
+        "watch size r\nwatch fibs\ndelw 0\npw\nc\nn\ndelw 1\nc\n",
+    );
+
+    // deleting watchpoint 0 leaves watchpoint 1 with its number
+    assert!(
+        stdout.contains("     1: 0x10010000    48  w    (fibs)"),
+        "{stdout}"
+    );
+    assert!(
+        stdout.contains("Watchpoint 1 (w) triggered: 0x10010000 written by sw"),
+        "{stdout}"
+    );
+    assert!(
+        stdout.contains("Watchpoint 1 (w) triggered: 0x10010004 written by sw"),
+        "{stdout}"
+    );
+    assert!(
+        stdout.contains("Successfully removed watchpoint 1."),
+        "{stdout}"
+    );
+
+    // the report is all there is to say about stopping, however execution got there
+    assert!(!stderr.contains("Watchpoint reached."), "{stderr}");
+}
+
+#[test]
+fn fib_db_breakpoint_numbers_test() {
+    let (stdout, stderr) = debug_fib(
+        "fib_db_breakpoint_numbers_test",
+        "b 30\nb print\ndel 0\ndisable 1\nb 31\nignore 1 0\ncondition 1 $a0 != 0\npb\nr\n",
+    );
+
+    // deleting breakpoint 0 leaves breakpoint 1 with its number, and 0 isn't handed out again
+    assert!(stdout.contains("Breakpoint 1 disabled."), "{stdout}");
+    assert!(
+        stdout.contains("Successfully added breakpoint 2 at 0x00400050 (line 31)."),
+        "{stdout}"
+    );
+    assert!(
+        stdout.contains("Will ignore next 0 hits of breakpoint 1."),
+        "{stdout}"
+    );
+    assert!(
+        stdout.contains("Breakpoint 1 will stop only if $a0 != 0."),
+        "{stdout}"
+    );
+    assert!(stdout.contains("     1: n        "), "{stdout}");
+    assert!(stdout.contains("     2: y        0x00400050"), "{stdout}");
+    assert!(!stdout.contains("     0: "), "{stdout}");
+
+    // breakpoint 1 is disabled, so the first stop is breakpoint 2
+    assert!(
+        stderr.contains("Breakpoint 2 at line 31 reached. (hit 1 time)"),
+        "{stderr}"
+    );
 }
@@ -0,0 +1,192 @@
+mod common;
+
+use std::io::Cursor;
+
+use name_core::layout::MemoryLayout;
+use name_core::structs::OperatingSystem;
+
+use name_emu::gdb;
+
+fn packet(command: &str) -> String {
+    let checksum: u8 = command.bytes().fold(0u8, |sum, b| sum.wrapping_add(b));
+    format!("${}#{:02x}", command, checksum)
+}
+
+/// Frames packets the way GDB would send them, after turning acks off.
+fn packets(commands: &[&str]) -> Vec<u8> {
+    // the OK to QStartNoAckMode is the last packet that gets acked
+    let mut text: String = packet("QStartNoAckMode") + "+";
+    for command in commands {
+        text.push_str(&packet(command));
+    }
+    text.into_bytes()
+}
+
+/// Splits the stub's output into packet bodies, dropping acks.
+fn replies(output: &[u8]) -> Vec<String> {
+    let text: String = String::from_utf8_lossy(output).to_string();
+    text.split('$')
+        .skip(1)
+        .map(|packet| packet.split('#').next().unwrap_or("").to_string())
+        .collect()
+}
+
+fn from_hex(text: &str) -> String {
+    (0..text.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&text[i..i + 2], 16).unwrap() as char)
+        .collect()
+}
+
+#[test]
+fn fib_gdb_test() {
+    let elf = common::fib();
+
+    let input: Vec<u8> = packets(&[
+        "qSupported:multiprocess+;swbreak+",
+        "?",
+        "g",
+        "Z0,400058,4",
+        "c",
+        "p25",
+        "p0d",
+        "m10010000,8",
+        "m400058,4",
+        "z0,400058,4",
+        "s",
+        "p25",
+        "c",
+    ]);
+    let mut output: Vec<u8> = Vec::new();
+
+    let mut operating_system: OperatingSystem = OperatingSystem::new();
+    operating_system.set_program_input(std::io::empty());
+    gdb::serve(
+        Cursor::new(input),
+        &mut output,
+        elf,
+        MemoryLayout::default(),
+        operating_system,
+    )
+    .expect("GDB session failed");
+
+    let replies: Vec<String> = replies(&output);
+    let mut replies = replies.iter().map(String::as_str);
+    let mut next = || replies.next().expect("Missing reply");
+
+    assert_eq!(next(), "OK");
+    assert!(next().contains("QStartNoAckMode+"));
+    assert_eq!(next(), "S05");
+
+    // 72 registers, with the pc at the entry point
+    let registers: &str = next();
+    assert_eq!(registers.len(), 72 * 8);
+    assert_eq!(&registers[37 * 8..38 * 8], "00400000");
+    assert_eq!(&registers[33 * 8..34 * 8], "00000000");
+    assert_eq!(&registers[38 * 8..39 * 8], "xxxxxxxx");
+
+    // print runs once the array is filled in
+    assert_eq!(next(), "OK");
+    assert_eq!(next(), "S05");
+    assert_eq!(next(), "00400058");
+    assert_eq!(next(), "0000000c");
+    assert_eq!(next(), "0000000100000001");
+
+    // the planted breakpoint doesn't show through
+    let instruction: &str = next();
+    assert!(!instruction.ends_with("0d"), "{instruction}");
+
+    assert_eq!(next(), "OK");
+    assert_eq!(next(), "S05");
+    assert_eq!(next(), "0040005c");
+
+    // the program's output comes through as console output before the exit
+    let mut console: String = String::new();
+    let mut reply: &str = next();
+    while let Some(hex) = reply.strip_prefix('O') {
+        console.push_str(&from_hex(hex));
+        reply = next();
+    }
+    assert_eq!(
+        console,
+        "The Fibonacci numbers are:\n1 1 2 3 5 8 13 21 34 55 89 144 "
+    );
+    assert_eq!(reply, "W00");
+}
+
+#[test]
+fn fib_gdb_interrupt_test() {
+    let elf = common::fib();
+
+    // every register written back as "g" gave it at the start, except $a0
+    let registers: String = (0..72)
+        .map(|register| match register {
+            4 => "00000058",
+            37 => "00400000",
+            38.. => "xxxxxxxx",
+            _ => "00000000",
+        })
+        .collect();
+    let mut input: Vec<u8> = packets(&[
+        "G0000",
+        &format!("G{}", &registers[..registers.len() - 1]),
+        &format!("G{}", registers.replacen('0', "g", 1)),
+        &format!("G{registers}"),
+        "p4",
+        "c",
+    ]);
+    // GDB interrupts with a bare 0x03 while the program runs
+    input.push(0x03);
+    input.extend(packets(&["c"]).split_off(packet("QStartNoAckMode").len() + 1));
+    let mut output: Vec<u8> = Vec::new();
+
+    let mut operating_system: OperatingSystem = OperatingSystem::new();
+    operating_system.set_program_input(std::io::empty());
+    gdb::serve(
+        Cursor::new(input),
+        &mut output,
+        elf,
+        MemoryLayout::default(),
+        operating_system,
+    )
+    .expect("GDB session failed");
+
+    let replies: Vec<String> = replies(&output);
+    let replies: Vec<&str> = replies.iter().map(String::as_str).collect();
+
+    assert_eq!(replies[..5], ["OK", "E01", "E01", "E01", "OK"]);
+    assert_eq!(replies[5], "00000058");
+    assert_eq!(replies[6], "S02");
+    assert_eq!(replies.last(), Some(&"W00"));
+}
+
+#[test]
+fn gdb_crash_test() {
+    let program: common::BuiltSample = common::build_sample(
+        "    .text\nmain:\n    lw $t0, 1($zero)\n    li $v0, 10\n    syscall\n",
+        "gdb-crash",
+    );
+
+    let input: Vec<u8> = packets(&["c", "c"]);
+    let mut output: Vec<u8> = Vec::new();
+
+    let mut operating_system: OperatingSystem = OperatingSystem::new();
+    operating_system.set_program_input(std::io::empty());
+    gdb::serve(
+        Cursor::new(input),
+        &mut output,
+        program.elf,
+        MemoryLayout::default(),
+        operating_system,
+    )
+    .expect("GDB session failed");
+
+    let replies: Vec<String> = replies(&output);
+    let replies: Vec<&str> = replies.iter().map(String::as_str).collect();
+
+    // the report comes through as console output, then the program stops on SIGSEGV for good
+    assert_eq!(replies[0], "OK");
+    let report: String = from_hex(&replies[1][1..]);
+    assert!(report.contains("Illegal address"), "{report}");
+    assert_eq!(replies[2..], ["S0b", "X0b"]);
+}
@@ -0,0 +1,83 @@
+mod common;
+
+use std::cell::RefCell;
+use std::io::{Cursor, Write};
+use std::rc::Rc;
+
+use name_core::machine::{Machine, Stop};
+use name_core::structs::Register;
+
+/// Output shared with the test, since the machine takes ownership of its writer.
+#[derive(Clone, Default)]
+struct SharedOutput(Rc<RefCell<Vec<u8>>>);
+
+impl Write for SharedOutput {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.borrow_mut().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn fib_machine_test() {
+    let mut machine: Machine = Machine::load_elf(&common::sample("fib"));
+    let fibs: u32 = machine.symbol("fibs").expect("fibs is a label in fib");
+
+    // only print the first five
+    let size: u32 = machine.symbol("size").unwrap();
+    assert_eq!(machine.read_word(size), Ok(12));
+    machine.write_word(size, 5).unwrap();
+
+    assert_eq!(machine.run_until(3), Ok(Stop::LimitReached));
+    assert_eq!(machine.instructions(), 3);
+    assert_eq!(machine.register(Register::T0), fibs);
+    assert!(machine.is_running());
+
+    assert_eq!(machine.run_until(100_000), Ok(Stop::Exited));
+    assert!(!machine.is_running());
+    assert_eq!(machine.step(), Ok(Some(Stop::Exited)));
+    assert_eq!(
+        String::from_utf8(machine.take_output()).unwrap(),
+        "The Fibonacci numbers are:\n1 1 2 3 5 "
+    );
+    assert!(machine.take_output().is_empty());
+
+    assert_eq!(
+        machine.read_memory(fibs, 8),
+        Ok(vec![0, 0, 0, 1, 0, 0, 0, 1])
+    );
+    assert_eq!(machine.read_word(fibs + 4 * 4), Ok(5));
+    assert!(machine.read_word(0).is_err());
+}
+
+#[test]
+fn hello_world_machine_test() {
+    let mut machine: Machine = Machine::load_elf(&common::sample("hello_world"));
+    let output: SharedOutput = SharedOutput::default();
+    machine.set_output(output.clone());
+    machine.set_input(Cursor::new(Vec::new()));
+
+    assert_eq!(machine.run_until(1_000), Ok(Stop::Exited));
+    assert_eq!(output.0.borrow().as_slice(), b"Hello, World!\n");
+    assert!(machine.take_output().is_empty());
+
+    // a crash comes back as an error instead of taking the caller down with it
+    let mut machine: Machine = Machine::load_elf(&common::sample("hello_world"));
+    machine.set_pc(0);
+    let error: String = machine.step().unwrap_err();
+    assert!(
+        error.contains("Illegal address provided for load/fetch"),
+        "{error}"
+    );
+    assert!(!machine.is_running());
+
+    // $zero stays zero
+    machine.set_register(Register::Zero, 5);
+    machine.set_register(Register::S0, 5);
+    assert_eq!(machine.register(Register::Zero), 0);
+    assert_eq!(machine.register(Register::S0), 5);
+}
@@ -0,0 +1,174 @@
+mod common;
+
+use std::cell::RefCell;
+use std::io::Cursor;
+use std::rc::Rc;
+
+use name_core::debug::debug_utils::information_of;
+use name_core::elf_def::Elf;
+use name_core::instruction::instruction::{AccessType, MemoryAccess, RawInstruction};
+use name_core::machine::{Machine, Stop};
+use name_core::observer::{ExecutedInstruction, Observer};
+use name_core::structs::ProgramState;
+
+use name_emu::memcheck::{Memcheck, WarningKind};
+use name_emu::simulator::{simulate_with_options, SimulatorOptions};
+
+fn step(
+    memcheck: &mut Memcheck,
+    state: &ProgramState,
+    address: u32,
+    word: u32,
+    memory_access: Option<MemoryAccess>,
+) {
+    let instruction: RawInstruction = RawInstruction::new(word);
+    memcheck.after_execute(
+        &ExecutedInstruction {
+            address,
+            instruction,
+            next_pc: address + 4,
+            memory_access,
+        },
+        information_of(instruction).expect("not an instruction"),
+        state,
+    );
+}
+
+fn word_at(address: u32, access_type: AccessType) -> Option<MemoryAccess> {
+    Some(MemoryAccess {
+        address,
+        size: 4,
+        access_type,
+    })
+}
+
+#[test]
+fn memcheck_warning_test() {
+    let mut memcheck: Memcheck = Memcheck::new(&common::fib());
+    let mut state: ProgramState = ProgramState::default();
+    let sp: u32 = 0x7ffffdf0;
+    state.cpu.general_purpose_registers[29] = sp;
+
+    // add $t0, $t0, $t1 spreads the undefined values without complaining
+    step(&mut memcheck, &state, 0x00400024, 0x0109_4020, None);
+    assert!(memcheck.warnings.is_empty());
+    assert!(!memcheck.register_is_defined(8));
+
+    // beq $t0, $zero, ... twice
+    step(&mut memcheck, &state, 0x00400028, 0x1100_0003, None);
+    step(&mut memcheck, &state, 0x00400028, 0x1100_0003, None);
+
+    // lw $a0, 4($sp) from stack nothing wrote
+    step(
+        &mut memcheck,
+        &state,
+        0x0040002c,
+        0x8FA4_0004,
+        word_at(sp + 4, AccessType::Read),
+    );
+    assert!(!memcheck.register_is_defined(4));
+
+    // addi $t1, $zero, 3; sw $t1, 0($sp); lw $a1, 0($sp) is fine
+    step(&mut memcheck, &state, 0x00400030, 0x2009_0003, None);
+    step(
+        &mut memcheck,
+        &state,
+        0x00400034,
+        0xAFA9_0000,
+        word_at(sp, AccessType::Write),
+    );
+    step(
+        &mut memcheck,
+        &state,
+        0x00400038,
+        0x8FA5_0000,
+        word_at(sp, AccessType::Read),
+    );
+    assert!(memcheck.register_is_defined(5));
+    assert!(memcheck.is_defined(sp) && !memcheck.is_defined(sp + 4));
+
+    // syscall with no service number, then print int with an undefined $a0
+    step(&mut memcheck, &state, 0x0040003c, 0x0000_000C, None);
+    step(&mut memcheck, &state, 0x00400040, 0x2002_0001, None);
+    state.cpu.general_purpose_registers[2] = 1;
+    step(&mut memcheck, &state, 0x00400044, 0x0000_000C, None);
+
+    let kinds: Vec<WarningKind> = memcheck.warnings.iter().map(|w| w.kind).collect();
+    assert_eq!(
+        kinds,
+        vec![
+            WarningKind::UndefinedBranch,
+            WarningKind::UndefinedLoad,
+            WarningKind::UndefinedSyscallArgument,
+            WarningKind::UndefinedSyscallArgument,
+        ]
+    );
+    assert_eq!(memcheck.warnings[0].count, 2);
+    assert!(memcheck.warnings[0].message.contains("$t0"));
+    assert!(memcheck.warnings[1]
+        .message
+        .contains("0x7ffffdf4 (on the stack, 4 bytes above $sp)"));
+    assert!(memcheck.warnings[2].message.contains("$v0"));
+    assert!(memcheck.warnings[3].message.contains("$a0"));
+
+    let report: String = memcheck.report();
+    assert!(report.starts_with("Memcheck: 4 uses of undefined values found.\n"));
+    assert!(report.contains("fib.asm:22 (0x00400028)"));
+    assert!(report.contains("(happened 2 times)"));
+}
+
+#[test]
+fn fib_memcheck_test() {
+    let elf: Elf = common::fib();
+    let memcheck = Rc::new(RefCell::new(Memcheck::new(&elf)));
+    let mut options: SimulatorOptions = SimulatorOptions::default();
+    options.observers.add(Box::new(memcheck.clone()));
+    simulate_with_options(elf, options).expect("fib failed");
+    let memcheck = memcheck.borrow();
+
+    assert!(memcheck.warnings.is_empty(), "{}", memcheck.report());
+}
+
+const READ_STRING: &str = "    .text
+main:
+    addiu $sp, $sp, -16
+    move $a0, $sp
+    li $a1, 16
+    li $v0, 8
+    syscall
+    lb $t0, 0($sp)
+    lb $t1, 15($sp)
+    mult $t2, $t3
+    mflo $t4
+    beq $t4, $zero, done
+done:
+    li $v0, 10
+    syscall
+";
+
+#[test]
+fn read_string_memcheck_test() {
+    let elf: Elf = common::build_sample(READ_STRING, "read-string-memcheck").elf;
+
+    let memcheck = Rc::new(RefCell::new(Memcheck::new(&elf)));
+    let mut machine: Machine = Machine::load_elf(&elf);
+    machine.set_input(Cursor::new(b"hi\n".to_vec()));
+    machine.add_observer(memcheck.clone());
+    assert_eq!(machine.run_until(1_000), Ok(Stop::Exited));
+    let memcheck = memcheck.borrow();
+
+    // read_string's whole buffer is defined, even past what was typed
+    assert!(memcheck.register_is_defined(8));
+    assert!(memcheck.register_is_defined(9));
+
+    // the product of two registers nothing set is undefined, and so is what comes out of LO
+    assert!(!memcheck.register_is_defined(12));
+    let kinds: Vec<WarningKind> = memcheck.warnings.iter().map(|w| w.kind).collect();
+    assert_eq!(
+        kinds,
+        vec![WarningKind::UndefinedBranch],
+        "{}",
+        memcheck.report()
+    );
+    assert!(memcheck.warnings[0].message.contains("$t4"));
+}
@@ -0,0 +1,118 @@
+mod common;
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use name_core::exception::definitions::ExceptionType;
+use name_core::instruction::information::InstructionInformation;
+use name_core::instruction::{MemoryAccess, RawInstruction};
+use name_core::machine::{Machine, Stop};
+use name_core::observer::{ExecutedInstruction, Observer};
+use name_core::structs::ProgramState;
+
+/// Keeps track of everything it's told.
+#[derive(Default)]
+struct Recorder {
+    before: u64,
+    after: u64,
+    mnemonics: Vec<&'static str>,
+    reads: Vec<(u32, u32)>,
+    writes: Vec<(u32, u32)>,
+    syscalls: Vec<u32>,
+    exceptions: Vec<ExceptionType>,
+    pc_matched: bool,
+}
+
+impl Observer for Recorder {
+    fn before_execute(
+        &mut self,
+        address: u32,
+        _instruction: RawInstruction,
+        program_state: &ProgramState,
+    ) {
+        self.before += 1;
+        self.pc_matched = address == program_state.cpu.pc;
+    }
+
+    fn after_execute(
+        &mut self,
+        _executed: &ExecutedInstruction,
+        information: &InstructionInformation,
+        _program_state: &ProgramState,
+    ) {
+        self.after += 1;
+        self.mnemonics.push(information.mnemonic);
+    }
+
+    fn memory_read(&mut self, access: MemoryAccess, value: u32, _program_state: &ProgramState) {
+        self.reads.push((access.address, value));
+    }
+
+    fn memory_write(&mut self, access: MemoryAccess, value: u32, _program_state: &ProgramState) {
+        self.writes.push((access.address, value));
+    }
+
+    fn syscall(&mut self, number: u32, _program_state: &ProgramState) {
+        self.syscalls.push(number);
+    }
+
+    fn exception(&mut self, exception: ExceptionType, _program_state: &ProgramState) {
+        self.exceptions.push(exception);
+    }
+}
+
+#[test]
+fn fib_observer_test() {
+    let mut machine: Machine = Machine::load_elf(&common::sample("fib"));
+    let fibs: u32 = machine.symbol("fibs").unwrap();
+    let size: u32 = machine.symbol("size").unwrap();
+    let head: u32 = machine.symbol("head").unwrap();
+    machine.write_word(size, 5).unwrap();
+
+    let recorder: Rc<RefCell<Recorder>> = Rc::default();
+    machine.add_observer(recorder.clone());
+    assert_eq!(machine.run_until(100_000), Ok(Stop::Exited));
+
+    let recorder = recorder.borrow();
+    assert_eq!(recorder.before, machine.instructions());
+    assert_eq!(recorder.after, machine.instructions());
+    assert!(recorder.pc_matched);
+    assert_eq!(recorder.mnemonics.last(), Some(&"syscall"));
+
+    // the program reads size, then stores every number it works out
+    assert!(recorder.reads.contains(&(size, 5)));
+    let stored: Vec<(u32, u32)> = recorder
+        .writes
+        .iter()
+        .copied()
+        .filter(|&(address, _)| (fibs..fibs + 5 * 4).contains(&address))
+        .collect();
+    assert_eq!(
+        stored,
+        vec![
+            (fibs, 1),
+            (fibs + 4, 1),
+            (fibs + 8, 2),
+            (fibs + 12, 3),
+            (fibs + 16, 5)
+        ]
+    );
+
+    // print_string reads the heading a byte at a time
+    let heading: &[u8] = b"The Fibonacci numbers are:\n\0";
+    for (offset, &byte) in heading.iter().enumerate() {
+        assert!(recorder
+            .reads
+            .contains(&(head + offset as u32, byte as u32)));
+    }
+
+    // print_string once, print_int and print_string for each number, then exit
+    assert_eq!(recorder.syscalls.first(), Some(&4));
+    assert_eq!(recorder.syscalls.last(), Some(&10));
+    assert_eq!(recorder.syscalls.len(), 1 + 5 * 2 + 1);
+    assert_eq!(recorder.exceptions.len(), recorder.syscalls.len());
+    assert!(recorder
+        .exceptions
+        .iter()
+        .all(|&exception| exception == ExceptionType::Syscall));
+}
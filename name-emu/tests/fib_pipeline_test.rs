@@ -0,0 +1,138 @@
+mod common;
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use name_core::debug::debug_utils::information_of;
+use name_core::instruction::instruction::RawInstruction;
+use name_core::observer::{ExecutedInstruction, Observer};
+use name_core::structs::ProgramState;
+
+use name_emu::pipeline::{Pipeline, PipelineOptions};
+use name_emu::simulator::{simulate_with_options, SimulatorOptions};
+
+fn execute(pipeline: &mut Pipeline, executed: ExecutedInstruction) {
+    pipeline.after_execute(
+        &executed,
+        information_of(executed.instruction).expect("not an instruction"),
+        &ProgramState::default(),
+    );
+}
+
+/// Feeds straight-line code through a pipeline.
+fn run_straight(pipeline: &mut Pipeline, words: &[u32]) {
+    for (i, &word) in words.iter().enumerate() {
+        let address: u32 = 0x00400000 + 4 * i as u32;
+        let instruction = RawInstruction::new(word);
+        execute(
+            pipeline,
+            ExecutedInstruction {
+                address,
+                instruction,
+                next_pc: address + 4,
+                memory_access: instruction.get_memory_access(&[0; 32]),
+            },
+        );
+    }
+}
+
+// lw $t3, 0($t0); add $t2, $t3, $t4
+const LOAD_USE: [u32; 2] = [0x8D0B_0000, 0x016C_5020];
+// add $t2, $t3, $t4; add $t2, $t2, $t4
+const ALU_USE: [u32; 2] = [0x016C_5020, 0x014C_5020];
+
+#[test]
+fn pipeline_hazard_test() {
+    let forwarding = PipelineOptions {
+        forwarding: true,
+        diagram: None,
+    };
+    let no_forwarding = PipelineOptions {
+        forwarding: false,
+        diagram: None,
+    };
+
+    // forwarding hides an ALU result completely, but a load still costs a cycle
+    let mut pipeline: Pipeline = Pipeline::new(forwarding.clone());
+    run_straight(&mut pipeline, &ALU_USE);
+    assert_eq!(pipeline.stats.cycles, 6);
+    assert_eq!(pipeline.stats.data_hazards, 1);
+    assert_eq!(pipeline.stats.data_stalls, 0);
+
+    let mut pipeline: Pipeline = Pipeline::new(forwarding);
+    run_straight(&mut pipeline, &LOAD_USE);
+    assert_eq!(pipeline.stats.cycles, 7);
+    assert_eq!(pipeline.stats.load_use_stalls, 1);
+
+    // without it, both wait for write back
+    let mut pipeline: Pipeline = Pipeline::new(no_forwarding.clone());
+    run_straight(&mut pipeline, &ALU_USE);
+    assert_eq!(pipeline.stats.data_stalls, 2);
+
+    let mut pipeline: Pipeline = Pipeline::new(no_forwarding);
+    run_straight(&mut pipeline, &LOAD_USE);
+    assert_eq!(pipeline.stats.cycles, 8);
+    assert_eq!(pipeline.stats.load_use_stalls, 2);
+}
+
+#[test]
+fn pipeline_branch_test() {
+    let mut pipeline: Pipeline = Pipeline::new(PipelineOptions {
+        forwarding: true,
+        diagram: Some(0x00400000..0x00400100),
+    });
+
+    // bgtz $t1, back to itself, taken once
+    let branch = RawInstruction::new(0x1D20_FFFF);
+    for next_pc in [0x00400000, 0x00400004] {
+        execute(
+            &mut pipeline,
+            ExecutedInstruction {
+                address: 0x00400000,
+                instruction: branch,
+                next_pc,
+                memory_access: None,
+            },
+        );
+    }
+
+    assert_eq!(pipeline.stats.control_hazards, 1);
+    assert_eq!(pipeline.stats.flushed, 2);
+    // the second run through is fetched the cycle after the first resolves
+    assert_eq!(pipeline.stats.cycles, 8);
+
+    let report: String = pipeline.report();
+    assert!(
+        report.contains(
+            "cycle              1   2   3   4   5   6   7   8\n\
+             0x00400000 bgtz    IF  ID  EX  MEM WB\n\
+             0x00400000 bgtz                IF  ID  EX  MEM WB\n"
+        ),
+        "{report}"
+    );
+}
+
+#[test]
+fn fib_pipeline_test() {
+    let elf = common::fib();
+
+    let with = Rc::new(RefCell::new(Pipeline::new(PipelineOptions {
+        forwarding: true,
+        diagram: None,
+    })));
+    let without = Rc::new(RefCell::new(Pipeline::new(PipelineOptions {
+        forwarding: false,
+        diagram: None,
+    })));
+    let mut options: SimulatorOptions = SimulatorOptions::default();
+    options.observers.add(Box::new(with.clone()));
+    options.observers.add(Box::new(without.clone()));
+    simulate_with_options(elf, options).expect("fib failed");
+    let (with, without) = (with.borrow(), without.borrow());
+
+    assert_eq!(with.stats.instructions, 212);
+    assert_eq!(with.stats.cycles, 270);
+    assert_eq!(with.stats.load_use_stalls, 10);
+    assert_eq!(with.stats.control_hazards, 22);
+    assert!(without.stats.cpi() > with.stats.cpi());
+}
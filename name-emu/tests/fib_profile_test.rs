@@ -0,0 +1,63 @@
+mod common;
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use name_emu::profile::{InstructionMix, Profile};
+use name_emu::simulator::{simulate_with_options, SimulatorOptions};
+
+#[test]
+fn fib_profile_test() {
+    let elf = common::fib();
+
+    let profile: Rc<RefCell<Profile>> = Rc::new(RefCell::new(Profile::new(&elf)));
+    let mut options: SimulatorOptions = SimulatorOptions::default();
+    options.observers.add(Box::new(profile.clone()));
+    simulate_with_options(elf, options).expect("Profiling fib failed");
+    let profile = profile.borrow();
+
+    assert_eq!(profile.total(), 212);
+    assert_eq!(
+        profile.mix,
+        InstructionMix {
+            alu: 117,
+            load: 33,
+            store: 12,
+            branch_taken: 20,
+            branch_not_taken: 2,
+            jump: 2,
+            syscall: 26,
+        }
+    );
+
+    // compute's loop body runs once per Fibonacci number after the first two
+    assert_eq!(profile.executions.get(&0x00400024), Some(&10));
+
+    // the print loop is the hottest code
+    assert_eq!(profile.by_function()[0], ("print5".to_string(), 121));
+    assert_eq!(
+        profile
+            .by_function()
+            .iter()
+            .map(|(_, count)| count)
+            .sum::<u64>(),
+        212
+    );
+
+    // la is two instructions, so its line counts both
+    let hottest = &profile.by_line()[0];
+    assert_eq!((hottest.line_number, hottest.count), (48, 24));
+
+    let report: String = profile.report();
+    assert!(
+        report.starts_with("212 instructions executed\n"),
+        "{report}"
+    );
+    assert!(report.contains("branch taken"), "{report}");
+
+    let json = profile.to_json();
+    assert_eq!(json["total"], 212);
+    assert_eq!(json["mix"]["syscall"], 26);
+    assert_eq!(json["functions"][0]["name"], "print5");
+    assert_eq!(json["lines"][0]["line"], 48);
+}
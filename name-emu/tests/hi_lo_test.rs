@@ -0,0 +1,60 @@
+mod common;
+
+use std::io::Cursor;
+
+use name_core::machine::{Machine, Stop};
+use name_core::structs::Register;
+
+// Multiplies and divides, keeping what comes out of HI and LO in $s registers, then reads a line with read_string.
+const PROGRAM: &str = "    .data
+buffer: .word 0 : 4
+
+    .text
+main:
+    addiu $t0, $zero, -7
+    li $t1, 2
+    mult $t0, $t1
+    mflo $s0
+    mfhi $s1
+    div $t0, $t1
+    mflo $s2
+    mfhi $s3
+    divu $t0, $t1
+    mflo $s4
+    mfhi $s5
+    div $t0, $zero          # leaves HI and LO as they were
+    mflo $s6
+    li $t2, 100
+    mtlo $t2
+    mflo $s7
+
+    la $a0, buffer
+    li $a1, 16
+    li $v0, 8               # read_string
+    syscall
+    lb $t3, 0($a0)
+    lb $t4, 2($a0)
+    lb $t5, 3($a0)
+    li $v0, 10
+    syscall
+";
+
+#[test]
+fn hi_lo_test() {
+    let mut machine: Machine = Machine::load_elf(&common::build_sample(PROGRAM, "hi-lo").elf);
+    machine.set_input(Cursor::new(b"hi\nthere".to_vec()));
+    assert_eq!(machine.run_until(1_000), Ok(Stop::Exited));
+
+    let signed = |register: Register| machine.register(register).cast_signed();
+    assert_eq!((signed(Register::S0), signed(Register::S1)), (-14, -1));
+    assert_eq!((signed(Register::S2), signed(Register::S3)), (-3, -1));
+    assert_eq!(machine.register(Register::S4), 0x7FFF_FFFC);
+    assert_eq!(machine.register(Register::S5), 1);
+    assert_eq!(machine.register(Register::S6), 0x7FFF_FFFC);
+    assert_eq!(machine.register(Register::S7), 100);
+
+    // read_string stops after the newline and terminates the string
+    assert_eq!(machine.register(Register::T3), u32::from(b'h'));
+    assert_eq!(machine.register(Register::T4), u32::from(b'\n'));
+    assert_eq!(machine.register(Register::T5), 0);
+}
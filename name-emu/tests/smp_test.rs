@@ -0,0 +1,160 @@
+use std::io::Cursor;
+
+use name_core::constants::MIPS_TEXT_START_ADDR;
+use name_core::debug::debug_utils::DebuggerState;
+use name_core::structs::{Memory, OperatingSystem, Processor, ProgramState, Register};
+
+use name_emu::smp::{Interleaving, Multiprocessor, HALT_ADDRESS};
+
+// Both programs add 1 to the word at 0x10010000 100 times, then return.
+const RACY: [u32; 8] = [
+    0x3C10_1001, // lui $s0, 0x1001
+    0x2011_0064, // addi $s1, $zero, 100
+    0x8E08_0000, // lw $t0, 0($s0)
+    0x2108_0001, // addi $t0, $t0, 1
+    0xAE08_0000, // sw $t0, 0($s0)
+    0x2231_FFFF, // addi $s1, $s1, -1
+    0x1E20_FFFB, // bgtz $s1, (the lw)
+    0x03E0_0008, // jr $ra
+];
+const ATOMIC: [u32; 9] = [
+    0x3C10_1001, // lui $s0, 0x1001
+    0x2011_0064, // addi $s1, $zero, 100
+    0xC208_0000, // ll $t0, 0($s0)
+    0x2108_0001, // addi $t0, $t0, 1
+    0xE208_0000, // sc $t0, 0($s0)
+    0x1100_FFFC, // beq $t0, $zero, (the ll)
+    0x2231_FFFF, // addi $s1, $s1, -1
+    0x1E20_FFFA, // bgtz $s1, (the ll)
+    0x03E0_0008, // jr $ra
+];
+
+// Core 0 links the word at 0x10010000 and tries to sc it after core 1 has read a string over it.
+const LINKED: [u32; 13] = [
+    0x3C10_1001, // lui $s0, 0x1001
+    0xC208_0000, // ll $t0, 0($s0)
+    0x0000_0000, // nop
+    0x0000_0000, // nop
+    0x0000_0000, // nop
+    0x0000_0000, // nop
+    0xE208_0000, // sc $t0, 0($s0)
+    0x03E0_0008, // jr $ra
+    0x3C04_1001, // lui $a0, 0x1001 (core 1 starts here)
+    0x2005_0004, // addi $a1, $zero, 4
+    0x2002_0008, // addi $v0, $zero, 8
+    0x0000_000C, // syscall
+    0x03E0_0008, // jr $ra
+];
+
+/// Runs the program on every core at once and gives back the machine and the counter it ended with.
+fn run(program: &[u32], cores: usize, interleaving: Interleaving) -> (Multiprocessor, u32) {
+    let text: Vec<u8> = program.iter().flat_map(|word| word.to_be_bytes()).collect();
+    let mut program_state: ProgramState = ProgramState::new(
+        Processor::new(MIPS_TEXT_START_ADDR),
+        Memory::new(vec![0; 4], text),
+    );
+    program_state.cpu.general_purpose_registers[Register::Ra as usize] = HALT_ADDRESS;
+
+    let mut machine: Multiprocessor =
+        Multiprocessor::new(program_state, cores, interleaving).expect("bad core count");
+    for number in 1..cores {
+        machine
+            .start_core(number, MIPS_TEXT_START_ADDR)
+            .expect("core didn't start");
+    }
+    machine.run(
+        &Vec::new(),
+        &mut OperatingSystem::new(),
+        &mut DebuggerState::new(),
+    );
+
+    let counter: u32 =
+        u32::from_be_bytes(machine.program_state.memory.data[0..4].try_into().unwrap());
+    (machine, counter)
+}
+
+#[test]
+fn smp_race_test() {
+    // in lockstep, each core's store overwrites the other's
+    let (machine, counter) = run(&RACY, 2, Interleaving::RoundRobin { quantum: 1 });
+    assert_eq!(counter, 100);
+    assert!(machine.cores().iter().all(|core| !core.running));
+    assert_eq!(machine.cores()[1].cp0.get_cpu_num(), 1);
+    assert_eq!(
+        machine.cores()[1].cpu.general_purpose_registers[Register::A0 as usize],
+        1
+    );
+
+    // a seed always interleaves the same way
+    let (_, first) = run(&RACY, 3, Interleaving::Random { seed: 7 });
+    let (_, second) = run(&RACY, 3, Interleaving::Random { seed: 7 });
+    assert_eq!(first, second);
+    assert!(first < 300);
+
+    // one core can't race
+    let (_, alone) = run(&RACY, 1, Interleaving::default());
+    assert_eq!(alone, 100);
+}
+
+#[test]
+fn smp_ll_sc_test() {
+    for interleaving in [
+        Interleaving::RoundRobin { quantum: 1 },
+        Interleaving::RoundRobin { quantum: 5 },
+        Interleaving::Random { seed: 3 },
+    ] {
+        let (machine, counter) = run(&ATOMIC, 3, interleaving);
+        assert_eq!(counter, 300, "{interleaving:?}");
+
+        // contention shows up as retries
+        if interleaving == (Interleaving::RoundRobin { quantum: 1 }) {
+            assert!(machine
+                .cores()
+                .iter()
+                .any(|core| core.instructions > 7 * 100 + 3));
+        }
+    }
+
+    assert_eq!(
+        Interleaving::parse("round-robin:4"),
+        Ok(Interleaving::RoundRobin { quantum: 4 })
+    );
+    assert_eq!(
+        Interleaving::parse("random"),
+        Ok(Interleaving::Random { seed: 1 })
+    );
+    assert!(Interleaving::parse("round-robin:0").is_err());
+    assert!(Interleaving::parse("lottery").is_err());
+}
+
+#[test]
+fn smp_syscall_breaks_link_test() {
+    let text: Vec<u8> = LINKED.iter().flat_map(|word| word.to_be_bytes()).collect();
+    let mut program_state: ProgramState = ProgramState::new(
+        Processor::new(MIPS_TEXT_START_ADDR),
+        Memory::new(vec![0; 4], text),
+    );
+    program_state.cpu.general_purpose_registers[Register::Ra as usize] = HALT_ADDRESS;
+
+    let mut machine: Multiprocessor =
+        Multiprocessor::new(program_state, 2, Interleaving::RoundRobin { quantum: 1 })
+            .expect("bad core count");
+    machine
+        .start_core(1, MIPS_TEXT_START_ADDR + 8 * 4)
+        .expect("core didn't start");
+
+    let mut operating_system: OperatingSystem = OperatingSystem::new();
+    operating_system.set_program_input(Cursor::new("hi\n"));
+    machine.run(
+        &Vec::new(),
+        &mut operating_system,
+        &mut DebuggerState::new(),
+    );
+
+    // read_string wrote over the linked word between the ll and the sc, so the sc fails and leaves it alone
+    assert_eq!(&machine.program_state.memory.data[0..3], b"hi\n");
+    assert_eq!(
+        machine.cores()[0].cpu.general_purpose_registers[Register::T0 as usize],
+        0
+    );
+}
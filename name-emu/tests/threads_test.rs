@@ -0,0 +1,146 @@
+mod common;
+
+use std::process::{Command, Output};
+
+// Two workers add to a counter under a mutex, yielding while they hold it, and post a semaphore once they're done.
+// The first returns from its entry and the second exits with exit_thread. Prints the counter, then the sum of what
+// main got from joining them.
+const PROGRAM: &str = r#"
+    .data
+counter:    .word 0
+mutex:      .word 0
+done:       .word 0
+stack1:     .word 0 : 32
+stack2:     .word 0 : 32
+stacks_end: .word 0
+
+    .text
+main:
+    li $v0, 104             # mutex_create
+    syscall
+    la $t0, mutex
+    sw $v0, 0($t0)
+    li $a0, 0
+    li $v0, 107             # sem_create
+    syscall
+    la $t0, done
+    sw $v0, 0($t0)
+
+    la $a0, worker
+    li $a1, 20
+    la $a2, stack2
+    li $v0, 100             # thread_create
+    syscall
+    move $s0, $v0
+    la $a0, worker
+    li $a1, 30
+    la $a2, stacks_end
+    li $v0, 100
+    syscall
+    move $s1, $v0
+
+    la $t0, done
+    lw $a0, 0($t0)
+    li $v0, 108             # sem_wait
+    syscall
+    la $t0, done
+    lw $a0, 0($t0)
+    li $v0, 108
+    syscall
+
+    move $a0, $s0
+    li $v0, 102             # join
+    syscall
+    move $s2, $v0
+    move $a0, $s1
+    li $v0, 102
+    syscall
+    add $s2, $s2, $v0
+
+    la $t0, counter
+    lw $a0, 0($t0)
+    li $v0, 1
+    syscall
+    li $a0, 32
+    li $v0, 11
+    syscall
+    move $a0, $s2
+    li $v0, 1
+    syscall
+    li $v0, 10
+    syscall
+
+worker:
+    move $s0, $a0
+    move $s1, $a0
+loop:
+    la $t0, mutex
+    lw $a0, 0($t0)
+    li $v0, 105             # mutex_lock
+    syscall
+    la $t0, counter
+    lw $t1, 0($t0)
+    li $v0, 101             # yield
+    syscall
+    addi $t1, $t1, 1
+    sw $t1, 0($t0)
+    la $t0, mutex
+    lw $a0, 0($t0)
+    li $v0, 106             # mutex_unlock
+    syscall
+    addi $s0, $s0, -1
+    bgtz $s0, loop
+
+    la $t0, done
+    lw $a0, 0($t0)
+    li $v0, 109             # sem_post
+    syscall
+    move $v0, $s1
+    li $t2, 30
+    beq $s1, $t2, quit
+    jr $ra
+quit:
+    move $a0, $s1
+    li $v0, 103             # exit_thread
+    syscall
+"#;
+
+/// Builds the program and runs it in the emulator with extra arguments, giving back what it printed.
+fn run(name: &str, arguments: &[&str]) -> Output {
+    let program: common::BuiltSample = common::build_sample(PROGRAM, &format!("threads-{name}"));
+    Command::new(env!("CARGO_BIN_EXE_name-emu"))
+        .args(arguments)
+        .arg(&program.path)
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn threads_test() {
+    // every switch lands in the middle of a syscall, so a resumed thread only gets the right registers back if the
+    // return from the syscall goes to it
+    let output: Output = run("cooperative", &[]);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "50 50",
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+}
+
+#[test]
+fn threads_preemption_test() {
+    // the mutex keeps the count right however the threads are switched
+    for arguments in [
+        &["--thread-quantum", "3"][..],
+        &["--thread-quantum", "2", "--thread-seed", "7"],
+    ] {
+        let output: Output = run(&arguments.join(""), arguments);
+        assert_eq!(
+            String::from_utf8_lossy(&output.stdout),
+            "50 50",
+            "{}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+}
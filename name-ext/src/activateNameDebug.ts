@@ -156,7 +156,9 @@ class NameConfigurationProvider implements vscode.DebugConfigurationProvider {
 
 class ExecutableDebugAdapterFactory implements vscode.DebugAdapterDescriptorFactory {
 
+	// name-emu serves the Debug Adapter Protocol over stdin/stdout. It's found on the PATH unless the user says where it is.
 	createDebugAdapterDescriptor(_session: vscode.DebugSession): ProviderResult<vscode.DebugAdapterDescriptor> {
-		return new vscode.DebugAdapterExecutable('/home/teqqy/Projects/name/name-emu/target/release/name');
+		const emulatorPath = vscode.workspace.getConfiguration('name-ext').get('emulatorPath', '') || 'name-emu';
+		return new vscode.DebugAdapterExecutable(emulatorPath, ['--dap']);
 	}
 }
\ No newline at end of file
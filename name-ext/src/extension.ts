@@ -9,7 +9,7 @@ import * as window from 'window';
 
 const termName = "NAME Emulator";
 
-const runMode: 'external' | 'server' | 'namedPipeServer' | 'inline' = 'server';
+const runMode: 'external' | 'server' | 'namedPipeServer' | 'inline' = 'external';
 
 // TODO: Allow this code to run on Windows, Linux, and macOS.
 // The current issue is that the paths are made with linux in mind.
@@ -170,8 +170,8 @@ export function activate(context: vscode.ExtensionContext) {
 			break;
 
 		case 'external': default:
-			// run the debug adapter as a separate process
-			//activateNameDebug(context, new DebugAdapterExecutableFactory());
+			// run the debug adapter as a separate process: without a factory, activateNameDebug starts name-emu --dap
+			activateNameDebug(context);
 			break;
 
 		case 'inline':
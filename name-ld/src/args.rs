@@ -10,4 +10,8 @@ pub struct Cli {
     /// Output file to generate (required)
     #[arg(short, long, required = true)]
     pub output_filename: std::path::PathBuf,
+
+    /// Memory layout to link for: a preset (name, mars-default, mars-compact-data-at-0, mars-compact-text-at-0) or a TOML file
+    #[arg(long, value_name = "LAYOUT")]
+    pub layout: Option<String>,
 }
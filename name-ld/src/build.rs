@@ -0,0 +1,66 @@
+//! Assembling and linking in memory, so running a program doesn't leave object files next to it.
+
+use std::fs::read_to_string;
+use std::path::{Path, PathBuf};
+
+use name_as::assembler::assemble_file::assemble_with_layout;
+use name_core::elf_def::{Elf, ElfType};
+use name_core::elf_utils::{
+    create_new_elf, elf_to_bytes, extract_symbol_table_to_sections, read_bytes_to_elf,
+};
+use name_core::layout::MemoryLayout;
+use name_core::narrate::quiet;
+
+use crate::linker::linker_with_layout;
+
+/// Assembles a program in a single .asm file (plus whatever it includes) and links it into an executable.
+/// The assembler and linker are kept quiet while they're at it, and go back to how they were afterwards.
+pub fn build(path: &Path) -> Result<Elf, String> {
+    build_with_layout(path, &MemoryLayout::default())
+}
+
+/// Builds a program for a memory layout other than the default.
+pub fn build_with_layout(path: &Path, layout: &MemoryLayout) -> Result<Elf, String> {
+    let _quiet = quiet();
+
+    let file_contents: String =
+        read_to_string(path).map_err(|e| format!("Couldn't read {}: {e}", path.display()))?;
+
+    // includes are found next to the file, as they are for name-as
+    let base_path: PathBuf = path.parent().map(Path::to_path_buf).unwrap_or_default();
+    let file_name: String = match path.file_name() {
+        Some(name) => name.to_string_lossy().to_string(),
+        None => String::new(),
+    };
+
+    let assembler_environment =
+        assemble_with_layout(file_contents, base_path, file_name, None, *layout).map_err(
+            |errors| {
+                format!(
+                    "Errors were encountered during assembly:\n{}",
+                    errors.join("\n")
+                )
+            },
+        )?;
+    let (section_dot_symtab, section_dot_strtab) =
+        extract_symbol_table_to_sections(assembler_environment.symbol_table);
+    let mut et_rel: Elf = create_new_elf(
+        vec![
+            assembler_environment.section_dot_data,
+            assembler_environment.section_dot_text,
+            assembler_environment.section_dot_rel,
+            section_dot_symtab,
+            section_dot_strtab,
+            assembler_environment.section_dot_line,
+        ],
+        ElfType::Relocatable,
+        true,
+    );
+    layout.apply_to(&mut et_rel);
+
+    // each tool expects an ELF the way it's read from a file, not the way the last one made it
+    let et_rel: Elf = read_bytes_to_elf(elf_to_bytes(&et_rel))?;
+    let et_exec: Elf = linker_with_layout(vec![et_rel], layout)
+        .map_err(|e| format!("Errors were encountered during linking:\n{e}"))?;
+    read_bytes_to_elf(elf_to_bytes(&et_exec))
+}
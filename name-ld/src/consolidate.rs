@@ -1,8 +1,10 @@
 // This file contains methods responsible for consolidating the sections of the ELF.
 
 use name_core::elf_def::Elf;
+use name_core::elf_utils::deserialize_line_info;
+use name_core::structs::LINE_SECTION_HEADER;
 
-use crate::constants::DATA;
+use crate::constants::{DATA, LINE, TEXT};
 
 /// Consolidate the ELF sections that will appear in the final ET_EXEC.
 /// No relocation is performed at this time.
@@ -49,6 +51,30 @@ pub fn consolidate_sections(elfs: Vec<Elf>, offsets: &Vec<Vec<u32>>) -> Vec<Vec<
                     .collect();
                 return_vector.push(padded_datas);
             },
+            LINE => {
+                // Line -> Each module was assembled as if its .text began at the start of .text,
+                // so shift the addresses in its line information by the module's .text offset.
+                // The modules' entries go under a single header.
+                let mut relocated_lines: Vec<u8> = LINE_SECTION_HEADER.to_vec();
+                relocated_lines.extend(elfs.iter().enumerate().flat_map(|(idx, elf)| {
+                    let text_offset: u32 = offsets[idx][TEXT];
+
+                    // read_bytes_to_elf has already turned away a .line this can't read
+                    deserialize_line_info(&elf.sections[current_section])
+                        .unwrap_or_else(|e| unreachable!("{e}"))
+                        .into_iter()
+                        .flat_map(|mut line| {
+                            // Lines outside .text have no address to shift.
+                            if line.start_address != 0 {
+                                line.start_address += text_offset;
+                                line.end_address += text_offset;
+                            }
+                            line.to_bytes()
+                        })
+                        .collect::<Vec<u8>>()
+                }));
+                return_vector.push(relocated_lines);
+            },
             _ => {
                 // Anything else -> one-liner.
                 return_vector.push(
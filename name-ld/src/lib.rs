@@ -1,4 +1,8 @@
+#[macro_use]
+extern crate name_core;
+
 pub mod args;
+pub mod build;
 pub mod conformity;
 pub mod consolidate;
 pub mod constants;
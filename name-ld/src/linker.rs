@@ -1,5 +1,6 @@
 /// This file contains the linker logic. If only one file was provided, it will invoke the far simpler single module linker
 use name_core::elf_def::Elf;
+use name_core::layout::MemoryLayout;
 
 use crate::{
     conformity::conformity_check, consolidate::consolidate_sections, offsets::calculate_offsets,
@@ -10,8 +11,16 @@ use crate::{
 /// Check the ELFs, calculate section offsets for each ELF, consolidate the sections, then perform relocation.
 /// Details inside.
 pub fn linker(elfs: Vec<Elf>) -> Result<Elf, String> {
+    linker_with_layout(elfs, &MemoryLayout::default())
+}
+
+/// Links for a memory layout other than the default. Every object file has to have been assembled for the same one.
+pub fn linker_with_layout(elfs: Vec<Elf>, layout: &MemoryLayout) -> Result<Elf, String> {
     // Ensure each ELF conforms to the correct standard
     conformity_check(&elfs)?;
+    for (i, elf) in elfs.iter().enumerate() {
+        layout.check(elf, &format!("Object file {}", i + 1))?;
+    }
 
     // Now that we know each ELF conforms to standard, we can try to do some work:
 
@@ -31,5 +40,10 @@ pub fn linker(elfs: Vec<Elf>) -> Result<Elf, String> {
     // Takes in the consolidated sections (consumed) and offsets (consumed)
     // Returns a Result<Elf>, with Ok(_) representing the relocated ELF.
     // Fallible due to issues like a label being undefined in scope.
-    return relocate(consolidated_sections, offsets);
+    let mut executable: Elf = relocate(consolidated_sections, offsets, layout.text_base)?;
+
+    // The consolidated sections could still be too big for the layout, even if each object fit.
+    layout.apply_to(&mut executable);
+    layout.check(&executable, "The linked program")?;
+    Ok(executable)
 }
@@ -1,15 +1,21 @@
 use name_ld::args::Cli;
-use name_ld::linker::linker;
+use name_ld::linker::linker_with_layout;
 use std::path::PathBuf;
 
 use name_core::elf_def::Elf;
 use name_core::elf_utils::{read_bytes_to_elf, write_elf_to_file};
+use name_core::layout::MemoryLayout;
 
 use clap::Parser;
 
 fn main() {
     // Take in all object files as cli arguments
     let args: Cli = Cli::parse();
+    let layout: MemoryLayout = match args.layout.as_deref().map(MemoryLayout::load) {
+        Some(Ok(layout)) => layout,
+        Some(Err(e)) => panic!("[*] FATAL: {e}"),
+        None => MemoryLayout::default(),
+    };
     let base_path: PathBuf = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
         .parent()
         .expect("[*] FATAL: No parent directory found (did you clone the entire repo?)")
@@ -48,7 +54,7 @@ fn main() {
             }
 
             // Invoke linker on collected Elfs
-            let executable_contents: Elf = match linker(elf_vector) {
+            let executable_contents: Elf = match linker_with_layout(elf_vector, &layout) {
                 Ok(elf) => elf,
                 Err(e) => panic!("{e}"),
             };
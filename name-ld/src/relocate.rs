@@ -11,7 +11,11 @@ use crate::{
     text_relocation::relocate_text_entries,
 };
 
-pub fn relocate(sections: Vec<Vec<u8>>, offsets: Vec<Vec<u32>>) -> Result<Elf, String> {
+pub fn relocate(
+    sections: Vec<Vec<u8>>,
+    offsets: Vec<Vec<u32>>,
+    text_base: u32,
+) -> Result<Elf, String> {
     // Relocation is the process of putting things where they ought to go.
     // The first thing to do is create a mutable ELF (it will still be relocatable). Now that the sections have been consolidated, we just need to operate on the structured data.
     let new_elf: Elf = create_new_elf(sections, ElfType::Relocatable, true);
@@ -30,7 +34,7 @@ pub fn relocate(sections: Vec<Vec<u8>>, offsets: Vec<Vec<u32>>) -> Result<Elf, S
     // Now, each entry in .rel needs to be reconciled.
     // This is a complex process, so it's also been extracted to a function.
     let relocated: Elf =
-        relocate_text_entries(relocated_symtab, &offsets).map_err(|e| e.to_string())?;
+        relocate_text_entries(relocated_symtab, &offsets, text_base).map_err(|e| e.to_string())?;
 
     // Now that each entry in .rel has been reconciled, there's nothing left to do.
     Ok(relocated)
@@ -3,7 +3,7 @@
 use std::collections::HashSet;
 
 use name_core::{
-    elf_def::{Elf, Elf32Sym, ElfType, RelocationEntry},
+    elf_def::{Elf, Elf32Sym, ElfType, RelocationEntry, SHN_UNDEF},
     elf_utils::{create_new_elf, parse_elf_symbols, parse_rel_info},
 };
 
@@ -257,10 +257,10 @@ pub fn check_duplicate_symbols(elf: &Elf) -> Result<(), DuplicateSymbolError> {
     let symtab: Vec<Elf32Sym> = parse_elf_symbols(&elf.sections[SYMTAB]);
 
     // Iterate through all symbols, collecting the linked names. If the name is already in that collection, error out.
-    // If the symbol's value is 0, DO NOT COLLECT IT FOR DUPLICATE CHECKING. That's a placeholder.
+    // If the symbol isn't in a section, DO NOT COLLECT IT FOR DUPLICATE CHECKING. That's a placeholder.
     let global_names: Vec<String> = symtab
         .iter()
-        .filter(|&symbol| symbol.st_value != 0)
+        .filter(|&symbol| symbol.st_shndx != SHN_UNDEF)
         .filter_map(|symbol| match symbol.get_bind() {
             1 => Some(symbol.get_linked_name(&elf.sections[STRTAB])),
             _ => None,
@@ -326,7 +326,7 @@ fn validate_duplicate_symbol_checker() {
             st_size: 4,
             st_info: 1 << 4,
             st_other: 0,
-            st_shndx: 0,
+            st_shndx: 1,
         },
         Elf32Sym {
             st_name: 5,
@@ -334,7 +334,7 @@ fn validate_duplicate_symbol_checker() {
             st_size: 4,
             st_info: 1 << 4,
             st_other: 0,
-            st_shndx: 0,
+            st_shndx: 1,
         },
         Elf32Sym {
             st_name: 9,
@@ -342,7 +342,7 @@ fn validate_duplicate_symbol_checker() {
             st_size: 4,
             st_info: 0,
             st_other: 0,
-            st_shndx: 0,
+            st_shndx: 1,
         },
         Elf32Sym {
             st_name: 13,
@@ -350,7 +350,7 @@ fn validate_duplicate_symbol_checker() {
             st_size: 4,
             st_info: 0,
             st_other: 0,
-            st_shndx: 0,
+            st_shndx: 1,
         },
     ];
 
@@ -1,7 +1,7 @@
 // This file is just responsible for performing .text relocation. That's it.
 
 use name_core::{
-    constants::MIPS_TEXT_START_ADDR, elf_def::{Elf, Elf32Sym, ElfType, RelocationEntry, RelocationEntryType}, elf_utils::{create_new_elf, parse_elf_symbols, parse_rel_info}
+    elf_def::{Elf, Elf32Sym, ElfType, RelocationEntry, RelocationEntryType, SHN_UNDEF}, elf_utils::{create_new_elf, parse_elf_symbols, parse_rel_info}
 };
 
 use crate::constants::{REL, SHSTRTAB, STRTAB, SYMTAB, TEXT};
@@ -30,6 +30,7 @@ impl std::fmt::Display for TextRelocationError {
 pub fn relocate_text_entries(
     adjusted_checked_elf: Elf,
     _offsets: &Vec<Vec<u32>>,
+    text_base: u32,
 ) -> Result<Elf, TextRelocationError> {
     // For each relocation entry in .rel, match on the type and perform the necessary relocation in .text.
     let mut new_text_section: Vec<u8> = adjusted_checked_elf.sections[TEXT].clone();
@@ -57,7 +58,7 @@ pub fn relocate_text_entries(
                 // For jump instructions:
                 let text_offset: usize = entry.r_offset as usize;
                 
-                println!("[+] At 0x{:x}: Linking symbol '{}' in R26 (jump) mode.", text_offset+(MIPS_TEXT_START_ADDR as usize), linked_symbol.get_linked_name(&string_table));
+                narrate!("[+] At 0x{:x}: Linking symbol '{}' in R26 (jump) mode.", text_offset+(text_base as usize), linked_symbol.get_linked_name(&string_table));
 
                 let address_to_pack: u32 = linked_symbol.st_value >> 2;
                 let old_value: u32 = u32::from_be_bytes(
@@ -67,7 +68,7 @@ pub fn relocate_text_entries(
                 );
                 let new_value: u32 = old_value | address_to_pack;
 
-                println!(" - Splicing out 0x{old_value:x} for 0x{new_value:x}");
+                narrate!(" - Splicing out 0x{old_value:x} for 0x{new_value:x}");
                 
                 new_text_section.splice(text_offset..(text_offset + 4), new_value.to_be_bytes());
             },
@@ -75,10 +76,10 @@ pub fn relocate_text_entries(
                 // For branch instructions:
                 let text_offset: usize = entry.r_offset as usize;
 
-                println!("[+] At 0x{:x}: Linking symbol '{}' in Pc16 (branch) mode.", text_offset+(MIPS_TEXT_START_ADDR as usize), linked_symbol.get_linked_name(&string_table));
+                narrate!("[+] At 0x{:x}: Linking symbol '{}' in Pc16 (branch) mode.", text_offset+(text_base as usize), linked_symbol.get_linked_name(&string_table));
 
                 let symbol_address: u32 = linked_symbol.st_value;
-                let pc_rel: u32 = entry.r_offset;
+                let pc_rel: u32 = text_base + entry.r_offset;
                 let relocation_value: u32 =
                     (((symbol_address as i32) - (pc_rel as i32 + 4))  >> 2) as i16 as u16 as u32;
                 let old_value: u32 = u32::from_be_bytes(
@@ -88,14 +89,14 @@ pub fn relocate_text_entries(
                 );
                 let new_value: u32 = old_value | relocation_value;
 
-                println!(" - Splicing out 0x{old_value:x} for 0x{new_value:x}");
+                narrate!(" - Splicing out 0x{old_value:x} for 0x{new_value:x}");
 
                 new_text_section.splice(text_offset..(text_offset + 4), new_value.to_be_bytes());
             },
             RelocationEntryType::Hi16 => {
                 let text_offset: usize = entry.r_offset as usize;
 
-                println!("[+] At 0x{:x}: Linking symbol '{}' in Hi16 (high 16 bits) mode.", text_offset+(MIPS_TEXT_START_ADDR as usize), linked_symbol.get_linked_name(&string_table));
+                narrate!("[+] At 0x{:x}: Linking symbol '{}' in Hi16 (high 16 bits) mode.", text_offset+(text_base as usize), linked_symbol.get_linked_name(&string_table));
 
                 let symbol_value: u32 = linked_symbol.st_value;
                 let relocation_value = symbol_value >> 16;
@@ -108,14 +109,14 @@ pub fn relocate_text_entries(
 
                 let new_value: u32 = old_value | relocation_value;
 
-                println!(" - Splicing out 0x{old_value:x} for 0x{new_value:x}");
+                narrate!(" - Splicing out 0x{old_value:x} for 0x{new_value:x}");
 
                 new_text_section.splice(text_offset..(text_offset+4), new_value.to_be_bytes());
             }
             RelocationEntryType::Lo16 => {
                 let text_offset: usize = entry.r_offset as usize;
 
-                println!("[+] At 0x{:x}: Linking symbol '{}' in Lo16 (low 16 bits) mode.", text_offset+(MIPS_TEXT_START_ADDR as usize), linked_symbol.get_linked_name(&string_table));
+                narrate!("[+] At 0x{:x}: Linking symbol '{}' in Lo16 (low 16 bits) mode.", text_offset+(text_base as usize), linked_symbol.get_linked_name(&string_table));
 
                 let symbol_value: u32 = linked_symbol.st_value;
                 let relocation_value = symbol_value & 0xFFFF;
@@ -128,14 +129,14 @@ pub fn relocate_text_entries(
 
                 let new_value: u32 = old_value | relocation_value;
 
-                println!(" - Splicing out 0x{old_value:x} for 0x{new_value:x}");
+                narrate!(" - Splicing out 0x{old_value:x} for 0x{new_value:x}");
 
                 new_text_section.splice(text_offset..(text_offset+4), new_value.to_be_bytes());
             },
             _ => return Err(TextRelocationError::UnimplementedRelType(entry.r_type)),
         }
 
-        println!("");
+        narrate!("");
 
     }
 
@@ -151,7 +152,7 @@ pub fn relocate_text_entries(
             _ => Some(section.clone()),
         })
         .collect();
-    Ok(create_new_elf(exec_sections, ElfType::Executable(MIPS_TEXT_START_ADDR), true))
+    Ok(create_new_elf(exec_sections, ElfType::Executable(text_base), true))
 }
 
 /// This function gets the correct linked symbol for a relocation entry. It looks to the local scope first by design.
@@ -160,11 +161,14 @@ fn get_linked_symbol(
     strtab: &Vec<u8>,
     symbol_idx: usize,
 ) -> Option<Elf32Sym> {
-    match symtab[symbol_idx].st_value {
-        0 => {
+    match symtab[symbol_idx].st_shndx {
+        SHN_UNDEF => {
             let name_to_match: String = symtab[symbol_idx].get_linked_name(strtab);
             return match symtab.iter().find(|symbol| {
-                symbol.get_linked_name(strtab) == name_to_match && symbol.get_bind() == 1
+                // another module's .globl of a label it doesn't define is no definition
+                symbol.get_linked_name(strtab) == name_to_match
+                    && symbol.get_bind() == 1
+                    && symbol.st_shndx != SHN_UNDEF
             }) {
                 Some(sym) => Some(sym.clone()),
                 None => None,
@@ -0,0 +1,52 @@
+use clap::Parser;
+
+#[derive(Parser, Debug)]
+#[command(author, version, about)]
+pub struct Cli {
+    /// Programs to test, or directories to look for them in
+    #[arg(required = true)]
+    pub paths: Vec<std::path::PathBuf>,
+
+    /// How many instructions a program may execute before it's taken to be stuck
+    #[arg(long, value_name = "N", default_value_t = 10_000_000)]
+    pub limit: u64,
+
+    /// Only mention the programs that fail
+    #[arg(short, long)]
+    pub quiet: bool,
+
+    /// Call the function at LABEL in the one program given, instead of running programs whole
+    #[arg(long, value_name = "LABEL")]
+    pub call: Option<String>,
+
+    /// An argument for --call, in order: a number, a "string", or an [array, of, words]
+    #[arg(
+        long = "arg",
+        value_name = "VALUE",
+        requires = "call",
+        allow_hyphen_values = true
+    )]
+    pub arguments: Vec<String>,
+
+    /// What the function should return in $v0
+    #[arg(
+        long,
+        value_name = "VALUE",
+        requires = "call",
+        allow_hyphen_values = true
+    )]
+    pub expect_v0: Option<String>,
+
+    /// What the function should return in $v1
+    #[arg(
+        long,
+        value_name = "VALUE",
+        requires = "call",
+        allow_hyphen_values = true
+    )]
+    pub expect_v1: Option<String>,
+
+    /// What a string or array argument should start with once the function returns, as N=VALUE, counting from 0
+    #[arg(long = "expect-arg", value_name = "N=VALUE", requires = "call")]
+    pub expect_arguments: Vec<String>,
+}
@@ -0,0 +1,259 @@
+//! Golden-output tests: a program, and files next to it saying how it should behave. For prog.asm,
+//!
+//! - prog.stdin is given to the program as its input (otherwise it gets none),
+//! - prog.stdout is exactly what it has to print (otherwise its output isn't checked),
+//! - prog.exitcode is the code it has to give SysExitValue (otherwise it has to exit with 0).
+//!
+//! Every program is assembled, linked, and run in memory, so tests can run side by side with nothing written to disk.
+//! Directories are searched for programs that have at least one of these files, which leaves out files that are only
+//! ever included, like SysCalls.asm.
+
+use std::fs::{read, read_dir, read_to_string};
+use std::io::Cursor;
+use std::path::{Path, PathBuf};
+
+use name_core::machine::{Machine, Stop};
+
+use name_ld::build::build;
+
+const SIDECARS: [&str; 3] = ["stdin", "stdout", "exitcode"];
+
+/// A program and what it's expected to do.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GoldenTest {
+    pub program: PathBuf,
+    pub input: Option<PathBuf>,
+    pub expected_output: Option<PathBuf>,
+    pub expected_exit_code: Option<PathBuf>,
+}
+
+impl GoldenTest {
+    /// Picks up whichever sidecar files a program has.
+    pub fn new(program: PathBuf) -> Self {
+        let sidecar =
+            |extension: &str| Some(program.with_extension(extension)).filter(|path| path.is_file());
+        GoldenTest {
+            input: sidecar("stdin"),
+            expected_output: sidecar("stdout"),
+            expected_exit_code: sidecar("exitcode"),
+            program,
+        }
+    }
+
+    /// Builds and runs the program, allowing it limit instructions, and checks what it did.
+    pub fn run(&self, limit: u64) -> TestResult {
+        let mut result: TestResult = TestResult {
+            program: self.program.clone(),
+            failures: Vec::new(),
+            instructions: 0,
+        };
+        if let Err(e) = self.check(limit, &mut result) {
+            result.failures.push(e);
+        }
+        result
+    }
+
+    /// Records every way the program went wrong. Errors are for tests that couldn't get as far as checking anything.
+    fn check(&self, limit: u64, result: &mut TestResult) -> Result<(), String> {
+        let input: Vec<u8> = match &self.input {
+            Some(path) => {
+                read(path).map_err(|e| format!("Couldn't read {}: {e}", path.display()))?
+            }
+            None => Vec::new(),
+        };
+        let expected_output: Option<String> = self
+            .expected_output
+            .as_deref()
+            .map(read_sidecar)
+            .transpose()?;
+        let expected_exit_code: i32 = match &self.expected_exit_code {
+            Some(path) => {
+                let text: String = read_sidecar(path)?;
+                text.trim().parse::<i32>().map_err(|_| {
+                    format!(
+                        "{} should hold an exit code, not {:?}.",
+                        path.display(),
+                        text.trim()
+                    )
+                })?
+            }
+            None => 0,
+        };
+
+        let mut machine: Machine = Machine::load_elf(&build(&self.program)?);
+        machine.set_input(Cursor::new(input));
+
+        let stop: Result<Stop, String> = machine.run_until(limit);
+        result.instructions = machine.instructions();
+        let output: String = String::from_utf8_lossy(&machine.take_output()).to_string();
+
+        match stop {
+            Ok(Stop::Exited | Stop::Returned) => {
+                let exit_code: i32 = machine.exit_code() as i32;
+                if exit_code != expected_exit_code {
+                    result.failures.push(format!(
+                        "Exited with {exit_code} instead of {expected_exit_code}."
+                    ));
+                }
+            }
+            Ok(Stop::Breakpoint) => result.failures.push(format!(
+                "Stopped at a break instruction at 0x{:08x}.",
+                machine.program_state.cp0.get_epc()
+            )),
+            Ok(Stop::LimitReached) => result.failures.push(format!(
+                "Was still running after {limit} instructions; it may be stuck in a loop."
+            )),
+            Err(e) => result.failures.push(format!("Crashed:\n{e}")),
+        }
+
+        // what a crashed program printed first is still worth comparing
+        if let Some(expected) = expected_output {
+            if output != expected {
+                result.failures.push(format!(
+                    "Printed something else (- expected, + actual):\n{}",
+                    diff(&expected, &output)
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+fn read_sidecar(path: &Path) -> Result<String, String> {
+    read_to_string(path).map_err(|e| format!("Couldn't read {}: {e}", path.display()))
+}
+
+/// How one program did.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TestResult {
+    pub program: PathBuf,
+    pub failures: Vec<String>,
+    pub instructions: u64,
+}
+
+impl TestResult {
+    pub fn passed(&self) -> bool {
+        self.failures.is_empty()
+    }
+
+    /// A line saying whether the program passed, followed by why it didn't.
+    pub fn report(&self) -> String {
+        let mut report: String = match self.passed() {
+            true => format!(
+                "PASS {} ({} instructions)\n",
+                self.program.display(),
+                self.instructions
+            ),
+            false => format!("FAIL {}\n", self.program.display()),
+        };
+        for failure in &self.failures {
+            for line in failure.lines() {
+                report += &format!("    {line}\n");
+            }
+        }
+        report
+    }
+}
+
+/// Finds the tests in each path. A program named directly is always a test; a directory is searched all the way down
+/// for programs with sidecar files.
+pub fn discover(paths: &[PathBuf]) -> Result<Vec<GoldenTest>, String> {
+    let mut programs: Vec<PathBuf> = Vec::new();
+    for path in paths {
+        if path.is_dir() {
+            search(path, &mut programs)?;
+        } else if path.is_file() {
+            programs.push(path.clone());
+        } else {
+            return Err(format!("{} doesn't exist.", path.display()));
+        }
+    }
+    Ok(programs.into_iter().map(GoldenTest::new).collect())
+}
+
+fn search(directory: &Path, programs: &mut Vec<PathBuf>) -> Result<(), String> {
+    let mut entries: Vec<PathBuf> = read_dir(directory)
+        .and_then(|entries| {
+            entries
+                .map(|entry| entry.map(|entry| entry.path()))
+                .collect()
+        })
+        .map_err(|e| format!("Couldn't look in {}: {e}", directory.display()))?;
+    entries.sort();
+
+    for path in entries {
+        if path.is_dir() {
+            search(&path, programs)?;
+        } else if path.extension().is_some_and(|extension| extension == "asm")
+            && SIDECARS
+                .iter()
+                .any(|extension| path.with_extension(extension).is_file())
+        {
+            programs.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// Shows how actual differs from expected line by line, with a couple of unchanged lines around each difference.
+/// Nothing to show means they're the same.
+pub fn diff(expected: &str, actual: &str) -> String {
+    const CONTEXT: usize = 2;
+
+    let expected: Vec<&str> = expected.split_inclusive('\n').collect();
+    let actual: Vec<&str> = actual.split_inclusive('\n').collect();
+
+    // longest common subsequence, from the back, so the lines can be walked through from the front
+    let mut common: Vec<Vec<usize>> = vec![vec![0; actual.len() + 1]; expected.len() + 1];
+    for i in (0..expected.len()).rev() {
+        for j in (0..actual.len()).rev() {
+            common[i][j] = match expected[i] == actual[j] {
+                true => common[i + 1][j + 1] + 1,
+                false => common[i + 1][j].max(common[i][j + 1]),
+            };
+        }
+    }
+
+    let mut lines: Vec<(char, &str)> = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < expected.len() || j < actual.len() {
+        if i < expected.len() && j < actual.len() && expected[i] == actual[j] {
+            lines.push((' ', expected[i]));
+            (i, j) = (i + 1, j + 1);
+        } else if i < expected.len() && (j == actual.len() || common[i + 1][j] >= common[i][j + 1])
+        {
+            lines.push(('-', expected[i]));
+            i += 1;
+        } else {
+            lines.push(('+', actual[j]));
+            j += 1;
+        }
+    }
+
+    let changed: Vec<usize> = (0..lines.len()).filter(|&n| lines[n].0 != ' ').collect();
+    if changed.is_empty() {
+        return String::new();
+    }
+    let shown = |n: usize| changed.iter().any(|&c| c.abs_diff(n) <= CONTEXT);
+
+    let mut diff: String = String::new();
+    let mut skipped: bool = false;
+    for (n, &(sign, line)) in lines.iter().enumerate() {
+        if !shown(n) {
+            skipped = true;
+            continue;
+        }
+        if skipped {
+            diff += "  ...\n";
+            skipped = false;
+        }
+        match line.strip_suffix('\n') {
+            Some(line) => diff += &format!("{sign} {line}\n"),
+            None => diff += &format!("{sign} {line}\n\\ No newline at the end\n"),
+        }
+    }
+    if skipped {
+        diff += "  ...\n";
+    }
+    diff
+}
@@ -0,0 +1,358 @@
+//! Unit tests for single functions, so a strlen or a sort can be checked on its own instead of through a whole
+//! program's output. A function is called the way the MIPS calling convention says it should be:
+//!
+//! - the first four arguments go in $a0-$a3, and the rest on the stack from 16($sp), above the space the caller
+//!   leaves for the first four,
+//! - buffers (strings and arrays) are copied to the top of the stack, and the function is given their address,
+//! - $ra is RETURN_ADDRESS, which nothing is loaded at, so the call is over once the function jumps there,
+//! - the callee-saved registers are filled with junk beforehand, and have to hold the same junk afterwards.
+//!
+//! Only functions made global with .globl can be called, the same as from another file.
+//!
+//! Nothing from the program runs before the call, so its .data is as it was assembled.
+
+use name_core::machine::{Machine, Stop};
+use name_core::structs::Register;
+
+/// Where the function returns to. The call is over as soon as execution gets there.
+pub const RETURN_ADDRESS: u32 = 0xffff_fff0;
+
+/// The registers a function has to leave as it found them.
+const CALLEE_SAVED: [(Register, &str); 11] = [
+    (Register::S0, "$s0"),
+    (Register::S1, "$s1"),
+    (Register::S2, "$s2"),
+    (Register::S3, "$s3"),
+    (Register::S4, "$s4"),
+    (Register::S5, "$s5"),
+    (Register::S6, "$s6"),
+    (Register::S7, "$s7"),
+    (Register::Gp, "$gp"),
+    (Register::Sp, "$sp"),
+    (Register::Fp, "$fp"),
+];
+
+/// Something to pass to a function.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Argument {
+    Word(u32),
+    String(String),  // passed as the address of a NUL-terminated copy
+    Words(Vec<u32>), // passed as the address of an array
+}
+
+impl Argument {
+    /// Reads an argument like 42, -1, 0x2a, "text", or [3, 1, 2].
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let text: &str = text.trim();
+        if let Some(string) = text.strip_prefix('"') {
+            let Some(string) = string.strip_suffix('"') else {
+                return Err(format!("{text} is missing its closing quote."));
+            };
+            return Ok(Argument::String(unescape(string)?));
+        }
+        if let Some(words) = text.strip_prefix('[') {
+            let Some(words) = words.strip_suffix(']') else {
+                return Err(format!("{text} is missing its closing bracket."));
+            };
+            return words
+                .split(',')
+                .map(str::trim)
+                .filter(|word| !word.is_empty())
+                .map(parse_word)
+                .collect::<Result<Vec<u32>, String>>()
+                .map(Argument::Words);
+        }
+        parse_word(text).map(Argument::Word)
+    }
+
+    /// What goes in memory for the argument, or None if it's passed as it is.
+    pub fn bytes(&self) -> Option<Vec<u8>> {
+        match self {
+            Argument::Word(_) => None,
+            Argument::String(string) => {
+                let mut bytes: Vec<u8> = string.as_bytes().to_vec();
+                bytes.push(0);
+                Some(bytes)
+            }
+            Argument::Words(words) => {
+                Some(words.iter().flat_map(|word| word.to_be_bytes()).collect())
+            }
+        }
+    }
+
+    /// Shows bytes from memory as the same kind of argument: a string up to its NUL, or an array of words.
+    pub fn describe(&self, bytes: &[u8]) -> String {
+        match self {
+            Argument::Word(word) => format!("{}", *word as i32),
+            Argument::String(_) => {
+                let end: usize = bytes
+                    .iter()
+                    .position(|&byte| byte == 0)
+                    .unwrap_or(bytes.len());
+                format!("{:?}", String::from_utf8_lossy(&bytes[..end]))
+            }
+            Argument::Words(_) => {
+                let words: Vec<String> = bytes
+                    .chunks(4)
+                    .map(|word| {
+                        let mut padded: [u8; 4] = [0; 4];
+                        padded[..word.len()].copy_from_slice(word);
+                        format!("{}", i32::from_be_bytes(padded))
+                    })
+                    .collect();
+                format!("[{}]", words.join(", "))
+            }
+        }
+    }
+}
+
+/// Reads a word in decimal or hex. Negative numbers are taken as two's complement.
+pub fn parse_word(text: &str) -> Result<u32, String> {
+    let (negative, digits) = match text.trim().strip_prefix('-') {
+        Some(digits) => (true, digits),
+        None => (false, text.trim()),
+    };
+    let magnitude: Option<i64> = match digits.strip_prefix("0x").or(digits.strip_prefix("0X")) {
+        Some(hex) => u32::from_str_radix(hex, 16).ok(),
+        None => digits.parse::<u32>().ok(),
+    }
+    .map(i64::from);
+    let value: Option<i64> = magnitude.map(|magnitude| match negative {
+        true => -magnitude,
+        false => magnitude,
+    });
+
+    match value {
+        Some(value) if (i64::from(i32::MIN)..=i64::from(u32::MAX)).contains(&value) => {
+            Ok(value as u32)
+        }
+        Some(_) | None => Err(format!("{text} is not a number that fits in a word.")),
+    }
+}
+
+fn unescape(string: &str) -> Result<String, String> {
+    let mut unescaped: String = String::new();
+    let mut characters = string.chars();
+    while let Some(character) = characters.next() {
+        if character != '\\' {
+            unescaped.push(character);
+            continue;
+        }
+        unescaped.push(match characters.next() {
+            Some('n') => '\n',
+            Some('t') => '\t',
+            Some('0') => '\0',
+            Some(escaped @ ('\\' | '"')) => escaped,
+            Some(other) => return Err(format!("\\{other} is not an escape sequence.")),
+            None => return Err(format!("\"{string}\" ends in a lone backslash.")),
+        });
+    }
+    Ok(unescaped)
+}
+
+/// A function to call, and what to call it with.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Call {
+    pub function: String,
+    pub arguments: Vec<Argument>,
+}
+
+/// What a function left behind when it returned.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Returned {
+    pub v0: u32,
+    pub v1: u32,
+    pub buffers: Vec<(usize, Vec<u8>)>, // what each buffer argument holds now, by its position
+    pub clobbered: Vec<String>,         // callee-saved registers it didn't restore
+    pub output: Vec<u8>,
+    pub instructions: u64,
+}
+
+/// What a function should give back. Anything left out isn't checked; callee-saved registers always are.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Expected {
+    pub v0: Option<u32>,
+    pub v1: Option<u32>,
+    pub buffers: Vec<(usize, Argument)>, // what a buffer argument, by its position, should start with
+}
+
+/// Calls a function in a freshly loaded program and runs it until it returns, for at most limit instructions.
+/// Errors are for calls that never came back: the function crashed, exited, or ran for too long.
+pub fn call(machine: &mut Machine, call: &Call, limit: u64) -> Result<Returned, String> {
+    let Some(entry) = machine.symbol(&call.function) else {
+        return Err(format!(
+            "There's no label called {} to call.",
+            call.function
+        ));
+    };
+    if !machine.is_global(&call.function) {
+        return Err(format!(
+            "{} isn't global; add `.globl {}` so it can be called.",
+            call.function, call.function
+        ));
+    }
+
+    // buffers go at the top of the stack, and the function's frame below them
+    let mut top: u32 = machine.program_state.memory.layout.stack_top;
+    let stack_limit: u32 = machine.program_state.memory.layout.stack_limit();
+    let too_big = || format!("The arguments to {} don't fit on the stack.", call.function);
+    let mut words: Vec<u32> = Vec::new();
+    let mut buffers: Vec<(usize, u32, u32)> = Vec::new();
+    for (position, argument) in call.arguments.iter().enumerate() {
+        match argument {
+            Argument::Word(word) => words.push(*word),
+            _ => {
+                let bytes: Vec<u8> = argument.bytes().unwrap_or_default();
+                top = u32::try_from(bytes.len())
+                    .ok()
+                    .and_then(|length| top.checked_sub(length))
+                    .map(|top| top & !7)
+                    .filter(|&top| top >= stack_limit)
+                    .ok_or_else(too_big)?;
+                machine.write_memory(top, &bytes)?;
+                buffers.push((position, top, bytes.len() as u32));
+                words.push(top);
+            }
+        }
+    }
+
+    let stack_words: u32 = words.len().saturating_sub(4) as u32;
+    let sp: u32 = stack_words
+        .checked_mul(4)
+        .and_then(|frame| frame.checked_add(16))
+        .and_then(|frame| top.checked_sub(frame))
+        .map(|sp| sp & !7)
+        .filter(|&sp| sp >= stack_limit)
+        .ok_or_else(too_big)?;
+    for (n, &word) in words.iter().enumerate().skip(4) {
+        machine.write_word(sp + 4 * n as u32, word)?;
+    }
+    for (register, &word) in [Register::A0, Register::A1, Register::A2, Register::A3]
+        .into_iter()
+        .zip(&words)
+    {
+        machine.set_register(register, word);
+    }
+    machine.set_register(Register::Sp, sp);
+    machine.set_register(Register::Ra, RETURN_ADDRESS);
+    machine.return_to(RETURN_ADDRESS);
+    for (n, (register, _)) in CALLEE_SAVED.iter().enumerate() {
+        if !matches!(register, Register::Sp | Register::Gp) {
+            machine.set_register(*register, 0x5afe_0000 + n as u32);
+        }
+    }
+    let saved: Vec<u32> = CALLEE_SAVED
+        .iter()
+        .map(|(register, _)| machine.register(*register))
+        .collect();
+    machine.set_pc(entry);
+
+    let started: u64 = machine.instructions();
+    match machine.run_until(limit) {
+        Ok(Stop::Returned) => {}
+        Ok(Stop::Exited) => {
+            return Err(format!(
+                "{} exited the program instead of returning.",
+                call.function
+            ))
+        }
+        Ok(Stop::Breakpoint) => {
+            return Err(format!(
+                "{} stopped at a break instruction at 0x{:08x}.",
+                call.function,
+                machine.program_state.cp0.get_epc()
+            ))
+        }
+        Ok(Stop::LimitReached) => {
+            return Err(format!(
+                "{} was still running after {limit} instructions; it may be stuck in a loop.",
+                call.function
+            ))
+        }
+        Err(e) => return Err(format!("{} crashed:\n{e}", call.function)),
+    }
+
+    let clobbered: Vec<String> = CALLEE_SAVED
+        .iter()
+        .zip(saved)
+        .filter(|((register, _), before)| machine.register(*register) != *before)
+        .map(|((register, name), before)| {
+            format!(
+                "{name} was 0x{before:08x} before the call and 0x{:08x} after",
+                machine.register(*register)
+            )
+        })
+        .collect();
+
+    Ok(Returned {
+        v0: machine.register(Register::V0),
+        v1: machine.register(Register::V1),
+        buffers: buffers
+            .into_iter()
+            .map(|(position, address, length)| {
+                machine
+                    .read_memory(address, length)
+                    .map(|bytes| (position, bytes))
+            })
+            .collect::<Result<_, String>>()?,
+        clobbered,
+        output: machine.take_output(),
+        instructions: machine.instructions() - started,
+    })
+}
+
+impl Returned {
+    /// Every way the function didn't do what was expected of it.
+    pub fn check(&self, expected: &Expected) -> Vec<String> {
+        let mut failures: Vec<String> = Vec::new();
+        for (name, value, expected) in
+            [("$v0", self.v0, expected.v0), ("$v1", self.v1, expected.v1)]
+        {
+            if let Some(expected) = expected.filter(|&expected| expected != value) {
+                failures.push(format!(
+                    "{name} is {} (0x{value:08x}) instead of {} (0x{expected:08x})",
+                    value as i32, expected as i32
+                ));
+            }
+        }
+
+        for (position, argument) in &expected.buffers {
+            let Some((_, bytes)) = self.buffers.iter().find(|(buffer, _)| buffer == position)
+            else {
+                failures.push(format!("Argument {position} wasn't a string or an array"));
+                continue;
+            };
+            let wanted: Vec<u8> = argument.bytes().unwrap_or_default();
+            if !bytes.starts_with(&wanted) {
+                failures.push(format!(
+                    "Argument {position} holds {} instead of {}",
+                    argument.describe(bytes),
+                    argument.describe(&wanted)
+                ));
+            }
+        }
+
+        for register in &self.clobbered {
+            failures.push(format!("{register}, but it's callee-saved"));
+        }
+        failures
+    }
+
+    /// Says what the function gave back, with the buffer arguments shown the way they were given.
+    pub fn report(&self, call: &Call) -> String {
+        let mut report: String = format!(
+            "{} returned $v0 = {} (0x{:08x}), $v1 = {} (0x{:08x}) after {} instructions\n",
+            call.function, self.v0 as i32, self.v0, self.v1 as i32, self.v1, self.instructions
+        );
+        for (position, bytes) in &self.buffers {
+            report += &format!(
+                "    argument {position} now holds {}\n",
+                call.arguments[*position].describe(bytes)
+            );
+        }
+        if !self.output.is_empty() {
+            report += &format!("    printed {:?}\n", String::from_utf8_lossy(&self.output));
+        }
+        report
+    }
+}
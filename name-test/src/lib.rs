@@ -0,0 +1,3 @@
+pub mod args;
+pub mod golden;
+pub mod harness;
@@ -0,0 +1,111 @@
+use name_test::args::Cli;
+use name_test::golden::{discover, GoldenTest, TestResult};
+use name_test::harness::{call, parse_word, Argument, Call, Expected, Returned};
+
+use name_core::machine::Machine;
+
+use name_ld::build::build;
+
+use clap::Parser;
+
+fn main() {
+    let args: Cli = Cli::parse();
+
+    if let Some(function) = &args.call {
+        match call_function(&args, function) {
+            Ok(true) => return,
+            Ok(false) => std::process::exit(1),
+            Err(e) => {
+                eprintln!("{e}");
+                std::process::exit(2);
+            }
+        }
+    }
+
+    let tests: Vec<GoldenTest> = match discover(&args.paths) {
+        Ok(tests) => tests,
+        Err(e) => {
+            eprintln!("{e}");
+            std::process::exit(2);
+        }
+    };
+    if tests.is_empty() {
+        eprintln!("No programs with .stdin, .stdout, or .exitcode files were found.");
+        std::process::exit(2);
+    }
+
+    let mut failed: usize = 0;
+    for test in &tests {
+        let result: TestResult = test.run(args.limit);
+        if !result.passed() {
+            failed += 1;
+        }
+        if !(args.quiet && result.passed()) {
+            print!("{}", result.report());
+        }
+    }
+
+    println!("\n{} passed, {failed} failed", tests.len() - failed);
+    if failed > 0 {
+        std::process::exit(1);
+    }
+}
+
+/// Calls the function --call names with the arguments given, and checks it against what's expected.
+/// Gives back whether it passed; errors are for a test that doesn't make sense.
+fn call_function(args: &Cli, function: &str) -> Result<bool, String> {
+    let [program] = args.paths.as_slice() else {
+        return Err("--call tests a function in one program, so give just the one.".to_string());
+    };
+
+    let function_call: Call = Call {
+        function: function.to_string(),
+        arguments: args
+            .arguments
+            .iter()
+            .map(|argument| Argument::parse(argument))
+            .collect::<Result<_, String>>()?,
+    };
+    let expected: Expected = Expected {
+        v0: args.expect_v0.as_deref().map(parse_word).transpose()?,
+        v1: args.expect_v1.as_deref().map(parse_word).transpose()?,
+        buffers: args
+            .expect_arguments
+            .iter()
+            .map(|expectation| {
+                let Some((position, value)) = expectation.split_once('=') else {
+                    return Err(format!("{expectation} should look like N=VALUE."));
+                };
+                match position.trim().parse::<usize>() {
+                    Ok(position) => Ok((position, Argument::parse(value)?)),
+                    Err(_) => Err(format!("{position} is not an argument's position.")),
+                }
+            })
+            .collect::<Result<_, String>>()?,
+    };
+
+    let returned: Result<Returned, String> = build(program).and_then(|elf| {
+        let mut machine: Machine = Machine::load_elf(&elf);
+        call(&mut machine, &function_call, args.limit)
+    });
+    let failures: Vec<String> = match returned {
+        Ok(returned) => {
+            print!("{}", returned.report(&function_call));
+            returned.check(&expected)
+        }
+        Err(e) => vec![e],
+    };
+
+    match failures.is_empty() {
+        true => println!("PASS"),
+        false => {
+            println!("FAIL");
+            for failure in &failures {
+                for line in failure.lines() {
+                    println!("    {line}");
+                }
+            }
+        }
+    }
+    Ok(failures.is_empty())
+}
@@ -0,0 +1,86 @@
+#[path = "../../name-emu/tests/common/mod.rs"]
+mod common;
+
+use std::path::PathBuf;
+
+use name_test::golden::{diff, discover, GoldenTest, TestResult};
+
+const ECHO: &str = "    .text
+main:
+    li $v0, 5
+    syscall
+    move $a0, $v0
+    li $v0, 1
+    syscall
+    li $v0, 17
+    li $a0, 3
+    syscall
+";
+
+#[test]
+fn samples_golden_test() {
+    let samples: PathBuf = common::samples();
+
+    let tests: Vec<GoldenTest> = discover(std::slice::from_ref(&samples)).unwrap();
+    assert!(tests
+        .iter()
+        .any(|test| test.program == samples.join("fib.asm")));
+    // SysCalls.asm is only included, so it has nothing to say how it should behave
+    assert!(tests
+        .iter()
+        .all(|test| test.program != samples.join("SysCalls.asm")));
+
+    for test in &tests {
+        let result: TestResult = test.run(1_000_000);
+        assert!(result.passed(), "{}", result.report());
+    }
+}
+
+#[test]
+fn golden_failures_test() {
+    let directory: common::TempDir = common::TempDir::new("golden-failures");
+    let file = |name: &str, contents: &str| directory.write(name, contents);
+
+    file("echo.asm", ECHO);
+    file("echo.stdin", "41\n");
+    file("echo.stdout", "41");
+    file("echo.exitcode", "3\n");
+
+    file("wrong.asm", ECHO);
+    file("wrong.stdin", "42\n");
+    file("wrong.stdout", "41");
+
+    file("loop.asm", "    .text\nmain:\n    j main\n");
+    file("loop.exitcode", "0");
+
+    let tests: Vec<GoldenTest> = discover(&[directory.path().to_path_buf()]).unwrap();
+    let results: Vec<TestResult> = tests.iter().map(|test| test.run(1_000)).collect();
+
+    // programs are run in order of their names
+    let [echo, looping, wrong] = results.as_slice() else {
+        panic!("expected three tests, found {results:?}");
+    };
+    assert!(echo.passed(), "{}", echo.report());
+    assert_eq!(echo.instructions, 8);
+
+    assert_eq!(
+        looping.failures,
+        vec!["Was still running after 1000 instructions; it may be stuck in a loop.".to_string()]
+    );
+
+    assert_eq!(wrong.failures.len(), 2);
+    assert_eq!(wrong.failures[0], "Exited with 3 instead of 0.");
+    assert!(wrong.failures[1]
+        .ends_with("- 41\n\\ No newline at the end\n+ 42\n\\ No newline at the end\n"));
+    assert!(wrong.report().starts_with("FAIL "));
+}
+
+#[test]
+fn diff_test() {
+    assert_eq!(diff("a\nb\n", "a\nb\n"), "");
+    assert_eq!(
+        diff("1\n2\n3\n4\n5\n6\n7\n", "1\n2\n3\n4\n5\nsix\n7\n"),
+        "  ...\n  4\n  5\n- 6\n+ six\n  7\n"
+    );
+    assert_eq!(diff("done\n", ""), "- done\n");
+}
@@ -0,0 +1,160 @@
+#[path = "../../name-emu/tests/common/mod.rs"]
+mod common;
+
+use name_core::machine::Machine;
+use name_test::harness::{call, Argument, Call, Expected, Returned};
+
+const FUNCTIONS: &str = "    .text
+    .globl strlen, sum6, sort
+    .globl spin
+# strlen(s): how long the NUL-terminated string s is
+strlen:
+    move $v0, $zero
+strlen_loop:
+    lb $t0, 0($a0)
+    beq $t0, $zero, strlen_done
+    addi $v0, $v0, 1
+    addi $a0, $a0, 1
+    j strlen_loop
+strlen_done:
+    jr $ra
+
+# sum6(a, b, c, d, e, f): the sum of six arguments, the last two on the stack
+sum6:
+    addu $v0, $a0, $a1
+    addu $v0, $v0, $a2
+    addu $v0, $v0, $a3
+    lw $t0, 16($sp)
+    addu $v0, $v0, $t0
+    lw $t0, 20($sp)
+    addu $v0, $v0, $t0
+    jr $ra
+
+# sort(array, n): bubble sorts n words in place, but uses $s0 without saving it
+sort:
+    addi $s0, $a1, -1
+sort_outer:
+    blez $s0, sort_done
+    move $t0, $a0
+    move $t1, $s0
+sort_inner:
+    lw $t2, 0($t0)
+    lw $t3, 4($t0)
+    slt $t4, $t3, $t2
+    beq $t4, $zero, sort_next
+    sw $t3, 0($t0)
+    sw $t2, 4($t0)
+sort_next:
+    addi $t0, $t0, 4
+    addi $t1, $t1, -1
+    bgtz $t1, sort_inner
+    addi $s0, $s0, -1
+    j sort_outer
+sort_done:
+    jr $ra
+
+spin:
+    j spin
+";
+
+/// Builds the functions, and calls one of them with a fresh copy of the program.
+fn call_function(function: &str, arguments: &[&str]) -> Result<Returned, String> {
+    let mut machine: Machine =
+        Machine::load_elf(&common::build_sample(FUNCTIONS, &format!("harness-{function}")).elf);
+
+    let function_call: Call = Call {
+        function: function.to_string(),
+        arguments: arguments
+            .iter()
+            .map(|argument| Argument::parse(argument).unwrap())
+            .collect(),
+    };
+    call(&mut machine, &function_call, 10_000)
+}
+
+#[test]
+fn strlen_harness_test() {
+    let returned: Returned = call_function("strlen", &["\"hello\\n\""]).unwrap();
+    assert_eq!(returned.v0, 6);
+    assert!(returned.clobbered.is_empty());
+    assert_eq!(returned.buffers, vec![(0, b"hello\n\0".to_vec())]);
+
+    let expected: Expected = Expected {
+        v0: Some(6),
+        ..Default::default()
+    };
+    assert!(returned.check(&expected).is_empty());
+    let expected: Expected = Expected {
+        v0: Some(5),
+        ..Default::default()
+    };
+    assert_eq!(
+        returned.check(&expected),
+        vec!["$v0 is 6 (0x00000006) instead of 5 (0x00000005)".to_string()]
+    );
+
+    // a null pointer isn't a string
+    let error: String = call_function("strlen", &["0"]).unwrap_err();
+    assert!(error.starts_with("strlen crashed:"), "{error}");
+
+    // a string bigger than the whole stack can't be passed
+    let huge: String = format!("\"{}\"", "a".repeat(0x10_0000));
+    assert_eq!(
+        call_function("strlen", &[&huge]).unwrap_err(),
+        "The arguments to strlen don't fit on the stack."
+    );
+}
+
+#[test]
+fn stack_arguments_harness_test() {
+    let returned: Returned = call_function("sum6", &["1", "2", "3", "4", "0x10", "-6"]).unwrap();
+    assert_eq!(returned.v0, 20);
+    assert_eq!(returned.instructions, 8);
+    assert!(returned.clobbered.is_empty());
+}
+
+#[test]
+fn sort_harness_test() {
+    let returned: Returned = call_function("sort", &["[3, -1, 2, 0]", "4"]).unwrap();
+    let expected: Expected = Expected {
+        buffers: vec![(0, Argument::parse("[-1, 0, 2, 3]").unwrap())],
+        ..Default::default()
+    };
+    assert_eq!(
+        returned.check(&expected),
+        vec![
+            "$s0 was 0x5afe0000 before the call and 0x00000000 after, but it's callee-saved"
+                .to_string()
+        ]
+    );
+
+    let expected: Expected = Expected {
+        buffers: vec![
+            (0, Argument::parse("[3, 2]").unwrap()),
+            (1, Argument::parse("[4]").unwrap()),
+        ],
+        ..Default::default()
+    };
+    let failures: Vec<String> = returned.check(&expected);
+    assert_eq!(
+        failures[0],
+        "Argument 0 holds [-1, 0, 2, 3] instead of [3, 2]"
+    );
+    assert_eq!(failures[1], "Argument 1 wasn't a string or an array");
+}
+
+#[test]
+fn runaway_harness_test() {
+    assert_eq!(
+        call_function("spin", &[]).unwrap_err(),
+        "spin was still running after 10000 instructions; it may be stuck in a loop."
+    );
+    assert_eq!(
+        call_function("nothing", &[]).unwrap_err(),
+        "There's no label called nothing to call."
+    );
+    assert_eq!(
+        call_function("strlen_loop", &[]).unwrap_err(),
+        "strlen_loop isn't global; add `.globl strlen_loop` so it can be called."
+    );
+}